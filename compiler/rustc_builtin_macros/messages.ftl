@@ -232,9 +232,17 @@ builtin_macros_test_bad_fn = {$kind} functions cannot be used for tests
 
 builtin_macros_test_case_non_item = `#[test_case]` attribute is only allowed on items
 
+builtin_macros_test_fixture_invalid = invalid test fixture kind
+    .help = expected `#[test_fixture(setup)]` or `#[test_fixture(teardown)]`
+
+builtin_macros_test_resource_invalid = test resources must be string literals
+
 builtin_macros_test_runner_invalid = `test_runner` argument must be a path
 builtin_macros_test_runner_nargs = `#![test_runner(..)]` accepts exactly 1 argument
 
+builtin_macros_test_timeout_invalid = invalid test timeout `{$value}`
+    .help = expected a non-zero duration such as `500ms`, `30s` or `2m`
+
 builtin_macros_tests_not_support = building tests with panic=abort is not supported without `-Zpanic_abort_tests`
 
 builtin_macros_trace_macros = trace_macros! accepts only `true` or `false`
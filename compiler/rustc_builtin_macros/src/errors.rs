@@ -684,6 +684,30 @@ pub(crate) struct TestBadFn {
     pub(crate) kind: &'static str,
 }
 
+#[derive(Diagnostic)]
+#[diag(builtin_macros_test_fixture_invalid)]
+#[help]
+pub(crate) struct TestFixtureInvalid {
+    #[primary_span]
+    pub(crate) span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(builtin_macros_test_resource_invalid)]
+pub(crate) struct TestResourceInvalid {
+    #[primary_span]
+    pub(crate) span: Span,
+}
+
+#[derive(Diagnostic)]
+#[diag(builtin_macros_test_timeout_invalid)]
+#[help]
+pub(crate) struct TestTimeoutInvalid {
+    #[primary_span]
+    pub(crate) span: Span,
+    pub(crate) value: Symbol,
+}
+
 #[derive(Diagnostic)]
 #[diag(builtin_macros_asm_explicit_register_name)]
 pub(crate) struct AsmExplicitRegisterName {
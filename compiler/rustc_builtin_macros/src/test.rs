@@ -10,6 +10,7 @@
 use rustc_span::symbol::{sym, Ident, Symbol};
 use rustc_span::{ErrorGuaranteed, FileNameDisplayPreference, Span};
 use std::iter;
+use std::time::Duration;
 use thin_vec::{thin_vec, ThinVec};
 
 /// #[test_case] is used by custom test authors to mark tests
@@ -179,6 +180,18 @@ pub fn expand_test_or_bench(
         )
     };
 
+    // creates test::TestFixture::$name
+    let test_fixture_path = |name| {
+        cx.path(
+            sp,
+            vec![
+                test_id,
+                Ident::from_str_and_span("TestFixture", sp),
+                Ident::from_str_and_span(name, sp),
+            ],
+        )
+    };
+
     // creates $name: $expr
     let field = |name, expr| cx.field_imm(sp, Ident::from_str_and_span(name, sp), expr);
 
@@ -344,6 +357,55 @@ pub fn expand_test_or_bench(
                                             }
                                         },
                                     ),
+                                    // timeout: Some(Duration::new(...)) | None
+                                    field(
+                                        "timeout",
+                                        if let Some(timeout) = test_timeout(cx, &item) {
+                                            cx.expr_some(
+                                                sp,
+                                                cx.expr_call_global(
+                                                    sp,
+                                                    cx.std_path(&[
+                                                        sym::time,
+                                                        sym::Duration,
+                                                        sym::new,
+                                                    ]),
+                                                    thin_vec![
+                                                        cx.expr_u64(sp, timeout.as_secs()),
+                                                        cx.expr_u32(sp, timeout.subsec_nanos()),
+                                                    ],
+                                                ),
+                                            )
+                                        } else {
+                                            cx.expr_none(sp)
+                                        },
+                                    ),
+                                    // fixture: Some(test::TestFixture::...) | None
+                                    field(
+                                        "fixture",
+                                        match test_fixture(cx, &item) {
+                                            Some(TestFixture::Setup) => cx.expr_some(
+                                                sp,
+                                                cx.expr_path(test_fixture_path("Setup")),
+                                            ),
+                                            Some(TestFixture::Teardown) => cx.expr_some(
+                                                sp,
+                                                cx.expr_path(test_fixture_path("Teardown")),
+                                            ),
+                                            None => cx.expr_none(sp),
+                                        },
+                                    ),
+                                    // resources: &["..."]
+                                    field(
+                                        "resources",
+                                        cx.expr_array_ref(
+                                            sp,
+                                            test_resources(cx, &item)
+                                                .into_iter()
+                                                .map(|resource| cx.expr_str(sp, resource))
+                                                .collect(),
+                                        ),
+                                    ),
                                     // },
                                 ],
                             ),
@@ -501,6 +563,73 @@ fn should_panic(cx: &ExtCtxt<'_>, i: &ast::Item) -> ShouldPanic {
     }
 }
 
+fn test_timeout(cx: &ExtCtxt<'_>, i: &ast::Item) -> Option<Duration> {
+    let attr = attr::find_by_name(&i.attrs, sym::test_timeout)?;
+    // Malformed attributes are reported by attribute validation.
+    let value = attr.value_str()?;
+    let timeout = parse_test_timeout(value.as_str()).filter(|timeout| !timeout.is_zero());
+    if timeout.is_none() {
+        cx.emit_err(errors::TestTimeoutInvalid { span: attr.span, value });
+    }
+    timeout
+}
+
+/// Parses a duration such as `500ms`, `30s` or `2m`. A number without a unit is interpreted
+/// as seconds.
+///
+/// This is the grammar of the `--test-timeout` option, and must be kept in sync with
+/// `parse_duration` in `library/test/src/time.rs` so that a timeout means the same thing
+/// on the command line and in the attribute.
+fn parse_test_timeout(s: &str) -> Option<Duration> {
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+enum TestFixture {
+    Setup,
+    Teardown,
+}
+
+fn test_fixture(cx: &ExtCtxt<'_>, i: &ast::Item) -> Option<TestFixture> {
+    let attr = attr::find_by_name(&i.attrs, sym::test_fixture)?;
+    // Malformed attributes are reported by attribute validation.
+    let list = attr.meta_item_list()?;
+    let fixture = match &list[..] {
+        [mi] if mi.is_word() && mi.has_name(sym::setup) => Some(TestFixture::Setup),
+        [mi] if mi.is_word() && mi.has_name(sym::teardown) => Some(TestFixture::Teardown),
+        _ => None,
+    };
+    if fixture.is_none() {
+        cx.emit_err(errors::TestFixtureInvalid { span: attr.span });
+    }
+    fixture
+}
+
+fn test_resources(cx: &ExtCtxt<'_>, i: &ast::Item) -> Vec<Symbol> {
+    let Some(attr) = attr::find_by_name(&i.attrs, sym::test_resources) else {
+        return Vec::new();
+    };
+    // Malformed attributes are reported by attribute validation.
+    let Some(list) = attr.meta_item_list() else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter_map(|mi| {
+            let resource = mi.lit().and_then(|lit| lit.kind.str());
+            if resource.is_none() {
+                cx.emit_err(errors::TestResourceInvalid { span: mi.span() });
+            }
+            resource
+        })
+        .collect()
+}
+
 enum TestType {
     UnitTest,
     IntegrationTest,
@@ -356,6 +356,12 @@ pub fn expr_u32(&self, span: Span, n: u32) -> P<ast::Expr> {
         self.expr(span, ast::ExprKind::Lit(lit))
     }
 
+    pub fn expr_u64(&self, span: Span, n: u64) -> P<ast::Expr> {
+        let suffix = Some(ast::UintTy::U64.name());
+        let lit = token::Lit::new(token::Integer, sym::integer(n), suffix);
+        self.expr(span, ast::ExprKind::Lit(lit))
+    }
+
     pub fn expr_bool(&self, span: Span, value: bool) -> P<ast::Expr> {
         let lit = token::Lit::new(token::Bool, if value { kw::True } else { kw::False }, None);
         self.expr(span, ast::ExprKind::Lit(lit))
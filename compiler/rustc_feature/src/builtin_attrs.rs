@@ -422,6 +422,18 @@ pub struct BuiltinAttribute {
         test_runner, CrateLevel, template!(List: "path"), ErrorFollowing, custom_test_frameworks,
         "custom test frameworks are an unstable feature",
     ),
+    gated!(
+        test_fixture, Normal, template!(List: "setup|teardown"), ErrorFollowing,
+        experimental!(test_fixture),
+    ),
+    gated!(
+        test_resources, Normal, template!(List: r#""resource", ..."#), ErrorFollowing,
+        experimental!(test_resources),
+    ),
+    gated!(
+        test_timeout, Normal, template!(NameValueStr: "duration"), ErrorFollowing,
+        experimental!(test_timeout),
+    ),
     // RFC #1268
     gated!(
         marker, Normal, template!(Word), WarnFollowing, @only_local: true,
@@ -558,6 +558,14 @@ pub fn internal(&self, feature: Symbol) -> bool {
     (unstable, string_deref_patterns, "1.67.0", Some(87121), None),
     /// Allows the use of `#[target_feature]` on safe functions.
     (unstable, target_feature_11, "1.45.0", Some(69098), None),
+    /// Allows running a test as the setup or teardown of the tests of its module with
+    /// `#[test_fixture(setup)]` and `#[test_fixture(teardown)]`.
+    (unstable, test_fixture, "CURRENT_RUSTC_VERSION", None, None),
+    /// Allows declaring the resources a test can't share with concurrently running tests
+    /// with `#[test_resources("resource", ...)]`.
+    (unstable, test_resources, "CURRENT_RUSTC_VERSION", None, None),
+    /// Allows setting a per-test timeout with `#[test_timeout = "duration"]`.
+    (unstable, test_timeout, "CURRENT_RUSTC_VERSION", None, None),
     /// Allows using `#[thread_local]` on `static` items.
     (unstable, thread_local, "1.0.0", Some(29594), None),
     /// Allows defining `trait X = A + B;` alias items.
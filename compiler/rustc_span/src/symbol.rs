@@ -808,6 +808,7 @@
         from_fn,
         from_iter,
         from_iter_fn,
+        from_millis,
         from_output,
         from_residual,
         from_size_align_unchecked,
@@ -1447,6 +1448,7 @@
         self_in_typedefs,
         self_struct_ctor,
         semitransparent,
+        setup,
         shadow_call_stack,
         shl,
         shl_assign,
@@ -1608,6 +1610,7 @@
         target_thread_local,
         target_vendor,
         tbm_target_feature,
+        teardown,
         termination,
         termination_trait,
         termination_trait_test,
@@ -1615,14 +1618,18 @@
         test_2018_feature,
         test_accepted_feature,
         test_case,
+        test_fixture,
         test_removed_feature,
+        test_resources,
         test_runner,
+        test_timeout,
         test_unstable_lint,
         thread,
         thread_local,
         thread_local_macro,
         thumb2,
         thumb_mode: "thumb-mode",
+        time,
         tmm_reg,
         to_owned_method,
         to_string,
@@ -22,10 +22,18 @@
     }
 }
 
-/// Flag to indicate OUTPUT_CAPTURE is used.
+thread_local! {
+    /// Used by the test crate to capture the output of the print macros and panics, with
+    /// separate buffers for stdout and stderr. Output also goes to `OUTPUT_CAPTURE` if it is set.
+    static SPLIT_OUTPUT_CAPTURE: Cell<Option<(LocalStream, LocalStream)>> = {
+        Cell::new(None)
+    }
+}
+
+/// Flag to indicate OUTPUT_CAPTURE or SPLIT_OUTPUT_CAPTURE is used.
 ///
-/// If it is None and was never set on any thread, this flag is set to false,
-/// and OUTPUT_CAPTURE can be safely ignored on all threads, saving some time
+/// If they are None and were never set on any thread, this flag is false,
+/// and both captures can be safely ignored on all threads, saving some time
 /// and memory registering an unused thread local.
 ///
 /// Note about memory ordering: This contains information about whether a
@@ -995,8 +1003,35 @@ pub fn set_output_capture(sink: Option<LocalStream>) -> Option<LocalStream> {
     OUTPUT_CAPTURE.with(move |slot| slot.replace(sink))
 }
 
-/// Write `args` to the capture buffer if enabled and possible, or `global_s`
-/// otherwise. `label` identifies the stream in a panic message.
+/// Sets the thread-local capture buffers for stdout and stderr, in that order, and returns the
+/// old ones.
+#[unstable(
+    feature = "internal_output_capture",
+    reason = "this function is meant for use in the test crate \
+        and may disappear in the future",
+    issue = "none"
+)]
+#[doc(hidden)]
+pub fn set_split_output_capture(
+    sinks: Option<(LocalStream, LocalStream)>,
+) -> Option<(LocalStream, LocalStream)> {
+    if sinks.is_none() && !OUTPUT_CAPTURE_USED.load(Ordering::Relaxed) {
+        // SPLIT_OUTPUT_CAPTURE is definitely None since OUTPUT_CAPTURE_USED is false.
+        return None;
+    }
+    OUTPUT_CAPTURE_USED.store(true, Ordering::Relaxed);
+    SPLIT_OUTPUT_CAPTURE.with(move |slot| slot.replace(sinks))
+}
+
+/// The standard stream that a print macro writes to.
+#[derive(Clone, Copy)]
+enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Write `args` to the capture buffer of `stream` if enabled and possible, or
+/// `global_s` otherwise.
 ///
 /// This function is used to print error messages, so it takes extra
 /// care to avoid causing a panic when `OUTPUT_CAPTURE` is unusable.
@@ -1008,38 +1043,55 @@ pub fn set_output_capture(sink: Option<LocalStream>) -> Option<LocalStream> {
 ///
 /// Writing to non-blocking stdout/stderr can cause an error, which will lead
 /// this function to panic.
-fn print_to<T>(args: fmt::Arguments<'_>, global_s: fn() -> T, label: &str)
+fn print_to<T>(args: fmt::Arguments<'_>, global_s: fn() -> T, stream: OutputStream)
 where
     T: Write,
 {
-    if print_to_buffer_if_capture_used(args) {
+    if print_to_buffer_if_capture_used(args, stream) {
         // Successfully wrote to capture buffer.
         return;
     }
 
     if let Err(e) = global_s().write_fmt(args) {
+        let label = match stream {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        };
         panic!("failed printing to {label}: {e}");
     }
 }
 
-fn print_to_buffer_if_capture_used(args: fmt::Arguments<'_>) -> bool {
-    OUTPUT_CAPTURE_USED.load(Ordering::Relaxed)
-        && OUTPUT_CAPTURE.try_with(|s| {
-            // Note that we completely remove a local sink to write to in case
-            // our printing recursively panics/prints, so the recursive
-            // panic/print goes to the global sink instead of our local sink.
-            s.take().map(|w| {
-                let _ = w.lock().unwrap_or_else(|e| e.into_inner()).write_fmt(args);
-                s.set(Some(w));
-            })
-        }) == Ok(Some(()))
+fn print_to_buffer_if_capture_used(args: fmt::Arguments<'_>, stream: OutputStream) -> bool {
+    if !OUTPUT_CAPTURE_USED.load(Ordering::Relaxed) {
+        return false;
+    }
+    // Note that we completely remove a local sink to write to in case
+    // our printing recursively panics/prints, so the recursive
+    // panic/print goes to the global sink instead of our local sink.
+    let split = SPLIT_OUTPUT_CAPTURE.try_with(|s| {
+        s.take().map(|(out, err)| {
+            let w = match stream {
+                OutputStream::Stdout => &out,
+                OutputStream::Stderr => &err,
+            };
+            let _ = w.lock().unwrap_or_else(|e| e.into_inner()).write_fmt(args);
+            s.set(Some((out, err)));
+        })
+    }) == Ok(Some(()));
+    let combined = OUTPUT_CAPTURE.try_with(|s| {
+        s.take().map(|w| {
+            let _ = w.lock().unwrap_or_else(|e| e.into_inner()).write_fmt(args);
+            s.set(Some(w));
+        })
+    }) == Ok(Some(()));
+    split || combined
 }
 
 /// Used by impl Termination for Result to print error after `main` or a test
 /// has returned. Should avoid panicking, although we can't help it if one of
 /// the Display impls inside args decides to.
 pub(crate) fn attempt_print_to_stderr(args: fmt::Arguments<'_>) {
-    if print_to_buffer_if_capture_used(args) {
+    if print_to_buffer_if_capture_used(args, OutputStream::Stderr) {
         return;
     }
 
@@ -1094,7 +1146,7 @@ fn is_terminal(&self) -> bool {
 #[doc(hidden)]
 #[cfg(not(test))]
 pub fn _print(args: fmt::Arguments<'_>) {
-    print_to(args, stdout, "stdout");
+    print_to(args, stdout, OutputStream::Stdout);
 }
 
 #[unstable(
@@ -1105,7 +1157,7 @@ pub fn _print(args: fmt::Arguments<'_>) {
 #[doc(hidden)]
 #[cfg(not(test))]
 pub fn _eprint(args: fmt::Arguments<'_>) {
-    print_to(args, stderr, "stderr");
+    print_to(args, stderr, OutputStream::Stderr);
 }
 
 #[cfg(test)]
@@ -25,11 +25,11 @@
 use crate::thread;
 
 #[cfg(not(test))]
-use crate::io::set_output_capture;
+use crate::io::{set_output_capture, set_split_output_capture};
 // make sure to use the stderr output configured
 // by libtest in the real copy of std
 #[cfg(test)]
-use realstd::io::set_output_capture;
+use realstd::io::{set_output_capture, set_split_output_capture};
 
 // Binary interface to the panic runtime that the standard library depends on.
 //
@@ -285,9 +285,20 @@ fn default_hook(info: &PanicInfo<'_>) {
         }
     };
 
-    if let Some(local) = set_output_capture(None) {
-        write(&mut *local.lock().unwrap_or_else(|e| e.into_inner()));
-        set_output_capture(Some(local));
+    let capture = set_output_capture(None);
+    let split_capture = set_split_output_capture(None);
+    if capture.is_some() || split_capture.is_some() {
+        // The message goes both to the combined output and to the captured stderr.
+        let mut buf = Vec::new();
+        write(&mut buf);
+        if let Some(local) = &capture {
+            local.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&buf);
+        }
+        if let Some((_, local_stderr)) = &split_capture {
+            local_stderr.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&buf);
+        }
+        set_output_capture(capture);
+        set_split_output_capture(split_capture);
     } else if let Some(mut out) = panic_output() {
         write(&mut out);
     }
@@ -486,6 +486,8 @@ unsafe fn spawn_unchecked_<'a, 'scope, F, T>(
 
         let output_capture = crate::io::set_output_capture(None);
         crate::io::set_output_capture(output_capture.clone());
+        let split_output_capture = crate::io::set_split_output_capture(None);
+        crate::io::set_split_output_capture(split_output_capture.clone());
 
         // Pass `f` in `MaybeUninit` because actually that closure might *run longer than the lifetime of `F`*.
         // See <https://github.com/rust-lang/rust/issues/101983> for more details.
@@ -518,6 +520,7 @@ fn drop(&mut self) {
             }
 
             crate::io::set_output_capture(output_capture);
+            crate::io::set_split_output_capture(split_output_capture);
 
             // SAFETY: we constructed `f` initialized.
             let f = f.into_inner();
@@ -0,0 +1,143 @@
+//! Saving benchmark results under a name, and comparing later runs against them.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::bench::{BaselineComparison, BenchSamples};
+use super::helpers::data_dir::binary_data_path;
+use super::stats::Summary;
+
+/// Benchmark results of a test binary saved by `--save-baseline`.
+pub struct Baseline {
+    path: PathBuf,
+    benches: BTreeMap<String, BenchSamples>,
+}
+
+impl Baseline {
+    /// Loads the baseline with the given name, which must have been saved before.
+    pub fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read baseline `{name}` from {}: {e}", path.display()),
+            )
+        })?;
+        let benches = parse_baseline(&contents).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("baseline `{name}` in {} is malformed", path.display()),
+            )
+        })?;
+        Ok(Baseline { path, benches })
+    }
+
+    /// Loads the baseline with the given name in order to update it, starting from an empty one
+    /// if it doesn't exist yet. Benchmarks that are not run again keep their saved results.
+    pub fn load_or_default(name: &str) -> io::Result<Baseline> {
+        match Baseline::load(name) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(Baseline { path: baseline_path(name)?, benches: BTreeMap::new() })
+            }
+            baseline => baseline,
+        }
+    }
+
+    pub fn compare(&self, bench_name: &str, samples: &BenchSamples) -> Option<BaselineComparison> {
+        let baseline = self.benches.get(bench_name)?;
+        Some(BaselineComparison::new(baseline, samples))
+    }
+
+    pub fn insert(&mut self, bench_name: &str, samples: &BenchSamples) {
+        let samples = BenchSamples { baseline: None, ..samples.clone() };
+        self.benches.insert(bench_name.to_owned(), samples);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, format_baseline(&self.benches))
+    }
+}
+
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    binary_data_path(&Path::new("libtest-baselines").join(name))
+}
+
+// Every benchmark is stored as three lines:
+//
+//     bench <name>
+//     summary <fields of `stats::Summary`, in declaration order>
+//     samples <nanoseconds per iteration of each sample>
+fn format_baseline(benches: &BTreeMap<String, BenchSamples>) -> String {
+    let mut out = String::new();
+    for (name, samples) in benches {
+        let Summary {
+            sum,
+            min,
+            max,
+            mean,
+            median,
+            var,
+            std_dev,
+            std_dev_pct,
+            median_abs_dev,
+            median_abs_dev_pct,
+            quartiles: (q1, q2, q3),
+            iqr,
+        } = samples.ns_iter_summ;
+        writeln!(out, "bench {name}").unwrap();
+        writeln!(
+            out,
+            "summary {sum} {min} {max} {mean} {median} {var} {std_dev} {std_dev_pct} \
+             {median_abs_dev} {median_abs_dev_pct} {q1} {q2} {q3} {iqr}"
+        )
+        .unwrap();
+        out.push_str("samples");
+        for sample in &samples.ns_iter_samples {
+            write!(out, " {sample}").unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_baseline(contents: &str) -> Option<BTreeMap<String, BenchSamples>> {
+    fn parse_numbers(line: &str, key: &str) -> Option<Vec<f64>> {
+        line.strip_prefix(key)?.split_whitespace().map(|n| n.parse().ok()).collect()
+    }
+
+    let mut benches = BTreeMap::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let name = line.strip_prefix("bench ")?;
+        let summary = parse_numbers(lines.next()?, "summary")?;
+        let &[sum, min, max, mean, median, var, std_dev, std_dev_pct, median_abs_dev, median_abs_dev_pct, q1, q2, q3, iqr] =
+            &summary[..]
+        else {
+            return None;
+        };
+        let ns_iter_summ = Summary {
+            sum,
+            min,
+            max,
+            mean,
+            median,
+            var,
+            std_dev,
+            std_dev_pct,
+            median_abs_dev,
+            median_abs_dev_pct,
+            quartiles: (q1, q2, q3),
+            iqr,
+        };
+        let ns_iter_samples = parse_numbers(lines.next()?, "samples")?;
+        let samples = BenchSamples { ns_iter_summ, mb_s: 0, ns_iter_samples, baseline: None };
+        benches.insert(name.to_owned(), samples);
+    }
+    Some(benches)
+}
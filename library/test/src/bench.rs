@@ -1,6 +1,6 @@
 //! Benchmarking module.
 use super::{
-    event::CompletedTest,
+    event::{CompletedTest, SplitOutput},
     options::BenchMode,
     test_result::TestResult,
     types::{TestDesc, TestId},
@@ -32,6 +32,7 @@ pub fn black_box<T>(dummy: T) -> T {
 pub struct Bencher {
     mode: BenchMode,
     summary: Option<stats::Summary>,
+    samples: Vec<f64>,
     pub bytes: u64,
 }
 
@@ -46,7 +47,9 @@ pub fn iter<T, F>(&mut self, mut inner: F)
             return;
         }
 
-        self.summary = Some(iter(&mut inner));
+        let (summary, samples) = iter_with_samples(&mut inner);
+        self.summary = Some(summary);
+        self.samples = samples;
     }
 
     pub fn bench<F>(&mut self, mut f: F) -> Result<Option<stats::Summary>, String>
@@ -62,6 +65,71 @@ pub fn bench<F>(&mut self, mut f: F) -> Result<Option<stats::Summary>, String>
 pub struct BenchSamples {
     pub ns_iter_summ: stats::Summary,
     pub mb_s: usize,
+    /// Nanoseconds per iteration of each sample that `ns_iter_summ` summarizes.
+    pub ns_iter_samples: Vec<f64>,
+    /// Comparison with the baseline passed to `--baseline`, if it has this benchmark.
+    pub baseline: Option<BaselineComparison>,
+}
+
+/// Change in the time per iteration of a benchmark since a saved baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaselineComparison {
+    /// Median nanoseconds per iteration in the baseline.
+    pub baseline_median: f64,
+    /// Change of the median since the baseline, in percent.
+    pub change_pct: f64,
+    /// Probability of seeing a difference this large between the samples if the time per
+    /// iteration did not change, as computed by a Mann-Whitney U test.
+    pub p_value: f64,
+}
+
+/// Verdict on a benchmark compared with its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchChange {
+    Improved,
+    Regressed,
+    Unchanged,
+}
+
+impl BaselineComparison {
+    /// Differences with a p-value at or above this threshold are attributed to noise.
+    const SIGNIFICANCE_LEVEL: f64 = 0.05;
+    /// Changes of the median below this percentage are not reported, however significant.
+    const NOISE_THRESHOLD_PCT: f64 = 1.0;
+
+    pub fn new(baseline: &BenchSamples, current: &BenchSamples) -> BaselineComparison {
+        let baseline_median = baseline.ns_iter_summ.median;
+        let change_pct = if baseline_median == 0.0 {
+            0.0
+        } else {
+            (current.ns_iter_summ.median - baseline_median) / baseline_median * 100.0
+        };
+        let p_value =
+            stats::mann_whitney_u_test(&baseline.ns_iter_samples, &current.ns_iter_samples);
+        BaselineComparison { baseline_median, change_pct, p_value }
+    }
+
+    pub fn change(&self) -> BenchChange {
+        if self.p_value >= Self::SIGNIFICANCE_LEVEL
+            || self.change_pct.abs() < Self::NOISE_THRESHOLD_PCT
+        {
+            BenchChange::Unchanged
+        } else if self.change_pct < 0.0 {
+            BenchChange::Improved
+        } else {
+            BenchChange::Regressed
+        }
+    }
+}
+
+impl BenchChange {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BenchChange::Improved => "improved",
+            BenchChange::Regressed => "regressed",
+            BenchChange::Unchanged => "no change",
+        }
+    }
 }
 
 pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
@@ -84,6 +152,15 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
     output
 }
 
+pub fn fmt_baseline_comparison(comparison: &BaselineComparison) -> String {
+    format!(
+        "{:+.2}% since baseline of {} ns/iter, p = {:.3}",
+        comparison.change_pct,
+        fmt_thousands_sep(comparison.baseline_median as usize, ','),
+        comparison.p_value
+    )
+}
+
 // Format a number with thousands separators
 fn fmt_thousands_sep(mut n: usize, sep: char) -> String {
     use std::fmt::Write;
@@ -120,6 +197,13 @@ fn ns_iter_inner<T, F>(inner: &mut F, k: u64) -> u64
 }
 
 pub fn iter<T, F>(inner: &mut F) -> stats::Summary
+where
+    F: FnMut() -> T,
+{
+    iter_with_samples(inner).0
+}
+
+fn iter_with_samples<T, F>(inner: &mut F) -> (stats::Summary, Vec<f64>)
 where
     F: FnMut() -> T,
 {
@@ -166,13 +250,13 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
             && summ.median_abs_dev_pct < 1.0
             && summ.median - summ5.median < summ5.median_abs_dev
         {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         total_run += loop_run;
         // Longest we ever run for is 3s.
         if total_run > Duration::from_secs(3) {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         // If we overflow here just return the results so far. We check a
@@ -182,7 +266,7 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
         n = match n.checked_mul(10) {
             Some(_) => n * 2,
             None => {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
         };
     }
@@ -197,17 +281,21 @@ pub fn benchmark<F>(
 ) where
     F: FnMut(&mut Bencher) -> Result<(), String>,
 {
-    let mut bs = Bencher { mode: BenchMode::Auto, summary: None, bytes: 0 };
+    let mut bs = Bencher { mode: BenchMode::Auto, summary: None, samples: Vec::new(), bytes: 0 };
 
     let data = Arc::new(Mutex::new(Vec::new()));
+    let stdout_data = Arc::new(Mutex::new(Vec::new()));
+    let stderr_data = Arc::new(Mutex::new(Vec::new()));
 
     if !nocapture {
         io::set_output_capture(Some(data.clone()));
+        io::set_split_output_capture(Some((stdout_data.clone(), stderr_data.clone())));
     }
 
     let result = catch_unwind(AssertUnwindSafe(|| bs.bench(f)));
 
     io::set_output_capture(None);
+    io::set_split_output_capture(None);
 
     let test_result = match result {
         //bs.bench(f) {
@@ -215,14 +303,24 @@ pub fn benchmark<F>(
             let ns_iter = cmp::max(ns_iter_summ.median as u64, 1);
             let mb_s = bs.bytes * 1000 / ns_iter;
 
-            let bs = BenchSamples { ns_iter_summ, mb_s: mb_s as usize };
+            let bs = BenchSamples {
+                ns_iter_summ,
+                mb_s: mb_s as usize,
+                ns_iter_samples: bs.samples,
+                baseline: None,
+            };
             TestResult::TrBench(bs)
         }
         Ok(Ok(None)) => {
             // iter not called, so no data.
             // FIXME: error in this case?
             let samples: &mut [f64] = &mut [0.0_f64; 1];
-            let bs = BenchSamples { ns_iter_summ: stats::Summary::new(samples), mb_s: 0 };
+            let bs = BenchSamples {
+                ns_iter_summ: stats::Summary::new(samples),
+                mb_s: 0,
+                ns_iter_samples: samples.to_vec(),
+                baseline: None,
+            };
             TestResult::TrBench(bs)
         }
         Err(_) => TestResult::TrFailed,
@@ -230,7 +328,11 @@ pub fn benchmark<F>(
     };
 
     let stdout = data.lock().unwrap().to_vec();
-    let message = CompletedTest::new(id, desc, test_result, None, stdout);
+    let output = SplitOutput {
+        stdout: stdout_data.lock().unwrap().to_vec(),
+        stderr: stderr_data.lock().unwrap().to_vec(),
+    };
+    let message = CompletedTest::new(id, desc, test_result, None, stdout).with_output(output);
     monitor_ch.send(message).unwrap();
 }
 
@@ -238,6 +340,6 @@ pub fn run_once<F>(f: F) -> Result<(), String>
 where
     F: FnMut(&mut Bencher) -> Result<(), String>,
 {
-    let mut bs = Bencher { mode: BenchMode::Single, summary: None, bytes: 0 };
+    let mut bs = Bencher { mode: BenchMode::Single, summary: None, samples: Vec::new(), bytes: 0 };
     bs.bench(f).map(|_| ())
 }
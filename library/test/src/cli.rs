@@ -2,9 +2,10 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
-use super::time::TestTimeOptions;
+use super::options::{ColorConfig, Options, OutputFormat, RunFailed, RunIgnored, TestShard};
+use super::time::{self, TestTimeOptions};
 use std::io::{self, IsTerminal};
 
 #[derive(Debug)]
@@ -15,6 +16,7 @@ pub struct TestOpts {
     pub force_run_in_process: bool,
     pub exclude_should_panic: bool,
     pub run_ignored: RunIgnored,
+    pub run_failed: RunFailed,
     pub run_tests: bool,
     pub bench_benchmarks: bool,
     pub logfile: Option<PathBuf>,
@@ -23,9 +25,19 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub shuffle: bool,
     pub shuffle_seed: Option<u64>,
+    /// Subset of the tests to run when the suite is split across several runs.
+    pub shard: Option<TestShard>,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
     pub time_options: Option<TestTimeOptions>,
+    /// Time after which a running test is stopped and reported as failed.
+    pub test_timeout: Option<Duration>,
+    /// Number of times a failed test is run again before it is reported as failed.
+    pub retries: usize,
+    /// Name under which to save the results of the benchmarks.
+    pub save_baseline: Option<String>,
+    /// Name of saved benchmark results to compare the benchmarks with.
+    pub baseline: Option<String>,
     /// Stop at first failing test.
     /// May run a few more tests due to threading, but will
     /// abort as soon as possible.
@@ -54,6 +66,8 @@ fn optgroups() -> getopts::Options {
         .optflag("", "ignored", "Run only ignored tests")
         .optflag("", "force-run-in-process", "Forces tests to run in-process when panic=abort")
         .optflag("", "exclude-should-panic", "Excludes tests marked as should_panic")
+        .optflag("", "failed-first", "Run the tests that failed in the previous run first")
+        .optflag("", "only-failed", "Run only the tests that failed in the previous run")
         .optflag("", "test", "Run tests and not benchmarks")
         .optflag("", "bench", "Run benchmarks instead of tests")
         .optflag("", "list", "List all tests and benchmarks")
@@ -102,8 +116,9 @@ fn optgroups() -> getopts::Options {
             pretty = Print verbose output;
             terse  = Display one character per test;
             json   = Output a json document;
-            junit  = Output a JUnit document",
-            "pretty|terse|json|junit",
+            junit  = Output a JUnit document;
+            tap    = Output a TAP version 14 document",
+            "pretty|terse|json|junit|tap",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -142,12 +157,59 @@ fn optgroups() -> getopts::Options {
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Stop tests that are still running after DURATION and report them as failed.
+
+            DURATION is a number followed by `ms`, `s` or `m`; a number without a unit
+            is interpreted as seconds. Tests run in subprocesses (`-Z panic-abort-tests`)
+            are killed; tests run in threads are reported as failed and abandoned.
+            A `#[test_timeout]` attribute on a test takes precedence over this option.",
+            "DURATION",
+        )
+        .optopt(
+            "",
+            "retries",
+            "Run failed tests again up to N times. Tests that pass on a later
+            attempt are reported as flaky rather than failed. Only tests that
+            are plain functions, such as `#[test]` functions, can be retried;
+            other tests, including documentation tests, are run once and a note
+            is added to their output if they fail.",
+            "N",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the results of the benchmarks under NAME, to compare later runs
+            against them with --baseline",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the results of the benchmarks with those saved under NAME by
+            --save-baseline, reporting significant regressions and improvements",
+            "NAME",
+        )
         .optflag("", "shuffle", "Run tests in random order")
         .optopt(
             "",
             "shuffle-seed",
             "Run tests in random order; seed the random number generator with SEED",
             "SEED",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Run only the tests of shard I, counting from 0; requires --shard-count",
+            "I",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into N disjoint shards; requires --shard-index",
+            "N",
         );
     opts
 }
@@ -171,6 +233,12 @@ fn usage(binary: &str, options: &getopts::Options) {
 tests in the same order again. Note that --shuffle and --shuffle-seed do not
 affect whether the tests are run in parallel.
 
+To split the tests across several runs, for example on different machines,
+pass the same --shard-count (or set RUST_TEST_SHARD_COUNT) to every run and a
+different --shard-index (or RUST_TEST_SHARD_INDEX) from 0 to N-1 to each. Tests
+are assigned to shards by a hash of their name, so each shard runs a disjoint
+subset of the tests and together the shards run all of them.
+
 All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or setting RUST_TEST_NOCAPTURE
 environment variable to a value other than "0". Logging is not captured by default.
@@ -258,9 +326,15 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     // Unstable flags
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
+    let run_failed = get_run_failed(&matches, allow_unstable)?;
     let time_options = get_time_options(&matches, allow_unstable)?;
     let shuffle = get_shuffle(&matches, allow_unstable)?;
     let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let shard = get_shard(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
+    let retries = get_retries(&matches, allow_unstable)?;
+    let save_baseline = get_baseline_name(&matches, allow_unstable, "save-baseline")?;
+    let baseline = get_baseline_name(&matches, allow_unstable, "baseline")?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -288,6 +362,7 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         force_run_in_process,
         exclude_should_panic,
         run_ignored,
+        run_failed,
         run_tests,
         bench_benchmarks,
         logfile,
@@ -296,9 +371,14 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         format,
         shuffle,
         shuffle_seed,
+        shard,
         test_threads,
         skip,
         time_options,
+        test_timeout,
+        retries,
+        save_baseline,
+        baseline,
         options,
         fail_fast: false,
     };
@@ -375,6 +455,102 @@ fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPart
     Ok(shuffle_seed)
 }
 
+fn get_shard_option(
+    opt: Option<String>,
+    allow_unstable: bool,
+    option_name: &str,
+    env_name: &str,
+) -> OptPartRes<Option<usize>> {
+    let (value, source) = match opt {
+        Some(value) => (value, format!("argument for --{option_name}")),
+        None if allow_unstable => match env::var(env_name) {
+            Ok(value) => (value, env_name.to_string()),
+            Err(_) => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    match value.parse::<usize>() {
+        Ok(n) => Ok(Some(n)),
+        Err(e) => Err(format!("{source} must be a number (error: {e})")),
+    }
+}
+
+fn get_shard(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<TestShard>> {
+    let index = unstable_optopt!(matches, allow_unstable, "shard-index");
+    let index = get_shard_option(index, allow_unstable, "shard-index", "RUST_TEST_SHARD_INDEX")?;
+    let count = unstable_optopt!(matches, allow_unstable, "shard-count");
+    let count = get_shard_option(count, allow_unstable, "shard-count", "RUST_TEST_SHARD_COUNT")?;
+
+    match (index, count) {
+        (None, None) => Ok(None),
+        (Some(_), None) => Err("--shard-index requires --shard-count".to_string()),
+        (None, Some(_)) => Err("--shard-count requires --shard-index".to_string()),
+        (Some(_), Some(0)) => Err("argument for --shard-count must not be 0".to_string()),
+        (Some(index), Some(count)) if index >= count => Err(format!(
+            "argument for --shard-index must be less than --shard-count ({index} >= {count})"
+        )),
+        (Some(index), Some(count)) => Ok(Some(TestShard { index, count })),
+    }
+}
+
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(s) => match time::parse_duration(&s) {
+            Some(timeout) if !timeout.is_zero() => Some(timeout),
+            _ => {
+                return Err(format!(
+                    "argument for --test-timeout must be a non-zero duration such as \
+                     `500ms`, `30s` or `2m` (was {s})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
+fn get_retries(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<usize> {
+    let retries = match unstable_optopt!(matches, allow_unstable, "retries") {
+        Some(n_str) => match n_str.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(format!(
+                    "argument for --retries must be a non-negative number (error: {e})"
+                ));
+            }
+        },
+        None => 0,
+    };
+
+    Ok(retries)
+}
+
+fn get_baseline_name(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+    option_name: &str,
+) -> OptPartRes<Option<String>> {
+    let name = matches.opt_str(option_name);
+    if !allow_unstable && name.is_some() {
+        return Err(format!(
+            "The \"{option_name}\" option is only accepted on the nightly compiler with -Z unstable-options"
+        ));
+    }
+
+    // The name is used as a directory name.
+    match name {
+        Some(name) if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') => {
+            Err(format!("argument for --{option_name} must be a name, not a path (was `{name}`)"))
+        }
+        name => Ok(name),
+    }
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -414,9 +590,15 @@ fn get_format(
             }
             OutputFormat::Junit
         }
+        Some("tap") => {
+            if !allow_unstable {
+                return Err("The \"tap\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Tap
+        }
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, json or junit (was \
+                "argument for --format must be pretty, terse, json, junit or tap (was \
                  {v})"
             ));
         }
@@ -467,6 +649,23 @@ fn get_run_ignored(matches: &getopts::Matches, include_ignored: bool) -> OptPart
     Ok(run_ignored)
 }
 
+fn get_run_failed(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<RunFailed> {
+    let failed_first = unstable_optflag!(matches, allow_unstable, "failed-first");
+    let only_failed = unstable_optflag!(matches, allow_unstable, "only-failed");
+    let run_failed = match (failed_first, only_failed) {
+        (true, true) => {
+            return Err(
+                "the options --failed-first and --only-failed are mutually exclusive".into()
+            );
+        }
+        (true, false) => RunFailed::First,
+        (false, true) => RunFailed::Only,
+        (false, false) => RunFailed::No,
+    };
+
+    Ok(run_failed)
+}
+
 fn get_allow_unstable(matches: &getopts::Matches) -> OptPartRes<bool> {
     let mut allow_unstable = false;
 
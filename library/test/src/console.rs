@@ -6,13 +6,18 @@
 use std::time::Instant;
 
 use super::{
+    baseline::Baseline,
     bench::fmt_bench_samples,
     cli::TestOpts,
     event::{CompletedTest, TestEvent},
     filter_tests,
-    formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter},
+    formatters::{
+        JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TapFormatter,
+        TerseFormatter,
+    },
     helpers::{concurrency::get_concurrency, metrics::MetricMap},
-    options::{Options, OutputFormat},
+    last_run::LastRun,
+    options::{Options, OutputFormat, RunFailed},
     run_tests, term,
     test_result::TestResult,
     time::{TestExecTime, TestSuiteExecTime},
@@ -95,6 +100,10 @@ pub struct ConsoleTestState {
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub ignores: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    /// Failed attempts of tests that were run again.
+    pub retries: Vec<CompletedTest>,
+    /// Tests that passed after being retried, with the output of their failed attempts.
+    pub flaky: Vec<(TestDesc, Vec<u8>)>,
     pub options: Options,
 }
 
@@ -119,6 +128,8 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             not_failures: Vec::new(),
             ignores: Vec::new(),
             time_failures: Vec::new(),
+            retries: Vec::new(),
+            flaky: Vec::new(),
             options: opts.options,
         })
     }
@@ -171,6 +182,16 @@ pub fn write_log_result(
         self.write_log(|| "\n")
     }
 
+    /// Returns the failed attempts of a test that was retried, in the order they ran.
+    pub fn failed_attempts<'a>(
+        &'a self,
+        test: &'a TestDesc,
+    ) -> impl Iterator<Item = &'a CompletedTest> + 'a {
+        self.retries
+            .iter()
+            .filter(move |attempt| attempt.desc.name.as_slice() == test.name.as_slice())
+    }
+
     fn current_test_count(&self) -> usize {
         self.passed + self.failed + self.ignored + self.measured
     }
@@ -184,7 +205,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
     };
 
     let mut out: Box<dyn OutputFormatter> = match opts.format {
-        OutputFormat::Pretty | OutputFormat::Junit => {
+        OutputFormat::Pretty | OutputFormat::Junit | OutputFormat::Tap => {
             Box::new(PrettyFormatter::new(output, false, 0, false, None))
         }
         OutputFormat::Terse => Box::new(TerseFormatter::new(output, false, 0, false)),
@@ -192,6 +213,9 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
     };
     let mut st = ConsoleTestDiscoveryState::new(opts)?;
 
+    // Fixtures are run around the tests, they are not tests of their own.
+    let tests = tests.into_iter().filter(|test| test.desc.fixture().is_none()).collect();
+
     out.write_discovery_start()?;
     for test in filter_tests(opts, tests).into_iter() {
         use crate::TestFn::*;
@@ -225,6 +249,11 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
     match completed_test.result {
         TestResult::TrOk => {
             st.passed += 1;
+            if st.failed_attempts(&test).next().is_some() {
+                let attempts_stdout =
+                    st.failed_attempts(&test).flat_map(|attempt| attempt.stdout.clone()).collect();
+                st.flaky.push((test.clone(), attempts_stdout));
+            }
             st.not_failures.push((test, stdout));
         }
         TestResult::TrIgnored => {
@@ -278,11 +307,23 @@ fn on_test_event(
             let result = &completed_test.result;
             let exec_time = &completed_test.exec_time;
             let stdout = &completed_test.stdout;
+            let output = &completed_test.output;
 
             st.write_log_result(test, result, exec_time.as_ref())?;
-            out.write_result(test, result, exec_time.as_ref(), stdout, st)?;
+            out.write_result(test, result, exec_time.as_ref(), stdout, output, st)?;
             handle_test_result(st, completed_test);
         }
+        TestEvent::TeRetry(completed_test) => {
+            let test = &completed_test.desc;
+            let result = &completed_test.result;
+            let exec_time = &completed_test.exec_time;
+            let stdout = &completed_test.stdout;
+            let output = &completed_test.output;
+
+            st.write_log_result(test, result, exec_time.as_ref())?;
+            out.write_retry(test, result, exec_time.as_ref(), stdout, output, st)?;
+            st.retries.push(completed_test);
+        }
     }
 
     Ok(())
@@ -290,12 +331,19 @@ fn on_test_event(
 
 /// A simple console test runner.
 /// Runs provided tests reporting process and results to the stdout.
-pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<bool> {
+pub fn run_tests_console(opts: &TestOpts, mut tests: Vec<TestDescAndFn>) -> io::Result<bool> {
     let output = match term::stdout() {
         None => OutputLocation::Raw(io::stdout()),
         Some(t) => OutputLocation::Pretty(t),
     };
 
+    let mut last_run = match opts.run_failed {
+        RunFailed::No => None,
+        RunFailed::First | RunFailed::Only => Some(LastRun::load()?),
+    };
+    let skipped =
+        last_run.as_ref().map_or(0, |last_run| last_run.apply(opts.run_failed, &mut tests));
+
     let max_name_len = tests
         .iter()
         .max_by_key(|t| len_if_padded(t))
@@ -317,6 +365,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
         OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
 
@@ -325,9 +374,38 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
     // - We disable it for miri because it's not available when isolation is enabled.
     let is_instant_supported = !cfg!(target_family = "wasm") && !cfg!(miri);
 
+    let baseline = opts.baseline.as_deref().map(Baseline::load).transpose()?;
+    let mut saved_baseline =
+        opts.save_baseline.as_deref().map(Baseline::load_or_default).transpose()?;
+
     let start_time = is_instant_supported.then(Instant::now);
-    run_tests(opts, tests, |x| on_test_event(&x, &mut st, &mut *out))?;
+    run_tests(opts, tests, |mut x| {
+        if let TestEvent::TeResult(CompletedTest {
+            ref desc,
+            result: TestResult::TrBench(ref mut bs),
+            ..
+        }) = x
+        {
+            if let Some(ref baseline) = baseline {
+                bs.baseline = baseline.compare(desc.name.as_slice(), bs);
+            }
+            if let Some(ref mut saved_baseline) = saved_baseline {
+                saved_baseline.insert(desc.name.as_slice(), bs);
+            }
+        }
+        on_test_event(&x, &mut st, &mut *out)
+    })?;
     st.exec_time = start_time.map(|t| TestSuiteExecTime(t.elapsed()));
+    st.filtered_out += skipped;
+
+    if let Some(ref mut last_run) = last_run {
+        last_run.update(&st);
+        last_run.save()?;
+    }
+
+    if let Some(saved_baseline) = saved_baseline {
+        saved_baseline.save()?;
+    }
 
     assert!(opts.fail_fast || st.current_test_count() == st.total);
 
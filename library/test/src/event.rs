@@ -5,13 +5,22 @@
 use super::time::TestExecTime;
 use super::types::{TestDesc, TestId};
 
+/// Output of a test, captured separately for stdout and stderr.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
 #[derive(Debug, Clone)]
 pub struct CompletedTest {
     pub id: TestId,
     pub desc: TestDesc,
     pub result: TestResult,
     pub exec_time: Option<TestExecTime>,
+    /// Everything the test wrote to stdout and stderr, in order.
     pub stdout: Vec<u8>,
+    pub output: SplitOutput,
 }
 
 impl CompletedTest {
@@ -22,7 +31,11 @@ pub fn new(
         exec_time: Option<TestExecTime>,
         stdout: Vec<u8>,
     ) -> Self {
-        Self { id, desc, result, exec_time, stdout }
+        Self { id, desc, result, exec_time, stdout, output: SplitOutput::default() }
+    }
+
+    pub fn with_output(self, output: SplitOutput) -> Self {
+        Self { output, ..self }
     }
 }
 
@@ -31,6 +44,8 @@ pub enum TestEvent {
     TeFiltered(usize, Option<u64>),
     TeWait(TestDesc),
     TeResult(CompletedTest),
+    /// A failed attempt of a test that is going to be run again.
+    TeRetry(CompletedTest),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
 }
@@ -0,0 +1,225 @@
+//! Setup and teardown fixtures, which run around the tests of the module that defines them.
+
+use std::io;
+use std::sync::mpsc::channel;
+
+use super::cli::TestOpts;
+use super::event::{CompletedTest, SplitOutput, TestEvent};
+use super::options::RunStrategy;
+use super::test_result::{TrBench, TrFailed, TrFailedMsg, TrIgnored, TrOk, TrTimedFail};
+use super::types::{TestDesc, TestDescAndFn, TestFixture, TestId};
+use super::{run_test, RunningTest};
+
+/// Fixtures of the modules that have tests to run, and the progress of those tests.
+pub struct Fixtures {
+    // Sorted so that modules come before their submodules.
+    scopes: Vec<Scope>,
+}
+
+struct Scope {
+    // Path of the module followed by `::`, or empty for the crate root.
+    prefix: String,
+    setups: Vec<(TestId, TestDescAndFn)>,
+    teardowns: Vec<(TestId, TestDescAndFn)>,
+    // Number of tests of the module and its submodules that haven't completed yet.
+    remaining: usize,
+    // Name and output of the setup fixture of the module that failed, if any.
+    failure: Option<(String, Vec<u8>, SplitOutput)>,
+}
+
+impl Scope {
+    fn contains(&self, desc: &TestDesc) -> bool {
+        desc.name.as_slice().starts_with(&self.prefix)
+    }
+}
+
+impl Fixtures {
+    /// Groups the fixtures by module, keeping only the modules that contain some of the tests
+    /// to run. Fixtures are given ids starting from `next_id`.
+    pub fn new(fixtures: Vec<TestDescAndFn>, tests: &[&TestDesc], next_id: &mut usize) -> Fixtures {
+        let mut scopes: Vec<Scope> = Vec::new();
+        for fixture in fixtures {
+            let scope = fixture.desc.fixture_scope();
+            let prefix = if scope.is_empty() { String::new() } else { format!("{scope}::") };
+            let index = match scopes.iter().position(|scope| scope.prefix == prefix) {
+                Some(index) => index,
+                None => {
+                    let scope = Scope {
+                        prefix,
+                        setups: Vec::new(),
+                        teardowns: Vec::new(),
+                        remaining: 0,
+                        failure: None,
+                    };
+                    scopes.push(scope);
+                    scopes.len() - 1
+                }
+            };
+            let scope = &mut scopes[index];
+            if !tests.iter().any(|test| scope.contains(test)) {
+                continue;
+            }
+            let id = TestId(*next_id);
+            *next_id += 1;
+            match fixture.desc.fixture() {
+                Some(TestFixture::Setup) => scope.setups.push((id, fixture)),
+                Some(TestFixture::Teardown) => scope.teardowns.push((id, fixture)),
+                None => panic!("`{}` is not a fixture", fixture.desc.name),
+            }
+        }
+
+        scopes.retain(|scope| !scope.setups.is_empty() || !scope.teardowns.is_empty());
+        for scope in &mut scopes {
+            scope.remaining = tests.iter().filter(|test| scope.contains(test)).count();
+        }
+        scopes.sort_by_key(|scope| scope.prefix.matches("::").count());
+        Fixtures { scopes }
+    }
+
+    /// Number of fixtures that are going to be reported.
+    pub fn len(&self) -> usize {
+        self.scopes.iter().map(|scope| scope.setups.len() + scope.teardowns.len()).sum()
+    }
+
+    /// Returns the next setup fixture that has to run before the given test, starting with
+    /// the outermost modules.
+    fn next_setup(&mut self, desc: &TestDesc) -> Option<(TestId, TestDescAndFn)> {
+        let scope = self
+            .scopes
+            .iter_mut()
+            .find(|scope| scope.contains(desc) && !scope.setups.is_empty())?;
+        Some(scope.setups.remove(0))
+    }
+
+    /// Records the result of a setup fixture. Once it failed, the remaining tests and fixtures
+    /// of its module are not run.
+    fn record_setup(&mut self, completed_test: &CompletedTest) {
+        if !matches!(completed_test.result, TrFailed | TrFailedMsg(_) | TrTimedFail) {
+            return;
+        }
+        let scope = self.scopes.iter_mut().rev().find(|scope| scope.contains(&completed_test.desc));
+        if let Some(scope) = scope {
+            if scope.failure.is_none() {
+                let name = completed_test.desc.name.as_slice().to_owned();
+                let stdout = completed_test.stdout.clone();
+                scope.failure = Some((name, stdout, completed_test.output.clone()));
+            }
+        }
+    }
+
+    /// Returns the result to report for a test or setup fixture instead of running it, if a
+    /// setup fixture it depends on failed.
+    fn setup_failure(&self, id: TestId, desc: &TestDesc) -> Option<CompletedTest> {
+        if desc.fixture() == Some(TestFixture::Teardown) {
+            return None;
+        }
+        let (name, stdout, output) = self.failed_scope(desc)?.failure.as_ref()?;
+        let result = TrFailedMsg(format!("setup fixture `{name}` failed"));
+        let completed_test = CompletedTest::new(id, desc.clone(), result, None, stdout.clone());
+        Some(completed_test.with_output(output.clone()))
+    }
+
+    /// Records that a test completed, returning the teardown fixtures of the modules that have
+    /// no tests left to run, starting with the innermost ones. Teardown fixtures of modules
+    /// whose setup failed are marked as ignored.
+    fn test_done(&mut self, desc: &TestDesc) -> Vec<(TestId, TestDescAndFn)> {
+        let mut teardowns = Vec::new();
+        for scope in self.scopes.iter_mut().rev() {
+            if scope.contains(desc) {
+                scope.remaining -= 1;
+                if scope.remaining == 0 {
+                    teardowns.append(&mut scope.teardowns);
+                }
+            }
+        }
+        for (_, teardown) in &mut teardowns {
+            if self.failed_scope(&teardown.desc).is_some() {
+                teardown.desc.ignore = true;
+                teardown.desc.ignore_message = Some("a setup fixture failed");
+            }
+        }
+        teardowns
+    }
+
+    /// Runs the setup fixtures of the modules of a test that didn't run yet. Returns the result
+    /// to report for the test instead of running it if one of them failed.
+    pub fn set_up<F>(
+        &mut self,
+        opts: &TestOpts,
+        id: TestId,
+        desc: &TestDesc,
+        run_strategy: RunStrategy,
+        notify_about_test_event: &mut F,
+    ) -> io::Result<Option<CompletedTest>>
+    where
+        F: FnMut(TestEvent) -> io::Result<()>,
+    {
+        while let Some((fixture_id, fixture)) = self.next_setup(desc) {
+            // A failed setup fails the test, which stops the run if needed.
+            self.run(opts, fixture_id, fixture, run_strategy, notify_about_test_event)?;
+        }
+        Ok(self.setup_failure(id, desc))
+    }
+
+    /// Runs the teardown fixtures of the modules that have no tests left to run once a test
+    /// completed. Returns whether the run has to stop because one of them failed.
+    pub fn tear_down<F>(
+        &mut self,
+        opts: &TestOpts,
+        desc: &TestDesc,
+        run_strategy: RunStrategy,
+        notify_about_test_event: &mut F,
+    ) -> io::Result<bool>
+    where
+        F: FnMut(TestEvent) -> io::Result<()>,
+    {
+        let mut fail_fast = false;
+        for (id, fixture) in self.test_done(desc) {
+            fail_fast |= self.run(opts, id, fixture, run_strategy, notify_about_test_event)?;
+        }
+        Ok(fail_fast)
+    }
+
+    // Runs a fixture on this thread and reports its result, unless a setup fixture it depends
+    // on failed. Returns whether the run has to stop because the fixture failed.
+    fn run<F>(
+        &mut self,
+        opts: &TestOpts,
+        id: TestId,
+        fixture: TestDescAndFn,
+        run_strategy: RunStrategy,
+        notify_about_test_event: &mut F,
+    ) -> io::Result<bool>
+    where
+        F: FnMut(TestEvent) -> io::Result<()>,
+    {
+        let event = TestEvent::TeWait(fixture.desc.clone());
+        notify_about_test_event(event)?;
+        let completed_test = match self.setup_failure(id, &fixture.desc) {
+            Some(completed_test) => completed_test,
+            None => {
+                let (tx, rx) = channel();
+                let join_handle = run_test(opts, false, id, fixture, run_strategy, tx);
+                let mut completed_test = rx.recv().unwrap();
+                RunningTest { join_handle }.join(&mut completed_test);
+                completed_test
+            }
+        };
+        if completed_test.desc.fixture() == Some(TestFixture::Setup) {
+            self.record_setup(&completed_test);
+        }
+
+        let fail_fast = match completed_test.result {
+            TrIgnored | TrOk | TrBench(_) => false,
+            TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+        };
+
+        let event = TestEvent::TeResult(completed_test);
+        notify_about_test_event(event)?;
+        Ok(fail_fast)
+    }
+
+    fn failed_scope(&self, desc: &TestDesc) -> Option<&Scope> {
+        self.scopes.iter().find(|scope| scope.contains(desc) && scope.failure.is_some())
+    }
+}
@@ -1,11 +1,13 @@
-use std::{borrow::Cow, io, io::prelude::Write};
+use std::{io, io::prelude::Write};
 
 use super::OutputFormatter;
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    event::SplitOutput,
+    options::ShouldPanic,
     test_result::TestResult,
     time,
-    types::TestDesc,
+    types::{TestDesc, TestType},
 };
 
 pub(crate) struct JsonFormatter<T> {
@@ -17,6 +19,11 @@ pub fn new(out: OutputLocation<T>) -> Self {
         Self { out }
     }
 
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
     fn writeln_message(&mut self, s: &str) -> io::Result<()> {
         // self.out will take a lock, but that lock is released when write_all returns. This
         // results in a race condition and json output may not end with a new line. We avoid this
@@ -32,7 +39,7 @@ fn write_event(
         name: &str,
         event: &str,
         exec_time: Option<&time::TestExecTime>,
-        stdout: Option<Cow<'_, str>>,
+        output: Option<&SplitOutput>,
         extra: Option<&str>,
     ) -> io::Result<()> {
         // A doc test's name includes a filename which must be escaped for correct json.
@@ -42,17 +49,21 @@ fn write_event(
         } else {
             String::from("")
         };
-        let stdout_json = if let Some(stdout) = stdout {
-            format!(r#", "stdout": "{}""#, EscapedString(stdout))
-        } else {
-            String::from("")
-        };
+        let mut output_json = String::new();
+        if let Some(SplitOutput { stdout, stderr }) = output {
+            for (field, captured) in [("stdout", stdout), ("stderr", stderr)] {
+                if !captured.is_empty() {
+                    let captured = EscapedString(String::from_utf8_lossy(captured));
+                    output_json.push_str(&format!(r#", "{field}": "{captured}""#));
+                }
+            }
+        }
         let extra_json =
             if let Some(extra) = extra { format!(r#", {extra}"#) } else { String::from("") };
         let newline = "\n";
 
         self.writeln_message(&format!(
-                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
+                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{exec_time_json}{output_json}{extra_json} }}{newline}"#))
     }
 }
 
@@ -71,16 +82,32 @@ fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Res
             start_col,
             end_line,
             end_col,
+            should_panic,
+            compile_fail,
+            no_run,
+            test_type: kind,
             ..
         } = desc;
 
         let name = EscapedString(name.as_slice());
-        let ignore_message = ignore_message.unwrap_or("");
+        let ignore_message = EscapedString(ignore_message.unwrap_or(""));
         let source_path = EscapedString(source_file);
+        let (should_panic, should_panic_message) = match should_panic {
+            ShouldPanic::No => (false, ""),
+            ShouldPanic::Yes => (true, ""),
+            ShouldPanic::YesWithMessage(msg) => (true, *msg),
+        };
+        let should_panic_message = EscapedString(should_panic_message);
+        let kind = match kind {
+            TestType::UnitTest => "unit",
+            TestType::IntegrationTest => "integration",
+            TestType::DocTest => "doc",
+            TestType::Unknown => "unknown",
+        };
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col} }}{newline}"#
+            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col}, "should_panic": {should_panic}, "should_panic_message": "{should_panic_message}", "compile_fail": {compile_fail}, "no_run": {no_run}, "test_type": "{kind}" }}{newline}"#
         ))
     }
 
@@ -119,53 +146,23 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
-        stdout: &[u8],
+        _stdout: &[u8],
+        output: &SplitOutput,
         state: &ConsoleTestState,
     ) -> io::Result<()> {
-        let display_stdout = state.options.display_output || *result != TestResult::TrOk;
-        let stdout = if display_stdout && !stdout.is_empty() {
-            Some(String::from_utf8_lossy(stdout))
-        } else {
-            None
-        };
-        match *result {
-            TestResult::TrOk => {
-                self.write_event("test", desc.name.as_slice(), "ok", exec_time, stdout, None)
+        let display_output = state.options.display_output || *result != TestResult::TrOk;
+        let output = display_output.then_some(output);
+        let retries = state.failed_attempts(desc).count();
+        let (event, extra) = match *result {
+            TestResult::TrOk if retries > 0 => ("flaky", None),
+            TestResult::TrOk => ("ok", None),
+            TestResult::TrFailed | TestResult::TrFailedMsg(_) | TestResult::TrTimedFail => {
+                ("failed", failure_details(result))
             }
-
-            TestResult::TrFailed => {
-                self.write_event("test", desc.name.as_slice(), "failed", exec_time, stdout, None)
-            }
-
-            TestResult::TrTimedFail => self.write_event(
-                "test",
-                desc.name.as_slice(),
-                "failed",
-                exec_time,
-                stdout,
-                Some(r#""reason": "time limit exceeded""#),
-            ),
-
-            TestResult::TrFailedMsg(ref m) => self.write_event(
-                "test",
-                desc.name.as_slice(),
-                "failed",
-                exec_time,
-                stdout,
-                Some(&*format!(r#""message": "{}""#, EscapedString(m))),
-            ),
-
-            TestResult::TrIgnored => self.write_event(
-                "test",
-                desc.name.as_slice(),
+            TestResult::TrIgnored => (
                 "ignored",
-                exec_time,
-                stdout,
-                desc.ignore_message
-                    .map(|msg| format!(r#""message": "{}""#, EscapedString(msg)))
-                    .as_deref(),
+                desc.ignore_message.map(|msg| format!(r#""message": "{}""#, EscapedString(msg))),
             ),
-
             TestResult::TrBench(ref bs) => {
                 let median = bs.ns_iter_summ.median as usize;
                 let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
@@ -175,16 +172,56 @@ fn write_result(
                 } else {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
+                let baseline = match bs.baseline {
+                    Some(ref comparison) => format!(
+                        r#", "baseline_median": {}, "change_pct": {}, "p_value": {}, "change": "{}""#,
+                        comparison.baseline_median as usize,
+                        comparison.change_pct,
+                        comparison.p_value,
+                        comparison.change().as_str()
+                    ),
+                    None => String::new(),
+                };
                 let name = EscapedString(desc.name.as_slice());
 
-                self.writeln_message(&format!(
+                return self.writeln_message(&format!(
                     "{{ \"type\": \"bench\", \
                      \"name\": \"{name}\", \
                      \"median\": {median}, \
-                     \"deviation\": {deviation}{mbps} }}\n",
-                ))
+                     \"deviation\": {deviation}{mbps}{baseline} }}\n",
+                ));
             }
-        }
+        };
+        let extra = match (extra, retries) {
+            (extra, 0) => extra,
+            (Some(extra), _) => Some(format!(r#"{extra}, "attempts": {}"#, retries + 1)),
+            (None, _) => Some(format!(r#""attempts": {}"#, retries + 1)),
+        };
+        self.write_event("test", desc.name.as_slice(), event, exec_time, output, extra.as_deref())
+    }
+
+    fn write_retry(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        _stdout: &[u8],
+        output: &SplitOutput,
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let attempt = state.failed_attempts(desc).count() + 1;
+        let extra = match failure_details(result) {
+            Some(details) => format!(r#""attempt": {attempt}, {details}"#),
+            None => format!(r#""attempt": {attempt}"#),
+        };
+        self.write_event(
+            "test",
+            desc.name.as_slice(),
+            "retry",
+            exec_time,
+            Some(output),
+            Some(&extra),
+        )
     }
 
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -202,6 +239,11 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let ignored = state.ignored;
         let measured = state.measured;
         let filtered_out = state.filtered_out;
+        let flaky_json = if state.flaky.is_empty() {
+            String::new()
+        } else {
+            format!(r#", "flaky": {}"#, state.flaky.len())
+        };
         let exec_time_json = if let Some(ref exec_time) = state.exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
         } else {
@@ -210,16 +252,25 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{exec_time_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{flaky_json}{exec_time_json} }}{newline}"#
         ))?;
 
         Ok(state.failed == 0)
     }
 }
 
+/// Returns the JSON fields describing why a test failed, if there is more to say than its outcome.
+fn failure_details(result: &TestResult) -> Option<String> {
+    match *result {
+        TestResult::TrTimedFail => Some(String::from(r#""reason": "time limit exceeded""#)),
+        TestResult::TrFailedMsg(ref m) => Some(format!(r#""message": "{}""#, EscapedString(m))),
+        _ => None,
+    }
+}
+
 /// A formatting utility used to print strings with characters in need of escaping.
 /// Base code taken form `libserialize::json::escape_str`
-struct EscapedString<S: AsRef<str>>(S);
+pub(super) struct EscapedString<S: AsRef<str>>(pub(super) S);
 
 impl<S: AsRef<str>> std::fmt::Display for EscapedString<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> ::std::fmt::Result {
@@ -4,6 +4,7 @@
 use super::OutputFormatter;
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    event::{CompletedTest, SplitOutput},
     test_result::TestResult,
     time,
     types::{TestDesc, TestType},
@@ -11,7 +12,7 @@
 
 pub struct JunitFormatter<T> {
     out: OutputLocation<T>,
-    results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+    results: Vec<(TestDesc, TestResult, Duration, SplitOutput)>,
 }
 
 impl<T: Write> JunitFormatter<T> {
@@ -24,6 +25,45 @@ fn write_message(&mut self, s: &str) -> io::Result<()> {
 
         self.out.write_all(s.as_ref())
     }
+
+    /// Writes the failed attempts of a retried test, using the elements introduced by Maven
+    /// Surefire for reruns: `flakyFailure` if the test eventually passed and `rerunFailure`
+    /// otherwise.
+    fn write_failed_attempts(
+        &mut self,
+        element: &str,
+        attempts: &[&CompletedTest],
+    ) -> io::Result<()> {
+        for attempt in attempts {
+            let ty = match attempt.result {
+                TestResult::TrTimedFail => "timeout",
+                _ => "assert",
+            };
+            match attempt.result {
+                TestResult::TrFailedMsg(ref m) => {
+                    let m = str_to_attribute(m);
+                    self.write_message(&format!("<{element} message=\"{m}\" type=\"{ty}\">"))?
+                }
+                _ => self.write_message(&format!("<{element} type=\"{ty}\">"))?,
+            }
+            self.write_output(&attempt.output)?;
+            self.write_message(&format!("</{element}>"))?;
+        }
+        Ok(())
+    }
+
+    /// Writes what a test printed to stdout and stderr as `system-out` and `system-err`.
+    fn write_output(&mut self, output: &SplitOutput) -> io::Result<()> {
+        for (element, captured) in [("system-out", &output.stdout), ("system-err", &output.stderr)]
+        {
+            if !captured.is_empty() {
+                self.write_message(&format!("<{element}>"))?;
+                self.write_message(&str_to_cdata(&String::from_utf8_lossy(captured)))?;
+                self.write_message(&format!("</{element}>"))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 fn str_to_cdata(s: &str) -> String {
@@ -38,6 +78,26 @@ fn str_to_cdata(s: &str) -> String {
     format!("<![CDATA[{}]]>", escaped_output)
 }
 
+fn str_to_attribute(s: &str) -> String {
+    // Attribute values can't hold a CDATA block, so the markup characters are replaced by
+    // references instead. Newlines are smuggled as &#xA; here as well.
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            '\t' => escaped.push_str("&#x9;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl<T: Write> OutputFormatter for JunitFormatter<T> {
     fn write_discovery_start(&mut self) -> io::Result<()> {
         Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
@@ -75,16 +135,30 @@ fn write_result(
         desc: &TestDesc,
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
-        stdout: &[u8],
+        _stdout: &[u8],
+        output: &SplitOutput,
         _state: &ConsoleTestState,
     ) -> io::Result<()> {
         // Because the testsuite node holds some of the information as attributes, we can't write it
         // until all of the tests have finished. Instead of writing every result as they come in, we add
         // them to a Vec and write them all at once when run is complete.
         let duration = exec_time.map(|t| t.0).unwrap_or_default();
-        self.results.push((desc.clone(), result.clone(), duration, stdout.to_vec()));
+        self.results.push((desc.clone(), result.clone(), duration, output.clone()));
         Ok(())
     }
+    fn write_retry(
+        &mut self,
+        _desc: &TestDesc,
+        _result: &TestResult,
+        _exec_time: Option<&time::TestExecTime>,
+        _stdout: &[u8],
+        _output: &SplitOutput,
+        _state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        // Failed attempts are written along with the final result of the test.
+        Ok(())
+    }
+
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         self.write_message("<testsuites>")?;
 
@@ -97,8 +171,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              >",
             state.failed, state.total, state.ignored
         ))?;
-        for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
+        for (desc, result, duration, output) in std::mem::take(&mut self.results) {
             let (class_name, test_name) = parse_class_name(&desc);
+            let attempts: Vec<_> = state.failed_attempts(&desc).collect();
             match result {
                 TestResult::TrIgnored => { /* no-op */ }
                 TestResult::TrFailed => {
@@ -110,11 +185,8 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         duration.as_secs_f64()
                     ))?;
                     self.write_message("<failure type=\"assert\"/>")?;
-                    if !stdout.is_empty() {
-                        self.write_message("<system-out>")?;
-                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
-                        self.write_message("</system-out>")?;
-                    }
+                    self.write_failed_attempts("rerunFailure", &attempts)?;
+                    self.write_output(&output)?;
                     self.write_message("</testcase>")?;
                 }
 
@@ -126,12 +198,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
+                    let m = str_to_attribute(m);
                     self.write_message(&format!("<failure message=\"{m}\" type=\"assert\"/>"))?;
-                    if !stdout.is_empty() {
-                        self.write_message("<system-out>")?;
-                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
-                        self.write_message("</system-out>")?;
-                    }
+                    self.write_failed_attempts("rerunFailure", &attempts)?;
+                    self.write_output(&output)?;
                     self.write_message("</testcase>")?;
                 }
 
@@ -144,6 +214,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         duration.as_secs_f64()
                     ))?;
                     self.write_message("<failure type=\"timeout\"/>")?;
+                    self.write_failed_attempts("rerunFailure", &attempts)?;
                     self.write_message("</testcase>")?;
                 }
 
@@ -163,12 +234,16 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                         test_name,
                         duration.as_secs_f64()
                     ))?;
-                    if stdout.is_empty() || !state.options.display_output {
+                    let display_output = state.options.display_output
+                        && (!output.stdout.is_empty() || !output.stderr.is_empty());
+                    if attempts.is_empty() && !display_output {
                         self.write_message("/>")?;
                     } else {
-                        self.write_message("><system-out>")?;
-                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
-                        self.write_message("</system-out>")?;
+                        self.write_message(">")?;
+                        self.write_failed_attempts("flakyFailure", &attempts)?;
+                        if display_output {
+                            self.write_output(&output)?;
+                        }
                         self.write_message("</testcase>")?;
                     }
                 }
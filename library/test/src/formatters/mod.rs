@@ -2,6 +2,7 @@
 
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState},
+    event::SplitOutput,
     test_result::TestResult,
     time,
     types::{TestDesc, TestName},
@@ -10,11 +11,13 @@
 mod json;
 mod junit;
 mod pretty;
+mod tap;
 mod terse;
 
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::junit::JunitFormatter;
 pub(crate) use self::pretty::PrettyFormatter;
+pub(crate) use self::tap::TapFormatter;
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
@@ -31,6 +34,16 @@ fn write_result(
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
         stdout: &[u8],
+        output: &SplitOutput,
+        state: &ConsoleTestState,
+    ) -> io::Result<()>;
+    fn write_retry(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        output: &SplitOutput,
         state: &ConsoleTestState,
     ) -> io::Result<()>;
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool>;
@@ -2,8 +2,9 @@
 
 use super::OutputFormatter;
 use crate::{
-    bench::fmt_bench_samples,
+    bench::{fmt_baseline_comparison, fmt_bench_samples, BaselineComparison, BenchChange},
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    event::SplitOutput,
     term,
     test_result::TestResult,
     time,
@@ -53,6 +54,14 @@ pub fn write_ignored(&mut self, message: Option<&'static str>) -> io::Result<()>
         }
     }
 
+    pub fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("ok (flaky)", term::color::YELLOW)
+    }
+
+    pub fn write_retrying(&mut self) -> io::Result<()> {
+        self.write_short_result("FAILED, retrying", term::color::YELLOW)
+    }
+
     pub fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
@@ -61,6 +70,17 @@ pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
 
+    pub fn write_baseline_comparison(&mut self, comparison: &BaselineComparison) -> io::Result<()> {
+        let change = comparison.change();
+        self.write_plain(" [")?;
+        match change {
+            BenchChange::Regressed => self.write_pretty(change.as_str(), term::color::RED)?,
+            BenchChange::Improved => self.write_pretty(change.as_str(), term::color::GREEN)?,
+            BenchChange::Unchanged => self.write_plain(change.as_str())?,
+        }
+        self.write_plain(format!(": {}]", fmt_baseline_comparison(comparison)))
+    }
+
     pub fn write_short_result(
         &mut self,
         result: &str,
@@ -160,6 +180,10 @@ pub fn write_successes(&mut self, state: &ConsoleTestState) -> io::Result<()> {
         self.write_results(&state.not_failures, "successes")
     }
 
+    pub fn write_flaky_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_results(&state.flaky, "flaky")
+    }
+
     pub fn write_failures(&mut self, state: &ConsoleTestState) -> io::Result<()> {
         self.write_results(&state.failures, "failures")
     }
@@ -236,19 +260,26 @@ fn write_result(
         result: &TestResult,
         exec_time: Option<&time::TestExecTime>,
         _: &[u8],
-        _: &ConsoleTestState,
+        _: &SplitOutput,
+        state: &ConsoleTestState,
     ) -> io::Result<()> {
         if self.is_multithreaded {
             self.write_test_name(desc)?;
         }
 
         match *result {
+            TestResult::TrOk if state.failed_attempts(desc).next().is_some() => {
+                self.write_flaky()?
+            }
             TestResult::TrOk => self.write_ok()?,
             TestResult::TrFailed | TestResult::TrFailedMsg(_) => self.write_failed()?,
             TestResult::TrIgnored => self.write_ignored(desc.ignore_message)?,
             TestResult::TrBench(ref bs) => {
                 self.write_bench()?;
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
+                if let Some(ref comparison) = bs.baseline {
+                    self.write_baseline_comparison(comparison)?;
+                }
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
         }
@@ -257,6 +288,24 @@ fn write_result(
         self.write_plain("\n")
     }
 
+    fn write_retry(
+        &mut self,
+        desc: &TestDesc,
+        _: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        _: &[u8],
+        _: &SplitOutput,
+        _: &ConsoleTestState,
+    ) -> io::Result<()> {
+        if self.is_multithreaded {
+            self.write_test_name(desc)?;
+        }
+
+        self.write_retrying()?;
+        self.write_time(desc, exec_time)?;
+        self.write_plain("\n")
+    }
+
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.write_plain(format!(
             "test {} has been running for over {} seconds\n",
@@ -269,6 +318,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_successes(state)?;
         }
+        if !state.flaky.is_empty() {
+            self.write_flaky_tests(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             if !state.failures.is_empty() {
@@ -0,0 +1,232 @@
+use std::{io, io::prelude::Write};
+
+use super::{json::EscapedString, OutputFormatter};
+use crate::{
+    console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    event::SplitOutput,
+    options::ShouldPanic,
+    test_result::TestResult,
+    time,
+    types::TestDesc,
+};
+
+/// Formatter emitting a [TAP version 14](https://testanything.org/tap-version-14-specification.html)
+/// document.
+///
+/// Every test is reported as a test point. Tests expected to panic and ignored tests additionally
+/// carry a subtest describing the expectation, and failure details, captured output and execution
+/// time are attached as a YAML diagnostics block.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    test_number: usize,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out, test_number: 0 }
+    }
+
+    fn writeln_message(&mut self, s: &str) -> io::Result<()> {
+        // Every line is written with a single `write_all` call, so that output of concurrently
+        // running tests can't interleave with it.
+        self.out.write_all(format!("{s}\n").as_bytes())
+    }
+
+    fn write_subtest(
+        &mut self,
+        desc: &TestDesc,
+        name: &TapDescription<'_>,
+        result: &TestResult,
+    ) -> io::Result<()> {
+        if *result == TestResult::TrIgnored {
+            self.writeln_message(&format!("    # Subtest: {name}"))?;
+            let reason = desc.ignore_message.map(|m| format!(" {}", TapDirective(m)));
+            return self.writeln_message(&format!("    1..0 # SKIP{}", reason.unwrap_or_default()));
+        }
+
+        if desc.should_panic == ShouldPanic::No {
+            return Ok(());
+        }
+
+        let status = match *result {
+            TestResult::TrOk => "ok",
+            _ => "not ok",
+        };
+        let expectation = match desc.should_panic {
+            ShouldPanic::YesWithMessage(msg) => {
+                format!("should panic with \"{}\"", TapDescription(msg))
+            }
+            _ => String::from("should panic"),
+        };
+        self.writeln_message(&format!("    # Subtest: {name}"))?;
+        self.writeln_message(&format!("    {status} 1 - {expectation}"))?;
+        self.writeln_message("    1..1")
+    }
+
+    fn write_diagnostics(&mut self, diagnostics: &[(&str, String)]) -> io::Result<()> {
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+
+        self.writeln_message("  ---")?;
+        for (key, value) in diagnostics {
+            self.writeln_message(&format!("  {key}: {value}"))?;
+        }
+        self.writeln_message("  ...")
+    }
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.writeln_message("TAP version 14")?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.writeln_message(&format!("# shuffle seed: {shuffle_seed}"))?;
+        }
+        self.writeln_message(&format!("1..{test_count}"))
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // Test points are only written once the result is known.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.writeln_message(&format!(
+            "# test {} has been running for over {} seconds",
+            desc.name,
+            time::TEST_WARN_TIMEOUT_S
+        ))
+    }
+
+    fn write_retry(
+        &mut self,
+        desc: &TestDesc,
+        _result: &TestResult,
+        _exec_time: Option<&time::TestExecTime>,
+        _stdout: &[u8],
+        _output: &SplitOutput,
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        // Failed attempts aren't test points of their own, they are only mentioned in a comment.
+        let attempt = state.failed_attempts(desc).count() + 1;
+        self.writeln_message(&format!(
+            "# test {} failed on attempt {attempt} and will be retried",
+            desc.name
+        ))
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        _output: &SplitOutput,
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        self.test_number += 1;
+        let number = self.test_number;
+        let name = TapDescription(desc.name.as_slice());
+
+        let mut diagnostics = Vec::new();
+        let status = match *result {
+            TestResult::TrOk | TestResult::TrIgnored | TestResult::TrBench(_) => "ok",
+            TestResult::TrFailed | TestResult::TrFailedMsg(_) | TestResult::TrTimedFail => {
+                diagnostics.push(("severity", String::from("fail")));
+                "not ok"
+            }
+        };
+        match *result {
+            TestResult::TrFailedMsg(ref m) => {
+                diagnostics.push(("message", format!("\"{}\"", EscapedString(m))));
+            }
+            TestResult::TrTimedFail => {
+                diagnostics.push(("message", String::from("\"time limit exceeded\"")));
+            }
+            TestResult::TrBench(ref bs) => {
+                let median = bs.ns_iter_summ.median as usize;
+                let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
+                diagnostics.push(("median_ns", median.to_string()));
+                diagnostics.push(("deviation_ns", deviation.to_string()));
+                if bs.mb_s != 0 {
+                    diagnostics.push(("mib_per_second", bs.mb_s.to_string()));
+                }
+            }
+            _ => {}
+        }
+        if let Some(exec_time) = exec_time {
+            diagnostics.push(("duration_ms", format!("{}", exec_time.0.as_secs_f64() * 1000.0)));
+        }
+        let retries = state.failed_attempts(desc).count();
+        if retries > 0 {
+            diagnostics.push(("attempts", (retries + 1).to_string()));
+        }
+        let display_stdout = state.options.display_output || *result != TestResult::TrOk;
+        if display_stdout && !stdout.is_empty() {
+            let stdout = String::from_utf8_lossy(stdout);
+            diagnostics.push(("stdout", format!("\"{}\"", EscapedString(stdout))));
+        }
+
+        self.write_subtest(desc, &name, result)?;
+
+        let directive = match *result {
+            TestResult::TrIgnored => match desc.ignore_message {
+                Some(msg) => format!(" # SKIP {}", TapDirective(msg)),
+                None => String::from(" # SKIP"),
+            },
+            _ => String::new(),
+        };
+        self.writeln_message(&format!("{status} {number} - {name}{directive}"))?;
+        self.write_diagnostics(&diagnostics)
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let exec_time =
+            state.exec_time.as_ref().map(|t| format!("; finished in {t}")).unwrap_or_default();
+        self.writeln_message(&format!(
+            "# {} passed; {} failed; {} ignored; {} measured; {} filtered out{exec_time}",
+            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+        ))?;
+
+        Ok(state.failed == 0)
+    }
+}
+
+/// Escapes a test name for use as the description of a test point, where a `#` would otherwise
+/// start a directive.
+struct TapDescription<'a>(&'a str);
+
+impl std::fmt::Display for TapDescription<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '#' => f.write_str("\\#")?,
+                '\n' => f.write_str(" ")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes the reason of a directive, which runs until the end of the line.
+struct TapDirective<'a>(&'a str);
+
+impl std::fmt::Display for TapDirective<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.replace('\n', " "))
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{
     bench::fmt_bench_samples,
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    event::SplitOutput,
     term,
     test_result::TestResult,
     time,
@@ -208,6 +209,7 @@ fn write_result(
         result: &TestResult,
         _: Option<&time::TestExecTime>,
         _: &[u8],
+        _: &SplitOutput,
         _: &ConsoleTestState,
     ) -> io::Result<()> {
         match *result {
@@ -226,6 +228,19 @@ fn write_result(
         }
     }
 
+    fn write_retry(
+        &mut self,
+        _: &TestDesc,
+        _: &TestResult,
+        _: Option<&time::TestExecTime>,
+        _: &[u8],
+        _: &SplitOutput,
+        _: &ConsoleTestState,
+    ) -> io::Result<()> {
+        // Only the final attempt of a test is counted and shown.
+        Ok(())
+    }
+
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         self.write_plain(format!(
             "test {} has been running for over {} seconds\n",
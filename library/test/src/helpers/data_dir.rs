@@ -0,0 +1,32 @@
+//! Location of the files libtest keeps between runs of a test binary.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Returns the path of the file under `dir` holding data of the running test binary.
+///
+/// Cargo places test binaries in `<target dir>/<profile>/deps`, so the file is
+/// `<target dir>/<dir>/<binary>`. Binaries run from elsewhere keep their data in `dir` next to
+/// them.
+pub fn binary_data_path(dir: &Path) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let exe_dir = exe.parent().unwrap_or(Path::new("."));
+    let base_dir = match exe_dir.file_name() {
+        Some(dir_name) if dir_name == "deps" => {
+            exe_dir.parent().and_then(Path::parent).unwrap_or(exe_dir)
+        }
+        _ => exe_dir,
+    };
+
+    let stem = exe.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    // Drop the hash Cargo appends to binary names, which changes along with the compiler.
+    let binary = match stem.rsplit_once('-') {
+        Some((binary, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            binary
+        }
+        _ => &stem,
+    };
+
+    Ok(base_dir.join(dir).join(binary))
+}
@@ -2,6 +2,8 @@
 //! but used in `libtest`.
 
 pub mod concurrency;
+pub mod data_dir;
 pub mod exit_code;
 pub mod metrics;
+pub mod shard;
 pub mod shuffle;
@@ -0,0 +1,18 @@
+use crate::options::TestShard;
+
+/// Returns whether the test with the given name belongs to the given shard.
+///
+/// Tests are assigned to shards by a hash of their name, which must be the same on every machine
+/// and with every version of the standard library, so that separate runs agree on the partition.
+/// `DefaultHasher` makes no such promise, hence the use of FNV-1a here.
+pub fn is_in_shard(test_name: &str, shard: TestShard) -> bool {
+    let hash = fnv1a(test_name.as_bytes());
+    hash % shard.count as u64 == shard.index as u64
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
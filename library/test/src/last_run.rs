@@ -0,0 +1,78 @@
+//! Record of the tests that failed in the previous run, for `--failed-first` and `--only-failed`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::console::ConsoleTestState;
+use super::helpers::data_dir::binary_data_path;
+use super::options::RunFailed;
+use super::types::TestDescAndFn;
+
+/// Names of the tests of a test binary that failed the last time they ran, stored one per line.
+pub struct LastRun {
+    path: PathBuf,
+    failed: BTreeSet<String>,
+}
+
+impl LastRun {
+    /// Loads the record of the running test binary, which is empty if it never ran before.
+    pub fn load() -> io::Result<LastRun> {
+        let path = binary_data_path(Path::new("libtest-last-run"))?;
+        let failed = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().map(str::to_owned).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(LastRun { path, failed })
+    }
+
+    #[cfg(test)]
+    pub fn with_failed(path: PathBuf, failed: &[&str]) -> LastRun {
+        LastRun { path, failed: failed.iter().map(|&name| name.to_owned()).collect() }
+    }
+
+    /// Reorders or filters the tests according to `run_failed`, returning the number of tests
+    /// left out. If none of the tests failed last time, they are all run as usual.
+    pub fn apply(&self, run_failed: RunFailed, tests: &mut Vec<TestDescAndFn>) -> usize {
+        let failed = |test: &TestDescAndFn| self.failed.contains(test.desc.name.as_slice());
+        if !tests.iter().any(failed) {
+            return 0;
+        }
+
+        match run_failed {
+            RunFailed::No => 0,
+            RunFailed::First => {
+                // The sort is stable, so tests keep their order within both groups.
+                tests.sort_by_key(|test| !failed(test));
+                0
+            }
+            RunFailed::Only => {
+                let len = tests.len();
+                // Fixtures are kept, they only run if some of the tests of their module do.
+                tests.retain(|test| test.desc.fixture().is_some() || failed(test));
+                len - tests.len()
+            }
+        }
+    }
+
+    /// Updates the record with the outcome of the tests that just ran. Tests that didn't run keep
+    /// their previous status.
+    pub fn update(&mut self, st: &ConsoleTestState) {
+        for (test, _) in &st.not_failures {
+            self.failed.remove(test.name.as_slice());
+        }
+        for (test, _) in st.failures.iter().chain(&st.time_failures) {
+            self.failed.insert(test.name.as_slice().to_owned());
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents: String = self.failed.iter().map(|name| format!("{name}\n")).collect();
+        fs::write(&self.path, contents)
+    }
+}
@@ -28,7 +28,9 @@
 // Public reexports
 pub use self::bench::{black_box, Bencher};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{
+    ColorConfig, Options, OutputFormat, RunFailed, RunIgnored, ShouldPanic, TestShard,
+};
 pub use self::types::TestName::*;
 pub use self::types::*;
 pub use self::ColorConfig::*;
@@ -48,7 +50,7 @@ pub mod test {
         time::{TestExecTime, TestTimeOptions},
         types::{
             DynTestFn, DynTestName, StaticBenchFn, StaticTestFn, StaticTestName, TestDesc,
-            TestDescAndFn, TestId, TestName, TestType,
+            TestDescAndFn, TestFixture, TestId, TestName, TestType,
         },
     };
 }
@@ -66,13 +68,17 @@ pub mod test {
     time::{Duration, Instant},
 };
 
+mod baseline;
 pub mod bench;
 mod cli;
 mod console;
 mod event;
+mod fixtures;
 mod formatters;
 mod helpers;
+mod last_run;
 mod options;
+mod resources;
 pub mod stats;
 mod term;
 mod test_result;
@@ -83,11 +89,14 @@ pub mod test {
 mod tests;
 
 use core::any::Any;
-use event::{CompletedTest, TestEvent};
+use event::{CompletedTest, SplitOutput, TestEvent};
+use fixtures::Fixtures;
 use helpers::concurrency::get_concurrency;
 use helpers::exit_code::get_exit_code;
+use helpers::shard::is_in_shard;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
+use resources::Resources;
 use test_result::*;
 use time::TestExecTime;
 
@@ -117,6 +126,15 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Option<Opt
             process::exit(ERROR_EXIT_CODE);
         }
     } else {
+        // Each test runs in its own subprocess, where a fixture couldn't set anything up for it.
+        let spawns_subprocesses = opts.options.panic_abort && !opts.force_run_in_process;
+        if spawns_subprocesses && tests.iter().any(|test| test.desc.fixture().is_some()) {
+            eprintln!(
+                "error: test fixtures are not supported when tests run in subprocesses, \
+                 as with `-Zpanic-abort-tests`"
+            );
+            process::exit(ERROR_EXIT_CODE);
+        }
         if !opts.nocapture {
             // If we encounter a non-unwinding panic, flush any captured output from the current test,
             // and stop capturing output to ensure that the non-unwinding panic message is visible.
@@ -256,6 +274,23 @@ fn total_len(&self) -> usize {
     }
 }
 
+struct RunningTest {
+    join_handle: Option<thread::JoinHandle<()>>,
+}
+
+impl RunningTest {
+    fn join(self, completed_test: &mut CompletedTest) {
+        if let Some(join_handle) = self.join_handle {
+            if let Err(_) = join_handle.join() {
+                if let TrOk = completed_test.result {
+                    completed_test.result =
+                        TrFailedMsg("panicked after reporting success".to_string());
+                }
+            }
+        }
+    }
+}
+
 pub fn run_tests<F>(
     opts: &TestOpts,
     tests: Vec<TestDescAndFn>,
@@ -268,23 +303,6 @@ pub fn run_tests<F>(
     use std::hash::BuildHasherDefault;
     use std::sync::mpsc::RecvTimeoutError;
 
-    struct RunningTest {
-        join_handle: Option<thread::JoinHandle<()>>,
-    }
-
-    impl RunningTest {
-        fn join(self, completed_test: &mut CompletedTest) {
-            if let Some(join_handle) = self.join_handle {
-                if let Err(_) = join_handle.join() {
-                    if let TrOk = completed_test.result {
-                        completed_test.result =
-                            TrFailedMsg("panicked after reporting success".to_string());
-                    }
-                }
-            }
-        }
-    }
-
     // Use a deterministic hasher
     type TestMap =
         HashMap<TestId, RunningTest, BuildHasherDefault<collections::hash_map::DefaultHasher>>;
@@ -295,6 +313,52 @@ struct TimeoutEntry {
         timeout: Instant,
     }
 
+    // Functions of the running tests that can be run again if they fail, along with the number
+    // of retries already made. Tests that can only be run once have no function.
+    struct Retries {
+        max_retries: usize,
+        next_id: usize,
+        tests: HashMap<TestId, (Option<TestFn>, usize)>,
+    }
+
+    impl Retries {
+        fn track(&mut self, id: TestId, testfn: &TestFn) {
+            if self.max_retries == 0 {
+                return;
+            }
+            self.tests.entry(id).or_insert((testfn.try_clone(), 0));
+        }
+
+        // Returns the test to run again if the completed test failed and has retries left. If it
+        // failed but cannot be run again, a note saying so is added to its output.
+        fn retry(&mut self, completed_test: &mut CompletedTest) -> Option<(TestId, TestDescAndFn)> {
+            let (testfn, retries) = self.tests.remove(&completed_test.id)?;
+            match completed_test.result {
+                TrFailed | TrFailedMsg(_) | TrTimedFail if retries < self.max_retries => {}
+                _ => return None,
+            }
+            let Some(testfn) = testfn else {
+                let note = "note: this test was not retried, since it can only be run once\n";
+                for output in [&mut completed_test.stdout, &mut completed_test.output.stderr] {
+                    if !output.is_empty() && !output.ends_with(b"\n") {
+                        output.push(b'\n');
+                    }
+                    output.extend_from_slice(note.as_bytes());
+                }
+                return None;
+            };
+            // A new id keeps the late result of an abandoned attempt apart from the next one.
+            let id = TestId(self.next_id);
+            self.next_id += 1;
+            self.tests.insert(id, (testfn.try_clone(), retries + 1));
+            Some((id, TestDescAndFn { desc: completed_test.desc.clone(), testfn }))
+        }
+    }
+
+    // Fixtures are not filtered, they run if some of the tests of their module do.
+    let (fixtures, tests): (Vec<_>, Vec<_>) =
+        tests.into_iter().partition(|test| test.desc.fixture().is_some());
+
     let tests_len = tests.len();
 
     let mut filtered = FilteredTests { tests: Vec::new(), benches: Vec::new(), next_id: 0 };
@@ -324,13 +388,30 @@ struct TimeoutEntry {
         };
     }
 
+    let fixtures = fixtures
+        .into_iter()
+        .map(|TestDescAndFn { mut desc, testfn }| {
+            desc.name = desc.name.with_padding(testfn.padding());
+            TestDescAndFn { desc, testfn }
+        })
+        .collect();
+    let dependents: Vec<&TestDesc> = filtered
+        .tests
+        .iter()
+        .filter(|_| opts.run_tests)
+        .chain(&filtered.benches)
+        .map(|(_, test)| &test.desc)
+        .filter(|desc| !desc.ignore)
+        .collect();
+    let mut fixtures = Fixtures::new(fixtures, &dependents, &mut filtered.next_id);
+
     let filtered_out = tests_len - filtered.total_len();
     let event = TestEvent::TeFilteredOut(filtered_out);
     notify_about_test_event(event)?;
 
     let shuffle_seed = get_shuffle_seed(opts);
 
-    let event = TestEvent::TeFiltered(filtered.total_len(), shuffle_seed);
+    let event = TestEvent::TeFiltered(filtered.total_len() + fixtures.len(), shuffle_seed);
     notify_about_test_event(event)?;
 
     let concurrency = opts.test_threads.unwrap_or_else(get_concurrency);
@@ -343,6 +424,8 @@ struct TimeoutEntry {
     // tests in the order they were passed (unless shuffled).
     let mut remaining = VecDeque::from(remaining);
     let mut pending = 0;
+    let mut retries =
+        Retries { max_retries: opts.retries, next_id: filtered.next_id, tests: HashMap::new() };
 
     let (tx, rx) = channel::<CompletedTest>();
     let run_strategy = if opts.options.panic_abort && !opts.force_run_in_process {
@@ -352,7 +435,11 @@ struct TimeoutEntry {
     };
 
     let mut running_tests: TestMap = HashMap::default();
+    let mut resources = Resources::default();
+    let mut abandoned_tests = false;
     let mut timeout_queue: VecDeque<TimeoutEntry> = VecDeque::new();
+    // Deadlines of tests to be abandoned, sorted by time.
+    let mut kill_queue: VecDeque<TimeoutEntry> = VecDeque::new();
 
     fn get_timed_out_tests(
         running_tests: &TestMap,
@@ -372,24 +459,117 @@ fn get_timed_out_tests(
         timed_out
     }
 
-    fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
-        timeout_queue.front().map(|&TimeoutEntry { timeout: next_timeout, .. }| {
-            let now = Instant::now();
-            if next_timeout >= now { next_timeout - now } else { Duration::new(0, 0) }
-        })
+    fn get_hung_test(
+        running_tests: &TestMap,
+        kill_queue: &mut VecDeque<TimeoutEntry>,
+    ) -> Option<TimeoutEntry> {
+        let now = Instant::now();
+        while let Some(kill_entry) = kill_queue.front() {
+            if now < kill_entry.timeout {
+                break;
+            }
+            let kill_entry = kill_queue.pop_front().unwrap();
+            if running_tests.contains_key(&kill_entry.id) {
+                return Some(kill_entry);
+            }
+        }
+        None
+    }
+
+    fn calc_timeout(
+        timeout_queue: &VecDeque<TimeoutEntry>,
+        kill_queue: &VecDeque<TimeoutEntry>,
+    ) -> Option<Duration> {
+        let next_timeout = [timeout_queue.front(), kill_queue.front()]
+            .into_iter()
+            .flatten()
+            .map(|timeout_entry| timeout_entry.timeout)
+            .min()?;
+        let now = Instant::now();
+        Some(if next_timeout >= now { next_timeout - now } else { Duration::new(0, 0) })
+    }
+
+    // Tests run in a subprocess are killed by the thread monitoring them, while tests run in a
+    // thread of this process can't be stopped: they are reported as timed out and abandoned.
+    let kill_timeout = |desc: &TestDesc| match run_strategy {
+        RunStrategy::InProcess => time::get_test_kill_timeout(desc, opts.test_timeout),
+        RunStrategy::SpawnPrimary => None,
+    };
+
+    fn abandoned_test(id: TestId, desc: TestDesc, timeout: Option<Duration>) -> CompletedTest {
+        let message = match timeout {
+            Some(timeout) => format!("test did not complete within {timeout:?} and was abandoned"),
+            None => String::from("test did not complete in time and was abandoned"),
+        };
+        let output = SplitOutput { stdout: Vec::new(), stderr: message.clone().into_bytes() };
+        CompletedTest::new(id, desc, TrTimedFail, None, message.into_bytes()).with_output(output)
     }
 
     if concurrency == 1 {
         while !remaining.is_empty() {
             let (id, test) = remaining.pop_front().unwrap();
+            let uses_fixtures = opts.run_tests && !test.desc.ignore;
+            let setup_failure = match uses_fixtures {
+                true => fixtures.set_up(
+                    opts,
+                    id,
+                    &test.desc,
+                    run_strategy,
+                    &mut notify_about_test_event,
+                )?,
+                false => None,
+            };
             let event = TestEvent::TeWait(test.desc.clone());
             notify_about_test_event(event)?;
-            let join_handle = run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
-            // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
-            RunningTest { join_handle }.join(&mut completed_test);
+            let desc = test.desc.clone();
+            let mut completed_test = match setup_failure {
+                Some(completed_test) => completed_test,
+                None => {
+                    let timeout = kill_timeout(&desc);
+                    retries.track(id, &test.testfn);
+                    let join_handle =
+                        run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
+                    // Wait for the test to complete, dropping late results of abandoned tests.
+                    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                    let completed_test = loop {
+                        let res = match deadline {
+                            Some(deadline) => {
+                                rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                            }
+                            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                        };
+                        match res {
+                            Ok(completed_test) if completed_test.id == id => {
+                                break Some(completed_test);
+                            }
+                            Ok(_) => {}
+                            Err(RecvTimeoutError::Timeout) => break None,
+                            Err(RecvTimeoutError::Disconnected) => {
+                                panic!("test result channel closed")
+                            }
+                        }
+                    };
+                    match completed_test {
+                        Some(mut completed_test) => {
+                            RunningTest { join_handle }.join(&mut completed_test);
+                            completed_test
+                        }
+                        None => {
+                            abandoned_tests = true;
+                            abandoned_test(id, desc.clone(), timeout)
+                        }
+                    }
+                }
+            };
 
-            let fail_fast = match completed_test.result {
+            if let Some(test) = retries.retry(&mut completed_test) {
+                let event = TestEvent::TeRetry(completed_test);
+                notify_about_test_event(event)?;
+                remaining.push_front(test);
+                continue;
+            }
+
+            let mut fail_fast = match completed_test.result {
                 TrIgnored | TrOk | TrBench(_) => false,
                 TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
             };
@@ -397,19 +577,68 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
 
+            if uses_fixtures {
+                fail_fast |=
+                    fixtures.tear_down(opts, &desc, run_strategy, &mut notify_about_test_event)?;
+            }
+
             if fail_fast {
                 return Ok(());
             }
         }
     } else {
         while pending > 0 || !remaining.is_empty() {
-            while pending < concurrency && !remaining.is_empty() {
-                let (id, test) = remaining.pop_front().unwrap();
+            while pending < concurrency {
+                // Run the first test that doesn't share a resource with a running test.
+                let Some(index) =
+                    remaining.iter().position(|(_, test)| !resources.conflicts(&test.desc))
+                else {
+                    break;
+                };
+                let (id, test) = remaining.remove(index).unwrap();
                 let timeout = time::get_default_test_timeout();
                 let desc = test.desc.clone();
 
+                let uses_fixtures = opts.run_tests && !desc.ignore;
+                let setup_failure = match uses_fixtures {
+                    true => fixtures.set_up(
+                        opts,
+                        id,
+                        &desc,
+                        run_strategy,
+                        &mut notify_about_test_event,
+                    )?,
+                    false => None,
+                };
+
                 let event = TestEvent::TeWait(desc.clone());
                 notify_about_test_event(event)?; //here no pad
+
+                if let Some(completed_test) = setup_failure {
+                    // The test is not run, its result is reported right away.
+                    let event = TestEvent::TeResult(completed_test);
+                    notify_about_test_event(event)?;
+                    let teardown_fail_fast = fixtures.tear_down(
+                        opts,
+                        &desc,
+                        run_strategy,
+                        &mut notify_about_test_event,
+                    )?;
+                    if opts.fail_fast || teardown_fail_fast {
+                        // Prevent remaining test threads from panicking
+                        std::mem::forget(rx);
+                        return Ok(());
+                    }
+                    continue;
+                }
+
+                if let Some(kill_timeout) = kill_timeout(&desc) {
+                    let timeout = Instant::now() + kill_timeout;
+                    let index = kill_queue.partition_point(|entry| entry.timeout <= timeout);
+                    kill_queue.insert(index, TimeoutEntry { id, desc: desc.clone(), timeout });
+                }
+                retries.track(id, &test.testfn);
+                resources.acquire(id, &desc);
                 let join_handle =
                     run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
                 running_tests.insert(id, RunningTest { join_handle });
@@ -417,9 +646,10 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 pending += 1;
             }
 
+            let mut hung_test = None;
             let mut res;
             loop {
-                if let Some(timeout) = calc_timeout(&timeout_queue) {
+                if let Some(timeout) = calc_timeout(&timeout_queue, &kill_queue) {
                     res = rx.recv_timeout(timeout);
                     for test in get_timed_out_tests(&running_tests, &mut timeout_queue) {
                         let event = TestEvent::TeTimeout(test);
@@ -428,8 +658,17 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
 
                     match res {
                         Err(RecvTimeoutError::Timeout) => {
+                            hung_test = get_hung_test(&running_tests, &mut kill_queue);
+                            if hung_test.is_some() {
+                                break;
+                            }
                             // Result is not yet ready, continue waiting.
                         }
+                        Ok(ref completed_test)
+                            if !running_tests.contains_key(&completed_test.id) =>
+                        {
+                            // Late result of an abandoned test, continue waiting.
+                        }
                         _ => {
                             // We've got a result, stop the loop.
                             break;
@@ -437,23 +676,59 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                     }
                 } else {
                     res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
-                    break;
+                    match res {
+                        Ok(ref completed_test)
+                            if !running_tests.contains_key(&completed_test.id) =>
+                        {
+                            // Late result of an abandoned test, continue waiting.
+                        }
+                        _ => break,
+                    }
                 }
             }
 
-            let mut completed_test = res.unwrap();
-            let running_test = running_tests.remove(&completed_test.id).unwrap();
-            running_test.join(&mut completed_test);
+            let mut completed_test = match hung_test {
+                Some(hung_test) => {
+                    // The thread running the test can't be joined, it is simply detached.
+                    let timeout = kill_timeout(&hung_test.desc);
+                    running_tests.remove(&hung_test.id);
+                    // Tests waiting for the resources of the abandoned test are run anyway.
+                    resources.release(hung_test.id);
+                    abandoned_tests = true;
+                    abandoned_test(hung_test.id, hung_test.desc, timeout)
+                }
+                None => {
+                    let mut completed_test = res.unwrap();
+                    let running_test = running_tests.remove(&completed_test.id).unwrap();
+                    running_test.join(&mut completed_test);
+                    resources.release(completed_test.id);
+                    completed_test
+                }
+            };
 
-            let fail_fast = match completed_test.result {
+            if let Some(test) = retries.retry(&mut completed_test) {
+                let event = TestEvent::TeRetry(completed_test);
+                notify_about_test_event(event)?;
+                remaining.push_front(test);
+                pending -= 1;
+                continue;
+            }
+
+            let mut fail_fast = match completed_test.result {
                 TrIgnored | TrOk | TrBench(_) => false,
                 TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
             };
 
+            let desc = completed_test.desc.clone();
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
             pending -= 1;
 
+            if opts.run_tests && !desc.ignore {
+                fail_fast |=
+                    fixtures.tear_down(opts, &desc, run_strategy, &mut notify_about_test_event)?;
+            }
+
             if fail_fast {
                 // Prevent remaining test threads from panicking
                 std::mem::forget(rx);
@@ -465,17 +740,39 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
     if opts.bench_benchmarks {
         // All benchmarks run at the end, in serial.
         for (id, b) in filtered.benches {
-            let event = TestEvent::TeWait(b.desc.clone());
+            let desc = b.desc.clone();
+            let setup_failure = match desc.ignore {
+                false => {
+                    fixtures.set_up(opts, id, &desc, run_strategy, &mut notify_about_test_event)?
+                }
+                true => None,
+            };
+            let event = TestEvent::TeWait(desc.clone());
             notify_about_test_event(event)?;
-            let join_handle = run_test(opts, false, id, b, run_strategy, tx.clone());
-            // Wait for the test to complete.
-            let mut completed_test = rx.recv().unwrap();
-            RunningTest { join_handle }.join(&mut completed_test);
+            let completed_test = match setup_failure {
+                Some(completed_test) => completed_test,
+                None => {
+                    let join_handle = run_test(opts, false, id, b, run_strategy, tx.clone());
+                    // Wait for the test to complete.
+                    let mut completed_test = rx.recv().unwrap();
+                    RunningTest { join_handle }.join(&mut completed_test);
+                    completed_test
+                }
+            };
 
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
+
+            if !desc.ignore {
+                fixtures.tear_down(opts, &desc, run_strategy, &mut notify_about_test_event)?;
+            }
         }
     }
+
+    if abandoned_tests {
+        // Prevent abandoned test threads from panicking if they ever complete
+        std::mem::forget(rx);
+    }
     Ok(())
 }
 
@@ -517,6 +814,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Keep only the tests assigned to this shard
+    if let Some(shard) = opts.shard {
+        filtered.retain(|test| is_in_shard(test.desc.name.as_slice(), shard));
+    }
+
     filtered
 }
 
@@ -569,6 +871,7 @@ pub fn run_test(
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
             let bench_benchmarks = opts.bench_benchmarks;
+            let kill_timeout = time::get_test_kill_timeout(&desc, opts.test_timeout);
 
             let runtest = move || match strategy {
                 RunStrategy::InProcess => run_test_in_process(
@@ -588,6 +891,7 @@ pub fn run_test(
                     monitor_ch,
                     time_options,
                     bench_benchmarks,
+                    kill_timeout,
                 ),
             };
 
@@ -640,11 +944,14 @@ fn run_test_in_process(
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
 ) {
-    // Buffer for capturing standard I/O
+    // Buffers for capturing standard I/O, both interleaved and separately
     let data = Arc::new(Mutex::new(Vec::new()));
+    let stdout_data = Arc::new(Mutex::new(Vec::new()));
+    let stderr_data = Arc::new(Mutex::new(Vec::new()));
 
     if !nocapture {
         io::set_output_capture(Some(data.clone()));
+        io::set_split_output_capture(Some((stdout_data.clone(), stderr_data.clone())));
     }
 
     let start = report_time.then(Instant::now);
@@ -655,13 +962,16 @@ fn run_test_in_process(
     });
 
     io::set_output_capture(None);
+    io::set_split_output_capture(None);
 
     let test_result = match result {
         Ok(()) => calc_result(&desc, Ok(()), &time_opts, &exec_time),
         Err(e) => calc_result(&desc, Err(e.as_ref()), &time_opts, &exec_time),
     };
-    let stdout = data.lock().unwrap_or_else(|e| e.into_inner()).to_vec();
-    let message = CompletedTest::new(id, desc, test_result, exec_time, stdout);
+    let take = |data: Arc<Mutex<Vec<u8>>>| data.lock().unwrap_or_else(|e| e.into_inner()).to_vec();
+    let output = SplitOutput { stdout: take(stdout_data), stderr: take(stderr_data) };
+    let message =
+        CompletedTest::new(id, desc, test_result, exec_time, take(data)).with_output(output);
     monitor_ch.send(message).unwrap();
 }
 
@@ -686,8 +996,9 @@ fn spawn_test_subprocess(
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
     bench_benchmarks: bool,
+    kill_timeout: Option<Duration>,
 ) {
-    let (result, test_output, exec_time) = (|| {
+    let (result, test_output, output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
         let current_exe = &args[0];
 
@@ -702,11 +1013,22 @@ fn spawn_test_subprocess(
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
-            Ok(out) => out,
+        let output = match kill_timeout {
+            Some(kill_timeout) => output_with_timeout(&mut command, nocapture, kill_timeout),
+            None => command.output().map(Some),
+        };
+        let output = match output {
+            Ok(Some(out)) => out,
+            Ok(None) => {
+                let err = format!("test did not complete within {kill_timeout:?} and was killed");
+                let exec_time = start.map(|start| TestExecTime(start.elapsed()));
+                let output = SplitOutput { stdout: Vec::new(), stderr: err.clone().into_bytes() };
+                return (TrTimedFail, err.into_bytes(), output, exec_time);
+            }
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
-                return (TrFailed, err.into_bytes(), None);
+                let output = SplitOutput { stdout: Vec::new(), stderr: err.clone().into_bytes() };
+                return (TrFailed, err.into_bytes(), output, None);
             }
         };
         let exec_time = start.map(|start| {
@@ -715,9 +1037,10 @@ fn spawn_test_subprocess(
         });
 
         let std::process::Output { stdout, stderr, status } = output;
-        let mut test_output = stdout;
+        let mut test_output = stdout.clone();
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
+        let mut output = SplitOutput { stdout, stderr };
 
         let result = match (|| -> Result<TestResult, String> {
             let exit_code = get_exit_code(status)?;
@@ -726,17 +1049,70 @@ fn spawn_test_subprocess(
             Ok(r) => r,
             Err(e) => {
                 write!(&mut test_output, "Unexpected error: {e}").unwrap();
+                write!(&mut output.stderr, "Unexpected error: {e}").unwrap();
                 TrFailed
             }
         };
 
-        (result, test_output, exec_time)
+        (result, test_output, output, exec_time)
     })();
 
-    let message = CompletedTest::new(id, desc, result, exec_time, test_output);
+    let message = CompletedTest::new(id, desc, result, exec_time, test_output).with_output(output);
     monitor_ch.send(message).unwrap();
 }
 
+/// Runs `command` to completion like `Command::output`, but kills the child
+/// process if it is still running after `timeout`, returning `None` instead.
+fn output_with_timeout(
+    command: &mut Command,
+    nocapture: bool,
+    timeout: Duration,
+) -> io::Result<Option<process::Output>> {
+    if !nocapture {
+        command.stdout(process::Stdio::piped());
+        command.stderr(process::Stdio::piped());
+    }
+    let deadline = Instant::now() + timeout;
+    let mut child = command.spawn()?;
+
+    // Drain the pipes concurrently, so that the child can't block on a full pipe.
+    fn read_pipe<R: io::Read + Send + 'static>(
+        pipe: Option<R>,
+    ) -> Option<thread::JoinHandle<Vec<u8>>> {
+        pipe.map(|mut pipe| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf);
+                buf
+            })
+        })
+    }
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            // The child may have exited in the meantime, in which case killing it fails.
+            let _ = child.kill();
+            child.wait()?;
+            break None;
+        }
+        thread::sleep((deadline - now).min(Duration::from_millis(10)));
+    };
+
+    let join_pipe = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader.map(|reader| reader.join().unwrap_or_default()).unwrap_or_default()
+    };
+    let stdout = join_pipe(stdout);
+    let stderr = join_pipe(stderr);
+
+    Ok(status.map(|status| process::Output { status, stdout, stderr }))
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicInfo<'_>>| {
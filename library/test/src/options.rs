@@ -36,6 +36,17 @@ pub enum OutputFormat {
     Json,
     /// JUnit output
     Junit,
+    /// TAP (Test Anything Protocol) version 14 output
+    Tap,
+}
+
+/// One of several disjoint subsets of the tests, selected by `--shard-index` and `--shard-count`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TestShard {
+    /// Zero-based index of the shard to run
+    pub index: usize,
+    /// Number of shards the tests are split into
+    pub count: usize,
 }
 
 /// Whether ignored test should be run or not
@@ -47,6 +58,17 @@ pub enum RunIgnored {
     Only,
 }
 
+/// Whether the tests that failed in the previous run should be run first, or only them
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunFailed {
+    /// Run the tests in the usual order
+    No,
+    /// Run the tests that failed in the previous run before the others
+    First,
+    /// Run only the tests that failed in the previous run
+    Only,
+}
+
 #[derive(Clone, Copy)]
 pub enum RunStrategy {
     /// Runs the test in the current process, and sends the result back over the
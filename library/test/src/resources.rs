@@ -0,0 +1,32 @@
+//! Resources that tests need exclusive access to, such as a port or an environment variable.
+
+use std::collections::HashMap;
+
+use super::types::{TestDesc, TestId};
+
+/// Resources held by the running tests, which the tests sharing them have to wait for.
+#[derive(Default)]
+pub struct Resources {
+    held: HashMap<TestId, &'static [&'static str]>,
+}
+
+impl Resources {
+    /// Returns whether the test needs a resource held by a running test.
+    pub fn conflicts(&self, desc: &TestDesc) -> bool {
+        desc.resources().iter().any(|resource| {
+            self.held.values().any(|held_resources| held_resources.contains(resource))
+        })
+    }
+
+    /// Marks the resources of a test as held until it completes.
+    pub fn acquire(&mut self, id: TestId, desc: &TestDesc) {
+        if !desc.resources().is_empty() {
+            self.held.insert(id, desc.resources());
+        }
+    }
+
+    /// Releases the resources held by a test.
+    pub fn release(&mut self, id: TestId) {
+        self.held.remove(&id);
+    }
+}
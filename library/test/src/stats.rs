@@ -300,3 +300,62 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
         }
     }
 }
+
+/// Two-sided Mann-Whitney U test of whether two independent sets of samples come from the same
+/// distribution. Returns the p-value: the probability of a difference in ranks at least as large
+/// as the observed one if they do.
+///
+/// Unlike a t-test, this makes no assumption about the shape of the distributions, which suits
+/// benchmark timings and their long tails. The U statistic is approximated by a normal
+/// distribution, with a correction for ties, which is accurate for the number of samples taken
+/// by benchmarks.
+///
+/// See: <https://en.wikipedia.org/wiki/Mann%E2%80%93Whitney_U_test>
+pub fn mann_whitney_u_test(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 1.0;
+    }
+
+    let mut samples: Vec<(f64, bool)> =
+        a.iter().map(|&x| (x, true)).chain(b.iter().map(|&x| (x, false))).collect();
+    samples.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Sum of the ranks of `a`, giving tied samples the mean of the ranks they span.
+    let mut rank_sum_a = 0.0;
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < samples.len() {
+        let end = start + samples[start..].iter().take_while(|s| s.0 == samples[start].0).count();
+        let rank = (start + 1 + end) as f64 / 2.0;
+        let in_a = samples[start..end].iter().filter(|s| s.1).count();
+        rank_sum_a += rank * in_a as f64;
+        let tied = (end - start) as f64;
+        ties += tied * tied * tied - tied;
+        start = end;
+    }
+
+    let n1 = a.len() as f64;
+    let n2 = b.len() as f64;
+    let n = n1 + n2;
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let var = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if var <= 0.0 {
+        // All samples are equal.
+        return 1.0;
+    }
+
+    let z = ((u - mean).abs() - 0.5).max(0.0) / var.sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+// Complementary error function for non-negative arguments, with an absolute error below 1.5e-7.
+// Formula 7.1.26 from Abramowitz and Stegun, "Handbook of Mathematical Functions".
+fn erfc(x: f64) -> f64 {
+    const P: f64 = 0.3275911;
+    const A: [f64; 5] = [0.254829592, -0.284496736, 1.421413741, -1.453152027, 1.061405429];
+
+    let t = 1.0 / (1.0 + P * x);
+    let poly = A.iter().rev().fold(0.0, |acc, &a| (acc + a) * t);
+    poly * (-x * x).exp()
+}
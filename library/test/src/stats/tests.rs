@@ -562,6 +562,21 @@ fn test_unif25() {
     check(val, summ);
 }
 
+#[test]
+fn test_mann_whitney_u_test() {
+    let low: Vec<f64> = (1..=10).map(f64::from).collect();
+    let high: Vec<f64> = (11..=20).map(f64::from).collect();
+    let interleaved: Vec<f64> = (1..=10).map(|x| f64::from(x) + 0.5).collect();
+
+    let p = mann_whitney_u_test(&low, &high);
+    assert!((p - 0.000183).abs() < 0.00001, "{p}");
+    assert_eq!(mann_whitney_u_test(&high, &low), p);
+    assert!(mann_whitney_u_test(&low, &interleaved) > 0.5);
+    assert!(mann_whitney_u_test(&low, &low) > 0.999);
+    assert_eq!(mann_whitney_u_test(&[5.0; 10], &[5.0; 10]), 1.0);
+    assert_eq!(mann_whitney_u_test(&low, &[]), 1.0);
+}
+
 #[test]
 fn test_sum_f64s() {
     assert_eq!([0.5f64, 3.2321f64, 1.5678f64].sum(), 5.2999);
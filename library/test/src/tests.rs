@@ -3,7 +3,8 @@
 use crate::{
     bench::Bencher,
     console::OutputLocation,
-    formatters::PrettyFormatter,
+    event::SplitOutput,
+    formatters::{JsonFormatter, OutputFormatter, PrettyFormatter},
     options::OutputFormat,
     test::{
         filter_tests,
@@ -27,6 +28,8 @@
     },
     time::{TestTimeOptions, TimeThreshold},
 };
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
 use std::time::Duration;
 
@@ -39,6 +42,7 @@ fn new() -> TestOpts {
             force_run_in_process: false,
             exclude_should_panic: false,
             run_ignored: RunIgnored::No,
+            run_failed: RunFailed::No,
             run_tests: false,
             bench_benchmarks: false,
             logfile: None,
@@ -47,9 +51,14 @@ fn new() -> TestOpts {
             format: OutputFormat::Pretty,
             shuffle: false,
             shuffle_seed: None,
+            shard: None,
             test_threads: None,
             skip: vec![],
             time_options: None,
+            test_timeout: None,
+            retries: 0,
+            save_baseline: None,
+            baseline: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -72,6 +81,12 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                timeout: None,
+                #[cfg(not(bootstrap))]
+                fixture: None,
+                #[cfg(not(bootstrap))]
+                resources: &[],
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -89,6 +104,12 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                timeout: None,
+                #[cfg(not(bootstrap))]
+                fixture: None,
+                #[cfg(not(bootstrap))]
+                resources: &[],
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -114,6 +135,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -142,6 +169,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -173,6 +206,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -204,6 +243,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -240,6 +285,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -280,6 +331,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -314,6 +371,12 @@ fn f() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                timeout: None,
+                #[cfg(not(bootstrap))]
+                fixture: None,
+                #[cfg(not(bootstrap))]
+                resources: &[],
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -347,6 +410,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -389,6 +458,12 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -433,6 +508,12 @@ fn typed_test_desc(test_type: TestType) -> TestDesc {
         compile_fail: false,
         no_run: false,
         test_type,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     }
 }
 
@@ -488,6 +569,409 @@ fn parse_show_output_flag() {
     assert!(opts.options.display_output);
 }
 
+#[test]
+fn parse_test_timeout_option() {
+    let args = vec![
+        "progname".to_string(),
+        "filter".to_string(),
+        "-Zunstable-options".to_string(),
+        "--test-timeout=250ms".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.test_timeout, Some(Duration::from_millis(250)));
+
+    let args = vec!["progname".to_string(), "--test-timeout=2m".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+fn test_parse_duration() {
+    use crate::time::parse_duration;
+
+    assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+    assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+    assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+    assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_duration("2h"), None);
+    assert_eq!(parse_duration("ms"), None);
+    assert_eq!(parse_duration("-1s"), None);
+}
+
+fn hung_test_result(opts: &TestOpts, timeout: Option<Duration>) -> TestResult {
+    fn f() -> Result<(), String> {
+        thread::sleep(Duration::from_secs(3600));
+        Ok(())
+    }
+    let desc = TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
+        },
+        testfn: DynTestFn(Box::new(f)),
+    };
+    #[cfg(bootstrap)]
+    let _ = timeout;
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    run_tests(opts, vec![desc], notify).unwrap();
+    rx.recv().unwrap().result
+}
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+fn test_timeout_abandons_hung_test() {
+    let timeout = Some(Duration::from_millis(50));
+    for test_threads in [1, 2] {
+        let opts = TestOpts {
+            run_tests: true,
+            test_threads: Some(test_threads),
+            test_timeout: timeout,
+            ..TestOpts::new()
+        };
+        assert_eq!(hung_test_result(&opts, None), TrTimedFail);
+    }
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+#[cfg(not(target_os = "emscripten"))]
+fn test_timeout_from_test_desc() {
+    let opts = TestOpts { run_tests: true, test_threads: Some(2), ..TestOpts::new() };
+    assert_eq!(hung_test_result(&opts, Some(Duration::from_millis(50))), TrTimedFail);
+}
+
+#[test]
+fn parse_retries_option() {
+    let args = vec![
+        "progname".to_string(),
+        "filter".to_string(),
+        "-Zunstable-options".to_string(),
+        "--retries=3".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.retries, 3);
+
+    let args = vec!["progname".to_string(), "--retries=3".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+// Runs a test with retries enabled, returning the number of failed attempts that were retried and
+// the final result.
+fn run_with_retries(retries: usize, test_threads: usize, testfn: TestFn) -> (usize, TestResult) {
+    let (failed_attempts, completed_test) = run_with_retries_output(retries, test_threads, testfn);
+    (failed_attempts, completed_test.result)
+}
+
+// Like `run_with_retries`, but returns the final completed test with its output.
+fn run_with_retries_output(
+    retries: usize,
+    test_threads: usize,
+    testfn: TestFn,
+) -> (usize, CompletedTest) {
+    let desc = TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
+        },
+        testfn,
+    };
+    let opts =
+        TestOpts { run_tests: true, test_threads: Some(test_threads), retries, ..TestOpts::new() };
+    let mut failed_attempts = 0;
+    let mut result = None;
+    let notify = |event: TestEvent| {
+        match event {
+            TestEvent::TeRetry(_) => failed_attempts += 1,
+            TestEvent::TeResult(completed_test) => result = Some(completed_test),
+            _ => {}
+        }
+        Ok(())
+    };
+    run_tests(&opts, vec![desc], notify).unwrap();
+    (failed_attempts, result.unwrap())
+}
+
+#[test]
+fn test_retries_flaky_test() {
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+    fn f() -> Result<(), String> {
+        if ATTEMPTS.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err("flaky".to_string())
+        } else {
+            Ok(())
+        }
+    }
+    for test_threads in [1, 2] {
+        ATTEMPTS.store(0, Ordering::SeqCst);
+        assert_eq!(run_with_retries(3, test_threads, StaticTestFn(f)), (2, TrOk));
+    }
+}
+
+#[cfg(not(bootstrap))]
+fn fixture_test(
+    name: &'static str,
+    fixture: Option<TestFixture>,
+    testfn: fn() -> Result<(), String>,
+) -> TestDescAndFn {
+    TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            test_type: TestType::Unknown,
+            timeout: None,
+            fixture,
+            resources: &[],
+        },
+        testfn: StaticTestFn(testfn),
+    }
+}
+
+// Runs the tests and returns their results in the order they were reported.
+#[cfg(not(bootstrap))]
+fn run_and_collect(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<CompletedTest> {
+    let mut total = 0;
+    let mut results = Vec::new();
+    let notify = |event: TestEvent| {
+        match event {
+            TestEvent::TeFiltered(filtered_tests, _) => total = filtered_tests,
+            TestEvent::TeResult(completed_test) => results.push(completed_test),
+            _ => {}
+        }
+        Ok(())
+    };
+    run_tests(opts, tests, notify).unwrap();
+    assert_eq!(results.len(), total);
+    results
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+fn fixtures_run_around_the_tests_of_their_module() {
+    fn ok() -> Result<(), String> {
+        Ok(())
+    }
+    let tests = || {
+        vec![
+            fixture_test("m::a", None, ok),
+            fixture_test("m::n::b", None, ok),
+            fixture_test("m::n::setup", Some(TestFixture::Setup), ok),
+            fixture_test("m::n::teardown", Some(TestFixture::Teardown), ok),
+            fixture_test("m::setup", Some(TestFixture::Setup), ok),
+            fixture_test("m::teardown", Some(TestFixture::Teardown), ok),
+            fixture_test("other", None, ok),
+        ]
+    };
+    let opts = TestOpts { run_tests: true, test_threads: Some(1), ..TestOpts::new() };
+    let names: Vec<String> = run_and_collect(&opts, tests())
+        .into_iter()
+        .map(|completed_test| completed_test.desc.name.to_string())
+        .collect();
+    assert_eq!(
+        names,
+        ["m::setup", "m::a", "m::n::setup", "m::n::b", "m::n::teardown", "m::teardown", "other"]
+    );
+
+    // Fixtures of modules without tests to run are left out.
+    let opts = TestOpts { filters: vec!["other".to_string()], ..opts };
+    let names: Vec<String> = run_and_collect(&opts, tests())
+        .into_iter()
+        .map(|completed_test| completed_test.desc.name.to_string())
+        .collect();
+    assert_eq!(names, ["other"]);
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+fn failed_setup_fails_the_tests_of_its_module() {
+    fn ok() -> Result<(), String> {
+        Ok(())
+    }
+    fn fail() -> Result<(), String> {
+        println!("could not connect");
+        Err("setup failed".to_string())
+    }
+    for test_threads in [1, 2] {
+        let tests = vec![
+            fixture_test("m::a", None, ok),
+            fixture_test("m::b", None, ok),
+            fixture_test("m::setup", Some(TestFixture::Setup), fail),
+            fixture_test("m::teardown", Some(TestFixture::Teardown), ok),
+            fixture_test("other", None, ok),
+        ];
+        let opts =
+            TestOpts { run_tests: true, test_threads: Some(test_threads), ..TestOpts::new() };
+        let mut results = run_and_collect(&opts, tests);
+        results.sort_by_key(|completed_test| completed_test.desc.name.to_string());
+        let results: Vec<(String, TestResult, String)> = results
+            .into_iter()
+            .map(|completed_test| {
+                let name = completed_test.desc.name.to_string();
+                let stdout = String::from_utf8(completed_test.stdout).unwrap();
+                (name, completed_test.result, stdout)
+            })
+            .collect();
+        let failed = TrFailedMsg("setup fixture `m::setup` failed".to_string());
+        let stdout = "could not connect\n".to_string();
+        assert_eq!(results[0], ("m::a".to_string(), failed.clone(), stdout.clone()));
+        assert_eq!(results[1], ("m::b".to_string(), failed, stdout.clone()));
+        assert_eq!(results[2], ("m::setup".to_string(), TrFailed, stdout));
+        assert_eq!(results[3], ("m::teardown".to_string(), TrIgnored, String::new()));
+        assert_eq!(results[4], ("other".to_string(), TrOk, String::new()));
+    }
+}
+
+#[cfg(not(bootstrap))]
+fn resource_test(
+    name: &'static str,
+    resources: &'static [&'static str],
+    testfn: fn() -> Result<(), String>,
+) -> TestDescAndFn {
+    TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            test_type: TestType::Unknown,
+            timeout: None,
+            fixture: None,
+            resources,
+        },
+        testfn: StaticTestFn(testfn),
+    }
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+fn tests_sharing_a_resource_do_not_run_concurrently() {
+    static HOLDERS: AtomicUsize = AtomicUsize::new(0);
+    static MAX_HOLDERS: AtomicUsize = AtomicUsize::new(0);
+    static OTHER_TEST_RAN: AtomicBool = AtomicBool::new(false);
+    fn uses_resource() -> Result<(), String> {
+        let holders = HOLDERS.fetch_add(1, Ordering::SeqCst) + 1;
+        MAX_HOLDERS.fetch_max(holders, Ordering::SeqCst);
+        // Keep the resource until the test that doesn't need it ran in parallel.
+        while !OTHER_TEST_RAN.load(Ordering::SeqCst) {
+            thread::yield_now();
+        }
+        HOLDERS.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+    }
+    fn uses_nothing() -> Result<(), String> {
+        OTHER_TEST_RAN.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+    let tests = vec![
+        resource_test("a", &["port"], uses_resource),
+        resource_test("b", &["port"], uses_resource),
+        resource_test("c", &["port"], uses_resource),
+        resource_test("d", &[], uses_nothing),
+    ];
+    let opts = TestOpts { run_tests: true, test_threads: Some(4), ..TestOpts::new() };
+    for completed_test in run_and_collect(&opts, tests) {
+        assert_eq!(completed_test.result, TrOk);
+    }
+    assert_eq!(MAX_HOLDERS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+fn resources_conflict_while_held() {
+    fn uses_nothing() -> Result<(), String> {
+        Ok(())
+    }
+    let port = resource_test("port", &["port", "env"], uses_nothing).desc;
+    let env = resource_test("env", &["env"], uses_nothing).desc;
+    let other = resource_test("other", &["file"], uses_nothing).desc;
+    let none = resource_test("none", &[], uses_nothing).desc;
+
+    let mut resources = Resources::default();
+    resources.acquire(TestId(0), &port);
+    assert!(resources.conflicts(&port));
+    assert!(resources.conflicts(&env));
+    assert!(!resources.conflicts(&other));
+    assert!(!resources.conflicts(&none));
+
+    // Tests without resources never hold any.
+    resources.acquire(TestId(1), &none);
+    resources.release(TestId(0));
+    assert!(!resources.conflicts(&env));
+}
+
+#[test]
+fn test_retries_are_limited() {
+    fn f() -> Result<(), String> {
+        Err("always fails".to_string())
+    }
+    let (failed_attempts, result) = run_with_retries(2, 1, StaticTestFn(f));
+    assert_eq!(failed_attempts, 2);
+    assert_ne!(result, TrOk);
+
+    // Closures can only be run once, so they are never retried, which the output points out.
+    for test_threads in [1, 2] {
+        let (failed_attempts, completed_test) =
+            run_with_retries_output(2, test_threads, DynTestFn(Box::new(f)));
+        assert_eq!(failed_attempts, 0);
+        assert_ne!(completed_test.result, TrOk);
+        let note = "note: this test was not retried, since it can only be run once\n";
+        assert!(String::from_utf8(completed_test.stdout).unwrap().ends_with(note));
+        assert!(String::from_utf8(completed_test.output.stderr).unwrap().ends_with(note));
+    }
+}
+
 #[test]
 fn parse_include_ignored_flag() {
     let args = vec!["progname".to_string(), "filter".to_string(), "--include-ignored".to_string()];
@@ -550,6 +1034,12 @@ pub fn exclude_should_panic_option() {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynTestFn(Box::new(move || Ok(()))),
     });
@@ -579,6 +1069,12 @@ fn tests() -> Vec<TestDescAndFn> {
                     compile_fail: false,
                     no_run: false,
                     test_type: TestType::Unknown,
+                    #[cfg(not(bootstrap))]
+                    timeout: None,
+                    #[cfg(not(bootstrap))]
+                    fixture: None,
+                    #[cfg(not(bootstrap))]
+                    resources: &[],
                 },
                 testfn: DynTestFn(Box::new(move || Ok(()))),
             })
@@ -675,6 +1171,12 @@ fn testfn() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                timeout: None,
+                #[cfg(not(bootstrap))]
+                fixture: None,
+                #[cfg(not(bootstrap))]
+                resources: &[],
             },
             testfn: DynTestFn(Box::new(testfn)),
         };
@@ -748,6 +1250,101 @@ pub fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+#[test]
+pub fn shards_partition_tests() {
+    let names: Vec<String> =
+        sample_tests().into_iter().map(|test| test.desc.name.to_string()).collect();
+
+    let mut sharded = Vec::new();
+    for index in 0..3 {
+        let opts = TestOpts { shard: Some(TestShard { index, count: 3 }), ..TestOpts::new() };
+        let shard: Vec<String> = filter_tests(&opts, sample_tests())
+            .into_iter()
+            .map(|test| test.desc.name.to_string())
+            .collect();
+        assert!(shard.len() < names.len());
+        sharded.extend(shard);
+    }
+
+    sharded.sort();
+    let mut names = names;
+    names.sort();
+    assert_eq!(sharded, names);
+}
+
+#[test]
+pub fn shard_assignment_is_stable() {
+    // The assignment must not change between releases, as separate runs may use different
+    // versions of libtest.
+    use crate::helpers::shard::is_in_shard;
+
+    assert!(is_in_shard("a", TestShard { index: 5, count: 7 }));
+    assert!(is_in_shard("test::sort_tests", TestShard { index: 2, count: 7 }));
+    assert!(!is_in_shard("test::sort_tests", TestShard { index: 0, count: 7 }));
+}
+
+#[test]
+fn parse_shard_options() {
+    let parse = |args: &[&str]| {
+        let mut full_args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        full_args.extend(args.iter().map(|arg| arg.to_string()));
+        parse_opts(&full_args).unwrap()
+    };
+
+    let opts = parse(&["--shard-index=1", "--shard-count=4"]).unwrap();
+    assert_eq!(opts.shard, Some(TestShard { index: 1, count: 4 }));
+
+    assert!(parse(&["--shard-index=1"]).is_err());
+    assert!(parse(&["--shard-count=4"]).is_err());
+    assert!(parse(&["--shard-index=4", "--shard-count=4"]).is_err());
+    assert!(parse(&["--shard-index=0", "--shard-count=0"]).is_err());
+}
+
+#[test]
+fn parse_run_failed_options() {
+    let parse = |args: &[&str]| {
+        let mut full_args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        full_args.extend(args.iter().map(|arg| arg.to_string()));
+        parse_opts(&full_args).unwrap()
+    };
+
+    assert_eq!(parse(&[]).unwrap().run_failed, RunFailed::No);
+    assert_eq!(parse(&["--failed-first"]).unwrap().run_failed, RunFailed::First);
+    assert_eq!(parse(&["--only-failed"]).unwrap().run_failed, RunFailed::Only);
+    assert!(parse(&["--failed-first", "--only-failed"]).is_err());
+}
+
+#[test]
+fn last_run_reorders_and_filters_tests() {
+    use crate::last_run::LastRun;
+
+    let names = |tests: &[TestDescAndFn]| -> Vec<String> {
+        tests.iter().map(|test| test.desc.name.to_string()).collect()
+    };
+    let last_run = LastRun::with_failed(
+        PathBuf::new(),
+        &["test::sort_tests", "isize::test_pow", "test::removed_test"],
+    );
+
+    let mut tests = sample_tests();
+    assert_eq!(last_run.apply(RunFailed::First, &mut tests), 0);
+    let mut expected = names(&sample_tests());
+    expected.retain(|name| name != "test::sort_tests" && name != "isize::test_pow");
+    expected.insert(0, "isize::test_pow".to_string());
+    expected.insert(1, "test::sort_tests".to_string());
+    assert_eq!(names(&tests), expected);
+
+    let mut tests = sample_tests();
+    assert_eq!(last_run.apply(RunFailed::Only, &mut tests), sample_tests().len() - 2);
+    assert_eq!(names(&tests), ["isize::test_pow", "test::sort_tests"]);
+
+    // Tests run as usual when none of them failed last time.
+    let last_run = LastRun::with_failed(PathBuf::new(), &["test::removed_test"]);
+    let mut tests = sample_tests();
+    assert_eq!(last_run.apply(RunFailed::Only, &mut tests), 0);
+    assert_eq!(names(&tests), names(&sample_tests()));
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -809,6 +1406,12 @@ fn f(_: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -837,6 +1440,12 @@ fn f(b: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -858,6 +1467,12 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     };
 
     let test_b = TestDesc {
@@ -873,6 +1488,12 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     };
 
     let mut out = PrettyFormatter::new(OutputLocation::Raw(Vec::new()), false, 10, false, None);
@@ -892,6 +1513,8 @@ fn should_sort_failures_before_printing_them() {
         not_failures: Vec::new(),
         ignores: Vec::new(),
         time_failures: Vec::new(),
+        retries: Vec::new(),
+        flaky: Vec::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -905,6 +1528,44 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn json_reports_stdout_and_stderr_separately() {
+    let desc = TestDesc {
+        name: StaticTestName("a"),
+        ignore: false,
+        ignore_message: None,
+        source_file: "",
+        start_line: 0,
+        start_col: 0,
+        end_line: 0,
+        end_col: 0,
+        should_panic: ShouldPanic::No,
+        compile_fail: false,
+        no_run: false,
+        test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
+    };
+    let output = SplitOutput { stdout: b"out\n".to_vec(), stderr: b"err\n".to_vec() };
+    let st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()));
+    out.write_result(&desc, &TrFailed, None, b"out\nerr\n", &output, &st).unwrap();
+    let s = match out.output_location() {
+        OutputLocation::Raw(m) => String::from_utf8_lossy(m).into_owned(),
+        OutputLocation::Pretty(_) => unreachable!(),
+    };
+
+    assert_eq!(
+        s.trim_end(),
+        r#"{ "type": "test", "name": "a", "event": "failed", "stdout": "out\n", "stderr": "err\n" }"#
+    );
+}
+
 #[test]
 #[cfg(not(target_os = "emscripten"))]
 fn test_dyn_bench_returning_err_fails_when_run_as_test() {
@@ -925,6 +1586,12 @@ fn f(_: &mut Bencher) -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            timeout: None,
+            #[cfg(not(bootstrap))]
+            fixture: None,
+            #[cfg(not(bootstrap))]
+            resources: &[],
         },
         testfn: DynBenchFn(Box::new(f)),
     };
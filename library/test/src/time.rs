@@ -61,6 +61,37 @@ pub fn get_default_test_timeout() -> Instant {
     Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S)
 }
 
+/// Returns the duration after which the test should be stopped and reported
+/// as timed out, if any. A timeout set on the test itself takes precedence
+/// over the one provided on the command line.
+pub fn get_test_kill_timeout(test: &TestDesc, default: Option<Duration>) -> Option<Duration> {
+    #[cfg(not(bootstrap))]
+    let timeout = test.timeout;
+    #[cfg(bootstrap)]
+    let timeout = {
+        let _ = test;
+        None
+    };
+
+    timeout.or(default)
+}
+
+/// Parses a duration such as `500ms`, `30s` or `2m`. A number without a unit
+/// is interpreted as seconds.
+///
+/// The `#[test_timeout]` attribute is parsed by `rustc_builtin_macros` with a
+/// copy of this function, which has to accept exactly the same values.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let number = u64::from_str(number).ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
 /// The measured execution time of a unit test.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TestExecTime(pub Duration);
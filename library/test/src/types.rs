@@ -3,6 +3,7 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
 use super::__rust_begin_short_backtrace;
 use super::bench::Bencher;
@@ -28,6 +29,17 @@ pub enum TestType {
     Unknown,
 }
 
+/// Role of a test that runs around the other tests of its module instead of
+/// being a test of its own, set by the `#[test_fixture]` attribute.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TestFixture {
+    /// Runs before the first test of the module and its submodules. If it
+    /// fails, those tests are not run and are reported as failed.
+    Setup,
+    /// Runs after the last test of the module and its submodules.
+    Teardown,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum NamePadding {
     PadNone,
@@ -103,6 +115,17 @@ pub fn padding(&self) -> NamePadding {
         }
     }
 
+    /// Returns a copy of the test function if it can be run more than once, which is only the
+    /// case for plain function pointers.
+    pub(crate) fn try_clone(&self) -> Option<TestFn> {
+        match *self {
+            StaticTestFn(f) => Some(StaticTestFn(f)),
+            StaticBenchFn(f) => Some(StaticBenchFn(f)),
+            StaticBenchAsTestFn(f) => Some(StaticBenchAsTestFn(f)),
+            DynTestFn(..) | DynBenchFn(..) | DynBenchAsTestFn(..) => None,
+        }
+    }
+
     pub(crate) fn into_runnable(self) -> Runnable {
         match self {
             StaticTestFn(f) => Runnable::Test(RunnableTest::Static(f)),
@@ -208,6 +231,18 @@ pub struct TestDesc {
     pub compile_fail: bool,
     pub no_run: bool,
     pub test_type: TestType,
+    /// Time after which the test is stopped and reported as failed, overriding
+    /// the `--test-timeout` command line option.
+    #[cfg(not(bootstrap))]
+    pub timeout: Option<Duration>,
+    /// Whether this is a setup or teardown fixture of its module rather than a
+    /// test.
+    #[cfg(not(bootstrap))]
+    pub fixture: Option<TestFixture>,
+    /// Names of the resources, such as a port or an environment variable, that
+    /// the test can't share with the tests running concurrently.
+    #[cfg(not(bootstrap))]
+    pub resources: &'static [&'static str],
 }
 
 impl TestDesc {
@@ -224,12 +259,42 @@ pub fn padded_name(&self, column_count: usize, align: NamePadding) -> String {
         }
     }
 
+    /// Returns the fixture role of the test, if any.
+    pub fn fixture(&self) -> Option<TestFixture> {
+        #[cfg(not(bootstrap))]
+        let fixture = self.fixture;
+        #[cfg(bootstrap)]
+        let fixture = None;
+        fixture
+    }
+
+    /// Returns the resources the test needs exclusive access to.
+    pub fn resources(&self) -> &'static [&'static str] {
+        #[cfg(not(bootstrap))]
+        let resources = self.resources;
+        #[cfg(bootstrap)]
+        let resources = &[];
+        resources
+    }
+
+    /// Returns the module whose tests a fixture runs around, which is empty for a
+    /// fixture defined at the crate root.
+    pub fn fixture_scope(&self) -> &str {
+        let name = self.name.as_slice();
+        name.rfind("::").map_or("", |i| &name[..i])
+    }
+
     /// Returns None for ignored test or tests that are just run, otherwise returns a description of the type of test.
-    /// Descriptions include "should panic", "compile fail" and "compile".
+    /// Descriptions include "should panic", "compile fail", "compile", "setup" and "teardown".
     pub fn test_mode(&self) -> Option<&'static str> {
         if self.ignore {
             return None;
         }
+        match self.fixture() {
+            Some(TestFixture::Setup) => return Some("setup"),
+            Some(TestFixture::Teardown) => return Some("teardown"),
+            None => {}
+        }
         match self.should_panic {
             options::ShouldPanic::Yes | options::ShouldPanic::YesWithMessage(_) => {
                 return Some("should panic");
@@ -683,6 +683,7 @@ macro_rules! describe {
                 tool::UnstableBookGen,
                 tool::Tidy,
                 tool::Linkchecker,
+                tool::JsonDocDiff,
                 tool::CargoTest,
                 tool::Compiletest,
                 tool::RemoteTestServer,
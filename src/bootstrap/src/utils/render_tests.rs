@@ -200,6 +200,10 @@ fn render_suite_outcome(&self, outcome: Outcome<'_>, suite: &SuiteOutcome) {
                         println!("note: {message}");
                     }
                 }
+                if let Some(stderr) = &failure.stderr {
+                    println!("---- {} stderr ----", failure.name);
+                    println!("{stderr}");
+                }
             }
 
             println!("\nfailures:");
@@ -266,6 +270,7 @@ fn render_message(&mut self, message: Message) {
                     name: outcome.name.clone(),
                     exec_time: None,
                     stdout: None,
+                    stderr: None,
                     message: None,
                 };
                 self.render_test_outcome(Outcome::BenchOk, &fake_test_outcome);
@@ -396,5 +401,6 @@ struct TestOutcome {
     name: String,
     exec_time: Option<f64>,
     stdout: Option<String>,
+    stderr: Option<String>,
     message: Option<String>,
 }
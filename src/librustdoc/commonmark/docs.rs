@@ -0,0 +1,207 @@
+//! Rewriting of documentation comments for the Markdown backend.
+//!
+//! Unlike the HTML backend, which renders doc comments to HTML, this backend keeps them as
+//! Markdown and only edits the source text where it has to: intra-doc links are replaced by
+//! relative links to the generated files, hidden lines are removed from Rust code blocks and
+//! headings are shifted so they nest below the headings of the page they appear on.
+
+use std::ops::Range;
+
+use pulldown_cmark::{BrokenLink, CodeBlockKind, Event, LinkType, Parser, Tag};
+
+use crate::clean::RenderedLink;
+use crate::html::markdown::{main_body_opts, map_line, ErrorCodes, LangString};
+
+#[cfg(test)]
+mod tests;
+
+const MAX_HEADER_LEVEL: usize = 6;
+
+/// A link whose destination has to be rewritten.
+struct PendingLink<'a> {
+    range: Range<usize>,
+    link: &'a RenderedLink,
+    /// Whether the link text should be replaced by the link's display text (`[fn@f]` -> `[f]`).
+    replace_text: bool,
+    /// End of the link text in the source.
+    text_end: usize,
+}
+
+/// Rewrites the doc comment `md` so it can be embedded in a generated Markdown page.
+///
+/// `links` are the resolved intra-doc links of the item, with their `href` already relative to
+/// the page. Every heading is moved `heading_offset` levels down.
+pub(crate) fn render_docs(
+    md: &str,
+    links: &[RenderedLink],
+    heading_offset: usize,
+    custom_code_classes_in_docs: bool,
+) -> String {
+    if md.is_empty() {
+        return String::new();
+    }
+
+    let mut replacer = |broken_link: BrokenLink<'_>| {
+        links
+            .iter()
+            .find(|link| *link.original_text == *broken_link.reference)
+            .map(|link| (link.href.as_str().into(), link.tooltip.as_str().into()))
+    };
+    let p = Parser::new_with_broken_link_callback(md, main_body_opts(), Some(&mut replacer));
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut pending_link: Option<PendingLink<'_>> = None;
+    let mut code_block: Option<(Range<usize>, String)> = None;
+
+    for (event, range) in p.into_offset_iter() {
+        if let Some(ref mut pending) = pending_link {
+            if !matches!(event, Event::End(Tag::Link(..))) {
+                pending.text_end = pending.text_end.max(range.end);
+            }
+        }
+        match event {
+            Event::Start(Tag::Link(link_type, dest, _)) => {
+                // Links resolved by the broken link callback already point to the right place,
+                // but the link still has to be written out since it had no destination.
+                let (link, replace_text) = match link_type {
+                    LinkType::ShortcutUnknown | LinkType::CollapsedUnknown => {
+                        (links.iter().find(|link| *link.href == *dest), true)
+                    }
+                    LinkType::ReferenceUnknown => {
+                        (links.iter().find(|link| *link.href == *dest), false)
+                    }
+                    _ => (links.iter().find(|link| *link.original_text == *dest), false),
+                };
+                if let Some(link) = link {
+                    let mut range = range;
+                    // The source range of a collapsed link does not include its trailing `[]`.
+                    if matches!(link_type, LinkType::Collapsed | LinkType::CollapsedUnknown)
+                        && md[range.end..].starts_with("[]")
+                    {
+                        range.end += 2;
+                    }
+                    let text_end = range.start + 1;
+                    pending_link = Some(PendingLink { range, link, replace_text, text_end });
+                }
+            }
+            Event::End(Tag::Link(..)) => {
+                if let Some(PendingLink { range, link, replace_text, text_end }) =
+                    pending_link.take()
+                {
+                    let text = &md[range.start + 1..text_end];
+                    let text = if replace_text && text == &*link.original_text {
+                        display_text(link)
+                    } else {
+                        text.to_owned()
+                    };
+                    edits.push((range, format!("[{text}]({href})", href = link.href)));
+                }
+            }
+            Event::Start(Tag::Heading(level, ..)) => {
+                let level = level as usize;
+                let new_level = MAX_HEADER_LEVEL.min(level + heading_offset);
+                if new_level == level {
+                    continue;
+                }
+                let heading = &md[range.clone()];
+                if heading.starts_with('#') {
+                    edits.push((range.start..range.start, "#".repeat(new_level - level)));
+                } else {
+                    // Setext headings only have two levels, so turn them into ATX headings.
+                    let heading = heading.trim_end();
+                    let underline = heading.rfind('\n').unwrap_or(0);
+                    edits.push((range.start..range.start, format!("{} ", "#".repeat(new_level))));
+                    for (i, _) in heading[..underline].match_indices('\n') {
+                        edits.push((range.start + i..range.start + i + 1, " ".to_owned()));
+                    }
+                    edits.push((
+                        range.start + underline..range.start + heading.len(),
+                        String::new(),
+                    ));
+                }
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                let lang = LangString::parse_without_check(
+                    info,
+                    ErrorCodes::No,
+                    false,
+                    custom_code_classes_in_docs,
+                );
+                if lang.rust {
+                    code_block = Some((range, String::new()));
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, ref mut code)) = code_block {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((range, code)) = code_block.take() {
+                    edits.push((range.clone(), rust_code_block(md, range, &code)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(md.len() * 11 / 10);
+    let mut last = 0;
+    for (range, replacement) in edits {
+        // Edits never overlap unless a link spans the lines of a setext heading; keep the first.
+        if range.start < last {
+            continue;
+        }
+        out.push_str(&md[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&md[last..]);
+    out
+}
+
+/// Returns the text to show for a shortcut intra-doc link, with its disambiguator removed.
+fn display_text(link: &RenderedLink) -> String {
+    let original = &*link.original_text;
+    if original.len() > 1 && original.starts_with('`') && original.ends_with('`') {
+        format!("`{}`", link.new_text)
+    } else {
+        link.new_text.to_string()
+    }
+}
+
+/// Writes the Rust code block at `range` back out without its hidden lines.
+fn rust_code_block(md: &str, range: Range<usize>, code: &str) -> String {
+    let block = &md[range.clone()];
+    let fence_char = if block.starts_with('~') { '~' } else { '`' };
+    let fence_len = block.chars().take_while(|&c| c == fence_char).count();
+    let fence = fence_char.to_string().repeat(fence_len);
+
+    // Code blocks inside block quotes or list items have to keep the same prefix on every line.
+    let line_start = md[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let prefix: String =
+        md[line_start..range.start].chars().map(|c| if c == '>' { '>' } else { ' ' }).collect();
+
+    let mut out = format!("{fence}rust\n");
+    for line in code.lines().filter_map(|l| map_line(l).for_html()) {
+        out.push_str(&prefix);
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str(&prefix);
+    out.push_str(&fence);
+    out
+}
+
+/// Returns the first paragraph of `md` on a single line, for use in item listings.
+pub(crate) fn summary(md: &str) -> String {
+    Parser::new_ext(md, main_body_opts())
+        .into_offset_iter()
+        .find_map(|(event, range)| match event {
+            Event::Start(Tag::Paragraph) => Some(md[range].trim_end()),
+            _ => None,
+        })
+        .map(|paragraph| paragraph.lines().map(str::trim).collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
@@ -0,0 +1,72 @@
+use super::{render_docs, summary};
+use crate::clean::RenderedLink;
+
+fn link(original_text: &str, new_text: &str, href: &str) -> RenderedLink {
+    RenderedLink {
+        original_text: original_text.into(),
+        new_text: new_text.into(),
+        href: href.to_owned(),
+        tooltip: String::new(),
+    }
+}
+
+#[test]
+fn test_intra_doc_links() {
+    fn t(input: &str, expect: &str) {
+        let links = [
+            link("Foo", "Foo", "struct.Foo.md"),
+            link("`fn@bar`", "bar", "../fn.bar.md"),
+            link("crate::baz", "crate::baz", "baz/index.md"),
+        ];
+        assert_eq!(render_docs(input, &links, 0, false), expect);
+    }
+
+    t("See [Foo].", "See [Foo](struct.Foo.md).");
+    t("See [Foo][].", "See [Foo](struct.Foo.md).");
+    t("See [`fn@bar`].", "See [`bar`](../fn.bar.md).");
+    t("See [the function][`fn@bar`].", "See [the function](../fn.bar.md).");
+    t("See [the module](crate::baz).", "See [the module](baz/index.md).");
+    t("See [*the* `baz`](crate::baz \"title\").", "See [*the* `baz`](baz/index.md).");
+    t("See [baz].\n\n[baz]: crate::baz", "See [baz](baz/index.md).\n\n[baz]: crate::baz");
+    t("See [Unknown] and <https://example.com>.", "See [Unknown] and <https://example.com>.");
+    t("Not a link: `[Foo]`.", "Not a link: `[Foo]`.");
+}
+
+#[test]
+fn test_code_blocks() {
+    fn t(input: &str, expect: &str) {
+        assert_eq!(render_docs(input, &[], 0, false), expect);
+    }
+
+    t(
+        "```\n# fn main() {\nlet x = 1;\n## not hidden\n# }\n```",
+        "```rust\nlet x = 1;\n# not hidden\n```",
+    );
+    t("```should_panic\n# let y = 2;\npanic!();\n```\nafter", "```rust\npanic!();\n```\nafter");
+    t("~~~~\n# hidden\nshown\n~~~~", "~~~~rust\nshown\n~~~~");
+    t("```text\n# not hidden\n```", "```text\n# not hidden\n```");
+    t("> ```\n> # hidden\n> shown\n> ```", "> ```rust\n> shown\n> ```");
+    t("- item\n\n  ```\n  # hidden\n  shown\n  ```", "- item\n\n  ```rust\n  shown\n  ```");
+}
+
+#[test]
+fn test_headings() {
+    fn t(input: &str, offset: usize, expect: &str) {
+        assert_eq!(render_docs(input, &[], offset, false), expect);
+    }
+
+    t("# Examples\n\ntext", 1, "## Examples\n\ntext");
+    t("## Panics", 2, "#### Panics");
+    t("##### Deep", 3, "###### Deep");
+    t("# Same", 0, "# Same");
+    t("Title\n=====\n\ntext", 1, "## Title\n\ntext");
+    t("Long\ntitle\n---", 1, "### Long title");
+    t("```\n# not a heading\n```", 1, "```rust\n```");
+}
+
+#[test]
+fn test_summary() {
+    assert_eq!(summary("First line\ncontinued.\n\nSecond paragraph."), "First line continued.");
+    assert_eq!(summary("# Heading\n\nBody with [a link](foo.md)."), "Body with [a link](foo.md).");
+    assert_eq!(summary(""), "");
+}
@@ -0,0 +1,655 @@
+//! Plain text printing of item signatures for the Markdown backend.
+//!
+//! The printers in [`crate::html::format`] need a full HTML [`Context`] to resolve links, so the
+//! Markdown backend prints declarations as plain Rust source instead.
+//!
+//! [`Context`]: crate::html::render::Context
+
+use std::fmt::{self, Write};
+
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir as hir;
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::hygiene::MacroKind;
+use rustc_span::symbol::{kw, Symbol};
+
+use crate::clean::{self, PrimitiveType};
+use crate::html::format::{
+    comma_sep, display_fn, print_abi_with_space, print_constness_with_space, print_default_space,
+    visibility_to_src_with_space, PrintWithSpace,
+};
+
+/// A clean type that is printed as plain Rust source.
+pub(crate) struct Plain<'a, 'tcx, T: ?Sized> {
+    inner: &'a T,
+    tcx: TyCtxt<'tcx>,
+}
+
+pub(crate) fn plain<'a, 'tcx, T: ?Sized>(inner: &'a T, tcx: TyCtxt<'tcx>) -> Plain<'a, 'tcx, T> {
+    Plain { inner, tcx }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::Type> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tcx = self.tcx;
+        match self.inner {
+            clean::Generic(name) => f.write_str(name.as_str()),
+            // Paths like `T::Output` and `Self::Output` should be printed with all segments.
+            clean::Type::Path { path } => print_path(f, path, path.is_assoc_ty(), tcx),
+            clean::DynTrait(bounds, lt) => {
+                f.write_str("dyn ")?;
+                for (i, bound) in bounds.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" + ")?;
+                    }
+                    write!(f, "{}", plain(bound, tcx))?;
+                }
+                if let Some(lt) = lt {
+                    write!(f, " + {}", lt.print())?;
+                }
+                Ok(())
+            }
+            clean::Infer => f.write_str("_"),
+            clean::Primitive(PrimitiveType::Never) => f.write_str("!"),
+            clean::Primitive(prim) => f.write_str(prim.as_sym().as_str()),
+            clean::BareFunction(decl) => {
+                print_hrtb(f, &decl.generic_params, tcx)?;
+                write!(
+                    f,
+                    "{}{:#}fn{}",
+                    decl.unsafety.print_with_space(),
+                    print_abi_with_space(decl.abi),
+                    plain(&decl.decl, tcx),
+                )
+            }
+            clean::Tuple(tys) => match &tys[..] {
+                [] => f.write_str("()"),
+                [one] => write!(f, "({},)", plain(one, tcx)),
+                many => write!(f, "({})", comma_sep(many.iter().map(|t| plain(t, tcx)), true)),
+            },
+            clean::Slice(t) => write!(f, "[{}]", plain(&**t, tcx)),
+            clean::Array(t, n) => write!(f, "[{}; {n}]", plain(&**t, tcx)),
+            clean::RawPointer(m, t) => {
+                let m = match m {
+                    hir::Mutability::Mut => "mut",
+                    hir::Mutability::Not => "const",
+                };
+                write!(f, "*{m} {}", plain(&**t, tcx))
+            }
+            clean::BorrowedRef { lifetime, mutability, type_ } => {
+                f.write_str("&")?;
+                if let Some(lt) = lifetime {
+                    write!(f, "{} ", lt.print())?;
+                }
+                f.write_str(mutability.print_with_space())?;
+                let needs_parens = match **type_ {
+                    clean::DynTrait(ref bounds, ref trait_lt) => {
+                        bounds.len() > 1 || trait_lt.is_some()
+                    }
+                    clean::ImplTrait(ref bounds) => bounds.len() > 1,
+                    _ => false,
+                };
+                if needs_parens {
+                    write!(f, "({})", plain(&**type_, tcx))
+                } else {
+                    write!(f, "{}", plain(&**type_, tcx))
+                }
+            }
+            clean::ImplTrait(bounds) => write!(f, "impl {}", plain(&bounds[..], tcx)),
+            clean::QPath(qpath) => {
+                let clean::QPathData { assoc, self_type, trait_, should_show_cast } = &**qpath;
+                if let Some(trait_) = trait_
+                    && *should_show_cast
+                {
+                    write!(f, "<{} as {}>::", plain(self_type, tcx), plain(trait_, tcx))?;
+                } else {
+                    write!(f, "{}::", plain(self_type, tcx))?;
+                }
+                write!(f, "{}{}", assoc.name, plain(&assoc.args, tcx))
+            }
+        }
+    }
+}
+
+fn print_path(
+    f: &mut fmt::Formatter<'_>,
+    path: &clean::Path,
+    print_all: bool,
+    tcx: TyCtxt<'_>,
+) -> fmt::Result {
+    let last = path.segments.last().unwrap();
+    if print_all {
+        for seg in &path.segments[..path.segments.len() - 1] {
+            write!(f, "{}::", if seg.name == kw::PathRoot { "" } else { seg.name.as_str() })?;
+        }
+    }
+    write!(f, "{}{}", last.name, plain(&last.args, tcx))
+}
+
+fn print_hrtb(
+    f: &mut fmt::Formatter<'_>,
+    params: &[clean::GenericParamDef],
+    tcx: TyCtxt<'_>,
+) -> fmt::Result {
+    if params.is_empty() {
+        return Ok(());
+    }
+    write!(f, "for<{}> ", comma_sep(params.iter().map(|p| plain(p, tcx)), true))
+}
+
+impl fmt::Display for Plain<'_, '_, clean::Path> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        print_path(f, self.inner, false, self.tcx)
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::GenericArgs> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tcx = self.tcx;
+        match self.inner {
+            clean::GenericArgs::AngleBracketed { args, bindings } => {
+                if args.is_empty() && bindings.is_empty() {
+                    return Ok(());
+                }
+                let args = args.iter().map(|arg| plain(arg, tcx).to_string());
+                let bindings = bindings.iter().map(|binding| plain(binding, tcx).to_string());
+                write!(f, "<{}>", comma_sep(args.chain(bindings), true))
+            }
+            clean::GenericArgs::Parenthesized { inputs, output } => {
+                write!(f, "({})", comma_sep(inputs.iter().map(|ty| plain(ty, tcx)), true))?;
+                if let Some(ty) = output {
+                    write!(f, " -> {}", plain(&**ty, tcx))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::GenericArg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            clean::GenericArg::Lifetime(lt) => write!(f, "{}", lt.print()),
+            clean::GenericArg::Type(ty) => write!(f, "{}", plain(ty, self.tcx)),
+            clean::GenericArg::Const(ct) => write!(f, "{:#}", ct.print(self.tcx)),
+            clean::GenericArg::Infer => f.write_str("_"),
+        }
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::TypeBinding> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let binding = self.inner;
+        write!(f, "{}{}", binding.assoc.name, plain(&binding.assoc.args, self.tcx))?;
+        match &binding.kind {
+            clean::TypeBindingKind::Equality { term } => {
+                write!(f, " = {}", plain(term, self.tcx))
+            }
+            clean::TypeBindingKind::Constraint { bounds } if !bounds.is_empty() => {
+                write!(f, ": {}", plain(&bounds[..], self.tcx))
+            }
+            clean::TypeBindingKind::Constraint { .. } => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::Term> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            clean::Term::Type(ty) => write!(f, "{}", plain(ty, self.tcx)),
+            clean::Term::Constant(ct) => write!(f, "{:#}", ct.print(self.tcx)),
+        }
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::PolyTrait> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        print_hrtb(f, &self.inner.generic_params, self.tcx)?;
+        write!(f, "{}", plain(&self.inner.trait_, self.tcx))
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, [clean::GenericBound]> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bounds_dup = FxHashSet::default();
+        for (i, bound) in self.inner.iter().filter(|b| bounds_dup.insert(*b)).enumerate() {
+            if i > 0 {
+                f.write_str(" + ")?;
+            }
+            match bound {
+                clean::GenericBound::Outlives(lt) => write!(f, "{}", lt.print())?,
+                clean::GenericBound::TraitBound(ty, modifier) => {
+                    let modifier_str = match modifier {
+                        hir::TraitBoundModifier::None => "",
+                        hir::TraitBoundModifier::Maybe => "?",
+                        hir::TraitBoundModifier::Negative => "!",
+                        // ~const is experimental; do not display those bounds in rustdoc
+                        hir::TraitBoundModifier::MaybeConst => "",
+                    };
+                    write!(f, "{modifier_str}{}", plain(ty, self.tcx))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::GenericParamDef> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let param = self.inner;
+        match &param.kind {
+            clean::GenericParamDefKind::Lifetime { outlives } => {
+                f.write_str(param.name.as_str())?;
+                if !outlives.is_empty() {
+                    let outlives =
+                        outlives.iter().map(|lt| lt.print().to_string()).collect::<Vec<_>>();
+                    write!(f, ": {}", outlives.join(" + "))?;
+                }
+                Ok(())
+            }
+            clean::GenericParamDefKind::Type { bounds, default, .. } => {
+                f.write_str(param.name.as_str())?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", plain(&bounds[..], self.tcx))?;
+                }
+                if let Some(ty) = default {
+                    write!(f, " = {}", plain(&**ty, self.tcx))?;
+                }
+                Ok(())
+            }
+            clean::GenericParamDefKind::Const { ty, default, .. } => {
+                write!(f, "const {}: {}", param.name, plain(&**ty, self.tcx))?;
+                if let Some(default) = default {
+                    write!(f, " = {default}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::Generics> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut real_params =
+            self.inner.params.iter().filter(|p| !p.is_synthetic_param()).peekable();
+        if real_params.peek().is_none() {
+            return Ok(());
+        }
+        write!(f, "<{}>", comma_sep(real_params.map(|p| plain(p, self.tcx)), true))
+    }
+}
+
+impl fmt::Display for Plain<'_, '_, clean::FnDecl> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tcx = self.tcx;
+        let decl = self.inner;
+        f.write_str("(")?;
+        for (i, input) in decl.inputs.values.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match input.to_self() {
+                Some(clean::SelfValue) => f.write_str("self")?,
+                Some(clean::SelfBorrowed(lt, mutability)) => {
+                    f.write_str("&")?;
+                    if let Some(lt) = lt {
+                        write!(f, "{} ", lt.print())?;
+                    }
+                    write!(f, "{}self", mutability.print_with_space())?;
+                }
+                Some(clean::SelfExplicit(ty)) => write!(f, "self: {}", plain(&ty, tcx))?,
+                None => {
+                    if input.is_const {
+                        f.write_str("const ")?;
+                    }
+                    write!(f, "{}: {}", input.name, plain(&input.type_, tcx))?;
+                }
+            }
+        }
+        if decl.c_variadic {
+            f.write_str(", ...")?;
+        }
+        f.write_str(")")?;
+        match &decl.output {
+            clean::Tuple(tys) if tys.is_empty() => Ok(()),
+            ty => write!(f, " -> {}", plain(ty, tcx)),
+        }
+    }
+}
+
+/// Prints the where clause of `generics` on its own lines, as rustfmt would.
+fn where_clause(generics: &clean::Generics, tcx: TyCtxt<'_>) -> String {
+    let mut out = String::new();
+    for pred in &generics.where_predicates {
+        let pred = display_fn(|f| match pred {
+            clean::WherePredicate::BoundPredicate { bounds, .. } if bounds.is_empty() => Ok(()),
+            clean::WherePredicate::BoundPredicate { ty, bounds, bound_params } => {
+                print_hrtb(f, bound_params, tcx)?;
+                write!(f, "{}: {}", plain(ty, tcx), plain(&bounds[..], tcx))
+            }
+            clean::WherePredicate::RegionPredicate { lifetime, bounds } => {
+                write!(f, "{}: {}", lifetime.print(), plain(&bounds[..], tcx))
+            }
+            clean::WherePredicate::EqPredicate { lhs, rhs } => {
+                write!(f, "{} == {}", plain(lhs, tcx), plain(rhs, tcx))
+            }
+        })
+        .to_string();
+        if !pred.is_empty() {
+            let _ = write!(out, "\n    {pred},");
+        }
+    }
+    if out.is_empty() {
+        out
+    } else {
+        format!("\nwhere{out}")
+    }
+}
+
+/// Starts the body of a declaration, on its own line if there is a where clause.
+fn open_brace(out: &mut String, where_clause: &str) {
+    out.push_str(where_clause);
+    out.push_str(if where_clause.is_empty() { " {\n" } else { "\n{\n" });
+}
+
+/// Prints an `impl` block header, such as `impl<T: Clone> Clone for Vec<T>`.
+pub(crate) fn impl_header(i: &clean::Impl, tcx: TyCtxt<'_>) -> String {
+    let mut out = format!("{}impl{}", i.unsafety.print_with_space(), plain(&i.generics, tcx));
+    out.push(' ');
+    if let Some(trait_) = &i.trait_ {
+        if let ty::ImplPolarity::Negative = i.polarity {
+            out.push('!');
+        }
+        let _ = write!(out, "{} for ", plain(trait_, tcx));
+    }
+    let for_ = i.kind.as_blanket_ty().unwrap_or(&i.for_);
+    let _ = write!(out, "{}{}", plain(for_, tcx), where_clause(&i.generics, tcx));
+    out
+}
+
+fn visibility(item: &clean::Item, tcx: TyCtxt<'_>) -> String {
+    match item.item_id.as_def_id() {
+        Some(did) => visibility_to_src_with_space(item.visibility(tcx), tcx, did).to_string(),
+        None => String::new(),
+    }
+}
+
+fn fn_signature(item: &clean::Item, f: &clean::Function, tcx: TyCtxt<'_>) -> String {
+    let header = item.fn_header(tcx).expect("printing a function which isn't a function");
+    format!(
+        "{vis}{defaultness}{constness}{asyncness}{unsafety}{abi:#}fn {name}{generics}{decl}{where_clause}",
+        vis = visibility(item, tcx),
+        defaultness = print_default_space(item.is_default()),
+        constness = print_constness_with_space(&header.constness, item.const_stability(tcx)),
+        asyncness = header.asyncness.print_with_space(),
+        unsafety = header.unsafety.print_with_space(),
+        abi = print_abi_with_space(header.abi),
+        name = item.name.unwrap(),
+        generics = plain(&f.generics, tcx),
+        decl = plain(&f.decl, tcx),
+        where_clause = where_clause(&f.generics, tcx),
+    )
+}
+
+/// Prints the fields of a struct, union or struct variant and the closing brace.
+fn braced_fields(out: &mut String, fields: &[clean::Item], indent: &str, tcx: TyCtxt<'_>) {
+    let mut has_stripped = false;
+    for field in fields {
+        match *field.kind {
+            clean::StructFieldItem(ref ty) => {
+                let _ = writeln!(
+                    out,
+                    "{indent}    {vis}{name}: {ty},",
+                    vis = visibility(field, tcx),
+                    name = field.name.unwrap(),
+                    ty = plain(ty, tcx),
+                );
+            }
+            _ => has_stripped = true,
+        }
+    }
+    if has_stripped {
+        let _ = writeln!(out, "{indent}    /* private fields */");
+    }
+    let _ = write!(out, "{indent}}}");
+}
+
+/// Prints the fields of a tuple struct or tuple variant between parentheses.
+fn tuple_fields(out: &mut String, fields: &[clean::Item], tcx: TyCtxt<'_>) {
+    let fields = fields.iter().map(|field| match *field.kind {
+        clean::StructFieldItem(ref ty) => format!("{}{}", visibility(field, tcx), plain(ty, tcx)),
+        _ => "_".to_owned(),
+    });
+    let _ = write!(out, "({})", comma_sep(fields, true));
+}
+
+fn variant(name: Symbol, variant: &clean::Variant, indent: &str, tcx: TyCtxt<'_>) -> String {
+    let mut out = name.to_string();
+    match &variant.kind {
+        clean::VariantKind::CLike => {
+            if let Some(expr) = variant.discriminant.as_ref().and_then(|d| d.expr(tcx)) {
+                let _ = write!(out, " = {expr}");
+            }
+        }
+        clean::VariantKind::Tuple(fields) => tuple_fields(&mut out, fields, tcx),
+        clean::VariantKind::Struct(s) => {
+            out.push_str(" {\n");
+            braced_fields(&mut out, &s.fields, indent, tcx);
+        }
+    }
+    out
+}
+
+/// Prints the declaration of `item` as it would appear in Rust source, without any body.
+///
+/// Returns `None` for items that don't have a declaration to show, such as modules.
+pub(crate) fn item_signature(item: &clean::Item, tcx: TyCtxt<'_>) -> Option<String> {
+    let name = item.name?;
+    let vis = visibility(item, tcx);
+    let mut out = String::new();
+    let kind = match *item.kind {
+        clean::StrippedItem(ref kind) => &**kind,
+        ref kind => kind,
+    };
+    match kind {
+        clean::FunctionItem(f) | clean::ForeignFunctionItem(f) => {
+            out = fn_signature(item, f, tcx);
+        }
+        clean::TyMethodItem(f) => {
+            out = fn_signature(item, f, tcx);
+            out.push(';');
+        }
+        clean::MethodItem(f, _) => out = fn_signature(item, f, tcx),
+        clean::StructItem(s) => {
+            let _ = write!(out, "{vis}struct {name}{}", plain(&s.generics, tcx));
+            match s.ctor_kind {
+                None => {
+                    open_brace(&mut out, &where_clause(&s.generics, tcx));
+                    braced_fields(&mut out, &s.fields, "", tcx);
+                }
+                Some(CtorKind::Fn) => {
+                    tuple_fields(&mut out, &s.fields, tcx);
+                    out.push_str(&where_clause(&s.generics, tcx));
+                    out.push(';');
+                }
+                Some(CtorKind::Const) => {
+                    out.push_str(&where_clause(&s.generics, tcx));
+                    out.push(';');
+                }
+            }
+        }
+        clean::UnionItem(u) => {
+            let _ = write!(out, "{vis}union {name}{}", plain(&u.generics, tcx));
+            open_brace(&mut out, &where_clause(&u.generics, tcx));
+            braced_fields(&mut out, &u.fields, "", tcx);
+        }
+        clean::EnumItem(e) => {
+            let _ = write!(out, "{vis}enum {name}{}", plain(&e.generics, tcx));
+            open_brace(&mut out, &where_clause(&e.generics, tcx));
+            for v in e.variants() {
+                if let clean::VariantItem(ref v_) = *v.kind {
+                    let _ = writeln!(out, "    {},", variant(v.name.unwrap(), v_, "    ", tcx));
+                }
+            }
+            if e.has_stripped_entries() {
+                out.push_str("    // some variants omitted\n");
+            }
+            out.push('}');
+        }
+        clean::VariantItem(v) => out = variant(name, v, "", tcx),
+        clean::StructFieldItem(ty) => {
+            let _ = write!(out, "{vis}{name}: {}", plain(ty, tcx));
+        }
+        clean::TraitItem(t) => {
+            let _ = write!(
+                out,
+                "{vis}{unsafety}{auto}trait {name}{generics}",
+                unsafety = t.unsafety(tcx).print_with_space(),
+                auto = if t.is_auto(tcx) { "auto " } else { "" },
+                generics = plain(&t.generics, tcx),
+            );
+            if !t.bounds.is_empty() {
+                let _ = write!(out, ": {}", plain(&t.bounds[..], tcx));
+            }
+            open_brace(&mut out, &where_clause(&t.generics, tcx));
+            for trait_item in &t.items {
+                if trait_item.is_stripped() {
+                    continue;
+                }
+                if let Some(sig) = trait_item_signature(trait_item, tcx) {
+                    for line in sig.lines() {
+                        let _ = writeln!(out, "    {line}");
+                    }
+                }
+            }
+            out.push('}');
+        }
+        clean::TraitAliasItem(ta) => {
+            let _ = write!(
+                out,
+                "trait {name}{} = {}{};",
+                plain(&ta.generics, tcx),
+                plain(&ta.bounds[..], tcx),
+                where_clause(&ta.generics, tcx),
+            );
+        }
+        clean::TypeAliasItem(ta) => {
+            let _ = write!(
+                out,
+                "{vis}type {name}{}{} = {};",
+                plain(&ta.generics, tcx),
+                where_clause(&ta.generics, tcx),
+                plain(&ta.type_, tcx),
+            );
+        }
+        clean::OpaqueTyItem(ty) => {
+            let _ = write!(
+                out,
+                "type {name}{}{} = impl {};",
+                plain(&ty.generics, tcx),
+                where_clause(&ty.generics, tcx),
+                plain(&ty.bounds[..], tcx),
+            );
+        }
+        clean::StaticItem(s) | clean::ForeignStaticItem(s) => {
+            let _ = write!(
+                out,
+                "{vis}static {mutability}{name}: {};",
+                plain(&s.type_, tcx),
+                mutability = s.mutability.print_with_space(),
+            );
+        }
+        clean::ConstantItem(c) => {
+            let _ = write!(out, "{vis}const {name}: {} = {};", plain(&*c.type_, tcx), c.expr(tcx),);
+        }
+        clean::ForeignTypeItem => {
+            let _ = write!(out, "{vis}type {name};");
+        }
+        clean::TyAssocConstItem(generics, ty) => {
+            let _ = write!(out, "const {name}{}: {};", plain(generics, tcx), plain(&**ty, tcx));
+        }
+        clean::AssocConstItem(generics, ty, default) => {
+            let _ = write!(
+                out,
+                "{vis}const {name}{}: {} = {};",
+                plain(generics, tcx),
+                plain(&**ty, tcx),
+                default.expr(tcx),
+            );
+        }
+        clean::TyAssocTypeItem(generics, bounds) => {
+            let _ = write!(out, "type {name}{}", plain(generics, tcx));
+            if !bounds.is_empty() {
+                let _ = write!(out, ": {}", plain(&bounds[..], tcx));
+            }
+            let _ = write!(out, "{};", where_clause(generics, tcx));
+        }
+        clean::AssocTypeItem(ta, bounds) => {
+            let _ = write!(out, "{vis}type {name}{}", plain(&ta.generics, tcx));
+            if !bounds.is_empty() {
+                let _ = write!(out, ": {}", plain(&bounds[..], tcx));
+            }
+            let _ =
+                write!(out, "{} = {};", where_clause(&ta.generics, tcx), plain(&ta.type_, tcx),);
+        }
+        clean::MacroItem(m) => out.push_str(m.source.trim_end()),
+        clean::ProcMacroItem(m) => match m.kind {
+            MacroKind::Bang => {
+                let _ = write!(out, "{name}!() {{ /* proc-macro */ }}");
+            }
+            MacroKind::Attr => {
+                let _ = write!(out, "#[{name}]");
+            }
+            MacroKind::Derive => {
+                let _ = write!(out, "#[derive({name})]");
+                if !m.helpers.is_empty() {
+                    out.push_str("\n\n/* Helper attributes */");
+                    for attr in &m.helpers {
+                        let _ = write!(out, "\n#[{attr}]");
+                    }
+                }
+            }
+        },
+        clean::ExternCrateItem { .. }
+        | clean::ImportItem(_)
+        | clean::ModuleItem(_)
+        | clean::ImplItem(_)
+        | clean::PrimitiveItem(_)
+        | clean::KeywordItem
+        | clean::StrippedItem(_) => return None,
+    }
+    Some(out)
+}
+
+/// Prints a re-export as it would appear in Rust source.
+pub(crate) fn reexport(item: &clean::Item, tcx: TyCtxt<'_>) -> Option<String> {
+    let vis = visibility(item, tcx);
+    match *item.kind {
+        clean::ExternCrateItem { src: Some(src) } => {
+            Some(format!("{vis}extern crate {src} as {};", item.name?))
+        }
+        clean::ExternCrateItem { src: None } => Some(format!("{vis}extern crate {};", item.name?)),
+        clean::ImportItem(ref import) => {
+            let source = import.source.path.whole_name();
+            Some(match import.kind {
+                clean::ImportKind::Simple(name) if name == import.source.path.last() => {
+                    format!("{vis}use {source};")
+                }
+                clean::ImportKind::Simple(name) => format!("{vis}use {source} as {name};"),
+                clean::ImportKind::Glob if source.is_empty() => format!("{vis}use *;"),
+                clean::ImportKind::Glob => format!("{vis}use {source}::*;"),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Prints the declaration of an item of a trait, with a placeholder body for provided methods.
+fn trait_item_signature(item: &clean::Item, tcx: TyCtxt<'_>) -> Option<String> {
+    let mut sig = item_signature(item, tcx)?;
+    if let clean::MethodItem(..) = *item.kind {
+        sig.push_str(" { ... }");
+    }
+    Some(sig)
+}
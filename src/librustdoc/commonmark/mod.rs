@@ -0,0 +1,501 @@
+//! Rustdoc's Markdown backend
+//!
+//! This module renders a crate as a tree of CommonMark files instead of HTML pages. The layout
+//! mirrors the HTML output: every module gets a directory with an `index.md` page, and every
+//! other item gets a `{type}.{name}.md` page next to it. Intra-doc links are rewritten to relative
+//! links between those files, so the output can be published as-is by any Markdown-based site.
+
+mod docs;
+mod format;
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rustc_middle::ty::TyCtxt;
+use rustc_span::def_id::DefId;
+use rustc_span::symbol::Symbol;
+
+use crate::clean::types::ExternalLocation;
+use crate::clean::{self, ItemLink, RenderedLink};
+use crate::config::{ModuleSorting, RenderOptions};
+use crate::docfs::PathError;
+use crate::error::Error;
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::formats::{FormatRenderer, Impl};
+use crate::html::format::{href_relative_parts, join_with_double_colon};
+use crate::html::render::{compare_names, item_ty_to_section, ItemSection};
+use crate::try_err;
+
+use self::docs::{render_docs, summary};
+use self::format::{impl_header, item_signature, plain, reexport};
+
+#[derive(Clone)]
+pub(crate) struct MarkdownRenderer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    cache: Rc<Cache>,
+    /// Current hierarchy of components leading down to what's currently being rendered.
+    current: Vec<Symbol>,
+    /// The directory the pages of the current module are written to.
+    dst: PathBuf,
+    /// Whether the current module was stripped. Stripped modules are recursed into, since they can
+    /// contain impls for public types, but no pages are written for them or their items.
+    in_stripped_module: bool,
+    module_sorting: ModuleSorting,
+    custom_code_classes_in_docs: bool,
+}
+
+impl<'tcx> MarkdownRenderer<'tcx> {
+    fn write_page(&self, file_name: &str, page: String) -> Result<(), Error> {
+        try_err!(fs::create_dir_all(&self.dst), &self.dst);
+        let path = self.dst.join(file_name);
+        try_err!(fs::write(&path, page), &path);
+        Ok(())
+    }
+
+    /// Returns the path of the page documenting `did`, relative to the current module.
+    ///
+    /// Items of crates whose documentation was generated elsewhere link to their HTML pages.
+    fn href(&self, did: DefId) -> Option<String> {
+        fn to_module_fqp(shortty: ItemType, fqp: &[Symbol]) -> &[Symbol] {
+            if shortty == ItemType::Module {
+                fqp
+            } else {
+                &fqp[..fqp.len() - 1]
+            }
+        }
+
+        let cache = &self.cache;
+        let (fqp, shortty, mut url, ext) = match cache.paths.get(&did) {
+            Some(&(ref fqp, shortty)) => {
+                let module_fqp = to_module_fqp(shortty, fqp);
+                (fqp, shortty, relative_url(module_fqp, &self.current), "md")
+            }
+            None => {
+                let &(ref fqp, shortty) = cache.external_paths.get(&did)?;
+                let module_fqp = to_module_fqp(shortty, fqp);
+                match cache.extern_locations.get(&did.krate)? {
+                    ExternalLocation::Remote(s) => {
+                        let mut url = s.trim_end_matches('/').to_owned();
+                        for component in module_fqp {
+                            url.push('/');
+                            url.push_str(component.as_str());
+                        }
+                        url.push('/');
+                        (fqp, shortty, url, "html")
+                    }
+                    ExternalLocation::Local => {
+                        (fqp, shortty, relative_url(module_fqp, &self.current), "md")
+                    }
+                    ExternalLocation::Unknown => return None,
+                }
+            }
+        };
+        match shortty {
+            ItemType::Module => write!(url, "index.{ext}").unwrap(),
+            _ => write!(url, "{shortty}.{name}.{ext}", name = fqp.last().unwrap()).unwrap(),
+        }
+        Some(url)
+    }
+
+    /// Resolves the intra-doc links of `item` relative to the current module.
+    fn links(&self, item: &clean::Item) -> Vec<RenderedLink> {
+        let Some(links) = self.cache.intra_doc_links.get(&item.item_id) else { return vec![] };
+        links
+            .iter()
+            .filter_map(|ItemLink { link, link_text, page_id, fragment }| {
+                let mut href = self.href(*page_id)?;
+                if let Some(fragment) = fragment {
+                    fragment.render(&mut href, self.tcx);
+                }
+                Some(RenderedLink {
+                    original_text: link.clone(),
+                    new_text: link_text.clone(),
+                    href,
+                    tooltip: String::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// Writes the documentation of `item`, with its headings moved `heading_offset` levels down.
+    fn write_docs(&self, page: &mut String, item: &clean::Item, heading_offset: usize) {
+        let docs = render_docs(
+            &item.doc_value(),
+            &self.links(item),
+            heading_offset,
+            self.custom_code_classes_in_docs,
+        );
+        if !docs.is_empty() {
+            page.push_str(docs.trim_end());
+            page.push_str("\n\n");
+        }
+    }
+
+    fn write_signature(&self, page: &mut String, item: &clean::Item) {
+        if let Some(signature) = item_signature(item, self.tcx) {
+            let _ = write!(page, "```rust\n{signature}\n```\n\n");
+        }
+    }
+
+    fn render_module(&self, item: &clean::Item, module: &clean::Module) -> String {
+        let mut page = if item.is_crate() {
+            format!("# Crate `{}`\n\n", item.name.unwrap())
+        } else {
+            format!("# Module `{}`\n\n", join_with_double_colon(&self.current))
+        };
+        self.write_docs(&mut page, item, 1);
+
+        let mut items = module.items.iter().filter(|i| !i.is_stripped()).collect::<Vec<_>>();
+        let section_index = |i: &clean::Item| {
+            ItemSection::ALL.iter().position(|&s| s == item_ty_to_section(i.type_()))
+        };
+        match self.module_sorting {
+            ModuleSorting::Alphabetical => items.sort_by(|i1, i2| {
+                section_index(i1).cmp(&section_index(i2)).then_with(|| match (i1.name, i2.name) {
+                    (Some(n1), Some(n2)) => compare_names(n1.as_str(), n2.as_str()),
+                    _ => Ordering::Equal,
+                })
+            }),
+            ModuleSorting::DeclarationOrder => items.sort_by_key(|i| section_index(i)),
+        }
+
+        let mut last_section = None;
+        for myitem in items {
+            let section = item_ty_to_section(myitem.type_());
+            let entry = match *myitem.kind {
+                clean::ExternCrateItem { .. } => reexport(myitem, self.tcx),
+                clean::ImportItem(ref import) if import.should_be_displayed => {
+                    reexport(myitem, self.tcx)
+                }
+                clean::ImportItem(_) => None,
+                _ => myitem.name.map(|name| {
+                    let file_name = match myitem.type_() {
+                        ItemType::Module => format!("{name}/index.md"),
+                        shortty => format!("{shortty}.{name}.md"),
+                    };
+                    let docs = render_docs(
+                        &myitem.doc_value(),
+                        &self.links(myitem),
+                        0,
+                        self.custom_code_classes_in_docs,
+                    );
+                    let summary = summary(&docs);
+                    if summary.is_empty() {
+                        format!("[`{name}`]({file_name})")
+                    } else {
+                        format!("[`{name}`]({file_name}): {summary}")
+                    }
+                }),
+            };
+            let Some(entry) = entry else { continue };
+            if last_section != Some(section) {
+                if last_section.is_some() {
+                    page.push('\n');
+                }
+                last_section = Some(section);
+                let _ = write!(page, "## {}\n\n", section.name());
+            }
+            if section == ItemSection::Reexports {
+                let _ = writeln!(page, "- `{entry}`");
+            } else {
+                let _ = writeln!(page, "- {entry}");
+            }
+        }
+        page
+    }
+
+    fn render_item(&self, item: &clean::Item) -> String {
+        let typ = match *item.kind {
+            clean::FunctionItem(..) | clean::ForeignFunctionItem(..) => "Function",
+            clean::TraitItem(..) => "Trait",
+            clean::StructItem(..) => "Struct",
+            clean::UnionItem(..) => "Union",
+            clean::EnumItem(..) => "Enum",
+            clean::TypeAliasItem(..) => "Type Alias",
+            clean::MacroItem(..) => "Macro",
+            clean::ProcMacroItem(ref mac) => match mac.kind {
+                rustc_span::hygiene::MacroKind::Bang => "Macro",
+                rustc_span::hygiene::MacroKind::Attr => "Attribute Macro",
+                rustc_span::hygiene::MacroKind::Derive => "Derive Macro",
+            },
+            clean::PrimitiveItem(..) => "Primitive Type",
+            clean::StaticItem(..) | clean::ForeignStaticItem(..) => "Static",
+            clean::ConstantItem(..) => "Constant",
+            clean::ForeignTypeItem => "Foreign Type",
+            clean::KeywordItem => "Keyword",
+            clean::OpaqueTyItem(..) => "Opaque Type",
+            clean::TraitAliasItem(..) => "Trait Alias",
+            _ => unreachable!("no page is generated for {:?}", item.type_()),
+        };
+        let name = item.name.unwrap();
+        let mut page = if item.is_primitive() || item.is_keyword() {
+            format!("# {typ} `{name}`\n\n")
+        } else {
+            format!("# {typ} `{}::{name}`\n\n", join_with_double_colon(&self.current))
+        };
+        self.write_signature(&mut page, item);
+        self.write_docs(&mut page, item, 1);
+
+        match *item.kind {
+            clean::StructItem(ref s) => {
+                if s.ctor_kind.is_none() {
+                    self.write_fields(&mut page, &s.fields);
+                }
+            }
+            clean::UnionItem(ref u) => self.write_fields(&mut page, &u.fields),
+            clean::EnumItem(ref e) => self.write_variants(&mut page, e),
+            clean::TraitItem(ref t) => self.write_trait_items(&mut page, item, t),
+            _ => {}
+        }
+        if let clean::StructItem(_)
+        | clean::UnionItem(_)
+        | clean::EnumItem(_)
+        | clean::PrimitiveItem(_)
+        | clean::ForeignTypeItem = *item.kind
+        {
+            self.write_impls(&mut page, item.item_id.expect_def_id());
+        }
+        page.truncate(page.trim_end().len());
+        page.push('\n');
+        page
+    }
+
+    fn write_fields(&self, page: &mut String, fields: &[clean::Item]) {
+        let fields = fields
+            .iter()
+            .filter_map(|f| match *f.kind {
+                clean::StructFieldItem(ref ty) => Some((f, ty)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return;
+        }
+        page.push_str("## Fields\n\n");
+        for (field, ty) in fields {
+            let name = field.name.unwrap();
+            let _ = write!(
+                page,
+                "<a id=\"{typ}.{name}\"></a>\n### `{name}: {ty}`\n\n",
+                typ = ItemType::StructField,
+                ty = plain(ty, self.tcx),
+            );
+            self.write_docs(page, field, 3);
+        }
+    }
+
+    fn write_variants(&self, page: &mut String, e: &clean::Enum) {
+        if e.variants().next().is_none() {
+            return;
+        }
+        page.push_str("## Variants\n\n");
+        for variant in e.variants() {
+            let name = variant.name.unwrap();
+            let _ = write!(page, "<a id=\"{typ}.{name}\"></a>\n", typ = ItemType::Variant);
+            self.write_signature(page, variant);
+            self.write_docs(page, variant, 2);
+
+            let clean::VariantItem(clean::Variant {
+                kind: clean::VariantKind::Struct(ref s), ..
+            }) = *variant.kind
+            else {
+                continue;
+            };
+            for field in &s.fields {
+                let clean::StructFieldItem(ref ty) = *field.kind else { continue };
+                let field_name = field.name.unwrap();
+                let _ = write!(
+                    page,
+                    "<a id=\"{variant_ty}.{name}.field.{field_name}\"></a>\n\
+                     #### `{field_name}: {ty}`\n\n",
+                    variant_ty = ItemType::Variant,
+                    ty = plain(ty, self.tcx),
+                );
+                self.write_docs(page, field, 4);
+            }
+        }
+    }
+
+    fn write_trait_items(&self, page: &mut String, it: &clean::Item, t: &clean::Trait) {
+        let items = t.items.iter().filter(|i| !i.is_stripped()).collect::<Vec<_>>();
+        let sections: [(&str, fn(&clean::Item) -> bool); 6] = [
+            ("Required Associated Types", |i| i.is_ty_associated_type()),
+            ("Provided Associated Types", |i| i.is_associated_type()),
+            ("Required Associated Constants", |i| i.is_ty_associated_const()),
+            ("Provided Associated Constants", |i| i.is_associated_const()),
+            ("Required Methods", |i| i.is_ty_method()),
+            ("Provided Methods", |i| i.is_method()),
+        ];
+        for (title, in_section) in sections {
+            let mut section_items = items.iter().filter(|i| in_section(i)).peekable();
+            if section_items.peek().is_none() {
+                continue;
+            }
+            let _ = write!(page, "## {title}\n\n");
+            for item in section_items {
+                self.write_assoc_item(page, item);
+            }
+        }
+
+        let Some(implementors) = self.cache.implementors.get(&it.item_id.expect_def_id()) else {
+            return;
+        };
+        let mut implementors = implementors
+            .iter()
+            .filter(|i| !i.impl_item.is_stripped())
+            .map(|i| impl_header(i.inner_impl(), self.tcx))
+            .collect::<Vec<_>>();
+        implementors.sort();
+        implementors.dedup();
+        if !implementors.is_empty() {
+            page.push_str("## Implementors\n\n");
+            for header in implementors {
+                let _ = writeln!(page, "- `{header}`");
+            }
+            page.push('\n');
+        }
+    }
+
+    fn write_assoc_item(&self, page: &mut String, item: &clean::Item) {
+        let name = item.name.unwrap();
+        let _ = write!(page, "<a id=\"{typ}.{name}\"></a>\n", typ = item.type_());
+        self.write_signature(page, item);
+        self.write_docs(page, item, 3);
+    }
+
+    fn write_impls(&self, page: &mut String, did: DefId) {
+        let Some(impls) = self.cache.impls.get(&did) else { return };
+        let (inherent, trait_impls): (Vec<&Impl>, Vec<&Impl>) = impls
+            .iter()
+            .filter(|i| !i.impl_item.is_stripped())
+            .partition(|i| i.trait_did().is_none());
+
+        if !inherent.is_empty() {
+            page.push_str("## Implementations\n\n");
+            for i in inherent {
+                let _ = write!(page, "### `{}`\n\n", impl_header(i.inner_impl(), self.tcx));
+                self.write_docs(page, &i.impl_item, 3);
+                for item in i.inner_impl().items.iter().filter(|i| !i.is_stripped()) {
+                    self.write_assoc_item(page, item);
+                }
+            }
+        }
+
+        let sections: [(&str, fn(&clean::ImplKind) -> bool); 3] = [
+            ("Trait Implementations", |k| !k.is_auto() && !k.is_blanket()),
+            ("Auto Trait Implementations", clean::ImplKind::is_auto),
+            ("Blanket Implementations", clean::ImplKind::is_blanket),
+        ];
+        for (title, in_section) in sections {
+            let mut headers = trait_impls
+                .iter()
+                .filter(|i| in_section(&i.inner_impl().kind))
+                .map(|i| {
+                    let header = impl_header(i.inner_impl(), self.tcx);
+                    match i.trait_did().and_then(|did| self.href(did)) {
+                        Some(href) => format!("[`{header}`]({href})"),
+                        None => format!("`{header}`"),
+                    }
+                })
+                .collect::<Vec<_>>();
+            if headers.is_empty() {
+                continue;
+            }
+            headers.sort();
+            let _ = write!(page, "## {title}\n\n");
+            for header in headers {
+                let _ = writeln!(page, "- {header}");
+            }
+            page.push('\n');
+        }
+    }
+}
+
+/// Builds the relative path from the current module directory to the directory of `fqp`.
+fn relative_url(fqp: &[Symbol], relative_to: &[Symbol]) -> String {
+    let mut url = String::new();
+    for component in href_relative_parts(fqp, relative_to) {
+        url.push_str(component.as_str());
+        url.push('/');
+    }
+    url
+}
+
+impl<'tcx> FormatRenderer<'tcx> for MarkdownRenderer<'tcx> {
+    fn descr() -> &'static str {
+        "markdown"
+    }
+
+    const RUN_ON_MODULE: bool = true;
+
+    fn init(
+        krate: clean::Crate,
+        options: RenderOptions,
+        cache: Cache,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<(Self, clean::Crate), Error> {
+        debug!("Initializing markdown renderer");
+
+        Ok((
+            MarkdownRenderer {
+                tcx,
+                cache: Rc::new(cache),
+                current: Vec::new(),
+                dst: options.output,
+                in_stripped_module: false,
+                module_sorting: options.module_sorting,
+                custom_code_classes_in_docs: tcx.features().custom_code_classes_in_docs,
+            },
+            krate,
+        ))
+    }
+
+    fn make_child_renderer(&self) -> Self {
+        self.clone()
+    }
+
+    fn item(&mut self, item: clean::Item) -> Result<(), Error> {
+        if self.in_stripped_module || item.is_stripped() {
+            return Ok(());
+        }
+        let page = self.render_item(&item);
+        let file_name = format!("{}.{}.md", item.type_(), item.name.unwrap());
+        self.write_page(&file_name, page)
+    }
+
+    fn mod_item_in(&mut self, item: &clean::Item) -> Result<(), Error> {
+        self.in_stripped_module |= item.is_stripped();
+        let item_name = item.name.unwrap();
+        self.dst.push(item_name.as_str());
+        self.current.push(item_name);
+
+        if self.in_stripped_module {
+            return Ok(());
+        }
+        let (clean::StrippedItem(box clean::ModuleItem(ref module))
+        | clean::ModuleItem(ref module)) = *item.kind
+        else {
+            unreachable!()
+        };
+        let page = self.render_module(item, module);
+        self.write_page("index.md", page)
+    }
+
+    fn mod_item_out(&mut self) -> Result<(), Error> {
+        self.dst.pop();
+        self.current.pop();
+        Ok(())
+    }
+
+    fn after_krate(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
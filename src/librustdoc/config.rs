@@ -20,12 +20,14 @@
 use rustc_target::spec::TargetTriple;
 
 use crate::core::new_handler;
+use crate::doctest::results::DoctestResults;
 use crate::externalfiles::ExternalHtml;
 use crate::html;
 use crate::html::markdown::IdMap;
 use crate::html::render::StylePath;
 use crate::html::static_files;
 use crate::opts;
+use crate::passes::calculate_doc_coverage::CoverageThreshold;
 use crate::passes::{self, Condition};
 use crate::scrape_examples::{AllCallLocations, ScrapeExamplesOptions};
 use crate::theme;
@@ -35,6 +37,7 @@ pub(crate) enum OutputFormat {
     Json,
     #[default]
     Html,
+    Markdown,
 }
 
 impl OutputFormat {
@@ -50,6 +53,7 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "json" => Ok(OutputFormat::Json),
             "html" => Ok(OutputFormat::Html),
+            "markdown" => Ok(OutputFormat::Markdown),
             _ => Err(format!("unknown output format `{value}`")),
         }
     }
@@ -125,6 +129,11 @@ pub(crate) struct Options {
     pub(crate) enable_per_target_ignores: bool,
     /// Do not run doctests, compile them if should_test is active.
     pub(crate) no_run: bool,
+    /// Where to write the results of the doctests, to show them in the documentation with
+    /// `--with-doctest-results`.
+    pub(crate) doctest_results_output_path: Option<PathBuf>,
+    /// Whether to compile the doctests that can be merged into a single binary.
+    pub(crate) merge_doctests: bool,
 
     /// The path to a rustc-like binary to build tests with. If not set, we
     /// default to loading from `$sysroot/bin/rustc`.
@@ -204,6 +213,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("enable-per-target-ignores", &self.enable_per_target_ignores)
             .field("run_check", &self.run_check)
             .field("no_run", &self.no_run)
+            .field("doctest_results_output_path", &self.doctest_results_output_path)
+            .field("merge_doctests", &self.merge_doctests)
             .field("nocapture", &self.nocapture)
             .field("scrape_examples_options", &self.scrape_examples_options)
             .field("unstable_features", &self.unstable_features)
@@ -265,10 +276,14 @@ pub(crate) struct RenderOptions {
     pub(crate) document_private: bool,
     /// Document items that have `doc(hidden)`.
     pub(crate) document_hidden: bool,
+    /// Minimum documentation coverages to enforce with `--show-coverage`.
+    pub(crate) coverage_thresholds: Vec<CoverageThreshold>,
     /// If `true`, generate a JSON file in the crate folder instead of HTML redirection files.
     pub(crate) generate_redirect_map: bool,
     /// Show the memory layout of types in the docs.
     pub(crate) show_type_layout: bool,
+    /// If `true`, generate an index of the text of doc comments for the search.
+    pub(crate) full_text_search: bool,
     /// Note: this field is duplicated in `Options` because it's useful to have
     /// it in both places.
     pub(crate) unstable_features: rustc_feature::UnstableFeatures,
@@ -277,6 +292,8 @@ pub(crate) struct RenderOptions {
     pub(crate) generate_link_to_definition: bool,
     /// Set of function-call locations to include as examples
     pub(crate) call_locations: AllCallLocations,
+    /// Results of the doctests to show under the code examples.
+    pub(crate) doctest_results: DoctestResults,
     /// If `true`, Context::init will not emit shared files.
     pub(crate) no_emit_shared: bool,
     /// If `true`, HTML source code pages won't be generated.
@@ -530,6 +547,19 @@ fn println_condition(condition: Condition) {
             return Err(1);
         }
 
+        let doctest_results_output_path =
+            matches.opt_str("doctest-results-output-path").map(PathBuf::from);
+        if !should_test && doctest_results_output_path.is_some() {
+            diag.err("the `--test` flag must be passed to enable `--doctest-results-output-path`");
+            return Err(1);
+        }
+
+        let merge_doctests = matches.opt_present("merge-doctests");
+        if !should_test && merge_doctests {
+            diag.err("the `--test` flag must be passed to enable `--merge-doctests`");
+            return Err(1);
+        }
+
         let out_dir = matches.opt_str("out-dir").map(|s| PathBuf::from(&s));
         let output = matches.opt_str("output").map(|s| PathBuf::from(&s));
         let output = match (out_dir, output) {
@@ -655,9 +685,9 @@ fn println_condition(condition: Condition) {
             Some(s) => match OutputFormat::try_from(s.as_str()) {
                 Ok(out_fmt) => {
                     if !out_fmt.is_json() && show_coverage {
-                        diag.struct_err(
-                            "html output format isn't supported for the --show-coverage option",
-                        )
+                        diag.struct_err(format!(
+                            "{s} output format isn't supported for the --show-coverage option",
+                        ))
                         .emit();
                         return Err(1);
                     }
@@ -702,6 +732,7 @@ fn println_condition(condition: Condition) {
         let run_check = matches.opt_present("check");
         let generate_redirect_map = matches.opt_present("generate-redirect-map");
         let show_type_layout = matches.opt_present("show-type-layout");
+        let full_text_search = matches.opt_present("full-text-search");
         let nocapture = matches.opt_present("nocapture");
         let generate_link_to_definition = matches.opt_present("generate-link-to-definition");
         let extern_html_root_takes_precedence =
@@ -716,9 +747,18 @@ fn println_condition(condition: Condition) {
             return Err(1);
         }
 
+        let coverage_thresholds = CoverageThreshold::from_matches(matches, &diag)?;
+        if !coverage_thresholds.is_empty() && !show_coverage {
+            diag.struct_err("--coverage-threshold option can only be used with --show-coverage")
+                .emit();
+            return Err(1);
+        }
+
         let scrape_examples_options = ScrapeExamplesOptions::new(matches, &diag)?;
         let with_examples = matches.opt_strs("with-examples");
         let call_locations = crate::scrape_examples::load_call_locations(with_examples, &diag)?;
+        let with_doctest_results = matches.opt_strs("with-doctest-results");
+        let doctest_results = DoctestResults::load(with_doctest_results, &diag)?;
 
         let unstable_features =
             rustc_feature::UnstableFeatures::from_environment(crate_name.as_deref());
@@ -756,6 +796,8 @@ fn println_condition(condition: Condition) {
             test_builder,
             run_check,
             no_run,
+            doctest_results_output_path,
+            merge_doctests,
             nocapture,
             crate_name,
             output_format,
@@ -784,12 +826,15 @@ fn println_condition(condition: Condition) {
             markdown_playground_url,
             document_private,
             document_hidden,
+            coverage_thresholds,
             generate_redirect_map,
             show_type_layout,
+            full_text_search,
             unstable_features,
             emit,
             generate_link_to_definition,
             call_locations,
+            doctest_results,
             no_emit_shared: false,
             html_no_source,
         };
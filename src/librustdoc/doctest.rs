@@ -14,7 +14,7 @@
 use rustc_session::config::{self, CrateType, ErrorOutputType};
 use rustc_session::parse::ParseSess;
 use rustc_session::{lint, Session};
-use rustc_span::edition::Edition;
+use rustc_span::edition::{Edition, ALL_EDITIONS};
 use rustc_span::source_map::SourceMap;
 use rustc_span::symbol::sym;
 use rustc_span::{BytePos, FileName, Pos, Span, DUMMY_SP};
@@ -35,6 +35,12 @@
 use crate::html::markdown::{self, ErrorCodes, Ignore, LangString};
 use crate::lint::init_lints;
 
+use self::merged::{can_merge, MergedDoctests, MERGED_DOCTEST_ENV};
+use self::results::{code_block_key, DoctestResult, DoctestResultsWriter, DoctestStatus};
+
+mod merged;
+pub(crate) mod results;
+
 /// Options that apply to all doctests in a crate or Markdown file (for `rustdoc foo.md`).
 #[derive(Clone, Default)]
 pub(crate) struct GlobalTestOptions {
@@ -114,7 +120,7 @@ pub(crate) fn run(options: RustdocOptions) -> Result<(), ErrorGuaranteed> {
     let externs = options.externs.clone();
     let json_unused_externs = options.json_unused_externs;
 
-    let (tests, unused_extern_reports, compiling_test_count) =
+    let (tests, unused_extern_reports, compiling_test_count, results, merged) =
         interface::run_compiler(config, |compiler| {
             compiler.enter(|queries| {
                 let collector = queries.global_ctxt()?.enter(|tcx| {
@@ -156,10 +162,25 @@ pub(crate) fn run(options: RustdocOptions) -> Result<(), ErrorGuaranteed> {
 
                 let unused_extern_reports = collector.unused_extern_reports.clone();
                 let compiling_test_count = collector.compiling_test_count.load(Ordering::SeqCst);
-                Ok((collector.tests, unused_extern_reports, compiling_test_count))
+                Ok((
+                    collector.tests,
+                    unused_extern_reports,
+                    compiling_test_count,
+                    collector.results,
+                    collector.merged,
+                ))
             })
         })?;
 
+    // Tests that are ignored are never run, so they are only written here.
+    if let Some(results) = &results {
+        results.write();
+    }
+    // The merged doctests are compiled before running any doctest, so that whether they could be
+    // merged is reported up front.
+    for merged in ALL_EDITIONS.iter().filter_map(|edition| merged.get(edition)) {
+        merged.compile_and_report();
+    }
     run_tests(test_args, nocapture, tests);
 
     // Collect and warn about unused externs, but only if we've gotten
@@ -317,7 +338,7 @@ fn run_test(
     path: PathBuf,
     test_id: &str,
     report_unused_externs: impl Fn(UnusedExterns),
-) -> Result<(), TestFailure> {
+) -> Result<Vec<u8>, TestFailure> {
     let (test, line_offset, supports_color) =
         make_test(test, Some(crate_name), lang_string.test_harness, opts, edition, Some(test_id));
 
@@ -325,25 +346,7 @@ fn run_test(
     let rust_out = add_exe_suffix("rust_out".to_owned(), &target);
     let output_file = outdir.path().join(rust_out);
 
-    let rustc_binary = rustdoc_options
-        .test_builder
-        .as_deref()
-        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
-    let mut compiler = Command::new(&rustc_binary);
-    compiler.arg("--crate-type").arg("bin");
-    for cfg in &rustdoc_options.cfgs {
-        compiler.arg("--cfg").arg(&cfg);
-    }
-    if !rustdoc_options.check_cfgs.is_empty() {
-        compiler.arg("-Z").arg("unstable-options");
-        for check_cfg in &rustdoc_options.check_cfgs {
-            compiler.arg("--check-cfg").arg(&check_cfg);
-        }
-    }
-    if let Some(sysroot) = rustdoc_options.maybe_sysroot {
-        compiler.arg("--sysroot").arg(sysroot);
-    }
-    compiler.arg("--edition").arg(&edition.to_string());
+    let mut compiler = doctest_compiler_command(&rustdoc_options, edition);
     compiler.env("UNSTABLE_RUSTDOC_TEST_PATH", path);
     compiler.env("UNSTABLE_RUSTDOC_TEST_LINE", format!("{}", line as isize - line_offset as isize));
     compiler.arg("-o").arg(&output_file);
@@ -356,28 +359,9 @@ fn run_test(
         compiler.arg("-Z").arg("unstable-options");
         compiler.arg("-W").arg("unused_crate_dependencies");
     }
-    for lib_str in &rustdoc_options.lib_strs {
-        compiler.arg("-L").arg(&lib_str);
-    }
-    for extern_str in &rustdoc_options.extern_strs {
-        compiler.arg("--extern").arg(&extern_str);
-    }
-    compiler.arg("-Ccodegen-units=1");
-    for codegen_options_str in &rustdoc_options.codegen_options_strs {
-        compiler.arg("-C").arg(&codegen_options_str);
-    }
-    for unstable_option_str in &rustdoc_options.unstable_opts_strs {
-        compiler.arg("-Z").arg(&unstable_option_str);
-    }
     if no_run && !lang_string.compile_fail && rustdoc_options.persist_doctests.is_none() {
         compiler.arg("--emit=metadata");
     }
-    compiler.arg("--target").arg(match target {
-        TargetTriple::TargetTriple(s) => s,
-        TargetTriple::TargetJson { path_for_rustdoc, .. } => {
-            path_for_rustdoc.to_str().expect("target path must be valid unicode").to_string()
-        }
-    });
     if let ErrorOutputType::HumanReadable(kind) = rustdoc_options.error_format {
         let (short, color_config) = kind.unzip();
 
@@ -461,10 +445,73 @@ fn drop(&mut self) {
     }
 
     if no_run {
-        return Ok(());
+        return Ok(Vec::new());
+    }
+
+    run_doctest_binary(
+        output_file,
+        None,
+        lang_string.should_panic,
+        runtool,
+        runtool_args,
+        &rustdoc_options,
+    )
+}
+
+/// Creates the command that compiles a doctest, with the options that are the same for all the
+/// doctests of a crate.
+fn doctest_compiler_command(rustdoc_options: &RustdocOptions, edition: Edition) -> Command {
+    let rustc_binary = rustdoc_options
+        .test_builder
+        .as_deref()
+        .unwrap_or_else(|| rustc_interface::util::rustc_path().expect("found rustc"));
+    let mut compiler = Command::new(&rustc_binary);
+    compiler.arg("--crate-type").arg("bin");
+    for cfg in &rustdoc_options.cfgs {
+        compiler.arg("--cfg").arg(&cfg);
+    }
+    if !rustdoc_options.check_cfgs.is_empty() {
+        compiler.arg("-Z").arg("unstable-options");
+        for check_cfg in &rustdoc_options.check_cfgs {
+            compiler.arg("--check-cfg").arg(&check_cfg);
+        }
+    }
+    if let Some(sysroot) = &rustdoc_options.maybe_sysroot {
+        compiler.arg("--sysroot").arg(sysroot);
+    }
+    compiler.arg("--edition").arg(&edition.to_string());
+    for lib_str in &rustdoc_options.lib_strs {
+        compiler.arg("-L").arg(&lib_str);
     }
+    for extern_str in &rustdoc_options.extern_strs {
+        compiler.arg("--extern").arg(&extern_str);
+    }
+    compiler.arg("-Ccodegen-units=1");
+    for codegen_options_str in &rustdoc_options.codegen_options_strs {
+        compiler.arg("-C").arg(&codegen_options_str);
+    }
+    for unstable_option_str in &rustdoc_options.unstable_opts_strs {
+        compiler.arg("-Z").arg(&unstable_option_str);
+    }
+    compiler.arg("--target").arg(match &rustdoc_options.target {
+        TargetTriple::TargetTriple(s) => s.clone(),
+        TargetTriple::TargetJson { path_for_rustdoc, .. } => {
+            path_for_rustdoc.to_str().expect("target path must be valid unicode").to_string()
+        }
+    });
+    compiler
+}
 
-    // Run the code!
+/// Runs a compiled doctest, and returns what it wrote to its standard output. `merged_test` is
+/// the doctest to run if `output_file` is the binary of merged doctests.
+fn run_doctest_binary(
+    output_file: PathBuf,
+    merged_test: Option<usize>,
+    should_panic: bool,
+    runtool: Option<String>,
+    runtool_args: Vec<String>,
+    rustdoc_options: &RustdocOptions,
+) -> Result<Vec<u8>, TestFailure> {
     let mut cmd;
 
     let output_file = make_maybe_absolute_path(output_file);
@@ -476,7 +523,10 @@ fn drop(&mut self) {
     } else {
         cmd = Command::new(output_file);
     }
-    if let Some(run_directory) = rustdoc_options.test_run_directory {
+    if let Some(merged_test) = merged_test {
+        cmd.env(MERGED_DOCTEST_ENV, merged_test.to_string());
+    }
+    if let Some(run_directory) = &rustdoc_options.test_run_directory {
         cmd.current_dir(run_directory);
     }
 
@@ -490,17 +540,17 @@ fn drop(&mut self) {
         cmd.output()
     };
     match result {
-        Err(e) => return Err(TestFailure::ExecutionError(e)),
+        Err(e) => Err(TestFailure::ExecutionError(e)),
         Ok(out) => {
-            if lang_string.should_panic && out.status.success() {
-                return Err(TestFailure::UnexpectedRunPass);
-            } else if !lang_string.should_panic && !out.status.success() {
-                return Err(TestFailure::ExecutionFailure(out));
+            if should_panic && out.status.success() {
+                Err(TestFailure::UnexpectedRunPass)
+            } else if !should_panic && !out.status.success() {
+                Err(TestFailure::ExecutionFailure(out))
+            } else {
+                Ok(out.stdout)
             }
         }
     }
-
-    Ok(())
 }
 
 /// Converts a path intended to use as a command to absolute if it is
@@ -912,6 +962,10 @@ pub(crate) struct Collector {
     visited_tests: FxHashMap<(String, usize), usize>,
     unused_extern_reports: Arc<Mutex<Vec<UnusedExterns>>>,
     compiling_test_count: AtomicUsize,
+    /// Where the results of the tests are recorded, with `--doctest-results-output-path`.
+    pub(crate) results: Option<Arc<DoctestResultsWriter>>,
+    /// The tests merged into a single binary with `--merge-doctests`, for each edition.
+    merged: FxHashMap<Edition, Arc<MergedDoctests>>,
 }
 
 impl Collector {
@@ -924,6 +978,10 @@ pub(crate) fn new(
         filename: Option<PathBuf>,
         enable_per_target_ignores: bool,
     ) -> Collector {
+        let results = rustdoc_options
+            .doctest_results_output_path
+            .clone()
+            .map(|path| Arc::new(DoctestResultsWriter::new(path)));
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -938,6 +996,8 @@ pub(crate) fn new(
             visited_tests: FxHashMap::default(),
             unused_extern_reports: Default::default(),
             compiling_test_count: AtomicUsize::new(0),
+            results,
+            merged: FxHashMap::default(),
         }
     }
 
@@ -987,6 +1047,25 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
         let target_str = target.to_string();
         let unused_externs = self.unused_extern_reports.clone();
         let no_run = config.no_run || rustdoc_options.no_run;
+        let ignore = match config.ignore {
+            Ignore::All => true,
+            Ignore::None => false,
+            Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
+        };
+        let results = self.results.clone();
+        let mut result = DoctestResult {
+            name: name.clone(),
+            file: filename.prefer_local().to_string(),
+            line,
+            key: code_block_key(&test),
+            status: DoctestStatus::Ignored,
+            no_run,
+            should_panic: config.should_panic,
+            stdout: String::new(),
+        };
+        if ignore && let Some(results) = &results {
+            results.add(result.clone());
+        }
         if !config.compile_fail {
             self.compiling_test_count.fetch_add(1, Ordering::SeqCst);
         }
@@ -1020,6 +1099,26 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
                 self.visited_tests.entry((file.clone(), line)).and_modify(|v| *v += 1).or_insert(0)
             },
         );
+        // Each test must be compiled on its own for `--persist-doctests` and for the unused externs
+        // to be reported.
+        let merged = if rustdoc_options.merge_doctests
+            && rustdoc_options.persist_doctests.is_none()
+            && !rustdoc_options.json_unused_externs.is_enabled()
+            && !ignore
+            && can_merge(&test, &config, edition, &opts)
+        {
+            let (program, line_offset, _) =
+                make_test(&test, Some(crate_name.as_str()), false, &opts, edition, Some(&test_id));
+            line.checked_sub(line_offset).map(|padding| {
+                let merged = self.merged.entry(edition).or_insert_with(|| {
+                    Arc::new(MergedDoctests::new(edition, rustdoc_options.clone()))
+                });
+                let index = merged.add(program, padding, path.clone());
+                (merged.clone(), index)
+            })
+        } else {
+            None
+        };
         let outdir = if let Some(mut path) = rustdoc_options.persist_doctests.clone() {
             path.push(&test_id);
 
@@ -1042,11 +1141,7 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
         self.tests.push(test::TestDescAndFn {
             desc: test::TestDesc {
                 name: test::DynTestName(name),
-                ignore: match config.ignore {
-                    Ignore::All => true,
-                    Ignore::None => false,
-                    Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
-                },
+                ignore,
                 ignore_message: None,
                 source_file: "",
                 start_line: 0,
@@ -1058,28 +1153,64 @@ fn add_test(&mut self, test: String, config: LangString, line: usize) {
                 compile_fail: config.compile_fail,
                 no_run,
                 test_type: test::TestType::DocTest,
+                #[cfg(not(bootstrap))]
+                timeout: None,
+                #[cfg(not(bootstrap))]
+                fixture: None,
+                #[cfg(not(bootstrap))]
+                resources: &[],
             },
             testfn: test::DynTestFn(Box::new(move || {
                 let report_unused_externs = |uext| {
                     unused_externs.lock().unwrap().push(uext);
                 };
-                let res = run_test(
-                    &test,
-                    &crate_name,
-                    line,
-                    rustdoc_options,
-                    config,
-                    no_run,
-                    runtool,
-                    runtool_args,
-                    target,
-                    &opts,
-                    edition,
-                    outdir,
-                    path,
-                    &test_id,
-                    report_unused_externs,
-                );
+                let merged_binary = merged
+                    .as_ref()
+                    .and_then(|(merged, index)| Some((merged.binary()?.to_owned(), *index)));
+                let res = if let Some((binary, index)) = merged_binary {
+                    if no_run {
+                        Ok(Vec::new())
+                    } else {
+                        run_doctest_binary(
+                            binary,
+                            Some(index),
+                            config.should_panic,
+                            runtool,
+                            runtool_args,
+                            &rustdoc_options,
+                        )
+                    }
+                } else {
+                    run_test(
+                        &test,
+                        &crate_name,
+                        line,
+                        rustdoc_options,
+                        config,
+                        no_run,
+                        runtool,
+                        runtool_args,
+                        target,
+                        &opts,
+                        edition,
+                        outdir,
+                        path,
+                        &test_id,
+                        report_unused_externs,
+                    )
+                };
+
+                if let Some(results) = &results {
+                    let stdout = match &res {
+                        Ok(stdout) => &stdout[..],
+                        Err(TestFailure::ExecutionFailure(out)) => &out.stdout[..],
+                        Err(_) => &[],
+                    };
+                    result.status =
+                        if res.is_ok() { DoctestStatus::Ok } else { DoctestStatus::Failed };
+                    result.stdout = String::from_utf8_lossy(stdout).into_owned();
+                    results.record(result);
+                }
 
                 if let Err(err) = res {
                     match err {
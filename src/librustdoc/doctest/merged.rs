@@ -0,0 +1,163 @@
+//! Merging of doctests into a single binary, with `--merge-doctests`.
+//!
+//! Compiling and linking one binary per doctest is what makes running doctests slow, so the
+//! doctests that can be merged are each put in a module of a single crate, whose `main` runs the
+//! doctest named by an environment variable. The merged binary is still run once per doctest, so
+//! that a doctest that panics or exits doesn't affect the others.
+//!
+//! If the merged crate doesn't compile, for instance because two doctests define the same
+//! `#[no_mangle]` function, each doctest is compiled on its own as usual, so that compilation
+//! errors are reported for the right doctest. A note printed before the doctests run says which of
+//! the two happened.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use rustc_span::edition::Edition;
+use tempfile::{Builder as TempFileBuilder, TempDir};
+
+use super::{add_exe_suffix, doctest_compiler_command, partition_source, GlobalTestOptions};
+use crate::config::Options as RustdocOptions;
+use crate::html::markdown::LangString;
+
+/// The environment variable that tells the merged binary which doctest to run.
+pub(crate) const MERGED_DOCTEST_ENV: &str = "RUSTDOC_MERGED_DOCTEST";
+
+/// The doctests of a crate that have the same edition, merged into a single binary.
+pub(crate) struct MergedDoctests {
+    edition: Edition,
+    rustdoc_options: RustdocOptions,
+    /// The doctests, added while they are collected.
+    tests: Mutex<Vec<MergedDoctest>>,
+    /// The merged binary, compiled before the doctests run. `None` if it failed to compile.
+    binary: OnceLock<Option<(TempDir, PathBuf)>>,
+}
+
+struct MergedDoctest {
+    /// The program generated by `make_test`.
+    program: String,
+    /// The number of empty lines to add before the program, so that its lines have the same
+    /// numbers as in the documentation, and panic messages point to the right line.
+    padding: usize,
+    /// The file the doctest comes from.
+    path: PathBuf,
+}
+
+impl MergedDoctests {
+    pub(crate) fn new(edition: Edition, rustdoc_options: RustdocOptions) -> Self {
+        MergedDoctests {
+            edition,
+            rustdoc_options,
+            tests: Default::default(),
+            binary: OnceLock::new(),
+        }
+    }
+
+    /// Adds a doctest, and returns the value of [`MERGED_DOCTEST_ENV`] that runs it.
+    pub(crate) fn add(&self, program: String, padding: usize, path: PathBuf) -> usize {
+        let mut tests = self.tests.lock().unwrap();
+        tests.push(MergedDoctest { program, padding, path });
+        tests.len() - 1
+    }
+
+    /// Compiles the merged binary, and prints a note saying whether the doctests were merged.
+    pub(crate) fn compile_and_report(&self) {
+        let count = self.tests.lock().unwrap().len();
+        let edition = self.edition;
+        if self.binary().is_some() {
+            eprintln!("note: merged {count} doctests of edition {edition} into a single binary");
+        } else {
+            eprintln!(
+                "note: the {count} doctests of edition {edition} failed to compile when merged, \
+                 so they were compiled separately"
+            );
+        }
+    }
+
+    /// Returns the merged binary, compiling it if no doctest did it yet.
+    pub(crate) fn binary(&self) -> Option<&Path> {
+        self.binary.get_or_init(|| self.compile()).as_ref().map(|(_, binary)| binary.as_path())
+    }
+
+    fn compile(&self) -> Option<(TempDir, PathBuf)> {
+        let tests = self.tests.lock().unwrap();
+        let outdir = TempFileBuilder::new().prefix("rustdoctest").tempdir().ok()?;
+        let mut compiler = doctest_compiler_command(&self.rustdoc_options, self.edition);
+
+        let mut modules = String::new();
+        let mut dispatch = String::new();
+        for (i, test) in tests.iter().enumerate() {
+            let file = outdir.path().join(format!("doctest_{i}.rs"));
+            let source = format!(
+                "{padding}{program}\n\
+                 pub(crate) fn __main() -> impl std::process::Termination {{ main() }}\n",
+                padding = "\n".repeat(test.padding),
+                program = test.program,
+            );
+            fs::write(&file, source).ok()?;
+            // Make the file names in panic messages the same as with a standalone doctest.
+            let mut remap = file.into_os_string();
+            remap.push("=");
+            remap.push(&test.path);
+            compiler.arg("--remap-path-prefix").arg(remap);
+
+            writeln!(modules, "#[path = \"doctest_{i}.rs\"] mod __doctest_{i};").unwrap();
+            writeln!(
+                dispatch,
+                "        \"{i}\" => std::process::Termination::report(__doctest_{i}::__main()),"
+            )
+            .unwrap();
+        }
+        let mut crate_root = modules;
+        crate_root.push_str("\nfn main() -> std::process::ExitCode {\n");
+        writeln!(
+            crate_root,
+            "    match std::env::var(\"{MERGED_DOCTEST_ENV}\").as_deref().unwrap_or_default() {{"
+        )
+        .unwrap();
+        crate_root.push_str(&dispatch);
+        crate_root.push_str("        test => panic!(\"unknown doctest `{test}`\"),\n    }\n}\n");
+        let crate_root_file = outdir.path().join("doctests.rs");
+        fs::write(&crate_root_file, crate_root).ok()?;
+
+        let output_file =
+            outdir.path().join(add_exe_suffix("rust_out".to_owned(), &self.rustdoc_options.target));
+        compiler.arg("-o").arg(&output_file).arg(&crate_root_file);
+
+        debug!("compiler invocation for merged doctests: {compiler:?}");
+        let output = compiler.output().ok()?;
+        if !output.status.success() {
+            debug!(
+                "merged doctests failed to compile, compiling them separately:\n{}",
+                String::from_utf8_lossy(&output.stderr),
+            );
+            return None;
+        }
+        Some((outdir, output_file))
+    }
+}
+
+/// Whether a doctest can be put in a module of the merged crate instead of being the crate root.
+pub(crate) fn can_merge(
+    test: &str,
+    config: &LangString,
+    edition: Edition,
+    opts: &GlobalTestOptions,
+) -> bool {
+    // In the 2015 edition, paths are relative to the crate root, not to the module of the doctest.
+    edition >= Edition::Edition2018
+        && !config.compile_fail
+        && !config.test_harness
+        // Crate attributes only work at the crate root, except for lint levels.
+        && !partition_source(test, edition).0.contains("#![")
+        && opts.attrs.iter().all(|attr| {
+            let name = attr.split('(').next().unwrap_or_default().trim();
+            matches!(name, "allow" | "warn" | "deny" | "forbid")
+        })
+        // Macros can only be imported with `#[macro_use]` at the crate root, and the macros
+        // exported by several doctests could clash.
+        && !test.contains("#[macro_use]")
+        && !test.contains("#[macro_export]")
+}
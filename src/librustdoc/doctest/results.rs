@@ -0,0 +1,166 @@
+//! The results of running doctests, which can be written with `--doctest-results-output-path`
+//! when running `rustdoc --test`, and shown in the documentation with `--with-doctest-results`.
+
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{Hash64, StableHasher};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DoctestStatus {
+    Ok,
+    Failed,
+    Ignored,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct DoctestResult {
+    /// The name of the test, as printed by `rustdoc --test`.
+    pub(crate) name: String,
+    pub(crate) file: String,
+    pub(crate) line: usize,
+    /// Identifies the code block in the documentation, see [`code_block_key`].
+    pub(crate) key: String,
+    pub(crate) status: DoctestStatus,
+    pub(crate) no_run: bool,
+    pub(crate) should_panic: bool,
+    /// What the test wrote to its standard output. Empty if the test was not run, or if its
+    /// output was not captured because of `--nocapture`.
+    pub(crate) stdout: String,
+}
+
+/// Computes the key that matches a code block of the documentation with the result of its
+/// doctest, from the code of the block once the lines hidden with `#` are included.
+///
+/// Both the test collector and the HTML renderer see the same code, so this does not depend on
+/// the item, file or line of the block, which the renderer doesn't know about.
+pub(crate) fn code_block_key(code: &str) -> String {
+    let mut hasher = StableHasher::new();
+    code.hash(&mut hasher);
+    format!("{:016x}", hasher.finish::<Hash64>().as_u64())
+}
+
+/// Writes the results of the doctests to the `--doctest-results-output-path` file, in the
+/// JSON Lines format: one result per line, appended as soon as the test completes.
+///
+/// The results are written as they come, because the test harness exits the process as soon as
+/// the tests are done if one of them failed.
+pub(crate) struct DoctestResultsWriter {
+    path: PathBuf,
+    /// The results of the tests which won't be run, until [`Self::write`] is called.
+    not_run: Mutex<Vec<DoctestResult>>,
+    /// The file, created when the first results are written.
+    file: Mutex<Option<File>>,
+}
+
+impl DoctestResultsWriter {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        DoctestResultsWriter { path, not_run: Default::default(), file: Default::default() }
+    }
+
+    /// Adds the result of a test which won't be run, without writing the file.
+    pub(crate) fn add(&self, result: DoctestResult) {
+        self.not_run.lock().unwrap().push(result);
+    }
+
+    /// Writes the result of a test which just ran.
+    pub(crate) fn record(&self, result: DoctestResult) {
+        self.append(&[result]);
+    }
+
+    /// Writes the results of the tests which won't be run, sorted by name.
+    pub(crate) fn write(&self) {
+        let mut results = std::mem::take(&mut *self.not_run.lock().unwrap());
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        self.append(&results);
+    }
+
+    fn append(&self, results: &[DoctestResult]) {
+        let mut lines = String::new();
+        for result in results {
+            lines.push_str(&serde_json::to_string(result).unwrap());
+            lines.push('\n');
+        }
+        let mut file = self.file.lock().unwrap();
+        let res = match file.take() {
+            Some(file) => Ok(file),
+            None => File::create(&self.path),
+        }
+        .and_then(|mut opened| {
+            let res = opened.write_all(lines.as_bytes());
+            *file = Some(opened);
+            res
+        });
+        if let Err(err) = res {
+            eprintln!("Couldn't write the doctest results to {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// Parses the results written by [`DoctestResultsWriter`], one per line.
+fn parse_results(contents: &str) -> serde_json::Result<Vec<DoctestResult>> {
+    contents.lines().filter(|line| !line.trim().is_empty()).map(serde_json::from_str).collect()
+}
+
+/// The results loaded with `--with-doctest-results`, keyed by [`code_block_key`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DoctestResults {
+    results: FxHashMap<String, DoctestResult>,
+}
+
+impl DoctestResults {
+    pub(crate) fn load(paths: Vec<String>, diag: &rustc_errors::Handler) -> Result<Self, i32> {
+        let inner = || {
+            let mut results = DoctestResults::default();
+            for path in paths {
+                let contents =
+                    fs::read_to_string(&path).map_err(|e| format!("{e} (for path {path})"))?;
+                let file_results =
+                    parse_results(&contents).map_err(|e| format!("{e} (for path {path})"))?;
+                // Results that come later, such as those of ignored tests run with `--ignored`,
+                // replace earlier ones.
+                for result in file_results {
+                    results.insert(result);
+                }
+            }
+            Ok(results)
+        };
+
+        inner().map_err(|e: String| {
+            diag.err(format!("failed to load doctest results: {e}"));
+            1
+        })
+    }
+
+    fn insert(&mut self, result: DoctestResult) {
+        // When the same code is tested in several places, a failure must not be hidden by a
+        // success elsewhere.
+        match self.results.get(&result.key) {
+            Some(DoctestResult { status: DoctestStatus::Failed, .. }) => {}
+            _ => {
+                self.results.insert(result.key.clone(), result);
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the result of the doctest of the code block containing `code`.
+    pub(crate) fn get(&self, code: &str) -> Option<&DoctestResult> {
+        if self.results.is_empty() {
+            return None;
+        }
+        self.results.get(&code_block_key(code))
+    }
+}
+
+#[cfg(test)]
+mod tests;
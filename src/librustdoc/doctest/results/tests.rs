@@ -0,0 +1,84 @@
+use super::{
+    code_block_key, parse_results, DoctestResult, DoctestResults, DoctestResultsWriter,
+    DoctestStatus,
+};
+
+fn result(name: &str, code: &str, status: DoctestStatus) -> DoctestResult {
+    DoctestResult {
+        name: name.to_owned(),
+        file: "src/lib.rs".to_owned(),
+        line: 1,
+        key: code_block_key(code),
+        status,
+        no_run: false,
+        should_panic: false,
+        stdout: String::new(),
+    }
+}
+
+#[test]
+fn test_code_block_key() {
+    assert_eq!(code_block_key("let x = 1;"), code_block_key("let x = 1;"));
+    assert_ne!(code_block_key("let x = 1;"), code_block_key("let x = 2;"));
+    assert_eq!(code_block_key("").len(), 16);
+}
+
+#[test]
+fn test_failure_not_hidden() {
+    let mut results = DoctestResults::default();
+    results.insert(result("a", "panic!()", DoctestStatus::Failed));
+    results.insert(result("b", "panic!()", DoctestStatus::Ok));
+    results.insert(result("c", "()", DoctestStatus::Ok));
+    results.insert(result("d", "()", DoctestStatus::Ignored));
+    assert_eq!(results.get("panic!()").unwrap().name, "a");
+    assert_eq!(results.get("()").unwrap().name, "d");
+    assert!(results.get("todo!()").is_none());
+}
+
+#[test]
+fn test_writer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("results.jsonl");
+    let writer = DoctestResultsWriter::new(path.clone());
+    writer.add(result("c", "()", DoctestStatus::Ignored));
+    writer.add(result("b", "()", DoctestStatus::Ignored));
+    assert!(!path.exists());
+    writer.write();
+    writer.record(result("a", "println!(\"hi\")", DoctestStatus::Ok));
+    // A test that is ignored can still be run with `--ignored`.
+    writer.record(result("b", "()", DoctestStatus::Ok));
+
+    // Every result is on its own line, in the order they were written.
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 4);
+    let written = parse_results(&contents).unwrap();
+    let names = written.iter().map(|r| (r.name.as_str(), r.status)).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            ("b", DoctestStatus::Ignored),
+            ("c", DoctestStatus::Ignored),
+            ("a", DoctestStatus::Ok),
+            ("b", DoctestStatus::Ok),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_results() {
+    let line = |name, code, status| serde_json::to_string(&result(name, code, status)).unwrap();
+    let contents = format!(
+        "{}\n\n{}\n",
+        line("a", "()", DoctestStatus::Ignored),
+        line("a", "()", DoctestStatus::Failed),
+    );
+    let mut results = DoctestResults::default();
+    for result in parse_results(&contents).unwrap() {
+        results.insert(result);
+    }
+    assert_eq!(results.get("()").unwrap().status, DoctestStatus::Failed);
+
+    assert!(parse_results("").unwrap().is_empty());
+    assert!(parse_results("[]").is_err());
+    assert!(parse_results("{\"name\": \"a\"}").is_err());
+}
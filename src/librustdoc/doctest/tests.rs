@@ -1,5 +1,7 @@
+use super::merged::can_merge;
 use super::{make_test, GlobalTestOptions};
-use rustc_span::edition::DEFAULT_EDITION;
+use crate::html::markdown::LangString;
+use rustc_span::edition::{Edition, DEFAULT_EDITION};
 
 #[test]
 fn make_test_basic() {
@@ -302,3 +304,23 @@ fn main() { #[allow(non_snake_case)] fn _doctest_main__some_unique_name() {
         make_test(input, None, false, &opts, DEFAULT_EDITION, Some("_some_unique_name"));
     assert_eq!((output, len), (expected, 2));
 }
+
+#[test]
+fn can_merge_doctests() {
+    let opts = GlobalTestOptions::default();
+    let config = LangString::default();
+    let edition = Edition::Edition2021;
+    assert!(can_merge("// comment\nlet x = 1;", &config, edition, &opts));
+    assert!(can_merge("fn main() {}", &config, edition, &opts));
+    assert!(!can_merge("let x = 1;", &config, Edition::Edition2015, &opts));
+    assert!(!can_merge("#![feature(never_type)]\nlet x = 1;", &config, edition, &opts));
+    assert!(!can_merge("#[macro_use] extern crate foo;", &config, edition, &opts));
+
+    let compile_fail = LangString { compile_fail: true, ..LangString::default() };
+    assert!(!can_merge("let x: u8 = \"\";", &compile_fail, edition, &opts));
+
+    let lint_opts = GlobalTestOptions { attrs: vec!["deny(warnings)".into()], ..opts.clone() };
+    assert!(can_merge("let x = 1;", &config, edition, &lint_opts));
+    let feature_opts = GlobalTestOptions { attrs: vec!["feature(never_type)".into()], ..opts };
+    assert!(!can_merge("let x = 1;", &config, edition, &feature_opts));
+}
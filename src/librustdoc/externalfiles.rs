@@ -48,6 +48,7 @@ pub(crate) fn load(
                 heading_offset: HeadingOffset::H2,
                 // For external files, it'll be disabled until the feature is enabled by default.
                 custom_code_classes_in_docs: false,
+                doctest_results: None,
             }
             .into_string()
         );
@@ -65,6 +66,7 @@ pub(crate) fn load(
                 heading_offset: HeadingOffset::H2,
                 // For external files, it'll be disabled until the feature is enabled by default.
                 custom_code_classes_in_docs: false,
+                doctest_results: None,
             }
             .into_string()
         );
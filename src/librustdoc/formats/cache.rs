@@ -11,7 +11,7 @@
 use crate::formats::item_type::ItemType;
 use crate::formats::Impl;
 use crate::html::format::join_with_double_colon;
-use crate::html::markdown::short_markdown_summary;
+use crate::html::markdown::{plain_text, short_markdown_summary};
 use crate::html::render::search_index::get_function_type_for_search;
 use crate::html::render::IndexItem;
 use crate::visit_lib::RustdocEffectiveVisibilities;
@@ -89,6 +89,8 @@ pub(crate) struct Cache {
     /// Whether to document hidden items.
     /// This is stored in `Cache` so it doesn't need to be passed through all rustdoc functions.
     pub(crate) document_hidden: bool,
+    /// Whether to keep the text of doc comments for the full-text search index.
+    pub(crate) full_text_search: bool,
 
     /// Crates marked with [`#[doc(masked)]`][doc_masked].
     ///
@@ -140,8 +142,12 @@ struct CacheBuilder<'a, 'tcx> {
 }
 
 impl Cache {
-    pub(crate) fn new(document_private: bool, document_hidden: bool) -> Self {
-        Cache { document_private, document_hidden, ..Cache::default() }
+    pub(crate) fn new(
+        document_private: bool,
+        document_hidden: bool,
+        full_text_search: bool,
+    ) -> Self {
+        Cache { document_private, document_hidden, full_text_search, ..Cache::default() }
     }
 
     /// Populates the `Cache` with more data. The returned `Crate` will be missing some data that was
@@ -370,6 +376,10 @@ fn is_from_private_dep(tcx: TyCtxt<'_>, cache: &Cache, def_id: DefId) -> bool {
                             ),
                             aliases: item.attrs.get_doc_aliases(),
                             deprecation: item.deprecation(self.tcx),
+                            full_text: self
+                                .cache
+                                .full_text_search
+                                .then(|| plain_text(&item.doc_value())),
                         });
                     }
                 }
@@ -45,6 +45,7 @@
 
 use crate::clean::RenderedLink;
 use crate::doctest;
+use crate::doctest::results::{DoctestResult, DoctestResults, DoctestStatus};
 use crate::html::escape::Escape;
 use crate::html::format::Buffer;
 use crate::html::highlight;
@@ -98,6 +99,8 @@ pub struct Markdown<'a> {
     pub heading_offset: HeadingOffset,
     /// `true` if the `custom_code_classes_in_docs` feature is enabled.
     pub custom_code_classes_in_docs: bool,
+    /// The results of the doctests to show under the code examples, if any.
+    pub(crate) doctest_results: Option<&'a DoctestResults>,
 }
 /// A struct like `Markdown` that renders the markdown with a table of contents.
 pub(crate) struct MarkdownWithToc<'a> {
@@ -108,6 +111,8 @@ pub(crate) struct MarkdownWithToc<'a> {
     pub(crate) playground: &'a Option<Playground>,
     /// `true` if the `custom_code_classes_in_docs` feature is enabled.
     pub(crate) custom_code_classes_in_docs: bool,
+    /// The results of the doctests to show under the code examples, if any.
+    pub(crate) doctest_results: Option<&'a DoctestResults>,
 }
 /// A tuple struct like `Markdown` that renders the markdown escaping HTML tags
 /// and includes no paragraph tags.
@@ -140,13 +145,13 @@ pub(crate) fn as_bool(self) -> bool {
 /// Controls whether a line will be hidden or shown in HTML output.
 ///
 /// All lines are used in documentation tests.
-enum Line<'a> {
+pub(crate) enum Line<'a> {
     Hidden(&'a str),
     Shown(Cow<'a, str>),
 }
 
 impl<'a> Line<'a> {
-    fn for_html(self) -> Option<Cow<'a, str>> {
+    pub(crate) fn for_html(self) -> Option<Cow<'a, str>> {
         match self {
             Line::Shown(l) => Some(l),
             Line::Hidden(_) => None,
@@ -166,7 +171,7 @@ fn for_code(self) -> Cow<'a, str> {
 // is done in the single # case. This inconsistency seems okay, if non-ideal. In
 // order to fix it we'd have to iterate to find the first non-# character, and
 // then reallocate to remove it; which would make us return a String.
-fn map_line(s: &str) -> Line<'_> {
+pub(crate) fn map_line(s: &str) -> Line<'_> {
     let trimmed = s.trim();
     if trimmed.starts_with("##") {
         Line::Shown(Cow::Owned(s.replacen("##", "#", 1)))
@@ -209,6 +214,7 @@ struct CodeBlocks<'p, 'a, I: Iterator<Item = Event<'a>>> {
     // optional crate name and the URL.
     playground: &'p Option<Playground>,
     custom_code_classes_in_docs: bool,
+    doctest_results: Option<&'p DoctestResults>,
 }
 
 impl<'p, 'a, I: Iterator<Item = Event<'a>>> CodeBlocks<'p, 'a, I> {
@@ -218,6 +224,7 @@ fn new(
         edition: Edition,
         playground: &'p Option<Playground>,
         custom_code_classes_in_docs: bool,
+        doctest_results: Option<&'p DoctestResults>,
     ) -> Self {
         CodeBlocks {
             inner: iter,
@@ -225,6 +232,7 @@ fn new(
             edition,
             playground,
             custom_code_classes_in_docs,
+            doctest_results,
         }
     }
 }
@@ -299,17 +307,21 @@ fn next(&mut self) -> Option<Self::Item> {
         let explicit_edition = edition.is_some();
         let edition = edition.unwrap_or(self.edition);
 
+        let code = || {
+            original_text
+                .lines()
+                .map(|l| map_line(l).for_code())
+                .intersperse("\n".into())
+                .collect::<String>()
+        };
+
         let playground_button = self.playground.as_ref().and_then(|playground| {
             let krate = &playground.crate_name;
             let url = &playground.url;
             if url.is_empty() {
                 return None;
             }
-            let test = original_text
-                .lines()
-                .map(|l| map_line(l).for_code())
-                .intersperse("\n".into())
-                .collect::<String>();
+            let test = code();
             let krate = krate.as_ref().map(|s| s.as_str());
             let (test, _, _) =
                 doctest::make_test(&test, krate, false, &Default::default(), edition, None);
@@ -347,10 +359,46 @@ fn next(&mut self) -> Option<Self::Item> {
             playground_button.as_deref(),
             &added_classes,
         );
+        if let Some(result) = self.doctest_results.and_then(|results| results.get(&code())) {
+            write_doctest_result(&mut s, result);
+        }
         Some(Event::Html(s.into_inner().into()))
     }
 }
 
+/// Writes the badges and the output of the doctest of a code example, if there is anything to
+/// show.
+fn write_doctest_result(out: &mut Buffer, result: &DoctestResult) {
+    let mut badges = Vec::new();
+    match result.status {
+        DoctestStatus::Ok => {}
+        DoctestStatus::Failed => badges.push(("failed", "This example failed when it was tested")),
+        DoctestStatus::Ignored => badges.push(("ignored", "This example is not tested")),
+    }
+    if result.should_panic {
+        badges.push(("should_panic", "This example panics"));
+    }
+    if result.no_run {
+        badges.push(("no_run", "This example is compiled but not run"));
+    }
+    if badges.is_empty() && result.stdout.is_empty() {
+        return;
+    }
+
+    out.write_str("<div class=\"doctest-result\">");
+    for (badge, title) in badges {
+        write!(out, "<span class=\"stab {badge}\" title=\"{title}\">{badge}</span>");
+    }
+    if !result.stdout.is_empty() {
+        write!(
+            out,
+            "<pre class=\"doctest-output\" title=\"Output of this example\"><code>{}</code></pre>",
+            Escape(result.stdout.trim_end_matches('\n')),
+        );
+    }
+    out.write_str("</div>");
+}
+
 /// Make headings links with anchor IDs and build up TOC.
 struct LinkReplacer<'a, I: Iterator<Item = Event<'a>>> {
     inner: I,
@@ -1218,7 +1266,7 @@ fn default() -> Self {
 }
 
 impl LangString {
-    fn parse_without_check(
+    pub(crate) fn parse_without_check(
         string: &str,
         allow_error_code_check: ErrorCodes,
         enable_per_target_ignores: bool,
@@ -1397,6 +1445,7 @@ pub fn into_string(self) -> String {
             playground,
             heading_offset,
             custom_code_classes_in_docs,
+            doctest_results,
         } = self;
 
         // This is actually common enough to special-case
@@ -1419,7 +1468,14 @@ pub fn into_string(self) -> String {
         let p = Footnotes::new(p);
         let p = LinkReplacer::new(p.map(|(ev, _)| ev), links);
         let p = TableWrapper::new(p);
-        let p = CodeBlocks::new(p, codes, edition, playground, custom_code_classes_in_docs);
+        let p = CodeBlocks::new(
+            p,
+            codes,
+            edition,
+            playground,
+            custom_code_classes_in_docs,
+            doctest_results,
+        );
         html::push_html(&mut s, p);
 
         s
@@ -1435,6 +1491,7 @@ pub(crate) fn into_string(self) -> String {
             edition,
             playground,
             custom_code_classes_in_docs,
+            doctest_results,
         } = self;
 
         let p = Parser::new_ext(md, main_body_opts()).into_offset_iter();
@@ -1447,7 +1504,14 @@ pub(crate) fn into_string(self) -> String {
             let p = HeadingLinks::new(p, Some(&mut toc), ids, HeadingOffset::H1);
             let p = Footnotes::new(p);
             let p = TableWrapper::new(p.map(|(ev, _)| ev));
-            let p = CodeBlocks::new(p, codes, edition, playground, custom_code_classes_in_docs);
+            let p = CodeBlocks::new(
+                p,
+                codes,
+                edition,
+                playground,
+                custom_code_classes_in_docs,
+                doctest_results,
+            );
             html::push_html(&mut s, p);
         }
 
@@ -1649,6 +1713,32 @@ pub(crate) fn plain_text_summary(md: &str, link_names: &[RenderedLink]) -> Strin
     s
 }
 
+/// Renders the whole markdown string as plain text, for the full-text search index.
+///
+/// - Headings, links, and formatting are stripped.
+/// - Inline code is rendered as-is, without backticks.
+/// - HTML and code blocks are ignored.
+pub(crate) fn plain_text(md: &str) -> String {
+    let mut s = String::with_capacity(md.len());
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(md, summary_opts()) {
+        match event {
+            Event::Start(Tag::CodeBlock(..)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(..)) => in_code_block = false,
+            Event::Text(text) | Event::Code(text) if !in_code_block => s.push_str(&text),
+            Event::HardBreak | Event::SoftBreak => s.push(' '),
+            Event::End(Tag::Paragraph | Tag::Heading(..) | Tag::Item | Tag::TableCell) => {
+                s.push(' ')
+            }
+            _ => (),
+        }
+    }
+
+    s.truncate(s.trim_end().len());
+    s
+}
+
 #[derive(Debug)]
 pub(crate) struct MarkdownLink {
     pub kind: LinkType,
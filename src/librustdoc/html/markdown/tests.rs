@@ -1,4 +1,4 @@
-use super::{find_testable_code, plain_text_summary, short_markdown_summary};
+use super::{find_testable_code, plain_text, plain_text_summary, short_markdown_summary};
 use super::{
     ErrorCodes, HeadingOffset, IdMap, Ignore, LangString, LangStringToken, Markdown,
     MarkdownItemInfo, TagIterator,
@@ -306,6 +306,7 @@ fn t(input: &str, expect: &str) {
             playground: &None,
             heading_offset: HeadingOffset::H2,
             custom_code_classes_in_docs: true,
+            doctest_results: None,
         }
         .into_string();
         assert_eq!(output, expect, "original: {}", input);
@@ -346,6 +347,7 @@ fn t(map: &mut IdMap, input: &str, expect: &str) {
             playground: &None,
             heading_offset: HeadingOffset::H2,
             custom_code_classes_in_docs: true,
+            doctest_results: None,
         }
         .into_string();
         assert_eq!(output, expect, "original: {}", input);
@@ -428,6 +430,23 @@ fn t(input: &str, expect: &str) {
     );
 }
 
+#[test]
+fn test_plain_text() {
+    fn t(input: &str, expect: &str) {
+        let output = plain_text(input);
+        assert_eq!(output, expect, "original: {}", input);
+    }
+
+    t("", "");
+    t("hello [Rust](https://www.rust-lang.org) :)", "hello Rust :)");
+    t("code `let x = i32;`", "code let x = i32;");
+    t("# header\n\nfollowed by some text", "header followed by some text");
+    t("first paragraph\n\nsecond paragraph", "first paragraph second paragraph");
+    t("before\n\n```\nfn main() {}\n```\n\nafter", "before after");
+    t("* one\n* two", "one two");
+    t("<div>hello</div>", "");
+}
+
 #[test]
 fn test_markdown_html_escape() {
     fn t(input: &str, expect: &str) {
@@ -476,6 +495,7 @@ fn t(input: &str, expect: &str) {
             playground: &None,
             heading_offset: HeadingOffset::H2,
             custom_code_classes_in_docs: true,
+            doctest_results: None,
         }
         .into_string();
         assert_eq!(output, expect, "original: {}", input);
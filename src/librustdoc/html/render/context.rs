@@ -25,6 +25,7 @@
 use crate::clean::{self, types::ExternalLocation, ExternalCrate};
 use crate::config::{ModuleSorting, RenderOptions};
 use crate::docfs::{DocFS, PathError};
+use crate::doctest::results::DoctestResults;
 use crate::error::Error;
 use crate::formats::cache::Cache;
 use crate::formats::item_type::ItemType;
@@ -131,6 +132,8 @@ pub(crate) struct SharedContext<'tcx> {
     pub(crate) cache: Cache,
 
     pub(crate) call_locations: AllCallLocations,
+    /// The results of the doctests, shown under the code examples.
+    pub(crate) doctest_results: Option<DoctestResults>,
 }
 
 impl SharedContext<'_> {
@@ -463,6 +466,7 @@ fn init(
             show_type_layout,
             generate_link_to_definition,
             call_locations,
+            doctest_results,
             no_emit_shared,
             html_no_source,
             ..
@@ -550,6 +554,7 @@ fn init(
             span_correspondence_map: matches,
             cache,
             call_locations,
+            doctest_results: (!doctest_results.is_empty()).then_some(doctest_results),
         };
 
         let dst = output;
@@ -0,0 +1,143 @@
+//! The full-text search index, generated with `--full-text-search`.
+//!
+//! The text of every doc comment is split into words, which are lowercased and stripped of their
+//! most common English suffixes, so that searching for "allocates" also finds "allocated". The
+//! index maps each of these stems to the items whose documentation contains it.
+//!
+//! The search in `static/js/search.js` applies the same processing to the words of the query, so
+//! `words` and `stem` must be kept in sync with `fullTextWords` and `stemFullTextWord` there.
+
+use std::collections::BTreeMap;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// Words that appear in nearly every doc comment, and would only make the index bigger.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "in", "is", "it", "its",
+    "of", "on", "or", "that", "the", "this", "to", "was", "will", "with",
+];
+
+/// Longer words are most likely hashes or long numbers, which nobody searches for.
+const MAX_WORD_LEN: usize = 32;
+
+/// Suffixes removed by `stem`, with their replacement. Only the first one that matches is used.
+const SUFFIXES: &[(&str, &str)] = &[
+    ("ies", "y"),
+    ("sses", "ss"),
+    ("ches", "ch"),
+    ("shes", "sh"),
+    ("xes", "x"),
+    ("ing", ""),
+    ("ed", ""),
+    ("ly", ""),
+    ("s", ""),
+];
+
+#[derive(Default)]
+pub(crate) struct FullTextIndex {
+    /// The ids of the items whose documentation contains each stem, in increasing order.
+    stems: BTreeMap<String, Vec<u32>>,
+}
+
+impl FullTextIndex {
+    /// Adds the words of `text` to the index of the item `id`.
+    ///
+    /// Items must be added in increasing order of `id`.
+    pub(crate) fn add(&mut self, id: u32, text: &str) {
+        for word in words(text) {
+            let ids = self.stems.entry(word).or_default();
+            if ids.last() != Some(&id) {
+                ids.push(id);
+            }
+        }
+    }
+}
+
+impl Serialize for FullTextIndex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let stems = self.stems.keys().collect::<Vec<_>>();
+        let ids = self.stems.values().map(|ids| encode_ids(ids)).collect::<Vec<_>>();
+        let mut index = serializer.serialize_struct("FullTextIndex", 2)?;
+        // `w[i]` is a stem, and `p[i]` the list of items whose documentation contains it.
+        index.serialize_field("w", &stems)?;
+        index.serialize_field("p", &ids)?;
+        index.end()
+    }
+}
+
+/// Splits `text` into the stems of its words, dropping the stop words.
+pub(crate) fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|word| (2..=MAX_WORD_LEN).contains(&word.chars().count()))
+        .map(|word| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word))
+}
+
+/// Whether `c` is part of a word. This is simpler than `char::is_alphanumeric`, so that it is
+/// easy to do the same in JavaScript: the only non-ASCII characters that separate words are the
+/// Latin-1 symbols and the general punctuation, such as the quotes and dashes that the markdown
+/// renderer uses.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || (c >= '\u{c0}' && !matches!(c, '\u{d7}' | '\u{f7}' | '\u{2000}'..='\u{206f}'))
+}
+
+/// Strips the most common suffixes of an English word.
+///
+/// This is much cruder than a real stemmer, but it is easy to keep in sync with the JavaScript
+/// version and it is enough to match most plurals and verb forms.
+pub(crate) fn stem(word: &str) -> String {
+    if !word.is_ascii() {
+        return word.to_owned();
+    }
+    let mut stem = word.to_owned();
+    for &(suffix, replacement) in SUFFIXES {
+        let Some(base) = word.strip_suffix(suffix) else { continue };
+        if base.len() + replacement.len() < 3
+            || (suffix == "s" && base.ends_with(|c| matches!(c, 's' | 'i' | 'u')))
+        {
+            continue;
+        }
+        stem = format!("{base}{replacement}");
+        break;
+    }
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+/// Encodes a list of increasing ids as the differences between consecutive ids, each written as
+/// hexadecimal digits where the last digit of a number is lowercase (`a` to `p`) and the other
+/// ones are uppercase (`A` to `P`).
+///
+/// This is much smaller than a JSON array, and still only needs characters that don't have to be
+/// escaped in a JavaScript string.
+fn encode_ids(ids: &[u32]) -> String {
+    let mut encoded = String::new();
+    let mut previous = 0;
+    for &id in ids {
+        let mut delta = id - previous;
+        previous = id;
+        let mut digits = Vec::new();
+        loop {
+            digits.push((delta & 0xf) as u8);
+            delta >>= 4;
+            if delta == 0 {
+                break;
+            }
+        }
+        for (i, digit) in digits.into_iter().enumerate().rev() {
+            let base = if i == 0 { b'a' } else { b'A' };
+            encoded.push(char::from(base + digit));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests;
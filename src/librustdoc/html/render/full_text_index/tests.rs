@@ -0,0 +1,58 @@
+use super::{encode_ids, stem, words, FullTextIndex};
+
+#[test]
+fn test_stem() {
+    fn t(word: &str, expect: &str) {
+        assert_eq!(stem(word), expect, "original: {}", word);
+    }
+
+    t("allocate", "allocat");
+    t("allocates", "allocat");
+    t("allocated", "allocat");
+    t("allocating", "allocat");
+    t("entries", "entry");
+    t("matches", "match");
+    t("boxes", "box");
+    t("classes", "class");
+    t("class", "class");
+    t("status", "status");
+    t("analysis", "analysis");
+    t("quickly", "quick");
+    // Too short to have a suffix removed.
+    t("ring", "ring");
+    t("use", "use");
+    t("données", "données");
+}
+
+#[test]
+fn test_words() {
+    fn t(text: &str, expect: &[&str]) {
+        assert_eq!(words(text).collect::<Vec<_>>(), expect, "original: {}", text);
+    }
+
+    t("", &[]);
+    t("Returns the number of elements.", &["return", "number", "element"]);
+    t("A `HashMap` is a hash_map", &["hashmap", "hash", "map"]);
+    t("Lock-free, safe x", &["lock", "fre", "saf"]);
+    t("don’t—ever", &["don", "ever"]);
+    t("naïve café", &["naïve", "café"]);
+}
+
+#[test]
+fn test_encode_ids() {
+    assert_eq!(encode_ids(&[]), "");
+    assert_eq!(encode_ids(&[0]), "a");
+    assert_eq!(encode_ids(&[1, 2, 18]), "bbBa");
+    assert_eq!(encode_ids(&[300]), "BCm");
+}
+
+#[test]
+fn test_serialize() {
+    let mut index = FullTextIndex::default();
+    index.add(0, "Sorts the slice.");
+    index.add(3, "Sorts the slice, sorting twice.");
+    assert_eq!(
+        serde_json::to_string(&index).unwrap(),
+        r#"{"w":["slic","sort","twic"],"p":["ad","ad","d"]}"#
+    );
+}
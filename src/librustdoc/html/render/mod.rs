@@ -29,6 +29,7 @@
 mod tests;
 
 mod context;
+mod full_text_index;
 mod print_item;
 mod sidebar;
 mod span_map;
@@ -36,6 +37,7 @@
 mod write_shared;
 
 pub(crate) use self::context::*;
+pub(crate) use self::print_item::compare_names;
 pub(crate) use self::span_map::{collect_spans_and_sources, LinkFromSrc};
 
 use std::collections::VecDeque;
@@ -106,6 +108,8 @@ pub(crate) struct IndexItem {
     pub(crate) search_type: Option<IndexItemFunctionType>,
     pub(crate) aliases: Box<[Symbol]>,
     pub(crate) deprecation: Option<Deprecation>,
+    /// The documentation of the item as plain text, if `--full-text-search` is used.
+    pub(crate) full_text: Option<String>,
 }
 
 /// A type used for the search index.
@@ -417,6 +421,7 @@ fn scrape_examples_help(shared: &SharedContext<'_>) -> String {
             playground: &shared.playground,
             heading_offset: HeadingOffset::H1,
             custom_code_classes_in_docs: false,
+            doctest_results: None,
         }
         .into_string()
     )
@@ -463,6 +468,7 @@ fn render_markdown<'a, 'cx: 'a>(
                 playground: &cx.shared.playground,
                 heading_offset,
                 custom_code_classes_in_docs,
+                doctest_results: cx.shared.doctest_results.as_ref(),
             }
             .into_string()
         )
@@ -1810,6 +1816,7 @@ fn render_default_items(
                     playground: &cx.shared.playground,
                     heading_offset: HeadingOffset::H4,
                     custom_code_classes_in_docs,
+                    doctest_results: cx.shared.doctest_results.as_ref(),
                 }
                 .into_string()
             );
@@ -2095,7 +2102,7 @@ pub(crate) enum ItemSection {
 }
 
 impl ItemSection {
-    const ALL: &'static [Self] = {
+    pub(crate) const ALL: &'static [Self] = {
         use ItemSection::*;
         // NOTE: The order here affects the order in the UI.
         // Keep this synchronized with addSidebarItems in main.js
@@ -2158,7 +2165,7 @@ fn id(self) -> &'static str {
         }
     }
 
-    fn name(self) -> &'static str {
+    pub(crate) fn name(self) -> &'static str {
         match self {
             Self::Reexports => "Re-exports",
             Self::Modules => "Modules",
@@ -2189,7 +2196,7 @@ fn name(self) -> &'static str {
     }
 }
 
-fn item_ty_to_section(ty: ItemType) -> ItemSection {
+pub(crate) fn item_ty_to_section(ty: ItemType) -> ItemSection {
     match ty {
         ItemType::ExternCrate | ItemType::Import => ItemSection::Reexports,
         ItemType::Module => ItemSection::Modules,
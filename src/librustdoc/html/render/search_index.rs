@@ -11,15 +11,24 @@
 use crate::formats::cache::{Cache, OrphanImplItem};
 use crate::formats::item_type::ItemType;
 use crate::html::format::join_with_double_colon;
-use crate::html::markdown::short_markdown_summary;
+use crate::html::markdown::{plain_text, short_markdown_summary};
+use crate::html::render::full_text_index::FullTextIndex;
 use crate::html::render::{self, IndexItem, IndexItemFunctionType, RenderType, RenderTypeId};
 
+/// The search index of a crate, ready to be written to the shared files.
+pub(crate) struct SerializedSearchIndex {
+    /// The `"{crate}":{data}` line of `search-index.js`.
+    pub(crate) index: String,
+    /// The JSON of the full-text index, if `--full-text-search` was passed.
+    pub(crate) full_text: Option<String>,
+}
+
 /// Builds the search index from the collected metadata
 pub(crate) fn build_index<'tcx>(
     krate: &clean::Crate,
     cache: &mut Cache,
     tcx: TyCtxt<'tcx>,
-) -> String {
+) -> SerializedSearchIndex {
     let mut itemid_to_pathid = FxHashMap::default();
     let mut primitives = FxHashMap::default();
     let mut crate_paths = vec![];
@@ -41,6 +50,7 @@ pub(crate) fn build_index<'tcx>(
                 search_type: get_function_type_for_search(item, tcx, impl_generics.as_ref(), cache),
                 aliases: item.attrs.get_doc_aliases(),
                 deprecation: item.deprecation(tcx),
+                full_text: cache.full_text_search.then(|| plain_text(&item.doc_value())),
             });
         }
     }
@@ -257,6 +267,8 @@ struct CrateData<'a> {
         aliases: &'a BTreeMap<String, Vec<usize>>,
         // Used when a type has more than one impl with an associated item with the same name.
         associated_item_disambiguators: &'a Vec<(usize, String)>,
+        // Whether a full-text index was generated for this crate.
+        has_full_text: bool,
     }
 
     struct Paths {
@@ -413,12 +425,29 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             if has_aliases {
                 crate_data.serialize_field("a", &self.aliases)?;
             }
+            if self.has_full_text {
+                crate_data.serialize_field("w", &1)?;
+            }
             crate_data.end()
         }
     }
 
+    // The ids of the full-text index are the positions in the search index of the crate, where
+    // the crate itself comes first.
+    let full_text = cache.full_text_search.then(|| {
+        let mut index = FullTextIndex::default();
+        index.add(0, &plain_text(&krate.module.doc_value()));
+        for (i, item) in crate_items.iter().enumerate() {
+            if let Some(text) = &item.full_text {
+                index.add(i as u32 + 1, text);
+            }
+        }
+        // The index only contains alphanumeric characters, so there is nothing to escape.
+        serde_json::to_string(&index).expect("failed serde conversion")
+    });
+
     // Collect the index into a string
-    format!(
+    let index = format!(
         r#""{}":{}"#,
         krate.name(tcx),
         serde_json::to_string(&CrateData {
@@ -427,6 +456,7 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             paths: crate_paths,
             aliases: &aliases,
             associated_item_disambiguators: &associated_item_disambiguators,
+            has_full_text: full_text.is_some(),
         })
         .expect("failed serde conversion")
         // All these `replace` calls are because we have to go through JS string for JSON content.
@@ -434,7 +464,8 @@ fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         .replace('\'', r"\'")
         // We need to escape double quotes for the JSON.
         .replace("\\\"", "\\\\\"")
-    )
+    );
+    SerializedSearchIndex { index, full_text }
 }
 
 pub(crate) fn get_function_type_for_search<'tcx>(
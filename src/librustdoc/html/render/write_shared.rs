@@ -15,6 +15,7 @@
 use serde::ser::SerializeSeq;
 use serde::{Serialize, Serializer};
 
+use super::search_index::SerializedSearchIndex;
 use super::{collect_paths_for_type, ensure_trailing_slash, Context};
 use crate::clean::{Crate, Item, ItemId, ItemKind};
 use crate::config::{EmitType, RenderOptions};
@@ -46,7 +47,7 @@
 pub(super) fn write_shared(
     cx: &mut Context<'_>,
     krate: &Crate,
-    search_index: String,
+    search_index: SerializedSearchIndex,
     options: &RenderOptions,
 ) -> Result<(), Error> {
     // Write out the shared files. Note that these are shared among all rustdoc
@@ -308,7 +309,7 @@ fn add_path(self: &Rc<Self>, path: &Path) {
     let dst = cx.dst.join(&format!("search-index{}.js", cx.shared.resource_suffix));
     let (mut all_indexes, mut krates) =
         try_err!(collect_json(&dst, krate.name(cx.tcx()).as_str()), &dst);
-    all_indexes.push(search_index);
+    all_indexes.push(search_index.index);
     krates.push(krate.name(cx.tcx()).to_string());
     krates.sort();
 
@@ -328,6 +329,25 @@ fn add_path(self: &Rc<Self>, path: &Path) {
         Ok(v.into_bytes())
     })?;
 
+    // The full-text index can be big, so each crate gets its own file, which the search only
+    // loads when it is needed.
+    if let Some(full_text) = &search_index.full_text {
+        cx.shared
+            .fs
+            .create_dir_all(cx.dst.join("full-text-index"))
+            .map_err(|e| PathError::new(e, "full-text-index"))?;
+        let crate_name = krate.name(cx.tcx());
+        write_invocation_specific(&format!("full-text-index/{crate_name}.js"), &|| {
+            Ok(format!(
+                "var fullTextIndex = JSON.parse('{full_text}');\n\
+                 if (typeof window !== 'undefined' && window.addFullTextIndex) \
+                 {{window.addFullTextIndex(\"{crate_name}\", fullTextIndex)}};\n\
+                 if (typeof exports !== 'undefined') {{exports.fullTextIndex = fullTextIndex}};\n"
+            )
+            .into_bytes())
+        })?;
+    }
+
     write_invocation_specific("crates.js", &|| {
         let krates = krates.iter().map(|k| format!("\"{k}\"")).join(",");
         Ok(format!("window.ALL_CRATES = [{krates}];").into_bytes())
@@ -1,4 +1,4 @@
-/* global addClass, getNakedUrl, getSettingValue */
+/* global addClass, getNakedUrl, getSettingValue, getVar */
 /* global onEachLazy, removeClass, searchState, browserSupportsHistoryApi, exports */
 
 "use strict";
@@ -86,6 +86,99 @@ function hasOwnPropertyRustdoc(obj, property) {
     return Object.prototype.hasOwnProperty.call(obj, property);
 }
 
+// The words and suffixes below, and the functions using them, must be kept in sync with
+// librustdoc/html/render/full_text_index.rs, which builds the full-text index.
+const FULL_TEXT_STOP_WORDS = new Set([
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "in", "is", "it", "its",
+    "of", "on", "or", "that", "the", "this", "to", "was", "will", "with",
+]);
+const FULL_TEXT_MAX_WORD_LEN = 32;
+const FULL_TEXT_SUFFIXES = [
+    ["ies", "y"],
+    ["sses", "ss"],
+    ["ches", "ch"],
+    ["shes", "sh"],
+    ["xes", "x"],
+    ["ing", ""],
+    ["ed", ""],
+    ["ly", ""],
+    ["s", ""],
+];
+
+/**
+ * Strips the most common suffixes of an English word.
+ *
+ * @param {string} word - A lowercase word.
+ *
+ * @return {string}
+ */
+function stemFullTextWord(word) {
+    if (!/^[\x00-\x7f]*$/.test(word)) {
+        return word;
+    }
+    let stem = word;
+    for (const [suffix, replacement] of FULL_TEXT_SUFFIXES) {
+        if (!word.endsWith(suffix)) {
+            continue;
+        }
+        const base = word.slice(0, word.length - suffix.length);
+        if (base.length + replacement.length < 3 ||
+            (suffix === "s" && "siu".indexOf(base[base.length - 1]) !== -1)) {
+            continue;
+        }
+        stem = base + replacement;
+        break;
+    }
+    if (stem.length > 3 && stem.endsWith("e")) {
+        stem = stem.slice(0, stem.length - 1);
+    }
+    return stem;
+}
+
+/**
+ * Splits a text into the stems of its words, dropping the stop words.
+ *
+ * @param {string} text
+ *
+ * @return {Array<string>}
+ */
+function fullTextWords(text) {
+    return text.split(/[^0-9A-Za-z\u00C0-\u00D6\u00D8-\u00F6\u00F8-\u1FFF\u2070-\uFFFF]+/)
+        .filter(word => {
+            const len = Array.from(word).length;
+            return len > 1 && len <= FULL_TEXT_MAX_WORD_LEN;
+        })
+        .map(word => word.toLowerCase())
+        .filter(word => !FULL_TEXT_STOP_WORDS.has(word))
+        .map(stemFullTextWord);
+}
+
+/**
+ * Decodes a list of item ids of the full-text index. Each id is stored as the difference with
+ * the previous one, in hexadecimal digits where the last digit of a number is lowercase
+ * (`a` to `p`) and the other ones are uppercase (`A` to `P`).
+ *
+ * @param {string} encoded
+ *
+ * @return {Array<integer>}
+ */
+function decodeFullTextIds(encoded) {
+    const ids = [];
+    let id = 0;
+    let delta = 0;
+    for (let i = 0, len = encoded.length; i < len; ++i) {
+        const c = encoded.charCodeAt(i);
+        if (c >= 97) { // lowercase, last digit
+            id += delta * 16 + c - 97;
+            ids.push(id);
+            delta = 0;
+        } else {
+            delta = delta * 16 + c - 65;
+        }
+    }
+    return ids;
+}
+
 // In the search display, allows to switch between tabs.
 function printTab(nb) {
     let iter = 0;
@@ -249,6 +342,21 @@ function initSearch(rawSearchIndex) {
      */
     let typeNameIdMap;
     const ALIASES = new Map();
+    /**
+     * Map from the crates that have a full-text index to the id of their first row in
+     * `searchIndex`, which is the crate itself.
+     *
+     * @type {Map<string, integer>}
+     */
+    const FULL_TEXT_CRATES = new Map();
+    /**
+     * Map from crate names to their full-text index, once it has been loaded. Each index maps
+     * stems to their encoded list of item ids (see `decodeFullTextIds`).
+     *
+     * @type {Map<string, Map<string, string>>}
+     */
+    const FULL_TEXT_INDEXES = new Map();
+    const fullTextRequested = new Set();
 
     /**
      * Special type name IDs for searching by array.
@@ -1809,6 +1917,66 @@ function initSearch(rawSearchIndex) {
             crateAliases.forEach(pushFunc);
         }
 
+        /**
+         * Adds the items whose documentation contains words of the query to the "In Names" tab,
+         * after the items whose name matched. Items that contain more of the words come first.
+         *
+         * The full-text index of a crate is only loaded the first time it is needed. The search
+         * is run again once it is.
+         *
+         * @param {ResultsTable} ret
+         * @param {string} query
+         * @param {string|null} filterCrates
+         * @param {string} currentCrate
+         */
+        function handleFullText(ret, query, filterCrates, currentCrate) {
+            const stems = fullTextWords(query);
+            if (stems.length === 0) {
+                return;
+            }
+            const scores = new Map();
+            for (const [crate, crateId] of FULL_TEXT_CRATES) {
+                if (filterCrates !== null && crate !== filterCrates) {
+                    continue;
+                }
+                if (!FULL_TEXT_INDEXES.has(crate)) {
+                    loadFullTextIndex(crate);
+                    continue;
+                }
+                const index = FULL_TEXT_INDEXES.get(crate);
+                for (const stem of new Set(stems)) {
+                    if (!index.has(stem)) {
+                        continue;
+                    }
+                    for (const localId of decodeFullTextIds(index.get(stem))) {
+                        const id = crateId + localId;
+                        scores.set(id, (scores.get(id) || 0) + 1);
+                    }
+                }
+            }
+
+            const found = new Set(ret.others.map(item => item.id));
+            const hits = [...scores.keys()].filter(id => !found.has(id));
+            hits.sort((a, b) => {
+                const aaa = searchIndex[a], bbb = searchIndex[b];
+                return scores.get(b) - scores.get(a) ||
+                    (aaa.crate !== currentCrate) - (bbb.crate !== currentCrate) ||
+                    aaa.deprecated - bbb.deprecated ||
+                    a - b;
+            });
+            for (const id of hits) {
+                if (ret.others.length >= MAX_RESULTS) {
+                    break;
+                }
+                const obj = searchIndex[id];
+                const res = buildHrefAndPath(obj);
+                obj.displayPath = pathSplitter(res[0]);
+                obj.fullPath = obj.displayPath + obj.name;
+                obj.href = res[1];
+                ret.others.push(obj);
+            }
+        }
+
         /**
          * This function adds the given result into the provided `results` map if it matches the
          * following condition:
@@ -2132,6 +2300,9 @@ function initSearch(rawSearchIndex) {
             sortResults(results_others, false, currentCrate),
             parsedQuery);
         handleAliases(ret, parsedQuery.original.replace(/"/g, ""), filterCrates, currentCrate);
+        if (parsedQuery.returned.length === 0) {
+            handleFullText(ret, parsedQuery.userQuery, filterCrates, currentCrate);
+        }
         if (parsedQuery.error !== null && ret.others.length !== 0) {
             // It means some doc aliases were found so let's "remove" the error!
             ret.query.error = null;
@@ -2744,6 +2915,8 @@ ${item.displayPath}<span class="${type}">${name}</span>\
              *
              * `c` is an array of item indices that are deprecated.
              *
+             * `w` is set if a full-text index was generated for the crate.
+             *
              * @type {{
              *   doc: string,
              *   a: Object,
@@ -2755,7 +2928,8 @@ ${item.displayPath}<span class="${type}">${name}</span>\
              *   f: Array<RawFunctionSearchType>,
              *   p: Array<Object>,
              *   b: Array<[Number, String]>,
-             *   c: Array<Number>
+             *   c: Array<Number>,
+             *   w: Number|undefined
              * }}
              */
             const crateCorpus = rawSearchIndex[crate];
@@ -2777,6 +2951,9 @@ ${item.displayPath}<span class="${type}">${name}</span>\
                 deprecated: null,
                 implDisambiguator: null,
             };
+            if (crateCorpus.w) {
+                FULL_TEXT_CRATES.set(crate, id);
+            }
             id += 1;
             searchIndex.push(crateRow);
             currentIndex += 1;
@@ -3056,6 +3233,33 @@ ${item.displayPath}<span class="${type}">${name}</span>\
         };
     }
 
+    function loadFullTextIndex(crate) {
+        if (typeof window === "undefined" || fullTextRequested.has(crate)) {
+            return;
+        }
+        fullTextRequested.add(crate);
+        const script = document.createElement("script");
+        script.src = ROOT_PATH + "full-text-index/" + crate + getVar("resource-suffix") + ".js";
+        document.head.append(script);
+    }
+
+    /**
+     * Called by the `full-text-index/{crate}.js` files when they are loaded.
+     *
+     * @param {string} crate
+     * @param {{w: Array<string>, p: Array<string>}} rawIndex
+     */
+    function addFullTextIndex(crate, rawIndex) {
+        const index = new Map();
+        for (let i = 0, len = rawIndex.w.length; i < len; ++i) {
+            index.set(rawIndex.w[i], rawIndex.p[i]);
+        }
+        FULL_TEXT_INDEXES.set(crate, index);
+        if (typeof window !== "undefined" && searchState.input.value.trim() !== "") {
+            search(undefined, true);
+        }
+    }
+
     function updateCrate(ev) {
         if (ev.target.value === "all crates") {
             // If we don't remove it from the URL, it'll be picked up again by the search.
@@ -3074,6 +3278,7 @@ ${item.displayPath}<span class="${type}">${name}</span>\
      */
     const searchWords = buildIndex(rawSearchIndex);
     if (typeof window !== "undefined") {
+        window.addFullTextIndex = addFullTextIndex;
         registerSearchEvents();
         // If there's a search term in the URL, execute the search now.
         if (window.searchState.getQueryStringParams().search) {
@@ -3085,6 +3290,7 @@ ${item.displayPath}<span class="${type}">${name}</span>\
         exports.initSearch = initSearch;
         exports.execQuery = execQuery;
         exports.parseQuery = parseQuery;
+        exports.addFullTextIndex = addFullTextIndex;
     }
     return searchWords;
 }
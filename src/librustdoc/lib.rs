@@ -104,6 +104,7 @@ macro_rules! map {
 }
 
 mod clean;
+mod commonmark;
 mod config;
 mod core;
 mod docfs;
@@ -239,7 +240,9 @@ fn opts() -> Vec<RustcOptGroup> {
         stable("h", |o| o.optflagmulti("h", "help", "show this help message")),
         stable("V", |o| o.optflagmulti("V", "version", "print rustdoc's version")),
         stable("v", |o| o.optflagmulti("v", "verbose", "use verbose output")),
-        stable("w", |o| o.optopt("w", "output-format", "the output type to write", "[html]")),
+        stable("w", |o| {
+            o.optopt("w", "output-format", "the output type to write", "[html|json|markdown]")
+        }),
         stable("output", |o| {
             o.optopt(
                 "",
@@ -520,6 +523,15 @@ fn opts() -> Vec<RustcOptGroup> {
                 "calculate percentage of public items with documentation",
             )
         }),
+        unstable("coverage-threshold", |o| {
+            o.optmulti(
+                "",
+                "coverage-threshold",
+                "with --show-coverage, fail if the documentation coverage of a kind of item is \
+                 below a percentage",
+                "KIND=PERCENTAGE",
+            )
+        }),
         unstable("enable-per-target-ignores", |o| {
             o.optflagmulti(
                 "",
@@ -565,9 +577,40 @@ fn opts() -> Vec<RustcOptGroup> {
         unstable("no-run", |o| {
             o.optflagmulti("", "no-run", "Compile doctests without running them")
         }),
+        unstable("merge-doctests", |o| {
+            o.optflag(
+                "",
+                "merge-doctests",
+                "Compile the doctests that allow it into a single binary",
+            )
+        }),
+        unstable("doctest-results-output-path", |o| {
+            o.optopt(
+                "",
+                "doctest-results-output-path",
+                "Write the results of the doctests to the given path, for `--with-doctest-results`",
+                "PATH",
+            )
+        }),
+        unstable("with-doctest-results", |o| {
+            o.optmulti(
+                "",
+                "with-doctest-results",
+                "Show the results of the doctests, written by `--doctest-results-output-path`, \
+                 under the code examples",
+                "PATH",
+            )
+        }),
         unstable("show-type-layout", |o| {
             o.optflagmulti("", "show-type-layout", "Include the memory layout of types in the docs")
         }),
+        unstable("full-text-search", |o| {
+            o.optflag(
+                "",
+                "full-text-search",
+                "Allow searching the text of doc comments, in addition to item names",
+            )
+        }),
         unstable("nocapture", |o| {
             o.optflag("", "nocapture", "Don't capture stdout and stderr of tests")
         }),
@@ -849,6 +892,14 @@ fn main_args(
                     config::OutputFormat::Json => sess.time("render_json", || {
                         run_renderer::<json::JsonRenderer<'_>>(krate, render_opts, cache, tcx)
                     }),
+                    config::OutputFormat::Markdown => sess.time("render_markdown", || {
+                        run_renderer::<commonmark::MarkdownRenderer<'_>>(
+                            krate,
+                            render_opts,
+                            cache,
+                            tcx,
+                        )
+                    }),
                 }
             })
         })
@@ -82,6 +82,7 @@ pub(crate) fn render<P: AsRef<Path>>(
             playground: &playground,
             // For markdown files, it'll be disabled until the feature is enabled by default.
             custom_code_classes_in_docs: false,
+            doctest_results: Some(&options.doctest_results),
         }
         .into_string()
     } else {
@@ -95,6 +96,7 @@ pub(crate) fn render<P: AsRef<Path>>(
             heading_offset: HeadingOffset::H1,
             // For markdown files, it'll be disabled until the feature is enabled by default.
             custom_code_classes_in_docs: false,
+            doctest_results: Some(&options.doctest_results),
         }
         .into_string()
     };
@@ -168,6 +170,9 @@ pub(crate) fn test(options: Options) -> Result<(), String> {
         false,
     );
 
+    if let Some(results) = &collector.results {
+        results.write();
+    }
     crate::doctest::run_tests(options.test_args, options.nocapture, collector.tests);
     Ok(())
 }
@@ -1,6 +1,7 @@
 //! Calculates information used for the --show-coverage flag.
 use crate::clean;
 use crate::core::DocContext;
+use crate::formats::item_type::ItemType;
 use crate::html::markdown::{find_testable_code, ErrorCodes};
 use crate::passes::check_doc_test_visibility::{should_have_doc_example, Tests};
 use crate::passes::Pass;
@@ -8,12 +9,12 @@
 use rustc_hir as hir;
 use rustc_lint::builtin::MISSING_DOCS;
 use rustc_middle::lint::LintLevelSource;
-use rustc_session::lint;
+use rustc_session::{getopts, lint};
 use rustc_span::FileName;
 use serde::Serialize;
 
 use std::collections::BTreeMap;
-use std::ops;
+use std::{fmt, ops};
 
 pub(crate) const CALCULATE_DOC_COVERAGE: Pass = Pass {
     name: "calculate-doc-coverage",
@@ -22,14 +23,147 @@
 };
 
 fn calculate_doc_coverage(krate: clean::Crate, ctx: &mut DocContext<'_>) -> clean::Crate {
-    let mut calc = CoverageCalculator { items: Default::default(), ctx };
+    let mut calc = CoverageCalculator { items: Default::default(), kinds: Default::default(), ctx };
     calc.visit_crate(&krate);
+    // Submodules are visited before the other items of a module, so restore the source order.
+    for coverage in calc.items.values_mut() {
+        coverage.undocumented.sort_by_key(|item| (item.line, item.column));
+        coverage.missing_examples.sort_by_key(|item| (item.line, item.column));
+    }
 
     calc.print_results();
+    calc.check_thresholds();
 
     krate
 }
 
+/// The items whose documentation coverage can be checked with `--coverage-threshold`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CoverageKind {
+    /// Every item counted in the coverage table.
+    Total,
+    /// Code examples, on the items that should have one.
+    Examples,
+    Modules,
+    Functions,
+    Structs,
+    Unions,
+    Enums,
+    Variants,
+    Fields,
+    Traits,
+    TypeAliases,
+    Constants,
+    Statics,
+    Macros,
+}
+
+impl CoverageKind {
+    const ALL: &'static [Self] = &[
+        CoverageKind::Total,
+        CoverageKind::Examples,
+        CoverageKind::Modules,
+        CoverageKind::Functions,
+        CoverageKind::Structs,
+        CoverageKind::Unions,
+        CoverageKind::Enums,
+        CoverageKind::Variants,
+        CoverageKind::Fields,
+        CoverageKind::Traits,
+        CoverageKind::TypeAliases,
+        CoverageKind::Constants,
+        CoverageKind::Statics,
+        CoverageKind::Macros,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CoverageKind::Total => "total",
+            CoverageKind::Examples => "examples",
+            CoverageKind::Modules => "modules",
+            CoverageKind::Functions => "functions",
+            CoverageKind::Structs => "structs",
+            CoverageKind::Unions => "unions",
+            CoverageKind::Enums => "enums",
+            CoverageKind::Variants => "variants",
+            CoverageKind::Fields => "fields",
+            CoverageKind::Traits => "traits",
+            CoverageKind::TypeAliases => "type-aliases",
+            CoverageKind::Constants => "constants",
+            CoverageKind::Statics => "statics",
+            CoverageKind::Macros => "macros",
+        }
+    }
+
+    fn from_item_type(ty: ItemType) -> Option<Self> {
+        Some(match ty {
+            ItemType::Module => CoverageKind::Modules,
+            ItemType::Function | ItemType::Method | ItemType::TyMethod => CoverageKind::Functions,
+            ItemType::Struct => CoverageKind::Structs,
+            ItemType::Union => CoverageKind::Unions,
+            ItemType::Enum => CoverageKind::Enums,
+            ItemType::Variant => CoverageKind::Variants,
+            ItemType::StructField => CoverageKind::Fields,
+            ItemType::Trait | ItemType::TraitAlias => CoverageKind::Traits,
+            ItemType::TypeAlias | ItemType::AssocType => CoverageKind::TypeAliases,
+            ItemType::Constant | ItemType::AssocConst => CoverageKind::Constants,
+            ItemType::Static => CoverageKind::Statics,
+            ItemType::Macro | ItemType::ProcAttribute | ItemType::ProcDerive => {
+                CoverageKind::Macros
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for CoverageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A minimum documentation coverage given with `--coverage-threshold KIND=PERCENTAGE`.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CoverageThreshold {
+    kind: CoverageKind,
+    percentage: f64,
+}
+
+impl CoverageThreshold {
+    pub(crate) fn from_matches(
+        matches: &getopts::Matches,
+        diag: &rustc_errors::Handler,
+    ) -> Result<Vec<Self>, i32> {
+        matches
+            .opt_strs("coverage-threshold")
+            .iter()
+            .map(|arg| {
+                Self::parse(arg).map_err(|err| {
+                    diag.struct_err(format!("invalid `--coverage-threshold` value `{arg}`: {err}"))
+                        .emit();
+                    1
+                })
+            })
+            .collect()
+    }
+
+    fn parse(arg: &str) -> Result<Self, String> {
+        let Some((kind, percentage)) = arg.split_once('=') else {
+            return Err("expected `KIND=PERCENTAGE`".to_owned());
+        };
+        let Some(&kind) = CoverageKind::ALL.iter().find(|k| k.as_str() == kind) else {
+            let kinds = CoverageKind::ALL.iter().map(|k| format!("`{k}`")).collect::<Vec<_>>();
+            return Err(format!("unknown kind `{kind}`, expected one of {}", kinds.join(", ")));
+        };
+        match percentage.strip_suffix('%').unwrap_or(percentage).parse::<f64>() {
+            Ok(percentage) if (0.0..=100.0).contains(&percentage) => {
+                Ok(CoverageThreshold { kind, percentage })
+            }
+            _ => Err(format!("`{percentage}` is not a percentage between 0 and 100")),
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, Serialize, Debug)]
 struct ItemCount {
     total: u64,
@@ -100,8 +234,28 @@ fn add_assign(&mut self, rhs: Self) {
     }
 }
 
+/// An item that is missing documentation or a code example.
+#[derive(Serialize, Debug)]
+struct MissingItem {
+    name: String,
+    kind: &'static str,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Default, Serialize, Debug)]
+struct FileCoverage {
+    #[serde(flatten)]
+    count: ItemCount,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    undocumented: Vec<MissingItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing_examples: Vec<MissingItem>,
+}
+
 struct CoverageCalculator<'a, 'b> {
-    items: BTreeMap<FileName, ItemCount>,
+    items: BTreeMap<FileName, FileCoverage>,
+    kinds: BTreeMap<CoverageKind, ItemCount>,
     ctx: &'a mut DocContext<'b>,
 }
 
@@ -122,7 +276,7 @@ fn to_json(&self) -> String {
                 .items
                 .iter()
                 .map(|(k, v)| (k.prefer_local().to_string(), v))
-                .collect::<BTreeMap<String, &ItemCount>>(),
+                .collect::<BTreeMap<String, &FileCoverage>>(),
         )
         .expect("failed to convert JSON data to string")
     }
@@ -160,7 +314,8 @@ fn print_table_record(
         );
         print_table_line();
 
-        for (file, &count) in &self.items {
+        for (file, coverage) in &self.items {
+            let count = coverage.count;
             if let Some(percentage) = count.percentage() {
                 print_table_record(
                     &limit_filename_len(file.prefer_local().to_string_lossy().into()),
@@ -182,6 +337,39 @@ fn print_table_record(
         );
         print_table_line();
     }
+
+    fn check_thresholds(&self) {
+        let mut total = ItemCount::default();
+        for coverage in self.items.values() {
+            total += coverage.count;
+        }
+
+        for threshold in &self.ctx.render_options.coverage_thresholds {
+            let percentage = match threshold.kind {
+                CoverageKind::Total => total.percentage(),
+                CoverageKind::Examples => total.examples_percentage(),
+                kind => self.kinds.get(&kind).and_then(ItemCount::percentage),
+            };
+            // Having no item of a kind can't go below any threshold.
+            let Some(percentage) = percentage else { continue };
+            if percentage < threshold.percentage {
+                let what = match threshold.kind {
+                    CoverageKind::Examples => "code example coverage".to_owned(),
+                    CoverageKind::Total => "documentation coverage".to_owned(),
+                    kind => format!("documentation coverage of {kind}"),
+                };
+                self.ctx
+                    .tcx
+                    .sess
+                    .struct_err(format!(
+                        "{what} is {percentage:.1}%, below the threshold of {}%",
+                        threshold.percentage,
+                    ))
+                    .note("use `--output-format json` to list the items missing documentation")
+                    .emit();
+            }
+        }
+    }
 }
 
 impl<'a, 'b> DocVisitor for CoverageCalculator<'a, 'b> {
@@ -259,12 +447,38 @@ fn visit_item(&mut self, i: &clean::Item) {
                 if let Some(span) = i.span(self.ctx.tcx) {
                     let filename = span.filename(self.ctx.sess());
                     debug!("counting {:?} {:?} in {filename:?}", i.type_(), i.name);
-                    self.items.entry(filename).or_default().count_item(
+                    let should_have_doc_example = should_have_doc_example(self.ctx, i);
+                    let coverage = self.items.entry(filename).or_default();
+                    coverage.count.count_item(
                         has_docs,
                         has_doc_example,
-                        should_have_doc_example(self.ctx, i),
+                        should_have_doc_example,
                         should_have_docs,
                     );
+                    if let Some(kind) = CoverageKind::from_item_type(i.type_()) {
+                        self.kinds.entry(kind).or_default().count_item(
+                            has_docs,
+                            has_doc_example,
+                            should_have_doc_example,
+                            should_have_docs,
+                        );
+                    }
+
+                    let missing_item = || {
+                        let lo = span.lo(self.ctx.sess());
+                        MissingItem {
+                            name: i.name.map(|name| name.to_string()).unwrap_or_default(),
+                            kind: i.type_().as_str(),
+                            line: lo.line,
+                            column: lo.col.0 + 1,
+                        }
+                    };
+                    if should_have_docs && !has_docs {
+                        coverage.undocumented.push(missing_item());
+                    }
+                    if should_have_doc_example && !has_doc_example {
+                        coverage.missing_examples.push(missing_item());
+                    }
                 }
             }
         }
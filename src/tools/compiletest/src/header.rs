@@ -996,6 +996,12 @@ macro_rules! decision {
         compile_fail: false,
         no_run: false,
         test_type: test::TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        timeout: None,
+        #[cfg(not(bootstrap))]
+        fixture: None,
+        #[cfg(not(bootstrap))]
+        resources: &[],
     }
 }
 
@@ -518,6 +518,8 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         filters: config.filters.clone(),
         filter_exact: config.filter_exact,
         run_ignored: if config.run_ignored { test::RunIgnored::Yes } else { test::RunIgnored::No },
+        #[cfg(not(bootstrap))]
+        run_failed: test::RunFailed::No,
         format: config.format,
         logfile: config.logfile.clone(),
         run_tests: true,
@@ -526,11 +528,21 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         color: config.color,
         shuffle: false,
         shuffle_seed: None,
+        #[cfg(not(bootstrap))]
+        shard: None,
         test_threads: None,
         skip: config.skip.clone(),
         list: false,
         options: test::Options::new(),
         time_options: None,
+        #[cfg(not(bootstrap))]
+        test_timeout: None,
+        #[cfg(not(bootstrap))]
+        retries: 0,
+        #[cfg(not(bootstrap))]
+        save_baseline: None,
+        #[cfg(not(bootstrap))]
+        baseline: None,
         force_run_in_process: false,
         fail_fast: std::env::var_os("RUSTC_TEST_FAIL_FAST").is_some(),
     }
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+use rustdoc_json_types::{Crate, Id, Item, ItemEnum, Visibility};
+
+/// The items of a crate, keyed by the paths they can be named by.
+///
+/// Paths are relative to the crate root, so that the two versions of a crate can be compared even
+/// if the crate was renamed. Re-exports are followed, so an item that is moved to another module
+/// but re-exported from its old location keeps its old path.
+pub(crate) struct Api<'a> {
+    pub(crate) krate: &'a Crate,
+    pub(crate) crate_name: &'a str,
+    pub(crate) items: BTreeMap<String, ApiItem<'a>>,
+    /// The path used to print each item when it is referred to from a type.
+    pub(crate) paths: FxHashMap<&'a Id, String>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ApiItem<'a> {
+    pub(crate) item: &'a Item,
+    /// Whether every component of the path is public. Non-public items are only present when the
+    /// JSON was generated with `--document-private-items`.
+    pub(crate) public: bool,
+}
+
+impl<'a> Api<'a> {
+    pub(crate) fn new(krate: &'a Crate) -> Self {
+        let root = &krate.index[&krate.root];
+        let mut api = Api {
+            krate,
+            crate_name: root.name.as_deref().unwrap_or_default(),
+            items: BTreeMap::new(),
+            paths: FxHashMap::default(),
+        };
+        api.collect_module(&krate.root, "", true, &mut vec![]);
+        api
+    }
+
+    pub(crate) fn public_items(&self) -> impl Iterator<Item = (&str, &'a Item)> + '_ {
+        self.items.iter().filter(|(_, i)| i.public).map(|(path, i)| (path.as_str(), i.item))
+    }
+
+    pub(crate) fn get(&self, id: &Id) -> Option<&'a Item> {
+        self.krate.index.get(id)
+    }
+
+    fn collect_module(&mut self, id: &'a Id, prefix: &str, public: bool, stack: &mut Vec<&'a Id>) {
+        // Glob re-exports of a parent module would otherwise make us loop forever.
+        if stack.contains(&id) {
+            return;
+        }
+        let index = &self.krate.index;
+        let Some(ItemEnum::Module(module)) = index.get(id).map(|i| &i.inner) else { return };
+        stack.push(id);
+        for child_id in &module.items {
+            let Some(child) = index.get(child_id) else { continue };
+            let child_public = public && child.visibility == Visibility::Public;
+            match &child.inner {
+                ItemEnum::Import(import) => {
+                    let target = import.id.as_ref().and_then(|id| index.get(id));
+                    match (target, import.glob) {
+                        (Some(target), true) => match &target.inner {
+                            ItemEnum::Module(_) => {
+                                self.collect_module(&target.id, prefix, child_public, stack)
+                            }
+                            ItemEnum::Enum(e) => {
+                                for variant in e.variants.iter().filter_map(|id| index.get(id)) {
+                                    let path = join(prefix, variant.name.as_deref().unwrap());
+                                    self.add(path, variant, child_public, stack);
+                                }
+                            }
+                            _ => {}
+                        },
+                        (Some(target), false) => {
+                            let path = join(prefix, &import.name);
+                            self.add(path, target, child_public, stack);
+                        }
+                        // Re-exports of items from other crates can't be compared any further
+                        // than the path they point to.
+                        (None, false) => {
+                            let path = join(prefix, &import.name);
+                            self.add(path, child, child_public, stack);
+                        }
+                        (None, true) => {}
+                    }
+                }
+                ItemEnum::ExternCrate { .. } | ItemEnum::Impl(_) => {}
+                _ => {
+                    let Some(name) = &child.name else { continue };
+                    let child_public = child_public
+                        && !matches!(&child.inner, ItemEnum::Module(m) if m.is_stripped);
+                    self.add(join(prefix, name), child, child_public, stack);
+                }
+            }
+        }
+        stack.pop();
+    }
+
+    fn add(&mut self, path: String, item: &'a Item, public: bool, stack: &mut Vec<&'a Id>) {
+        if let Some(existing) = self.items.get(&path) {
+            if existing.public || !public {
+                return;
+            }
+        }
+        if public {
+            let shortest = match self.paths.get(&item.id) {
+                Some(existing) => existing.len() > path.len(),
+                None => true,
+            };
+            if shortest {
+                self.paths.insert(&item.id, path.clone());
+            }
+        }
+        self.items.insert(path.clone(), ApiItem { item, public });
+
+        let impls = match &item.inner {
+            ItemEnum::Module(_) => return self.collect_module(&item.id, &path, public, stack),
+            ItemEnum::Struct(s) => &s.impls,
+            ItemEnum::Enum(e) => &e.impls,
+            ItemEnum::Union(u) => &u.impls,
+            _ => return,
+        };
+        // Inherent associated items can be named like module items, so they get a path too.
+        let index = &self.krate.index;
+        for impl_ in impls.iter().filter_map(|id| index.get(id)) {
+            let ItemEnum::Impl(impl_) = &impl_.inner else { continue };
+            if impl_.trait_.is_some() {
+                continue;
+            }
+            for assoc in impl_.items.iter().filter_map(|id| index.get(id)) {
+                let Some(name) = &assoc.name else { continue };
+                let assoc_public = public && assoc.visibility == Visibility::Public;
+                let assoc_path = join(&path, name);
+                if !self.items.get(&assoc_path).is_some_and(|i| i.public || !assoc_public) {
+                    self.items.insert(assoc_path, ApiItem { item: assoc, public: assoc_public });
+                }
+            }
+        }
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{prefix}::{name}")
+    }
+}
@@ -0,0 +1,613 @@
+//! Classification of the differences between two versions of a crate's public API.
+//!
+//! The rules follow the [SemVer compatibility] chapter of the Cargo book, erring on the side of a
+//! bigger version bump where the JSON doesn't have enough information to tell.
+//!
+//! [SemVer compatibility]: https://doc.rust-lang.org/cargo/reference/semver.html
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use rustdoc_json_types::{
+    Enum, Function, Generics, Id, Item, ItemEnum, MacroKind, StructKind, Trait, VariantKind,
+    Visibility,
+};
+use serde::Serialize;
+
+use crate::api::Api;
+use crate::print::Printer;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Severity {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Patch => "patch",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Clone)]
+pub(crate) struct Change {
+    pub(crate) severity: Severity,
+    /// The path of the item that changed, relative to the crate root.
+    pub(crate) path: String,
+    pub(crate) message: String,
+}
+
+/// Returns the changes from `old` to `new`, most severe first.
+pub(crate) fn diff(old: &Api<'_>, new: &Api<'_>) -> Vec<Change> {
+    let mut differ = Differ {
+        old_api: old,
+        new_api: new,
+        old: Printer::new(old),
+        new: Printer::new(new),
+        changes: Vec::new(),
+    };
+
+    for (path, old_item) in old.public_items() {
+        match new.items.get(path) {
+            Some(new_item) if new_item.public => differ.item(path, old_item, new_item.item),
+            Some(_) => differ.change(Severity::Major, path, "is no longer public".to_owned()),
+            None => differ.change(
+                Severity::Major,
+                path,
+                format!("removed {}", kind_name(&old_item.inner)),
+            ),
+        }
+    }
+    for (path, new_item) in new.public_items() {
+        if !old.items.get(path).is_some_and(|i| i.public) {
+            differ.change(Severity::Minor, path, format!("added {}", kind_name(&new_item.inner)));
+        }
+    }
+
+    let mut changes = differ.changes;
+    changes.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.path.cmp(&b.path)));
+    changes
+}
+
+/// Returns the version bump needed for `changes`, or `None` if there are none.
+pub(crate) fn required_bump(changes: &[Change]) -> Option<Severity> {
+    changes.iter().map(|c| c.severity).max()
+}
+
+struct Differ<'a> {
+    old_api: &'a Api<'a>,
+    new_api: &'a Api<'a>,
+    old: Printer<'a>,
+    new: Printer<'a>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn change(&mut self, severity: Severity, path: &str, message: String) {
+        self.changes.push(Change { severity, path: path.to_owned(), message });
+    }
+
+    fn item(&mut self, path: &str, old: &'a Item, new: &'a Item) {
+        if std::mem::discriminant(&old.inner) != std::mem::discriminant(&new.inner) {
+            let message =
+                format!("changed from {} to {}", kind_name(&old.inner), kind_name(&new.inner),);
+            return self.change(Severity::Major, path, message);
+        }
+
+        match (&old.deprecation, &new.deprecation) {
+            (None, Some(_)) => self.change(Severity::Minor, path, "deprecated".to_owned()),
+            (Some(_), None) => {
+                self.change(Severity::Patch, path, "no longer deprecated".to_owned())
+            }
+            _ => {}
+        }
+        match (is_non_exhaustive(old), is_non_exhaustive(new)) {
+            (false, true) => {
+                self.change(Severity::Major, path, "is now `#[non_exhaustive]`".to_owned())
+            }
+            (true, false) => {
+                self.change(Severity::Minor, path, "is no longer `#[non_exhaustive]`".to_owned())
+            }
+            _ => {}
+        }
+        if old.docs != new.docs {
+            self.change(Severity::Patch, path, "documentation changed".to_owned());
+        }
+
+        let name = new.name.as_deref().unwrap_or_default();
+        match (&old.inner, &new.inner) {
+            (ItemEnum::Function(o), ItemEnum::Function(n)) => self.function(path, name, o, n),
+            (ItemEnum::Struct(o), ItemEnum::Struct(n)) => {
+                let constructible = !is_non_exhaustive(old);
+                match (&o.kind, &n.kind) {
+                    (StructKind::Unit, StructKind::Unit) => {}
+                    (StructKind::Tuple(of), StructKind::Tuple(nf)) => {
+                        let of = self.old_api.tuple_fields(of);
+                        let nf = self.new_api.tuple_fields(nf);
+                        self.fields(path, "", of, nf, constructible);
+                    }
+                    (
+                        StructKind::Plain { fields: of, fields_stripped: os },
+                        StructKind::Plain { fields: nf, fields_stripped: ns },
+                    ) => {
+                        let of = self.old_api.named_fields(of, *os);
+                        let nf = self.new_api.named_fields(nf, *ns);
+                        self.fields(path, "", of, nf, constructible);
+                    }
+                    (o, n) => self.change(
+                        Severity::Major,
+                        path,
+                        format!(
+                            "changed from a {} struct to a {} struct",
+                            struct_kind_name(o),
+                            struct_kind_name(n),
+                        ),
+                    ),
+                }
+                self.generics(path, &o.generics, &n.generics);
+                self.trait_impls(path, &o.impls, &n.impls);
+            }
+            (ItemEnum::Union(o), ItemEnum::Union(n)) => {
+                let of = self.old_api.named_fields(&o.fields, o.fields_stripped);
+                let nf = self.new_api.named_fields(&n.fields, n.fields_stripped);
+                // Union literals only ever initialize a single field.
+                self.fields(path, "", of, nf, false);
+                self.generics(path, &o.generics, &n.generics);
+                self.trait_impls(path, &o.impls, &n.impls);
+            }
+            (ItemEnum::Enum(o), ItemEnum::Enum(n)) => {
+                self.enum_(path, is_non_exhaustive(old), o, n);
+                self.generics(path, &o.generics, &n.generics);
+                self.trait_impls(path, &o.impls, &n.impls);
+            }
+            (ItemEnum::Trait(o), ItemEnum::Trait(n)) => self.trait_(path, o, n),
+            (ItemEnum::TraitAlias(o), ItemEnum::TraitAlias(n)) => {
+                self.generics(path, &o.generics, &n.generics);
+                let old_bounds = o.params.iter().map(|b| self.old.bound(b)).collect();
+                let new_bounds = n.params.iter().map(|b| self.new.bound(b)).collect();
+                self.bounds(
+                    path,
+                    "bound",
+                    old_bounds,
+                    new_bounds,
+                    Severity::Major,
+                    Severity::Major,
+                );
+            }
+            (ItemEnum::TypeAlias(o), ItemEnum::TypeAlias(n)) => {
+                self.ty(path, "type", self.old.ty(&o.type_), self.new.ty(&n.type_));
+                self.generics(path, &o.generics, &n.generics);
+            }
+            (ItemEnum::Constant(o), ItemEnum::Constant(n)) => {
+                self.ty(path, "type", self.old.ty(&o.type_), self.new.ty(&n.type_));
+                if o.expr != n.expr {
+                    let message = format!("value changed from `{}` to `{}`", o.expr, n.expr);
+                    self.change(Severity::Patch, path, message);
+                }
+            }
+            (ItemEnum::Static(o), ItemEnum::Static(n)) => {
+                self.ty(path, "type", self.old.ty(&o.type_), self.new.ty(&n.type_));
+                if o.mutable != n.mutable {
+                    let message = if n.mutable { "is now mutable" } else { "is no longer mutable" };
+                    self.change(Severity::Major, path, message.to_owned());
+                }
+            }
+            (ItemEnum::ProcMacro(o), ItemEnum::ProcMacro(n)) => {
+                if o.kind != n.kind {
+                    let message = format!(
+                        "changed from {} to {}",
+                        proc_macro_kind_name(&o.kind),
+                        proc_macro_kind_name(&n.kind),
+                    );
+                    self.change(Severity::Major, path, message);
+                }
+                for helper in o.helpers.iter().filter(|h| !n.helpers.contains(h)) {
+                    self.change(
+                        Severity::Major,
+                        path,
+                        format!("removed helper attribute `{helper}`"),
+                    );
+                }
+            }
+            (ItemEnum::Import(o), ItemEnum::Import(n)) if o.source != n.source => {
+                let message = format!("now re-exports `{}` instead of `{}`", n.source, o.source);
+                self.change(Severity::Major, path, message);
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&mut self, path: &str, name: &str, o: &Function, n: &Function) {
+        let old_sig = self.old.fn_signature(name, o);
+        let new_sig = self.new.fn_signature(name, n);
+        if self.old.fn_decl(&o.decl) != self.new.fn_decl(&n.decl)
+            || o.header.abi != n.header.abi
+            || o.header.async_ != n.header.async_
+        {
+            let message = format!("signature changed from `{old_sig}` to `{new_sig}`");
+            self.change(Severity::Major, path, message);
+        }
+        match (o.header.const_, n.header.const_) {
+            (true, false) => self.change(Severity::Major, path, "is no longer const".to_owned()),
+            (false, true) => self.change(Severity::Minor, path, "is now const".to_owned()),
+            _ => {}
+        }
+        match (o.header.unsafe_, n.header.unsafe_) {
+            (false, true) => self.change(Severity::Major, path, "is now unsafe".to_owned()),
+            (true, false) => self.change(Severity::Minor, path, "is no longer unsafe".to_owned()),
+            _ => {}
+        }
+        self.generics(path, &o.generics, &n.generics);
+    }
+
+    fn generics(&mut self, path: &str, o: &Generics, n: &Generics) {
+        let old_params = self.old.generic_params(o);
+        let new_params = self.new.generic_params(n);
+        if old_params != new_params {
+            // New parameters with a default can be added without breaking any existing use.
+            let only_added_defaults = new_params.starts_with(&old_params)
+                && new_params[old_params.len()..].iter().all(|p| p.contains(" = "));
+            let severity = if only_added_defaults { Severity::Minor } else { Severity::Major };
+            let message = format!(
+                "generic parameters changed from `<{}>` to `<{}>`",
+                old_params.join(", "),
+                new_params.join(", "),
+            );
+            self.change(severity, path, message);
+        }
+
+        let old_bounds = self.old.predicates(o);
+        let new_bounds = self.new.predicates(n);
+        self.bounds(path, "bound", old_bounds, new_bounds, Severity::Major, Severity::Minor);
+    }
+
+    fn bounds(
+        &mut self,
+        path: &str,
+        what: &str,
+        old: Vec<String>,
+        new: Vec<String>,
+        added: Severity,
+        removed: Severity,
+    ) {
+        let old = old.into_iter().collect::<BTreeSet<_>>();
+        let new = new.into_iter().collect::<BTreeSet<_>>();
+        for bound in new.difference(&old) {
+            self.change(added, path, format!("added {what} `{bound}`"));
+        }
+        for bound in old.difference(&new) {
+            self.change(removed, path, format!("removed {what} `{bound}`"));
+        }
+    }
+
+    fn ty(&mut self, path: &str, what: &str, old: String, new: String) {
+        if old != new {
+            self.change(Severity::Major, path, format!("{what} changed from `{old}` to `{new}`"));
+        }
+    }
+
+    /// Compares the fields of a struct, union or enum variant.
+    ///
+    /// `constructible` is whether the old version could be built with a struct literal outside of
+    /// the crate if all of its fields are public, in which case adding a field is a breaking
+    /// change.
+    fn fields(
+        &mut self,
+        path: &str,
+        context: &str,
+        old: Fields<'a>,
+        new: Fields<'a>,
+        constructible: bool,
+    ) {
+        let constructible = constructible && !old.hidden;
+        if constructible && new.hidden {
+            let message = format!(
+                "added private fields{context}, which breaks struct literals and patterns \
+                 without `..`"
+            );
+            self.change(Severity::Major, path, message);
+        }
+        let old = old.public;
+        let new = new.public.into_iter().collect::<BTreeMap<_, _>>();
+        for (name, old_field) in &old {
+            let Some(new_field) = new.get(name) else {
+                let message = format!("removed field `{name}`{context}");
+                self.change(Severity::Major, path, message);
+                continue;
+            };
+            if let (ItemEnum::StructField(o), ItemEnum::StructField(n)) =
+                (&old_field.inner, &new_field.inner)
+            {
+                let what = format!("type of field `{name}`{context}");
+                self.ty(path, &what, self.old.ty(o), self.new.ty(n));
+            }
+        }
+        for name in new.keys().filter(|name| !old.iter().any(|(o, _)| o == *name)) {
+            if constructible {
+                let message =
+                    format!("added field `{name}`{context}, which breaks struct literals");
+                self.change(Severity::Major, path, message);
+            } else {
+                self.change(Severity::Minor, path, format!("added field `{name}`{context}"));
+            }
+        }
+    }
+
+    fn enum_(&mut self, path: &str, non_exhaustive: bool, o: &Enum, n: &Enum) {
+        let old_variants = self.old_api.named_items(&o.variants);
+        let new_variants =
+            self.new_api.named_items(&n.variants).into_iter().collect::<BTreeMap<_, _>>();
+        for (name, old_variant) in &old_variants {
+            let Some(new_variant) = new_variants.get(name) else {
+                self.change(Severity::Major, path, format!("removed variant `{name}`"));
+                continue;
+            };
+            let (ItemEnum::Variant(ov), ItemEnum::Variant(nv)) =
+                (&old_variant.inner, &new_variant.inner)
+            else {
+                continue;
+            };
+            let context = format!(" of variant `{name}`");
+            let constructible = !is_non_exhaustive(old_variant);
+            match (&ov.kind, &nv.kind) {
+                (VariantKind::Plain, VariantKind::Plain) => {}
+                (VariantKind::Tuple(of), VariantKind::Tuple(nf)) => {
+                    let of = self.old_api.tuple_fields(of);
+                    let nf = self.new_api.tuple_fields(nf);
+                    self.fields(path, &context, of, nf, constructible);
+                }
+                (
+                    VariantKind::Struct { fields: of, fields_stripped: os },
+                    VariantKind::Struct { fields: nf, fields_stripped: ns },
+                ) => {
+                    let of = self.old_api.named_fields(of, *os);
+                    let nf = self.new_api.named_fields(nf, *ns);
+                    self.fields(path, &context, of, nf, constructible);
+                }
+                (o, n) => self.change(
+                    Severity::Major,
+                    path,
+                    format!(
+                        "variant `{name}` changed from a {} variant to a {} variant",
+                        variant_kind_name(o),
+                        variant_kind_name(n),
+                    ),
+                ),
+            }
+        }
+        for name in new_variants.keys().filter(|name| !old_variants.iter().any(|(o, _)| o == *name))
+        {
+            if non_exhaustive {
+                self.change(Severity::Minor, path, format!("added variant `{name}`"));
+            } else {
+                let message = format!("added variant `{name}`, which breaks exhaustive matches");
+                self.change(Severity::Major, path, message);
+            }
+        }
+    }
+
+    fn trait_(&mut self, path: &str, o: &Trait, n: &Trait) {
+        if o.is_unsafe != n.is_unsafe {
+            let message = if n.is_unsafe { "is now unsafe" } else { "is no longer unsafe" };
+            self.change(Severity::Major, path, message.to_owned());
+        }
+        if o.is_auto != n.is_auto {
+            let message =
+                if n.is_auto { "is now an auto trait" } else { "is no longer an auto trait" };
+            self.change(Severity::Major, path, message.to_owned());
+        }
+        self.generics(path, &o.generics, &n.generics);
+        let old_bounds = o.bounds.iter().map(|b| self.old.bound(b)).collect();
+        let new_bounds = n.bounds.iter().map(|b| self.new.bound(b)).collect();
+        self.bounds(path, "supertrait", old_bounds, new_bounds, Severity::Major, Severity::Minor);
+
+        let old_items = self.old_api.named_items(&o.items);
+        let new_items = self.new_api.named_items(&n.items).into_iter().collect::<BTreeMap<_, _>>();
+        for (name, old_item) in &old_items {
+            let kind = assoc_kind_name(&old_item.inner);
+            let Some(new_item) = new_items.get(name) else {
+                self.change(Severity::Major, path, format!("removed {kind} `{name}`"));
+                continue;
+            };
+            let item_path = format!("{path}::{name}");
+            match (&old_item.inner, &new_item.inner) {
+                (ItemEnum::Function(of), ItemEnum::Function(nf)) => {
+                    if of.has_body && !nf.has_body {
+                        let message = "no longer has a default implementation".to_owned();
+                        self.change(Severity::Major, &item_path, message);
+                    }
+                    self.function(&item_path, name, of, nf);
+                }
+                (
+                    ItemEnum::AssocType { generics: og, bounds: ob, default: od },
+                    ItemEnum::AssocType { generics: ng, bounds: nb, default: nd },
+                ) => {
+                    if od.is_some() && nd.is_none() {
+                        let message = "no longer has a default".to_owned();
+                        self.change(Severity::Major, &item_path, message);
+                    }
+                    self.generics(&item_path, og, ng);
+                    let old_bounds = ob.iter().map(|b| self.old.bound(b)).collect();
+                    let new_bounds = nb.iter().map(|b| self.new.bound(b)).collect();
+                    let major = Severity::Major;
+                    self.bounds(&item_path, "bound", old_bounds, new_bounds, major, major);
+                }
+                (
+                    ItemEnum::AssocConst { type_: ot, default: od },
+                    ItemEnum::AssocConst { type_: nt, default: nd },
+                ) => {
+                    if od.is_some() && nd.is_none() {
+                        let message = "no longer has a default".to_owned();
+                        self.change(Severity::Major, &item_path, message);
+                    }
+                    self.ty(&item_path, "type", self.old.ty(ot), self.new.ty(nt));
+                }
+                (o, n) => {
+                    let message =
+                        format!("changed from {} to {}", assoc_kind_name(o), assoc_kind_name(n));
+                    self.change(Severity::Major, &item_path, message);
+                }
+            }
+        }
+        for (name, new_item) in &new_items {
+            if old_items.iter().any(|(o, _)| o == name) {
+                continue;
+            }
+            let kind = assoc_kind_name(&new_item.inner);
+            if has_default(new_item) {
+                self.change(Severity::Minor, path, format!("added provided {kind} `{name}`"));
+            } else {
+                self.change(Severity::Major, path, format!("added required {kind} `{name}`"));
+            }
+        }
+    }
+
+    fn trait_impls(&mut self, path: &str, old: &[Id], new: &[Id]) {
+        let old_impls = self.old_api.implemented_traits(&self.old, old);
+        let new_impls = self.new_api.implemented_traits(&self.new, new);
+        for trait_ in old_impls.difference(&new_impls) {
+            self.change(Severity::Major, path, format!("no longer implements `{trait_}`"));
+        }
+        for trait_ in new_impls.difference(&old_impls) {
+            self.change(Severity::Minor, path, format!("now implements `{trait_}`"));
+        }
+    }
+}
+
+impl<'a> Api<'a> {
+    fn named_items(&self, ids: &[Id]) -> Vec<(String, &'a Item)> {
+        ids.iter()
+            .filter_map(|id| self.get(id))
+            .filter_map(|item| Some((item.name.clone()?, item)))
+            .collect()
+    }
+
+    fn named_fields(&self, ids: &[Id], stripped: bool) -> Fields<'a> {
+        let fields = self.named_items(ids);
+        let hidden = stripped || fields.iter().any(|(_, field)| !is_public_field(field));
+        let public = fields.into_iter().filter(|(_, field)| is_public_field(field)).collect();
+        Fields { public, hidden }
+    }
+
+    /// Tuple fields are named by their index. Stripped fields are `None`.
+    fn tuple_fields(&self, ids: &[Option<Id>]) -> Fields<'a> {
+        let fields = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| Some((i.to_string(), self.get(id.as_ref()?)?)))
+            .collect::<Vec<_>>();
+        let hidden =
+            fields.iter().any(|field| !field.as_ref().is_some_and(|(_, f)| is_public_field(f)));
+        let public = fields.into_iter().flatten().filter(|(_, f)| is_public_field(f)).collect();
+        Fields { public, hidden }
+    }
+
+    /// Returns the traits implemented by the impls `ids`, leaving out blanket impls since they
+    /// don't depend on the type.
+    fn implemented_traits(&self, printer: &Printer<'_>, ids: &[Id]) -> BTreeSet<String> {
+        ids.iter()
+            .filter_map(|id| match &self.get(id)?.inner {
+                ItemEnum::Impl(impl_) if impl_.blanket_impl.is_none() => {
+                    let trait_ = printer.path(impl_.trait_.as_ref()?);
+                    Some(if impl_.negative { format!("!{trait_}") } else { trait_ })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The fields of one version of a struct, union or enum variant.
+struct Fields<'a> {
+    /// The fields that can be named outside of the crate, by name.
+    public: Vec<(String, &'a Item)>,
+    /// Whether some fields can't be named outside of the crate: private fields, or fields that
+    /// were stripped from the JSON because it was generated without `--document-private-items`.
+    hidden: bool,
+}
+
+/// Whether `field` can be named outside of the crate. The fields of enum variants have the
+/// `Default` visibility, since they inherit the visibility of the enum.
+fn is_public_field(field: &Item) -> bool {
+    matches!(field.visibility, Visibility::Public | Visibility::Default)
+}
+
+fn is_non_exhaustive(item: &Item) -> bool {
+    item.attrs.iter().any(|attr| attr == "#[non_exhaustive]")
+}
+
+fn has_default(item: &Item) -> bool {
+    match &item.inner {
+        ItemEnum::Function(f) => f.has_body,
+        ItemEnum::AssocType { default, .. } => default.is_some(),
+        ItemEnum::AssocConst { default, .. } => default.is_some(),
+        _ => false,
+    }
+}
+
+fn kind_name(item: &ItemEnum) -> &'static str {
+    match item {
+        ItemEnum::Module(_) => "module",
+        ItemEnum::ExternCrate { .. } => "extern crate",
+        ItemEnum::Import(_) => "re-export",
+        ItemEnum::Union(_) => "union",
+        ItemEnum::Struct(_) => "struct",
+        ItemEnum::StructField(_) => "field",
+        ItemEnum::Enum(_) => "enum",
+        ItemEnum::Variant(_) => "variant",
+        ItemEnum::Function(_) => "function",
+        ItemEnum::Trait(_) => "trait",
+        ItemEnum::TraitAlias(_) => "trait alias",
+        ItemEnum::Impl(_) => "impl",
+        ItemEnum::TypeAlias(_) => "type alias",
+        ItemEnum::OpaqueTy(_) => "opaque type",
+        ItemEnum::Constant(_) => "constant",
+        ItemEnum::Static(_) => "static",
+        ItemEnum::ForeignType => "foreign type",
+        ItemEnum::Macro(_) => "macro",
+        ItemEnum::ProcMacro(m) => proc_macro_kind_name(&m.kind),
+        ItemEnum::Primitive(_) => "primitive",
+        ItemEnum::AssocConst { .. } => "associated constant",
+        ItemEnum::AssocType { .. } => "associated type",
+    }
+}
+
+fn assoc_kind_name(item: &ItemEnum) -> &'static str {
+    match item {
+        ItemEnum::Function(_) => "method",
+        _ => kind_name(item),
+    }
+}
+
+fn proc_macro_kind_name(kind: &MacroKind) -> &'static str {
+    match kind {
+        MacroKind::Bang => "macro",
+        MacroKind::Attr => "attribute macro",
+        MacroKind::Derive => "derive macro",
+    }
+}
+
+fn struct_kind_name(kind: &StructKind) -> &'static str {
+    match kind {
+        StructKind::Unit => "unit",
+        StructKind::Tuple(_) => "tuple",
+        StructKind::Plain { .. } => "plain",
+    }
+}
+
+fn variant_kind_name(kind: &VariantKind) -> &'static str {
+    match kind {
+        VariantKind::Plain => "plain",
+        VariantKind::Tuple(_) => "tuple",
+        VariantKind::Struct { .. } => "struct",
+    }
+}
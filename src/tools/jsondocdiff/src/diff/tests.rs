@@ -0,0 +1,386 @@
+use rustc_hash::FxHashMap;
+use rustdoc_json_types::{
+    Abi, Crate, FORMAT_VERSION, FnDecl, Function, GenericBound, GenericParamDef,
+    GenericParamDefKind, Generics, Header, Id, Import, Item, ItemEnum, ItemKind, ItemSummary,
+    Module, Path, Struct, StructKind, Trait, TraitBoundModifier, Type, Variant, VariantKind,
+    Visibility,
+};
+
+use super::*;
+
+fn id(s: &str) -> Id {
+    Id(s.to_owned())
+}
+
+fn item(item_id: &str, name: &str, inner: ItemEnum) -> Item {
+    Item {
+        id: id(item_id),
+        crate_id: 0,
+        name: Some(name.to_owned()),
+        span: None,
+        visibility: Visibility::Public,
+        docs: None,
+        links: FxHashMap::default(),
+        attrs: vec![],
+        deprecation: None,
+        inner,
+    }
+}
+
+fn module(item_id: &str, name: &str, items: &[&str]) -> Item {
+    item(
+        item_id,
+        name,
+        ItemEnum::Module(Module {
+            is_crate: false,
+            items: items.iter().map(|i| id(i)).collect(),
+            is_stripped: false,
+        }),
+    )
+}
+
+/// Builds a crate whose root module contains `root_items`.
+fn krate(root_items: &[&str], items: Vec<Item>) -> Crate {
+    let mut root = module("root", "krate", root_items);
+    if let ItemEnum::Module(m) = &mut root.inner {
+        m.is_crate = true;
+    }
+    let clone = ItemSummary {
+        crate_id: 1,
+        path: vec!["core".to_owned(), "clone".to_owned(), "Clone".to_owned()],
+        kind: ItemKind::Trait,
+    };
+    Crate {
+        root: id("root"),
+        crate_version: None,
+        includes_private: true,
+        index: items.into_iter().chain([root]).map(|i| (i.id.clone(), i)).collect(),
+        paths: FxHashMap::from_iter([(id("clone"), clone)]),
+        external_crates: FxHashMap::default(),
+        format_version: FORMAT_VERSION,
+    }
+}
+
+fn function(inputs: Vec<Type>, generics: Generics, has_body: bool) -> ItemEnum {
+    ItemEnum::Function(Function {
+        decl: FnDecl {
+            inputs: inputs.into_iter().map(|t| ("x".to_owned(), t)).collect(),
+            output: None,
+            c_variadic: false,
+        },
+        generics,
+        header: Header { const_: false, unsafe_: false, async_: false, abi: Abi::Rust },
+        has_body,
+    })
+}
+
+fn no_generics() -> Generics {
+    Generics { params: vec![], where_predicates: vec![] }
+}
+
+fn type_param(bounds: Vec<GenericBound>) -> Generics {
+    Generics {
+        params: vec![GenericParamDef {
+            name: "T".to_owned(),
+            kind: GenericParamDefKind::Type { bounds, default: None, synthetic: false },
+        }],
+        where_predicates: vec![],
+    }
+}
+
+fn clone_bound() -> GenericBound {
+    GenericBound::TraitBound {
+        trait_: Path { name: "Clone".to_owned(), id: id("clone"), args: None },
+        generic_params: vec![],
+        modifier: TraitBoundModifier::None,
+    }
+}
+
+fn primitive(name: &str) -> Type {
+    Type::Primitive(name.to_owned())
+}
+
+#[track_caller]
+fn check(old: &Crate, new: &Crate, expected: &[(Severity, &str, &str)]) {
+    let changes = diff(&Api::new(old), &Api::new(new));
+    let changes = changes
+        .iter()
+        .map(|c| (c.severity, c.path.as_str(), c.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(changes, expected);
+}
+
+#[test]
+fn added_and_removed_items() {
+    let old = krate(
+        &["1", "2"],
+        vec![
+            item("1", "foo", function(vec![], no_generics(), true)),
+            item(
+                "2",
+                "Bar",
+                ItemEnum::Struct(Struct {
+                    kind: StructKind::Unit,
+                    generics: no_generics(),
+                    impls: vec![],
+                }),
+            ),
+        ],
+    );
+    let new = krate(
+        &["2", "3"],
+        vec![
+            item(
+                "2",
+                "Bar",
+                ItemEnum::Struct(Struct {
+                    kind: StructKind::Unit,
+                    generics: no_generics(),
+                    impls: vec![],
+                }),
+            ),
+            item("3", "baz", function(vec![], no_generics(), true)),
+        ],
+    );
+    check(
+        &old,
+        &new,
+        &[(Severity::Major, "foo", "removed function"), (Severity::Minor, "baz", "added function")],
+    );
+}
+
+#[test]
+fn moved_items_keep_their_reexported_path() {
+    let old = krate(&["1"], vec![item("1", "foo", function(vec![], no_generics(), true))]);
+    let mut private = module("2", "private", &["1"]);
+    private.visibility = Visibility::Crate;
+    let reexport = item(
+        "3",
+        "foo",
+        ItemEnum::Import(Import {
+            source: "self::private::foo".to_owned(),
+            name: "foo".to_owned(),
+            id: Some(id("1")),
+            glob: false,
+        }),
+    );
+    let new = krate(
+        &["2", "3"],
+        vec![private, reexport, item("1", "foo", function(vec![], no_generics(), true))],
+    );
+    check(&old, &new, &[]);
+}
+
+#[test]
+fn changed_signature() {
+    let old = krate(
+        &["1"],
+        vec![item("1", "foo", function(vec![primitive("u32")], no_generics(), true))],
+    );
+    let new = krate(
+        &["1"],
+        vec![item("1", "foo", function(vec![primitive("u64")], no_generics(), true))],
+    );
+    check(
+        &old,
+        &new,
+        &[(Severity::Major, "foo", "signature changed from `fn foo(u32)` to `fn foo(u64)`")],
+    );
+}
+
+#[test]
+fn added_and_removed_bounds() {
+    let t = || vec![Type::Generic("T".to_owned())];
+    let unbounded = krate(&["1"], vec![item("1", "foo", function(t(), type_param(vec![]), true))]);
+    let bounded =
+        krate(&["1"], vec![item("1", "foo", function(t(), type_param(vec![clone_bound()]), true))]);
+    check(&unbounded, &bounded, &[(Severity::Major, "foo", "added bound `T: core::clone::Clone`")]);
+    check(
+        &bounded,
+        &unbounded,
+        &[(Severity::Minor, "foo", "removed bound `T: core::clone::Clone`")],
+    );
+}
+
+#[test]
+fn new_trait_items() {
+    let trait_ = |items: &[&str]| {
+        item(
+            "1",
+            "Tr",
+            ItemEnum::Trait(Trait {
+                is_auto: false,
+                is_unsafe: false,
+                items: items.iter().map(|i| id(i)).collect(),
+                generics: no_generics(),
+                bounds: vec![],
+                implementations: vec![],
+            }),
+        )
+    };
+    let old = krate(&["1"], vec![trait_(&[])]);
+    let new = krate(
+        &["1"],
+        vec![
+            trait_(&["2", "3"]),
+            item("2", "required", function(vec![], no_generics(), false)),
+            item("3", "provided", function(vec![], no_generics(), true)),
+        ],
+    );
+    check(
+        &old,
+        &new,
+        &[
+            (Severity::Major, "Tr", "added required method `required`"),
+            (Severity::Minor, "Tr", "added provided method `provided`"),
+        ],
+    );
+}
+
+#[test]
+fn visibility_changes() {
+    let old = krate(&["1"], vec![item("1", "foo", function(vec![], no_generics(), true))]);
+    let mut private_foo = item("1", "foo", function(vec![], no_generics(), true));
+    private_foo.visibility = Visibility::Crate;
+    let new = krate(&["1"], vec![private_foo]);
+    check(&old, &new, &[(Severity::Major, "foo", "is no longer public")]);
+    check(&new, &old, &[(Severity::Minor, "foo", "added function")]);
+}
+
+#[test]
+fn added_variants() {
+    let enum_ = |variants: &[&str], non_exhaustive: bool| {
+        let mut e = item(
+            "1",
+            "E",
+            ItemEnum::Enum(Enum {
+                generics: no_generics(),
+                variants_stripped: false,
+                variants: variants.iter().map(|i| id(i)).collect(),
+                impls: vec![],
+            }),
+        );
+        if non_exhaustive {
+            e.attrs.push("#[non_exhaustive]".to_owned());
+        }
+        e
+    };
+    let variant = |item_id: &str, name: &str| {
+        item(
+            item_id,
+            name,
+            ItemEnum::Variant(Variant { kind: VariantKind::Plain, discriminant: None }),
+        )
+    };
+
+    let old = krate(&["1"], vec![enum_(&["2"], false), variant("2", "A")]);
+    let new = krate(&["1"], vec![enum_(&["2", "3"], false), variant("2", "A"), variant("3", "B")]);
+    check(
+        &old,
+        &new,
+        &[(Severity::Major, "E", "added variant `B`, which breaks exhaustive matches")],
+    );
+
+    let old = krate(&["1"], vec![enum_(&["2"], true), variant("2", "A")]);
+    let new = krate(&["1"], vec![enum_(&["2", "3"], true), variant("2", "A"), variant("3", "B")]);
+    check(&old, &new, &[(Severity::Minor, "E", "added variant `B`")]);
+}
+
+fn field(item_id: &str, name: &str, ty: &str) -> Item {
+    item(item_id, name, ItemEnum::StructField(primitive(ty)))
+}
+
+fn private_field(item_id: &str, name: &str, ty: &str) -> Item {
+    let mut field = field(item_id, name, ty);
+    field.visibility = Visibility::Crate;
+    field
+}
+
+fn struct_(kind: StructKind) -> Item {
+    item("1", "S", ItemEnum::Struct(Struct { kind, generics: no_generics(), impls: vec![] }))
+}
+
+fn plain_struct(fields: &[&str], fields_stripped: bool) -> Item {
+    struct_(StructKind::Plain { fields: fields.iter().map(|i| id(i)).collect(), fields_stripped })
+}
+
+#[test]
+fn added_public_field() {
+    let old = krate(&["1"], vec![plain_struct(&["2"], false), field("2", "a", "u32")]);
+    let new = krate(
+        &["1"],
+        vec![plain_struct(&["2", "3"], false), field("2", "a", "u32"), field("3", "b", "u32")],
+    );
+    check(&old, &new, &[(Severity::Major, "S", "added field `b`, which breaks struct literals")]);
+
+    // A struct with private fields can't be built with a struct literal anyway.
+    let old = krate(&["1"], vec![plain_struct(&["2"], true), field("2", "a", "u32")]);
+    let new = krate(
+        &["1"],
+        vec![plain_struct(&["2", "3"], true), field("2", "a", "u32"), field("3", "b", "u32")],
+    );
+    check(&old, &new, &[(Severity::Minor, "S", "added field `b`")]);
+}
+
+#[test]
+fn added_private_field() {
+    let expected = [(
+        Severity::Major,
+        "S",
+        "added private fields, which breaks struct literals and patterns without `..`",
+    )];
+    let old = krate(&["1"], vec![plain_struct(&["2"], false), field("2", "a", "u32")]);
+    // Without `--document-private-items`, the private field is stripped.
+    let stripped = krate(&["1"], vec![plain_struct(&["2"], true), field("2", "a", "u32")]);
+    check(&old, &stripped, &expected);
+    // With it, the field is there, but not public.
+    let documented = krate(
+        &["1"],
+        vec![
+            plain_struct(&["2", "3"], false),
+            field("2", "a", "u32"),
+            private_field("3", "b", "u32"),
+        ],
+    );
+    check(&old, &documented, &expected);
+
+    // Once there are private fields, adding or removing more is not a change.
+    let more = krate(
+        &["1"],
+        vec![
+            plain_struct(&["2", "3", "4"], false),
+            field("2", "a", "u32"),
+            private_field("3", "b", "u32"),
+            private_field("4", "c", "u32"),
+        ],
+    );
+    check(&documented, &more, &[]);
+    check(&more, &documented, &[]);
+
+    let old = krate(
+        &["1"],
+        vec![struct_(StructKind::Tuple(vec![Some(id("2"))])), field("2", "0", "u32")],
+    );
+    let new = krate(
+        &["1"],
+        vec![struct_(StructKind::Tuple(vec![Some(id("2")), None])), field("2", "0", "u32")],
+    );
+    check(&old, &new, &expected);
+}
+
+#[test]
+fn removed_field() {
+    let old = krate(
+        &["1"],
+        vec![plain_struct(&["2", "3"], false), field("2", "a", "u32"), field("3", "b", "u32")],
+    );
+    let new = krate(&["1"], vec![plain_struct(&["2"], false), field("2", "a", "u32")]);
+    check(&old, &new, &[(Severity::Major, "S", "removed field `b`")]);
+}
+
+#[test]
+fn changed_field_type() {
+    let old = krate(&["1"], vec![plain_struct(&["2"], false), field("2", "a", "u32")]);
+    let new = krate(&["1"], vec![plain_struct(&["2"], false), field("2", "a", "u64")]);
+    check(&old, &new, &[(Severity::Major, "S", "type of field `a` changed from `u32` to `u64`")]);
+}
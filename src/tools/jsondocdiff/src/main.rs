@@ -0,0 +1,99 @@
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use fs_err as fs;
+use rustdoc_json_types::{Crate, FORMAT_VERSION};
+use serde::Serialize;
+
+use crate::api::Api;
+use crate::diff::{Change, Severity};
+
+mod api;
+mod diff;
+mod print;
+
+#[derive(Debug, Serialize)]
+struct JsonOutput {
+    old: PathBuf,
+    new: PathBuf,
+    /// The version bump required by the changes, `None` if the public API didn't change.
+    bump: Option<Severity>,
+    changes: Vec<Change>,
+}
+
+/// Compares the rustdoc JSON output of two versions of a crate and classifies the changes to its
+/// public API as major, minor or patch changes.
+#[derive(Parser)]
+struct Cli {
+    /// The json file of the old version of the crate
+    old: PathBuf,
+
+    /// The json file of the new version of the crate
+    new: PathBuf,
+
+    /// Fail if the changes require a bigger version bump than this
+    #[arg(long, value_enum)]
+    max_bump: Option<Severity>,
+
+    /// Also list changes that only need a patch release, such as documentation changes
+    #[arg(long)]
+    verbose: bool,
+
+    #[arg(long)]
+    json_output: Option<String>,
+}
+
+fn load(path: &Path) -> Result<Crate> {
+    let contents = fs::read_to_string(path)?;
+    let krate: Crate = serde_json::from_str(&contents)?;
+    if krate.format_version != FORMAT_VERSION {
+        bail!(
+            "{} has format version {}, but only version {FORMAT_VERSION} is supported",
+            path.display(),
+            krate.format_version,
+        );
+    }
+    Ok(krate)
+}
+
+fn main() -> Result<()> {
+    let Cli { old, new, max_bump, verbose, json_output } = Cli::parse();
+
+    let old_krate = load(&old)?;
+    let new_krate = load(&new)?;
+    let old_api = Api::new(&old_krate);
+    let new_api = Api::new(&new_krate);
+
+    let changes = diff::diff(&old_api, &new_api);
+    let bump = diff::required_bump(&changes);
+
+    for change in &changes {
+        if change.severity > Severity::Patch || verbose {
+            println!(
+                "{}: `{}::{}`: {}",
+                change.severity, new_api.crate_name, change.path, change.message
+            );
+        }
+    }
+    match bump {
+        Some(bump) => println!("required version bump: {bump}"),
+        None => println!("no changes to the public API"),
+    }
+
+    if let Some(json_output) = json_output {
+        let output = JsonOutput { old, new, bump, changes };
+        let mut f = BufWriter::new(fs::File::create(json_output)?);
+        serde_json::to_writer(&mut f, &output)?;
+        f.flush()?;
+    }
+
+    if let (Some(bump), Some(max_bump)) = (bump, max_bump) {
+        if bump > max_bump {
+            bail!("the changes require a {bump} version bump, but at most {max_bump} is allowed");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,268 @@
+//! Printing of types and signatures as Rust source.
+//!
+//! The ids in rustdoc's JSON output are not stable between two runs, so types are compared by
+//! their printed form instead. Paths to items of the crate are printed relative to the crate root,
+//! and paths to items of other crates are printed in full.
+
+use rustdoc_json_types::{
+    Abi, FnDecl, Function, GenericArg, GenericArgs, GenericBound, GenericParamDef,
+    GenericParamDefKind, Generics, Header, Path, Term, TraitBoundModifier, Type, TypeBindingKind,
+    WherePredicate,
+};
+
+use crate::api::Api;
+
+pub(crate) struct Printer<'a> {
+    api: &'a Api<'a>,
+}
+
+impl<'a> Printer<'a> {
+    pub(crate) fn new(api: &'a Api<'a>) -> Self {
+        Printer { api }
+    }
+
+    pub(crate) fn path(&self, path: &Path) -> String {
+        let external_path = self.api.krate.paths.get(&path.id).filter(|s| s.crate_id != 0);
+        let name = match (self.api.paths.get(&path.id), external_path) {
+            (Some(public_path), _) => public_path.clone(),
+            (None, Some(summary)) => summary.path.join("::"),
+            (None, None) => path.name.clone(),
+        };
+        match &path.args {
+            Some(args) => format!("{name}{}", self.generic_args(args)),
+            None => name,
+        }
+    }
+
+    pub(crate) fn ty(&self, ty: &Type) -> String {
+        match ty {
+            Type::ResolvedPath(path) => self.path(path),
+            Type::DynTrait(dyn_trait) => {
+                let mut bounds = dyn_trait
+                    .traits
+                    .iter()
+                    .map(|t| format!("{}{}", self.hrtb(&t.generic_params), self.path(&t.trait_)))
+                    .collect::<Vec<_>>();
+                bounds.extend(dyn_trait.lifetime.clone());
+                format!("dyn {}", bounds.join(" + "))
+            }
+            Type::Generic(name) | Type::Primitive(name) => name.clone(),
+            Type::FunctionPointer(f) => format!(
+                "{}{}fn{}",
+                self.hrtb(&f.generic_params),
+                header(&f.header),
+                self.fn_decl(&f.decl),
+            ),
+            Type::Tuple(types) if types.len() == 1 => format!("({},)", self.ty(&types[0])),
+            Type::Tuple(types) => format!("({})", self.comma_sep(types, |t| self.ty(t))),
+            Type::Slice(ty) => format!("[{}]", self.ty(ty)),
+            Type::Array { type_, len } => format!("[{}; {len}]", self.ty(type_)),
+            Type::ImplTrait(bounds) => format!("impl {}", self.bounds(bounds)),
+            Type::Infer => "_".to_owned(),
+            Type::RawPointer { mutable, type_ } => {
+                format!("*{} {}", if *mutable { "mut" } else { "const" }, self.ty(type_))
+            }
+            Type::BorrowedRef { lifetime, mutable, type_ } => format!(
+                "&{}{}{}",
+                lifetime.as_ref().map(|l| format!("{l} ")).unwrap_or_default(),
+                if *mutable { "mut " } else { "" },
+                self.ty(type_),
+            ),
+            Type::QualifiedPath { name, args, self_type, trait_ } => {
+                let self_type = match trait_ {
+                    Some(trait_) => format!("<{} as {}>", self.ty(self_type), self.path(trait_)),
+                    None => self.ty(self_type),
+                };
+                format!("{self_type}::{name}{}", self.generic_args(args))
+            }
+        }
+    }
+
+    fn generic_args(&self, args: &GenericArgs) -> String {
+        match args {
+            GenericArgs::AngleBracketed { args, bindings } => {
+                let mut printed = args
+                    .iter()
+                    .map(|arg| match arg {
+                        GenericArg::Lifetime(lt) => lt.clone(),
+                        GenericArg::Type(ty) => self.ty(ty),
+                        GenericArg::Const(c) => c.expr.clone(),
+                        GenericArg::Infer => "_".to_owned(),
+                    })
+                    .collect::<Vec<_>>();
+                for binding in bindings {
+                    let name = format!("{}{}", binding.name, self.generic_args(&binding.args));
+                    printed.push(match &binding.binding {
+                        TypeBindingKind::Equality(term) => format!("{name} = {}", self.term(term)),
+                        TypeBindingKind::Constraint(bounds) => {
+                            format!("{name}: {}", self.bounds(bounds))
+                        }
+                    });
+                }
+                if printed.is_empty() {
+                    String::new()
+                } else {
+                    format!("<{}>", printed.join(", "))
+                }
+            }
+            GenericArgs::Parenthesized { inputs, output } => format!(
+                "({}){}",
+                self.comma_sep(inputs, |t| self.ty(t)),
+                output.as_ref().map(|t| format!(" -> {}", self.ty(t))).unwrap_or_default(),
+            ),
+        }
+    }
+
+    fn term(&self, term: &Term) -> String {
+        match term {
+            Term::Type(ty) => self.ty(ty),
+            Term::Constant(c) => c.expr.clone(),
+        }
+    }
+
+    pub(crate) fn bound(&self, bound: &GenericBound) -> String {
+        match bound {
+            GenericBound::TraitBound { trait_, generic_params, modifier } => {
+                let modifier = match modifier {
+                    TraitBoundModifier::None => "",
+                    TraitBoundModifier::Maybe => "?",
+                    TraitBoundModifier::MaybeConst => "~const ",
+                };
+                format!("{}{modifier}{}", self.hrtb(generic_params), self.path(trait_))
+            }
+            GenericBound::Outlives(lt) => lt.clone(),
+        }
+    }
+
+    pub(crate) fn bounds(&self, bounds: &[GenericBound]) -> String {
+        bounds.iter().map(|b| self.bound(b)).collect::<Vec<_>>().join(" + ")
+    }
+
+    fn hrtb(&self, params: &[GenericParamDef]) -> String {
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("for<{}> ", self.comma_sep(params, |p| p.name.clone()))
+        }
+    }
+
+    /// Prints a generic parameter without its bounds, which are compared separately.
+    fn generic_param(&self, param: &GenericParamDef) -> String {
+        match &param.kind {
+            GenericParamDefKind::Lifetime { .. } => param.name.clone(),
+            GenericParamDefKind::Type { default: Some(default), .. } => {
+                format!("{} = {}", param.name, self.ty(default))
+            }
+            GenericParamDefKind::Type { default: None, .. } => param.name.clone(),
+            GenericParamDefKind::Const { type_, default } => format!(
+                "const {}: {}{}",
+                param.name,
+                self.ty(type_),
+                default.as_ref().map(|d| format!(" = {d}")).unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Returns every bound of `generics`, whether it was declared inline or in a where clause.
+    pub(crate) fn predicates(&self, generics: &Generics) -> Vec<String> {
+        let mut predicates = Vec::new();
+        for param in &generics.params {
+            match &param.kind {
+                GenericParamDefKind::Lifetime { outlives } => {
+                    for lt in outlives {
+                        predicates.push(format!("{}: {lt}", param.name));
+                    }
+                }
+                // Bounds of `impl Trait` arguments are part of the argument's type.
+                GenericParamDefKind::Type { synthetic: true, .. } => {}
+                GenericParamDefKind::Type { bounds, .. } => {
+                    for bound in bounds {
+                        predicates.push(format!("{}: {}", param.name, self.bound(bound)));
+                    }
+                }
+                GenericParamDefKind::Const { .. } => {}
+            }
+        }
+        for predicate in &generics.where_predicates {
+            match predicate {
+                WherePredicate::BoundPredicate { type_, bounds, generic_params } => {
+                    for bound in bounds {
+                        predicates.push(format!(
+                            "{}{}: {}",
+                            self.hrtb(generic_params),
+                            self.ty(type_),
+                            self.bound(bound),
+                        ));
+                    }
+                }
+                WherePredicate::RegionPredicate { lifetime, bounds } => {
+                    for bound in bounds {
+                        predicates.push(format!("{lifetime}: {}", self.bound(bound)));
+                    }
+                }
+                WherePredicate::EqPredicate { lhs, rhs } => {
+                    predicates.push(format!("{} == {}", self.ty(lhs), self.term(rhs)));
+                }
+            }
+        }
+        predicates
+    }
+
+    /// Prints the generic parameters that were written in the source.
+    pub(crate) fn generic_params(&self, generics: &Generics) -> Vec<String> {
+        generics
+            .params
+            .iter()
+            .filter(|p| !matches!(p.kind, GenericParamDefKind::Type { synthetic: true, .. }))
+            .map(|p| self.generic_param(p))
+            .collect()
+    }
+
+    pub(crate) fn fn_signature(&self, name: &str, function: &Function) -> String {
+        let params = self.generic_params(&function.generics);
+        let params =
+            if params.is_empty() { String::new() } else { format!("<{}>", params.join(", ")) };
+        format!("{}fn {name}{params}{}", header(&function.header), self.fn_decl(&function.decl))
+    }
+
+    pub(crate) fn fn_decl(&self, decl: &FnDecl) -> String {
+        let mut inputs = decl.inputs.iter().map(|(_, ty)| self.ty(ty)).collect::<Vec<_>>();
+        if decl.c_variadic {
+            inputs.push("...".to_owned());
+        }
+        format!(
+            "({}){}",
+            inputs.join(", "),
+            decl.output.as_ref().map(|t| format!(" -> {}", self.ty(t))).unwrap_or_default(),
+        )
+    }
+
+    fn comma_sep<T>(&self, items: &[T], f: impl Fn(&T) -> String) -> String {
+        items.iter().map(f).collect::<Vec<_>>().join(", ")
+    }
+}
+
+fn header(header: &Header) -> String {
+    let abi = match &header.abi {
+        Abi::Rust => String::new(),
+        Abi::C { unwind } => abi_with_unwind("C", *unwind),
+        Abi::Cdecl { unwind } => abi_with_unwind("cdecl", *unwind),
+        Abi::Stdcall { unwind } => abi_with_unwind("stdcall", *unwind),
+        Abi::Fastcall { unwind } => abi_with_unwind("fastcall", *unwind),
+        Abi::Aapcs { unwind } => abi_with_unwind("aapcs", *unwind),
+        Abi::Win64 { unwind } => abi_with_unwind("win64", *unwind),
+        Abi::SysV64 { unwind } => abi_with_unwind("sysv64", *unwind),
+        Abi::System { unwind } => abi_with_unwind("system", *unwind),
+        Abi::Other(abi) => format!("extern {abi} "),
+    };
+    format!(
+        "{}{}{}{abi}",
+        if header.const_ { "const " } else { "" },
+        if header.async_ { "async " } else { "" },
+        if header.unsafe_ { "unsafe " } else { "" },
+    )
+}
+
+fn abi_with_unwind(abi: &str, unwind: bool) -> String {
+    format!("extern \"{abi}{}\" ", if unwind { "-unwind" } else { "" })
+}
@@ -15,7 +15,7 @@
 
 use std::env;
 use std::num::NonZeroU64;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::debug;
@@ -405,6 +405,33 @@ fn main() {
                         "-Zmiri-isolation-error must be `abort`, `hide`, `warn`, or `warn-nobacktrace`"
                     ),
             };
+        } else if arg == "-Zmiri-virtual-fs" {
+            miri_config.virtual_fs = true;
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-virtual-fs-snapshot=") {
+            if !Path::new(param).is_dir() {
+                show_error!("-Zmiri-virtual-fs-snapshot `{}` is not a directory", param);
+            }
+            let snapshot = miri::VfsSnapshot::read(Path::new(param)).unwrap_or_else(|err| {
+                show_error!("-Zmiri-virtual-fs-snapshot could not read `{}`: {}", param, err)
+            });
+            miri_config.virtual_fs = true;
+            miri_config.virtual_fs_snapshot = Some(snapshot);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-record=") {
+            miri_config.record = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-replay=") {
+            let recording = miri::Recording::read(Path::new(param)).unwrap_or_else(|err| {
+                show_error!("-Zmiri-replay could not read recording `{}`: {}", param, err)
+            });
+            miri_config.replay = Some(recording);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-debugger=") {
+            let endpoint = if param == "stdio" {
+                miri::DebuggerEndpoint::Stdio
+            } else if let Ok(port) = param.parse::<u16>() {
+                miri::DebuggerEndpoint::Tcp(format!("127.0.0.1:{port}"))
+            } else {
+                miri::DebuggerEndpoint::Tcp(param.to_owned())
+            };
+            miri_config.debugger = Some(endpoint);
         } else if arg == "-Zmiri-ignore-leaks" {
             miri_config.ignore_leaks = true;
             miri_config.collect_leak_backtraces = false;
@@ -512,6 +539,14 @@ fn main() {
                     ),
             };
             miri_config.preemption_rate = rate;
+        } else if arg == "-Zmiri-explore-schedules" {
+            miri_config.explore_schedules = Some(2);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-explore-schedules=") {
+            let bound = match param.parse::<u32>() {
+                Ok(bound) => bound,
+                Err(err) => show_error!("-Zmiri-explore-schedules requires a `u32`: {}", err),
+            };
+            miri_config.explore_schedules = Some(bound);
         } else if arg == "-Zmiri-report-progress" {
             // This makes it take a few seconds between progress reports on my laptop.
             miri_config.report_progress = Some(1_000_000);
@@ -529,6 +564,8 @@ fn main() {
             miri_config.gc_interval = interval;
         } else if let Some(param) = arg.strip_prefix("-Zmiri-measureme=") {
             miri_config.measureme_out = Some(param.to_string());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-coverage=") {
+            miri_config.coverage_out = Some(param.into());
         } else if let Some(param) = arg.strip_prefix("-Zmiri-backtrace=") {
             miri_config.backtrace_style = match param {
                 "0" => BacktraceStyle::Off,
@@ -581,6 +618,23 @@ fn main() {
             "-Zmiri-unique-is-unique only has an effect when -Zmiri-tree-borrows is also used"
         );
     }
+    if miri_config.virtual_fs && miri_config.isolated_op == miri::IsolatedOp::Allow {
+        show_error!("-Zmiri-virtual-fs cannot be used along with -Zmiri-disable-isolation");
+    }
+    if miri_config.explore_schedules.is_some()
+        && (miri_config.record.is_some() || miri_config.replay.is_some())
+    {
+        show_error!(
+            "-Zmiri-explore-schedules cannot be used along with -Zmiri-record or -Zmiri-replay"
+        );
+    }
+    if miri_config.debugger.is_some() && miri_config.explore_schedules.is_some() {
+        show_error!("-Zmiri-debugger cannot be used along with -Zmiri-explore-schedules");
+    }
+    // Unless a seed is given explicitly, a replay uses the seed of the recording.
+    if let Some(recording) = &miri_config.replay {
+        miri_config.seed = miri_config.seed.or(Some(recording.seed));
+    }
 
     debug!("rustc arguments: {:?}", rustc_args);
     debug!("crate arguments: {:?}", miri_config.args);
@@ -355,6 +355,37 @@ fn print_borrow_state(&mut self, alloc_id: AllocId, show_unnamed: bool) -> Inter
             BorrowTrackerMethod::TreeBorrows => this.print_tree(alloc_id, show_unnamed),
         }
     }
+
+    /// Describes what the borrow tracker knows about the location `ptr` points to, from the
+    /// point of view of the tag of `ptr`. Used by the debugger.
+    fn describe_borrow_state(
+        &self,
+        ptr: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx, String> {
+        let this = self.eval_context_ref();
+        let Some(borrow_tracker) = &this.machine.borrow_tracker else {
+            return Ok("borrow tracking is disabled".to_owned());
+        };
+        let method = borrow_tracker.borrow().borrow_tracker_method;
+        let Ok((alloc_id, offset, extra)) = this.ptr_try_get_alloc_id(ptr) else {
+            return Ok("the pointer does not point to an allocation".to_owned());
+        };
+        let ProvenanceExtra::Concrete(tag) = extra else {
+            return Ok("the pointer has a wildcard tag".to_owned());
+        };
+        let (size, _align, kind) = this.get_alloc_info(alloc_id);
+        if !matches!(kind, AllocKind::LiveData) {
+            return Ok(format!("{alloc_id:?} is not live memory"));
+        }
+        if offset >= size {
+            return Ok(format!("the pointer is out of bounds of {alloc_id:?}"));
+        }
+        match method {
+            BorrowTrackerMethod::StackedBorrows =>
+                this.sb_describe_location(alloc_id, tag, offset),
+            BorrowTrackerMethod::TreeBorrows => this.tb_describe_tag(alloc_id, tag, offset),
+        }
+    }
 }
 
 /// Extra per-allocation data for borrow tracking
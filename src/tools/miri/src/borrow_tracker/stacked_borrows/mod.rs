@@ -1045,4 +1045,35 @@ fn print_stacks(&mut self, alloc_id: AllocId) -> InterpResult<'tcx> {
         }
         Ok(())
     }
+
+    /// Describe the stack at `offset`, marking the items with the given tag (for the debugger).
+    fn sb_describe_location(
+        &self,
+        alloc_id: AllocId,
+        tag: BorTag,
+        offset: Size,
+    ) -> InterpResult<'tcx, String> {
+        let this = self.eval_context_ref();
+        let alloc_extra = this.get_alloc_extra(alloc_id)?;
+        let stacks = alloc_extra.borrow_tracker_sb().borrow();
+        let (_range, stack) = stacks.stacks.iter(offset, Size::from_bytes(1)).next().unwrap();
+        let mut description = String::from("[");
+        if let Some(bottom) = stack.unknown_bottom() {
+            write!(description, " unknown-bottom(..{bottom:?})").unwrap();
+        }
+        let mut found = false;
+        for i in 0..stack.len() {
+            let item = stack.get(i).unwrap();
+            write!(description, " {:?}{:?}", item.perm(), item.tag()).unwrap();
+            if item.tag() == tag {
+                description.push('*');
+                found = true;
+            }
+        }
+        description.push_str(" ]");
+        if !found {
+            write!(description, " ({tag:?} is not in the stack)").unwrap();
+        }
+        Ok(description)
+    }
 }
@@ -563,6 +563,22 @@ fn print_tree(&mut self, alloc_id: AllocId, show_unnamed: bool) -> InterpResult<
         tree_borrows.print_tree(&borrow_tracker.protected_tags, show_unnamed)
     }
 
+    /// Describe the permissions of `tag` and its ancestors at `offset` (for the debugger).
+    fn tb_describe_tag(
+        &self,
+        alloc_id: AllocId,
+        tag: BorTag,
+        offset: Size,
+    ) -> InterpResult<'tcx, String> {
+        let this = self.eval_context_ref();
+        let alloc_extra = this.get_alloc_extra(alloc_id)?;
+        let tree_borrows = alloc_extra.borrow_tracker_tb().borrow();
+        let borrow_tracker = &this.machine.borrow_tracker.as_ref().unwrap().borrow();
+        Ok(tree_borrows
+            .describe_tag(tag, offset, &borrow_tracker.protected_tags)
+            .unwrap_or_else(|| format!("{tag:?} is not in the tree of {alloc_id:?}")))
+    }
+
     /// Give a name to the pointer, usually the name it has in the source code (for debugging).
     /// The name given is `name` and the pointer that receives it is the `nth_parent`
     /// of `ptr` (with 0 representing `ptr` itself)
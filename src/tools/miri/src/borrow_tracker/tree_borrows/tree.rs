@@ -15,7 +15,7 @@
 use smallvec::SmallVec;
 
 use rustc_const_eval::interpret::InterpResult;
-use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_span::Span;
 use rustc_target::abi::Size;
 
@@ -677,6 +677,42 @@ pub fn perform_access(
     }
 }
 
+/// Inspection of the tree by the debugger
+impl Tree {
+    /// Describes the permissions of `tag` and of all its ancestors at `offset`, from `tag` itself
+    /// up to the root of the allocation. Returns `None` if `tag` is not part of this tree.
+    pub fn describe_tag(
+        &self,
+        tag: BorTag,
+        offset: Size,
+        protected_tags: &FxHashMap<BorTag, ProtectorKind>,
+    ) -> Option<String> {
+        let (_perms_range, perms) = self.rperms.iter(offset, Size::from_bytes(1)).next()?;
+        let mut idx = self.tag_mapping.get(&tag)?;
+        let mut ancestors = Vec::new();
+        loop {
+            let node = self.nodes.get(idx).unwrap();
+            let state = perms
+                .get(idx)
+                .copied()
+                .unwrap_or_else(|| LocationState::new(node.default_initial_perm));
+            let mut description = format!("{}: {}", node.debug_info, state.permission());
+            if !state.is_initialized() {
+                description.push_str(" (lazy)");
+            }
+            if protected_tags.contains_key(&node.tag) {
+                description.push_str(" (protected)");
+            }
+            ancestors.push(description);
+            match node.parent {
+                Some(parent) => idx = parent,
+                None => break,
+            }
+        }
+        Some(ancestors.join(" -> "))
+    }
+}
+
 /// Integration with the BorTag garbage collector
 impl Tree {
     pub fn remove_unreachable_tags(&mut self, live_tags: &FxHashSet<BorTag>) {
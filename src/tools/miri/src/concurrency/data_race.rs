@@ -42,6 +42,7 @@
 
 use std::{
     cell::{Cell, Ref, RefCell, RefMut},
+    collections::VecDeque,
     fmt::Debug,
     mem,
 };
@@ -57,6 +58,7 @@
 use crate::*;
 
 use super::{
+    exploration::SyncLocation,
     vector_clock::{VClock, VTimestamp, VectorIdx},
     weak_memory::EvalContextExt as _,
 };
@@ -115,19 +117,34 @@ pub(super) struct ThreadClockSet {
     /// have been released by this thread by a fence.
     fence_release: VClock,
 
-    /// Timestamps of the last SC fence performed by each
-    /// thread, updated when this thread performs an SC fence
+    /// The timestamps of everything that happens-before the latest SC fence
+    /// that happens-before this thread, or an SC fence preceding it in the
+    /// SC order, updated before this thread performs an atomic load
     pub(super) fence_seqcst: VClock,
 
-    /// Timestamps of the last SC write performed by each
-    /// thread, updated when this thread performs an SC fence
+    /// Timestamps of the last SC write performed by each thread before
+    /// the latest SC fence that happens-before this thread, updated
+    /// before this thread performs an atomic load
     pub(super) write_seqcst: VClock,
 
-    /// Timestamps of the last SC fence performed by each
-    /// thread, updated when this thread performs an SC read
+    /// The timestamps of everything that happens-before any SC fence,
+    /// updated when this thread performs an SC read
     pub(super) read_seqcst: VClock,
 }
 
+/// An SC fence, as seen by the loads that happen after it.
+#[derive(Clone, Debug)]
+struct ScFence {
+    /// The timestamp of the fencing thread when it performed the fence.
+    timestamp: VTimestamp,
+    /// The timestamps of everything that happens-before this fence or an
+    /// earlier SC fence.
+    view: VClock,
+    /// The timestamp of the last SC write performed by each thread before
+    /// this fence.
+    sc_writes: VClock,
+}
+
 impl ThreadClockSet {
     /// Apply the effects of a release fence to this
     /// set of thread vector clocks.
@@ -708,7 +725,8 @@ fn atomic_compare_exchange_scalar(
         let success_rate = 1.0 - this.machine.cmpxchg_weak_failure_rate;
         let cmpxchg_success = eq.to_scalar().to_bool()?
             && if can_fail_spuriously {
-                this.machine.rng.get_mut().gen_bool(success_rate)
+                let failure = !this.machine.rng.get_mut().gen_bool(success_rate);
+                !this.machine.choice_log.get_mut().compare_exchange_weak_failure(failure)
             } else {
                 true
             };
@@ -737,6 +755,7 @@ fn atomic_compare_exchange_scalar(
     /// Update the data-race detector for an atomic fence on the current thread.
     fn atomic_fence(&mut self, atomic: AtomicFenceOrd) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
+        this.machine.threads.record_atomic_access(SyncLocation::Fence, /* write */ true);
         let current_span = this.machine.current_span();
         if let Some(data_race) = &mut this.machine.data_race {
             data_race.maybe_perform_sync_operation(
@@ -757,18 +776,18 @@ fn atomic_fence(&mut self, atomic: AtomicFenceOrd) -> InterpResult<'tcx> {
                         clocks.apply_release_fence();
                     }
                     if atomic == AtomicFenceOrd::SeqCst {
-                        data_race.last_sc_fence.borrow_mut().set_at_index(&clocks.clock, index);
-                        clocks.fence_seqcst.join(&data_race.last_sc_fence.borrow());
-                        clocks.write_seqcst.join(&data_race.last_sc_write.borrow());
+                        data_race.sc_fence(index, &clocks);
                     }
 
                     // Increment timestamp in case of release semantics.
                     Ok(atomic != AtomicFenceOrd::Acquire)
                 },
-            )
-        } else {
-            Ok(())
+            )?;
+            if atomic == AtomicFenceOrd::SeqCst {
+                data_race.prune_sc_fences();
+            }
         }
+        Ok(())
     }
 
     /// After all threads are done running, this allows data races to occur for subsequent
@@ -1119,7 +1138,8 @@ fn allow_data_races_mut<R>(
         result
     }
 
-    /// Checks that an atomic access is legal at the given place.
+    /// Checks that an atomic access is legal at the given place, and records it for the schedule
+    /// exploration.
     fn atomic_access_check(
         &self,
         place: &MPlaceTy<'tcx, Provenance>,
@@ -1138,7 +1158,7 @@ fn atomic_access_check(
         // <https://github.com/rust-lang/miri/pull/2464#discussion_r939636130> for details.
         // We avoid `get_ptr_alloc` since we do *not* want to run the access hooks -- the actual
         // access will happen later.
-        let (alloc_id, _offset, _prov) = this
+        let (alloc_id, offset, _prov) = this
             .ptr_try_get_alloc_id(place.ptr())
             .expect("there are no zero-sized atomic accesses");
         if this.get_alloc_mutability(alloc_id)? == Mutability::Not {
@@ -1171,6 +1191,9 @@ fn atomic_access_check(
                 }
             }
         }
+        // When exploring schedules, other threads may run after this access.
+        let write = !matches!(access_type, AtomicAccessType::Load(_));
+        this.machine.threads.record_atomic_access(SyncLocation::Memory(alloc_id, offset), write);
         Ok(())
     }
 
@@ -1394,8 +1417,14 @@ pub struct GlobalState {
     /// after the join operation occurs.
     terminated_threads: RefCell<FxHashMap<ThreadId, VectorIdx>>,
 
-    /// The timestamp of last SC fence performed by each thread
-    last_sc_fence: RefCell<VClock>,
+    /// The timestamps of everything that happens-before any SC fence so far.
+    sc_fence_view: RefCell<VClock>,
+
+    /// The SC fences performed by each thread, in order. Since the SC fences
+    /// are totally ordered and each fence's view includes the views of all
+    /// earlier ones, fences that every thread has already seen a later fence
+    /// of the same thread for are removed.
+    sc_fences: RefCell<FxHashMap<VectorIdx, VecDeque<ScFence>>>,
 
     /// The timestamp of last SC write performed by each thread
     last_sc_write: RefCell<VClock>,
@@ -1422,7 +1451,8 @@ pub fn new(config: &MiriConfig) -> Self {
             thread_info: RefCell::new(IndexVec::new()),
             reuse_candidates: RefCell::new(FxHashSet::default()),
             terminated_threads: RefCell::new(FxHashMap::default()),
-            last_sc_fence: RefCell::new(VClock::default()),
+            sc_fence_view: RefCell::new(VClock::default()),
+            sc_fences: RefCell::new(FxHashMap::default()),
             last_sc_write: RefCell::new(VClock::default()),
             track_outdated_loads: config.track_outdated_loads,
         };
@@ -1773,6 +1803,57 @@ pub(super) fn sc_write(&self, thread_mgr: &ThreadManager<'_, '_>) {
     // SC ATOMIC READ rule in the paper.
     pub(super) fn sc_read(&self, thread_mgr: &ThreadManager<'_, '_>) {
         let (.., mut clocks) = self.current_thread_state_mut(thread_mgr);
-        clocks.read_seqcst.join(&self.last_sc_fence.borrow());
+        clocks.read_seqcst.join(&self.sc_fence_view.borrow());
+    }
+
+    // SC FENCE rule in the paper, strengthened to C++20: a fence also orders everything that
+    // happens-before it, not just what is sequenced-before it.
+    fn sc_fence(&self, index: VectorIdx, clocks: &ThreadClockSet) {
+        let mut view = self.sc_fence_view.borrow_mut();
+        view.join(&clocks.clock);
+        let fence = ScFence {
+            timestamp: clocks.clock[index],
+            view: view.clone(),
+            sc_writes: self.last_sc_write.borrow().clone(),
+        };
+        self.sc_fences.borrow_mut().entry(index).or_default().push_back(fence);
+    }
+
+    /// Updates the SC fence clocks of the active thread with the latest SC fences that
+    /// happen-before it. In C++20, the effects of an SC fence on later loads are not limited
+    /// to the fencing thread (C++20 §31.4 [atomics.order] paragraph 4).
+    ///
+    /// This runs before every atomic load from a location with a store buffer, so it needs to be
+    /// cheap. Most programs never perform an SC fence, and then there is nothing to do. Otherwise,
+    /// the cost is a binary search in the fences of each thread that performed SC fences, and two
+    /// vector clock joins per fence found. `prune_sc_fences` drops the fences that can no longer be
+    /// found, so the search only covers fences that some thread has not caught up with yet.
+    pub(super) fn observe_sc_fences(&self, thread_mgr: &ThreadManager<'_, '_>) {
+        let sc_fences = self.sc_fences.borrow();
+        if sc_fences.is_empty() {
+            return;
+        }
+        let (.., mut clocks) = self.current_thread_state_mut(thread_mgr);
+        for (&fence_index, fences) in sc_fences.iter() {
+            // The fences of each thread are ordered by timestamp.
+            let seen = fences.partition_point(|fence| fence.timestamp <= clocks.clock[fence_index]);
+            if let Some(fence) = seen.checked_sub(1).map(|latest| &fences[latest]) {
+                clocks.fence_seqcst.join(&fence.view);
+                clocks.write_seqcst.join(&fence.sc_writes);
+            }
+        }
+    }
+
+    /// Removes the SC fences that are no longer needed by `observe_sc_fences` because every
+    /// thread has already seen a later fence of the same thread.
+    fn prune_sc_fences(&self) {
+        let vector_clocks = self.vector_clocks.borrow();
+        for (&fence_index, fences) in self.sc_fences.borrow_mut().iter_mut() {
+            while fences.get(1).is_some_and(|next| {
+                vector_clocks.iter().all(|clocks| next.timestamp <= clocks.clock[fence_index])
+            }) {
+                fences.pop_front();
+            }
+        }
     }
 }
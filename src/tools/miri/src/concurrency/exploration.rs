@@ -0,0 +1,608 @@
+//! Systematic exploration of thread interleavings.
+//!
+//! Instead of picking a single schedule based on the random seed, Miri can run the program over
+//! and over again from the start, following a different schedule each time, until all schedules
+//! that can lead to a different outcome have been explored (this is known as "stateless model
+//! checking"). A schedule is the sequence of threads picked at each *scheduling point*, which is
+//! reached whenever the active thread has performed an atomic operation or fence, or can no longer
+//! continue (because it blocked, yielded, or terminated). The code a thread executes between two
+//! scheduling points is called a *transition*. Non-atomic accesses do not need scheduling points:
+//! the data race detector reports racing non-atomic accesses no matter in which order they happen
+//! to be executed.
+//!
+//! Exploring every schedule is infeasible even for tiny programs, so we use two standard techniques
+//! to keep the search manageable:
+//! - *Dynamic partial-order reduction* (Flanagan and Godefroid, POPL 2005): two transitions of
+//!   different threads only need to be explored in both orders if they are dependent, i.e., if
+//!   they access the same location and at least one of them writes to it. We track which
+//!   transitions are dependent while executing a schedule, and whenever we see two dependent
+//!   transitions that are not ordered by the transitions between them, we remember to try running
+//!   the second thread before the first one in a later execution.
+//! - *Preemption bounding* (Musuvathi and Qadeer, PLDI 2007): we only explore schedules that switch
+//!   away from a thread that could have continued at most a given number of times. Most
+//!   concurrency bugs need very few preemptions to show up. Since reversing a race can need more
+//!   preemptions than the bound allows, we then also try to switch to the thread at the latest
+//!   earlier point where doing so is not a preemption (Coons, Musuvathi and McKinley, OOPSLA 2013).
+//!
+//! All other non-determinism (such as allocation addresses and weak memory effects) is still
+//! resolved using the random seed. Since each execution starts from a fresh interpreter with the
+//! same seed, replaying the start of a schedule reproduces the exact same execution.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::mem;
+use std::rc::Rc;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_index::Idx;
+use rustc_middle::ty::TyCtxt;
+use rustc_target::abi::Size;
+
+use crate::shims::unix::FileDescriptor;
+use crate::*;
+
+/// A location on which atomic operations can synchronize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyncLocation {
+    /// Atomic memory accesses at the given offset of an allocation.
+    Memory(AllocId, Size),
+    /// Fences. They all share one location, so that we explore all the orders in which fences
+    /// of different threads can happen.
+    Fence,
+}
+
+/// A standard output stream of the program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// What the program wrote to its standard output streams during the current execution, in order.
+/// Only the output of the failing or the last execution is shown, so that the output of the
+/// program does not get repeated for every schedule.
+#[derive(Debug, Default)]
+struct CapturedOutput(Vec<(OutputStream, Vec<u8>)>);
+
+impl CapturedOutput {
+    fn push(&mut self, stream: OutputStream, bytes: &[u8]) {
+        match self.0.last_mut() {
+            Some((last, buffer)) if *last == stream => buffer.extend_from_slice(bytes),
+            _ => self.0.push((stream, bytes.to_vec())),
+        }
+    }
+
+    /// Writes the captured output to our own standard output streams.
+    fn emit(&mut self) {
+        for (stream, bytes) in self.0.drain(..) {
+            // Nothing sensible can be done if writing fails, the program itself would not have
+            // noticed either.
+            let _ = match stream {
+                OutputStream::Stdout => io::stdout().write_all(&bytes),
+                OutputStream::Stderr => io::stderr().write_all(&bytes),
+            };
+        }
+        let _ = io::stdout().flush();
+    }
+}
+
+/// A standard output stream of the program whose output is captured, see `CapturedOutput`.
+#[derive(Debug)]
+pub struct CapturingOutput {
+    output: Rc<RefCell<CapturedOutput>>,
+    stream: OutputStream,
+}
+
+impl FileDescriptor for CapturingOutput {
+    fn name(&self) -> &'static str {
+        match self.stream {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+
+    fn write<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        _tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        self.output.borrow_mut().push(self.stream, bytes);
+        Ok(Ok(bytes.len()))
+    }
+
+    fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
+        Ok(Box::new(CapturingOutput { output: Rc::clone(&self.output), stream: self.stream }))
+    }
+}
+
+/// A decision the scheduler made during the current execution.
+#[derive(Debug)]
+struct SchedulingPoint {
+    /// The threads that could be scheduled at this point, ordered by their id.
+    enabled: Vec<ThreadId>,
+    /// The thread that was active before this point, if it could have kept running. Scheduling
+    /// any other thread is a preemption.
+    preemptible: Option<ThreadId>,
+    /// The thread that was scheduled at this point.
+    chosen: ThreadId,
+    /// The number of preemptions in the schedule before this point.
+    preemptions: u32,
+    /// Threads that still need to be tried at this point in a later execution.
+    backtrack: BTreeSet<ThreadId>,
+    /// Threads that have already been tried at this point.
+    done: BTreeSet<ThreadId>,
+}
+
+impl SchedulingPoint {
+    /// The number of preemptions in the schedule if `thread` is scheduled at this point.
+    fn preemptions_with(&self, thread: ThreadId) -> u32 {
+        let preempted = self.preemptible.is_some_and(|active| active != thread);
+        self.preemptions.checked_add(u32::from(preempted)).unwrap()
+    }
+}
+
+/// Tracks, for each thread, how many of its transitions happen before some transition: the value
+/// for a thread is one more than the index of the latest of its transitions that does.
+#[derive(Clone, Debug, Default)]
+struct TransitionClock(Vec<usize>);
+
+impl TransitionClock {
+    fn get(&self, thread: ThreadId) -> usize {
+        self.0.get(thread.index()).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, thread: ThreadId, value: usize) {
+        if self.0.len() <= thread.index() {
+            self.0.resize(thread.index().checked_add(1).unwrap(), 0);
+        }
+        self.0[thread.index()] = value;
+    }
+
+    fn join(&mut self, other: &Self) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (l, &r) in self.0.iter_mut().zip(other.0.iter()) {
+            *l = (*l).max(r);
+        }
+    }
+}
+
+/// A transition that has finished executing.
+#[derive(Debug)]
+struct Transition {
+    /// The thread that executed this transition.
+    thread: ThreadId,
+    /// The transitions that happen before this one, including itself.
+    clock: TransitionClock,
+}
+
+/// The transitions that accessed a location.
+#[derive(Debug, Default)]
+struct LocationAccesses {
+    /// The last transition that wrote to this location.
+    last_write: Option<usize>,
+    /// The transitions that read from this location since the last write.
+    reads: Vec<usize>,
+}
+
+/// The state of the exploration, which is kept across executions.
+#[derive(Debug)]
+pub struct Exploration {
+    /// The maximal number of preemptions in an explored schedule.
+    preemption_bound: u32,
+    /// The scheduling points of the current execution, and those of the previous execution that
+    /// will be replayed.
+    points: Vec<SchedulingPoint>,
+    /// The number of scheduling points reached so far in the current execution.
+    reached: usize,
+    /// The number of executions so far, including the current one.
+    executions: u64,
+    /// The transitions of the current execution that have finished.
+    transitions: Vec<Transition>,
+    /// The accesses of the transition that is currently being executed.
+    accesses: Vec<(SyncLocation, bool)>,
+    /// For each thread, the transitions that happen before its next transition.
+    thread_clocks: FxHashMap<ThreadId, TransitionClock>,
+    /// The transitions of the current execution that accessed each location.
+    locations: FxHashMap<SyncLocation, LocationAccesses>,
+    /// Whether the current transition has ended, and the scheduler needs to pick the thread that
+    /// executes the next one.
+    at_scheduling_point: bool,
+    /// Whether a thread started to panic in the current execution.
+    panicked: bool,
+    /// The output of the current execution.
+    output: Rc<RefCell<CapturedOutput>>,
+}
+
+impl Exploration {
+    pub fn new(preemption_bound: u32) -> Self {
+        Exploration {
+            preemption_bound,
+            points: Vec::new(),
+            reached: 0,
+            executions: 1,
+            transitions: Vec::new(),
+            accesses: Vec::new(),
+            thread_clocks: FxHashMap::default(),
+            locations: FxHashMap::default(),
+            // The program starts with a scheduling point.
+            at_scheduling_point: true,
+            panicked: false,
+            output: Rc::default(),
+        }
+    }
+
+    /// The number of executions so far, including the current one.
+    pub fn executions(&self) -> u64 {
+        self.executions
+    }
+
+    /// The maximal number of preemptions in an explored schedule.
+    pub fn preemption_bound(&self) -> u32 {
+        self.preemption_bound
+    }
+
+    /// Whether a thread started to panic in the current execution. Together with a non-zero exit
+    /// code, this means that the execution failed.
+    pub fn panicked(&self) -> bool {
+        self.panicked
+    }
+
+    /// Records that the active thread started to panic.
+    pub(crate) fn record_panic(&mut self) {
+        self.panicked = true;
+    }
+
+    /// Returns a file descriptor that captures what the program writes to `stream` in the current
+    /// execution.
+    pub(crate) fn capture(&self, stream: OutputStream) -> CapturingOutput {
+        CapturingOutput { output: Rc::clone(&self.output), stream }
+    }
+
+    /// Captures what the program writes to `stream` without going through a file descriptor.
+    pub(crate) fn capture_bytes(&self, stream: OutputStream, bytes: &[u8]) {
+        self.output.borrow_mut().push(stream, bytes);
+    }
+
+    /// Shows what the program wrote in the current execution.
+    pub fn emit_output(&self) {
+        self.output.borrow_mut().emit();
+    }
+
+    /// Whether the current transition has ended and the scheduler needs to make a decision.
+    pub(crate) fn at_scheduling_point(&self) -> bool {
+        self.at_scheduling_point
+    }
+
+    /// Records an atomic access of the active thread. This ends the current transition.
+    pub(crate) fn record_access(&mut self, location: SyncLocation, write: bool) {
+        self.accesses.push((location, write));
+        self.at_scheduling_point = true;
+    }
+
+    /// The transitions that happen before the next transition of `thread`, taking into account
+    /// that the current transition might be executed by `thread`.
+    fn current_clock(&self, thread: ThreadId) -> TransitionClock {
+        let mut clock = self.thread_clocks.get(&thread).cloned().unwrap_or_default();
+        if self.current_thread() == Some(thread) {
+            clock.set(thread, self.transitions.len().checked_add(1).unwrap());
+        }
+        clock
+    }
+
+    /// The thread executing the current transition.
+    fn current_thread(&self) -> Option<ThreadId> {
+        self.reached.checked_sub(1).map(|index| self.points[index].chosen)
+    }
+
+    /// The current transition of `parent` created the thread `child`, so everything the child
+    /// does happens after it.
+    pub(crate) fn thread_created(&mut self, parent: ThreadId, child: ThreadId) {
+        let clock = self.current_clock(parent);
+        self.thread_clocks.insert(child, clock);
+    }
+
+    /// `joiner` finished joining `joined`, so everything the joiner does from now on happens after
+    /// everything `joined` did.
+    pub(crate) fn thread_joined(&mut self, joiner: ThreadId, joined: ThreadId) {
+        let clock = self.current_clock(joined);
+        self.thread_clocks.entry(joiner).or_default().join(&clock);
+    }
+
+    /// Decides which of the `enabled` threads executes the next transition, ending the current
+    /// one. `preemptible` is the active thread, if it could continue executing.
+    pub(crate) fn schedule<'tcx>(
+        &mut self,
+        active: ThreadId,
+        preemptible: Option<ThreadId>,
+        enabled: Vec<ThreadId>,
+    ) -> InterpResult<'tcx, ThreadId> {
+        assert!(!enabled.is_empty());
+        if self.reached > 0 {
+            self.finish_transition();
+        }
+        self.at_scheduling_point = false;
+
+        let index = self.reached;
+        self.reached = index.checked_add(1).unwrap();
+        if let Some(point) = self.points.get(index) {
+            // We are replaying the schedule of an earlier execution.
+            if point.enabled != enabled || point.preemptible != preemptible {
+                throw_unsup_format!(
+                    "the program behaved differently when executed again with the same schedule, \
+                    but exploring schedules requires the program to be deterministic"
+                );
+            }
+            return Ok(point.chosen);
+        }
+
+        // We reached a new scheduling point. By default, we keep running the active thread if it
+        // can continue, and otherwise pick the next thread in a round-robin fashion.
+        let chosen = preemptible.unwrap_or_else(|| {
+            enabled
+                .iter()
+                .copied()
+                .find(|thread| thread.index() > active.index())
+                .unwrap_or(enabled[0])
+        });
+        let preemptions =
+            self.points.last().map_or(0, |point| point.preemptions_with(point.chosen));
+        self.points.push(SchedulingPoint {
+            enabled,
+            preemptible,
+            chosen,
+            preemptions,
+            backtrack: BTreeSet::new(),
+            done: BTreeSet::from([chosen]),
+        });
+        Ok(chosen)
+    }
+
+    /// Ends the current transition, and looks for races with earlier transitions.
+    fn finish_transition(&mut self) {
+        let index = self.transitions.len();
+        let thread = self.points[index].chosen;
+        let mut clock = self.thread_clocks.get(&thread).cloned().unwrap_or_default();
+
+        let mut races = Vec::new();
+        for (location, write) in mem::take(&mut self.accesses) {
+            let accesses = self.locations.entry(location).or_default();
+            // Reads only depend on writes, but writes depend on everything.
+            let mut dependent: Vec<usize> = accesses.last_write.into_iter().collect();
+            if write {
+                dependent.extend(&accesses.reads);
+            }
+            // Handle the latest transitions first: once we know that this transition happens
+            // after them, we also know that it happens after everything that they happen after.
+            dependent.sort_unstable_by_key(|&index| Reverse(index));
+            for other_index in dependent {
+                let other = &self.transitions[other_index];
+                if other.thread == thread || clock.get(other.thread) > other_index {
+                    // Same thread, or we already know that it happens before this transition.
+                    continue;
+                }
+                races.push(other_index);
+                clock.join(&other.clock);
+            }
+            if write {
+                accesses.last_write = Some(index);
+                accesses.reads.clear();
+            } else {
+                accesses.reads.push(index);
+            }
+        }
+
+        clock.set(thread, index.checked_add(1).unwrap());
+        self.thread_clocks.insert(thread, clock.clone());
+        self.transitions.push(Transition { thread, clock });
+        for race in races {
+            self.add_backtrack(race, thread);
+        }
+    }
+
+    /// The transition at `index` races with a later transition of `thread`, so we need to try
+    /// running `thread` at that point.
+    fn add_backtrack(&mut self, index: usize, thread: ThreadId) {
+        let point = &self.points[index];
+        // If `thread` cannot run at that point, we do not know which thread would lead to it
+        // being able to run earlier, so we try all of them.
+        let candidates =
+            if point.enabled.contains(&thread) { vec![thread] } else { point.enabled.clone() };
+        for candidate in candidates {
+            if self.try_backtrack(index, candidate) {
+                continue;
+            }
+            // Switching to `candidate` here exceeds the preemption bound. Switch at the latest
+            // earlier point where this is not a preemption instead.
+            let earlier = self.points[..index].iter().rposition(|point| {
+                point.preemptible.is_none() && point.enabled.contains(&candidate)
+            });
+            if let Some(earlier) = earlier {
+                self.try_backtrack(earlier, candidate);
+            }
+        }
+    }
+
+    /// Remembers to try scheduling `thread` at the point with the given index, unless that exceeds
+    /// the preemption bound. Returns whether the bound was respected.
+    fn try_backtrack(&mut self, index: usize, thread: ThreadId) -> bool {
+        let point = &mut self.points[index];
+        if point.preemptions_with(thread) > self.preemption_bound {
+            return false;
+        }
+        if !point.done.contains(&thread) {
+            point.backtrack.insert(thread);
+        }
+        true
+    }
+
+    /// Ends the current execution. Returns `false` if all schedules have been explored, otherwise
+    /// prepares the next execution and returns `true`.
+    pub fn next_execution(&mut self) -> bool {
+        if self.reached > 0 {
+            self.finish_transition();
+        }
+        // Backtrack to the latest point where there is still another thread to try.
+        self.points.truncate(self.reached);
+        while let Some(point) = self.points.last_mut() {
+            if let Some(thread) = point.backtrack.pop_first() {
+                point.done.insert(thread);
+                point.chosen = thread;
+                break;
+            }
+            self.points.pop();
+        }
+        if self.points.is_empty() {
+            return false;
+        }
+
+        self.executions = self.executions.checked_add(1).unwrap();
+        self.reached = 0;
+        self.transitions.clear();
+        self.accesses.clear();
+        self.thread_clocks.clear();
+        self.locations.clear();
+        self.at_scheduling_point = true;
+        self.panicked = false;
+        self.output.borrow_mut().0.clear();
+        true
+    }
+
+    /// Describes the schedule of the current execution, i.e., which threads ran in which order.
+    pub fn describe_schedule(&self) -> String {
+        let mut description = String::new();
+        let mut points = self.points[..self.reached].iter().peekable();
+        while let Some(first) = points.next() {
+            let mut steps = 1u64;
+            while points.next_if(|point| point.chosen == first.chosen).is_some() {
+                steps = steps.checked_add(1).unwrap();
+            }
+            if !description.is_empty() {
+                description.push_str(", ");
+            }
+            write!(
+                description,
+                "thread {} ({steps} {}",
+                first.chosen.to_u32(),
+                if steps == 1 { "step" } else { "steps" }
+            )
+            .unwrap();
+            if let Some(preempted) = first.preemptible.filter(|&active| active != first.chosen) {
+                write!(description, ", preempting thread {}", preempted.to_u32()).unwrap();
+            }
+            description.push(')');
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use super::*;
+
+    /// A location at the given offset of some allocation.
+    fn location(offset: u64) -> SyncLocation {
+        SyncLocation::Memory(AllocId(NonZeroU64::new(1).unwrap()), Size::from_bytes(offset))
+    }
+
+    /// Runs a program in which each thread performs the given atomic accesses, following the
+    /// schedule chosen by the exploration. Returns the threads in the order they executed their
+    /// accesses, and the number of preemptions.
+    fn execute(exploration: &mut Exploration, program: &[&[(u64, bool)]]) -> (Vec<usize>, u32) {
+        let mut next = vec![0; program.len()];
+        let mut active = ThreadId::new(0);
+        let mut order = Vec::new();
+        let mut preemptions = 0;
+        loop {
+            let enabled: Vec<ThreadId> = (0..program.len())
+                .filter(|&thread| next[thread] < program[thread].len())
+                .map(ThreadId::new)
+                .collect();
+            if enabled.is_empty() {
+                return (order, preemptions);
+            }
+            let preemptible = Some(active).filter(|active| enabled.contains(active));
+            let chosen = exploration.schedule(active, preemptible, enabled).unwrap();
+            if preemptible.is_some_and(|active| active != chosen) {
+                preemptions += 1;
+            }
+            let (offset, write) = program[chosen.index()][next[chosen.index()]];
+            exploration.record_access(location(offset), write);
+            next[chosen.index()] += 1;
+            order.push(chosen.index());
+            active = chosen;
+        }
+    }
+
+    /// Explores all schedules of the program, and returns them along with their preemptions.
+    fn explore(bound: u32, program: &[&[(u64, bool)]]) -> Vec<(Vec<usize>, u32)> {
+        let mut exploration = Exploration::new(bound);
+        let mut schedules = Vec::new();
+        loop {
+            schedules.push(execute(&mut exploration, program));
+            if !exploration.next_execution() {
+                assert_eq!(exploration.executions(), u64::try_from(schedules.len()).unwrap());
+                return schedules;
+            }
+        }
+    }
+
+    #[test]
+    fn backtracks_to_reverse_races() {
+        // Two writes to the same location need to be explored in both orders.
+        let schedules = explore(2, &[&[(0, true)], &[(0, true)]]);
+        assert_eq!(schedules, vec![(vec![0, 1], 0), (vec![1, 0], 1)]);
+
+        // Two reads do not race, and neither do accesses to different locations.
+        assert_eq!(explore(2, &[&[(0, false)], &[(0, false)]]), vec![(vec![0, 1], 0)]);
+        assert_eq!(explore(2, &[&[(0, true)], &[(8, true)]]), vec![(vec![0, 1], 0)]);
+    }
+
+    #[test]
+    fn backtracks_at_the_latest_point_first() {
+        // Thread 0 writes two locations, and thread 1 reads the second one.
+        let schedules = explore(2, &[&[(0, true), (8, true)], &[(8, false)]]);
+        // Only the accesses to the second location race, so the read is moved before the second
+        // write but not before the first one.
+        assert_eq!(schedules, vec![(vec![0, 0, 1], 0), (vec![0, 1, 0], 1)]);
+    }
+
+    #[test]
+    fn respects_the_preemption_bound() {
+        let program: &[&[(u64, bool)]] =
+            &[&[(0, true), (0, true)], &[(0, true), (0, true)], &[(0, true), (0, true)]];
+        for bound in 0..=3 {
+            let schedules = explore(bound, program);
+            for (order, preemptions) in &schedules {
+                assert!(*preemptions <= bound, "{order:?} has {preemptions} preemptions");
+            }
+            // Every schedule is only explored once.
+            let distinct: BTreeSet<_> = schedules.iter().map(|(order, _)| order).collect();
+            assert_eq!(distinct.len(), schedules.len());
+        }
+
+        // Without preemptions, the only choice is which thread runs after thread 0 finished.
+        let schedules = explore(0, program);
+        assert_eq!(schedules, vec![(vec![0, 0, 1, 1, 2, 2], 0), (vec![0, 0, 2, 2, 1, 1], 0)]);
+        // A single preemption already allows thread 1 to run in between the writes of thread 0.
+        let schedules = explore(1, program);
+        assert!(schedules.iter().any(|(order, _)| order[..3] == [0, 1, 0]));
+    }
+
+    #[test]
+    fn switches_early_if_a_race_needs_too_many_preemptions() {
+        // Reversing the race between the last writes of threads 1 and 2 needs a preemption,
+        // which the bound of 0 does not allow. Instead, thread 2 is run before thread 1 as soon
+        // as thread 0 has finished, where switching to it is not a preemption.
+        let program: &[&[(u64, bool)]] = &[&[(16, true)], &[(8, true), (0, true)], &[(0, true)]];
+        let schedules = explore(0, program);
+        assert_eq!(schedules, vec![(vec![0, 1, 1, 2], 0), (vec![0, 2, 1, 1], 0)]);
+    }
+}
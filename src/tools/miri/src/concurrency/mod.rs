@@ -1,4 +1,5 @@
 pub mod data_race;
+pub mod exploration;
 mod range_object_map;
 #[macro_use]
 pub mod sync;
@@ -6,3 +7,5 @@
 pub mod thread;
 mod vector_clock;
 pub mod weak_memory;
+
+pub use vector_clock::VClock;
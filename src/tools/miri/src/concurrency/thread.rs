@@ -4,6 +4,7 @@
 use std::collections::hash_map::Entry;
 use std::num::TryFromIntError;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Once;
 use std::task::Poll;
 use std::time::{Duration, SystemTime};
 
@@ -19,8 +20,10 @@
 use rustc_target::spec::abi::Abi;
 
 use crate::concurrency::data_race;
+use crate::concurrency::exploration::{Exploration, OutputStream, SyncLocation};
 use crate::concurrency::sync::SynchronizationState;
 use crate::shims::tls;
+use crate::shims::unix::signal::EvalContextExt as _;
 use crate::*;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -330,6 +333,10 @@ pub struct ThreadManager<'mir, 'tcx> {
     yield_active_thread: bool,
     /// Callbacks that are called once the specified time passes.
     timeout_callbacks: FxHashMap<ThreadId, TimeoutCallbackInfo<'mir, 'tcx>>,
+    /// The state of the schedule exploration, if we are systematically exploring schedules
+    /// instead of picking a single one. This is a `RefCell` because atomic accesses need to
+    /// record themselves with only shared access to the machine.
+    exploration: Option<RefCell<Exploration>>,
 }
 
 impl VisitTags for ThreadManager<'_, '_> {
@@ -340,6 +347,7 @@ fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
             timeout_callbacks,
             active_thread: _,
             yield_active_thread: _,
+            exploration: _,
             sync,
         } = self;
 
@@ -368,6 +376,7 @@ fn default() -> Self {
             thread_local_alloc_ids: Default::default(),
             yield_active_thread: false,
             timeout_callbacks: FxHashMap::default(),
+            exploration: None,
         }
     }
 }
@@ -384,6 +393,39 @@ pub(crate) fn init(
         }
     }
 
+    /// Follow the schedule chosen by `exploration` in this execution.
+    pub(crate) fn set_exploration(&mut self, exploration: Exploration) {
+        self.exploration = Some(RefCell::new(exploration));
+    }
+
+    /// Stop following the schedule of the exploration and return its state.
+    pub(crate) fn take_exploration(&mut self) -> Option<Exploration> {
+        self.exploration.take().map(RefCell::into_inner)
+    }
+
+    /// Record an atomic access of the active thread. When exploring schedules, this is a
+    /// scheduling point.
+    pub(crate) fn record_atomic_access(&self, location: SyncLocation, write: bool) {
+        if let Some(exploration) = &self.exploration {
+            exploration.borrow_mut().record_access(location, write);
+        }
+    }
+
+    /// Record that the active thread started to panic.
+    pub(crate) fn record_panic(&self) {
+        if let Some(exploration) = &self.exploration {
+            exploration.borrow_mut().record_panic();
+        }
+    }
+
+    /// When exploring schedules, capture what the program writes to `stream` and return `true`.
+    pub(crate) fn capture_output(&self, stream: OutputStream, bytes: &[u8]) -> bool {
+        if let Some(exploration) = &self.exploration {
+            exploration.borrow().capture_bytes(stream, bytes);
+        }
+        self.exploration.is_some()
+    }
+
     /// Check if we have an allocation for the given thread local static for the
     /// active thread.
     fn get_thread_local_alloc_id(&self, def_id: DefId) -> Option<Pointer<Provenance>> {
@@ -419,10 +461,29 @@ pub fn all_stacks(
         self.threads.iter().map(|t| &t.stack[..])
     }
 
+    /// Borrow the stack of the given thread.
+    pub fn thread_stack(
+        &self,
+        thread: ThreadId,
+    ) -> &[Frame<'mir, 'tcx, Provenance, FrameExtra<'tcx>>] {
+        &self.threads[thread].stack
+    }
+
+    /// The ids and names of all threads that have not terminated yet.
+    pub fn live_threads(&self) -> impl Iterator<Item = (ThreadId, &[u8])> {
+        self.threads
+            .iter_enumerated()
+            .filter(|(_, thread)| thread.state != ThreadState::Terminated)
+            .map(|(id, thread)| (id, thread.thread_name()))
+    }
+
     /// Create a new thread and returns its id.
     fn create_thread(&mut self, on_stack_empty: StackEmptyCallback<'mir, 'tcx>) -> ThreadId {
         let new_thread_id = ThreadId::new(self.threads.len());
         self.threads.push(Thread::new(None, Some(on_stack_empty)));
+        if let Some(exploration) = &mut self.exploration {
+            exploration.get_mut().thread_created(self.active_thread, new_thread_id);
+        }
         new_thread_id
     }
 
@@ -451,7 +512,7 @@ pub fn get_live_thread_count(&self) -> usize {
     }
 
     /// Has the given thread terminated?
-    fn has_terminated(&self, thread_id: ThreadId) -> bool {
+    pub fn has_terminated(&self, thread_id: ThreadId) -> bool {
         self.threads[thread_id].state == ThreadState::Terminated
     }
 
@@ -521,14 +582,16 @@ fn join_thread(
             self.active_thread_mut().state = ThreadState::BlockedOnJoin(joined_thread_id);
             trace!(
                 "{:?} blocked on {:?} when trying to join",
-                self.active_thread,
-                joined_thread_id
+                self.active_thread, joined_thread_id
             );
         } else {
             // The thread has already terminated - mark join happens-before
             if let Some(data_race) = data_race {
                 data_race.thread_joined(self, self.active_thread, joined_thread_id);
             }
+            if let Some(exploration) = &mut self.exploration {
+                exploration.get_mut().thread_joined(self.active_thread, joined_thread_id);
+            }
         }
         Ok(())
     }
@@ -665,6 +728,9 @@ fn thread_terminated(
                 }
                 trace!("unblocking {:?} because {:?} terminated", i, self.active_thread);
                 thread.state = ThreadState::Enabled;
+                if let Some(exploration) = &mut self.exploration {
+                    exploration.get_mut().thread_joined(i, self.active_thread);
+                }
             }
         }
         for &i in &joined_threads {
@@ -678,11 +744,17 @@ fn thread_terminated(
     /// The currently implemented scheduling policy is the one that is commonly
     /// used in stateless model checkers such as Loom: run the active thread as
     /// long as we can and switch only when we have to (the active thread was
-    /// blocked, terminated, or has explicitly asked to be preempted).
+    /// blocked, terminated, or has explicitly asked to be preempted). When exploring schedules,
+    /// the active thread also stops at every scheduling point, and the exploration decides which
+    /// thread runs next.
     fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
         // This thread and the program can keep going.
         if self.threads[self.active_thread].state == ThreadState::Enabled
             && !self.yield_active_thread
+            && !self
+                .exploration
+                .as_ref()
+                .is_some_and(|exploration| exploration.borrow().at_scheduling_point())
         {
             // The currently active thread is still enabled, just continue with it.
             return Ok(SchedulingAction::ExecuteStep);
@@ -699,23 +771,45 @@ fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
             return Ok(SchedulingAction::ExecuteTimeoutCallback);
         }
         // No callbacks immediately scheduled, pick a regular thread to execute.
-        // The active thread blocked or yielded. So we go search for another enabled thread.
-        // Crucially, we start searching at the current active thread ID, rather than at 0, since we
-        // want to avoid always scheduling threads 0 and 1 without ever making progress in thread 2.
-        //
-        // `skip(N)` means we start iterating at thread N, so we skip 1 more to start just *after*
-        // the active thread. Then after that we look at `take(N)`, i.e., the threads *before* the
-        // active thread.
-        let threads = self
-            .threads
-            .iter_enumerated()
-            .skip(self.active_thread.index() + 1)
-            .chain(self.threads.iter_enumerated().take(self.active_thread.index()));
-        for (id, thread) in threads {
-            debug_assert_ne!(self.active_thread, id);
-            if thread.state == ThreadState::Enabled {
-                self.active_thread = id;
-                break;
+        if let Some(exploration) = &mut self.exploration {
+            // Let the exploration decide. A thread that yielded is only scheduled again if no
+            // other thread can run, to make sure that spin loops make progress.
+            let mut enabled: Vec<ThreadId> = self
+                .threads
+                .iter_enumerated()
+                .filter(|(_, thread)| thread.state == ThreadState::Enabled)
+                .map(|(id, _)| id)
+                .collect();
+            if self.yield_active_thread && enabled.len() > 1 {
+                enabled.retain(|&id| id != self.active_thread);
+            }
+            let preemptible = (self.threads[self.active_thread].state == ThreadState::Enabled
+                && !self.yield_active_thread)
+                .then_some(self.active_thread);
+            if !enabled.is_empty() {
+                self.active_thread =
+                    exploration.get_mut().schedule(self.active_thread, preemptible, enabled)?;
+            }
+        } else {
+            // The active thread blocked or yielded. So we go search for another enabled thread.
+            // Crucially, we start searching at the current active thread ID, rather than at 0,
+            // since we want to avoid always scheduling threads 0 and 1 without ever making
+            // progress in thread 2.
+            //
+            // `skip(N)` means we start iterating at thread N, so we skip 1 more to start just
+            // *after* the active thread. Then after that we look at `take(N)`, i.e., the threads
+            // *before* the active thread.
+            let threads = self
+                .threads
+                .iter_enumerated()
+                .skip(self.active_thread.index() + 1)
+                .chain(self.threads.iter_enumerated().take(self.active_thread.index()));
+            for (id, thread) in threads {
+                debug_assert_ne!(self.active_thread, id);
+                if thread.state == ThreadState::Enabled {
+                    self.active_thread = id;
+                    break;
+                }
             }
         }
         self.yield_active_thread = false;
@@ -831,6 +925,7 @@ fn start_regular_thread(
             let mut state = tls::TlsDtorsState::default();
             Box::new(move |m| state.on_stack_empty(m))
         });
+        this.machine.signals.thread_created(this.get_active_thread(), new_thread_id);
         let current_span = this.machine.current_span();
         if let Some(data_race) = &mut this.machine.data_race {
             data_race.thread_created(&this.machine.threads, new_thread_id, current_span);
@@ -999,7 +1094,9 @@ fn maybe_preempt_active_thread(&mut self) {
         use rand::Rng as _;
 
         let this = self.eval_context_mut();
-        if this.machine.rng.get_mut().gen_bool(this.machine.preemption_rate) {
+        let preempt = this.machine.rng.get_mut().gen_bool(this.machine.preemption_rate);
+        let thread = this.get_active_thread().to_u32();
+        if this.machine.choice_log.get_mut().preemption(thread, preempt) {
             this.yield_active_thread();
         }
     }
@@ -1028,17 +1125,22 @@ fn unregister_timeout_callback_if_exists(&mut self, thread: ThreadId) {
     /// termination).
     fn run_threads(&mut self) -> InterpResult<'tcx, !> {
         static SIGNALED: AtomicBool = AtomicBool::new(false);
-        ctrlc::set_handler(move || {
-            // Indicate that we have ben signaled to stop. If we were already signaled, exit
-            // immediately. In our interpreter loop we try to consult this value often, but if for
-            // whatever reason we don't get to that check or the cleanup we do upon finding that
-            // this bool has become true takes a long time, the exit here will promptly exit the
-            // process on the second Ctrl-C.
-            if SIGNALED.swap(true, Relaxed) {
-                std::process::exit(1);
-            }
-        })
-        .unwrap();
+        // The handler can only be installed once, but when exploring schedules we run the program
+        // many times.
+        static INSTALL_HANDLER: Once = Once::new();
+        INSTALL_HANDLER.call_once(|| {
+            ctrlc::set_handler(move || {
+                // Indicate that we have ben signaled to stop. If we were already signaled, exit
+                // immediately. In our interpreter loop we try to consult this value often, but if
+                // for whatever reason we don't get to that check or the cleanup we do upon finding
+                // that this bool has become true takes a long time, the exit here will promptly
+                // exit the process on the second Ctrl-C.
+                if SIGNALED.swap(true, Relaxed) {
+                    std::process::exit(1);
+                }
+            })
+            .unwrap();
+        });
         let this = self.eval_context_mut();
         loop {
             if SIGNALED.load(Relaxed) {
@@ -1047,6 +1149,12 @@ fn run_threads(&mut self) -> InterpResult<'tcx, !> {
             }
             match this.machine.threads.schedule(&this.machine.clock)? {
                 SchedulingAction::ExecuteStep => {
+                    if this.machine.signals.any_pending() {
+                        this.deliver_pending_signal()?;
+                    }
+                    if this.machine.debugger.is_some() {
+                        this.debugger_before_step()?;
+                    }
                     if !this.step()? {
                         // See if this thread can do something else.
                         match this.run_on_stack_empty()? {
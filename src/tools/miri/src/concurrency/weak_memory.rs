@@ -6,10 +6,17 @@
 //! but it is incapable of producing all possible weak behaviours allowed by the model. There are
 //! certain weak behaviours observable on real hardware but not while using this.
 //!
-//! Note that this implementation does not fully take into account of C++20's memory model revision to SC accesses
-//! and fences introduced by P0668 (<https://www.open-std.org/jtc1/sc22/wg21/docs/papers/2018/p0668r5.html>).
-//! This implementation is not fully correct under the revised C++20 model and may generate behaviours C++20
-//! disallows (<https://github.com/rust-lang/miri/issues/2301>).
+//! SC fences follow C++20's memory model revision introduced by P0668
+//! (<https://www.open-std.org/jtc1/sc22/wg21/docs/papers/2018/p0668r5.html>): an SC fence orders everything that
+//! happens-before it (not just what is sequenced-before it), and it constrains every load that happens-after it
+//! (not just loads of the fencing thread). The single total order S of SC fences is the order in which they are executed.
+//! Each fence records a view of everything that happens-before it or an earlier fence in S, and before each load we
+//! look up the latest fence that happens-before the load, which gives us the rules of C++20 §31.4 [atomics.order]
+//! paragraph 4 involving fences.
+//!
+//! SC accesses do not fully take into account C++20's revision, since the position of an SC access in S
+//! is not known when it is executed. This implementation is therefore not fully correct under the revised C++20 model
+//! and may generate behaviours C++20 disallows (<https://github.com/rust-lang/miri/issues/2301>).
 //!
 //! A modification is made to the paper's model to partially address C++20 changes.
 //! Specifically, if an SC load reads from an atomic store of any ordering, then a later SC load cannot read from
@@ -76,12 +83,18 @@
 // This is implemented correctly in tsan11
 // (https://github.com/ChrisLidbury/tsan11/blob/ecbd6b81e9b9454e01cba78eb9d88684168132c7/lib/tsan/rtl/tsan_relaxed.cc#L295)
 // and here.
+//
+// 5. The SC FENCE and ATOMIC LOAD rules of the paper only consider the SC fences of the loading thread, and the
+// stores of each thread sequenced-before its SC fences, as in C++11. Following C++20, we instead use the SC fences
+// that happen-before the load, and everything that happens-before those fences (see data_race::GlobalState::sc_fences).
 
 use std::{
     cell::{Ref, RefCell},
     collections::VecDeque,
 };
 
+use smallvec::SmallVec;
+
 use rustc_const_eval::interpret::{alloc_range, AllocRange, InterpResult, MPlaceTy, Scalar};
 use rustc_data_structures::fx::FxHashMap;
 
@@ -281,7 +294,7 @@ fn buffered_read(
         global: &DataRaceState,
         thread_mgr: &ThreadManager<'_, '_>,
         is_seqcst: bool,
-        rng: &mut (impl rand::Rng + ?Sized),
+        choose: impl FnOnce(usize) -> usize,
         validate: impl FnOnce() -> InterpResult<'tcx>,
     ) -> InterpResult<'tcx, (Scalar<Provenance>, LoadRecency)> {
         // Having a live borrow to store_buffer while calling validate_atomic_load is fine
@@ -292,7 +305,7 @@ fn buffered_read(
             // as the race detector will update it
             let (.., clocks) = global.current_thread_state(thread_mgr);
             // Load from a valid entry in the store buffer
-            self.fetch_store(is_seqcst, &clocks, &mut *rng)
+            self.fetch_store(is_seqcst, &clocks, choose)
         };
 
         // Unlike in buffered_atomic_write, thread clock updates have to be done
@@ -320,14 +333,14 @@ fn buffered_write(
     }
 
     #[allow(clippy::if_same_then_else, clippy::needless_bool)]
-    /// Selects a valid store element in the buffer.
-    fn fetch_store<R: rand::Rng + ?Sized>(
+    /// Selects a valid store element in the buffer. `choose` picks the index of the element among
+    /// the given number of candidates, starting from the latest one.
+    fn fetch_store(
         &self,
         is_seqcst: bool,
         clocks: &ThreadClockSet,
-        rng: &mut R,
+        choose: impl FnOnce(usize) -> usize,
     ) -> (&StoreElement, LoadRecency) {
-        use rand::seq::IteratorRandom;
         let mut found_sc = false;
         // FIXME: we want an inclusive take_while (stops after a false predicate, but
         // includes the element that gave the false), but such function doesn't yet
@@ -348,31 +361,33 @@ fn fetch_store<R: rand::Rng + ?Sized>(
                     // then we can't read-from anything earlier in modification order.
                     // C++20 §6.9.2.2 [intro.races] paragraph 18
                     false
-                } else if store_elem.load_info.borrow().timestamps.iter().any(
-                    |(&load_index, &load_timestamp)| load_timestamp <= clocks.clock[load_index],
-                ) {
+                } else if store_elem.loaded_before(&clocks.clock) {
                     // CoRR: if there was a load from this store which happened-before the current load,
                     // then we cannot read-from anything earlier in modification order.
                     // C++20 §6.9.2.2 [intro.races] paragraph 16
                     false
-                } else if store_elem.timestamp <= clocks.fence_seqcst[store_elem.store_index] {
-                    // The current load, which may be sequenced-after an SC fence, cannot read-before
-                    // the last store sequenced-before an SC fence in another thread.
-                    // C++17 §32.4 [atomics.order] paragraph 6
+                } else if store_elem.timestamp <= clocks.fence_seqcst[store_elem.store_index]
+                    || store_elem.loaded_before(&clocks.fence_seqcst)
+                {
+                    // The current load, which may happen-after an SC fence, cannot read-before
+                    // a store, or the store of a load, that happens-before the same or an earlier
+                    // SC fence (in any thread).
+                    // C++20 §31.4 [atomics.order] paragraph 4.4
                     false
                 } else if store_elem.timestamp <= clocks.write_seqcst[store_elem.store_index]
                     && store_elem.is_seqcst
                 {
-                    // The current non-SC load, which may be sequenced-after an SC fence,
+                    // The current non-SC load, which may happen-after an SC fence,
                     // cannot read-before the last SC store executed before the fence.
-                    // C++17 §32.4 [atomics.order] paragraph 4
+                    // C++20 §31.4 [atomics.order] paragraph 4.3
                     false
                 } else if is_seqcst
-                    && store_elem.timestamp <= clocks.read_seqcst[store_elem.store_index]
+                    && (store_elem.timestamp <= clocks.read_seqcst[store_elem.store_index]
+                        || store_elem.loaded_before(&clocks.read_seqcst))
                 {
-                    // The current SC load cannot read-before the last store sequenced-before
-                    // the last SC fence.
-                    // C++17 §32.4 [atomics.order] paragraph 5
+                    // The current SC load cannot read-before a store, or the store of a load,
+                    // that happens-before an earlier SC fence.
+                    // C++20 §31.4 [atomics.order] paragraph 4.2
                     false
                 } else if is_seqcst && store_elem.load_info.borrow().sc_loaded {
                     // The current SC load cannot read-before a store that an earlier SC load has observed.
@@ -396,9 +411,11 @@ fn fetch_store<R: rand::Rng + ?Sized>(
                 } else {
                     true
                 }
-            });
+            })
+            .collect::<SmallVec<[_; 4]>>();
 
-        let chosen = candidates.choose(rng).expect("store buffer cannot be empty");
+        assert!(!candidates.is_empty(), "store buffer cannot be empty");
+        let chosen = candidates[choose(candidates.len())];
         if std::ptr::eq(chosen, self.buffer.back().expect("store buffer cannot be empty")) {
             (chosen, LoadRecency::Latest)
         } else {
@@ -443,6 +460,15 @@ fn store_impl(
 }
 
 impl StoreElement {
+    /// Whether some load from this store element happens-before the given view.
+    fn loaded_before(&self, view: &VClock) -> bool {
+        self.load_info
+            .borrow()
+            .timestamps
+            .iter()
+            .any(|(&load_index, &load_timestamp)| load_timestamp <= view[load_index])
+    }
+
     /// ATOMIC LOAD IMPL in the paper
     /// Unlike the operational semantics in the paper, we don't need to keep track
     /// of the thread timestamp for every single load. Keeping track of the first (smallest)
@@ -501,14 +527,20 @@ fn buffered_atomic_read(
         latest_in_mo: Scalar<Provenance>,
         validate: impl FnOnce() -> InterpResult<'tcx>,
     ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        use rand::Rng as _;
+
         let this = self.eval_context_ref();
         if let Some(global) = &this.machine.data_race {
             let (alloc_id, base_offset, ..) = this.ptr_get_alloc_id(place.ptr())?;
             if let Some(alloc_buffers) = this.get_alloc_extra(alloc_id)?.weak_memory.as_ref() {
+                global.observe_sc_fences(&this.machine.threads);
                 if atomic == AtomicReadOrd::SeqCst {
                     global.sc_read(&this.machine.threads);
                 }
-                let mut rng = this.machine.rng.borrow_mut();
+                let choose = |candidates| {
+                    let index = this.machine.rng.borrow_mut().gen_range(0..candidates);
+                    this.machine.choice_log.borrow_mut().weak_memory_load(candidates, index)
+                };
                 let buffer = alloc_buffers.get_or_create_store_buffer(
                     alloc_range(base_offset, place.layout.size),
                     latest_in_mo,
@@ -517,7 +549,7 @@ fn buffered_atomic_read(
                     global,
                     &this.machine.threads,
                     atomic == AtomicReadOrd::SeqCst,
-                    &mut *rng,
+                    choose,
                     validate,
                 )?;
                 if global.track_outdated_loads && recency == LoadRecency::Outdated {
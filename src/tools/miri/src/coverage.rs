@@ -0,0 +1,208 @@
+//! Coverage and profile output for `-Zmiri-coverage=<dir>`.
+//!
+//! While the program runs, Miri counts how often each function is called and how often each of
+//! its MIR basic blocks is executed. At the end, two files are written to `<dir>`:
+//!
+//! - `<crate>-<pid>.lcov`, the line coverage of the local crates in the [lcov tracefile
+//!   format](https://manpages.debian.org/unstable/lcov/geninfo.1.en.html#TRACEFILE_FORMAT). A
+//!   line is considered executed as often as the most frequently executed basic block with a
+//!   statement on that line. Functions of the crate being interpreted that never ran are included
+//!   with a count of 0; for the other local crates, only functions that ran are known.
+//! - `<crate>-<pid>.profile`, a table of all functions (including the standard library), sorted by
+//!   the number of MIR statements and terminators that were executed in them. This does not
+//!   include the callees of a function, and is a rough measure of where interpretation time goes.
+//!
+//! With `-Zmiri-explore-schedules`, the counts are summed over all executions.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use either::Either;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_index::IndexVec;
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::{FileName, Span};
+
+use crate::*;
+
+#[derive(Default)]
+struct FunctionCounts {
+    calls: u64,
+    /// The number of MIR statements and terminators executed in this function.
+    instructions: u64,
+    /// How often each basic block was executed. Blocks past the end were never executed.
+    blocks: IndexVec<mir::BasicBlock, u64>,
+}
+
+/// The execution counts of all functions that were called, per monomorphization.
+#[derive(Default)]
+pub struct Coverage<'tcx> {
+    functions: FxHashMap<ty::Instance<'tcx>, FunctionCounts>,
+}
+
+/// The lcov records of a single source file.
+#[derive(Default)]
+struct SourceFile {
+    /// The line, name and number of calls of each function defined in this file.
+    functions: Vec<(usize, String, u64)>,
+    /// The execution count of each line that has code on it.
+    lines: BTreeMap<usize, u64>,
+}
+
+impl<'tcx> Coverage<'tcx> {
+    pub fn record_call(&mut self, instance: ty::Instance<'tcx>) {
+        self.functions.entry(instance).or_default().calls += 1;
+    }
+
+    /// Called when `frame` is about to execute the terminator of its current basic block, i.e.,
+    /// once per executed basic block.
+    pub fn record_block(&mut self, frame: &Frame<'_, 'tcx, Provenance, FrameExtra<'tcx>>) {
+        let Either::Left(loc) = frame.loc else { return };
+        let counts = self.functions.entry(frame.instance).or_default();
+        let statements = frame.body.basic_blocks[loc.block].statements.len();
+        counts.instructions += u64::try_from(statements).unwrap() + 1;
+        *counts.blocks.ensure_contains_elem(loc.block, || 0) += 1;
+    }
+
+    /// Writes the lcov file and the profile to `dir`.
+    pub fn write(&self, tcx: TyCtxt<'tcx>, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        // Use the same file names as `-Zmiri-measureme`.
+        let crate_name =
+            tcx.sess.opts.crate_name.clone().unwrap_or_else(|| "unknown-crate".to_string());
+        let filename = format!("{crate_name}-{:07}", process::id());
+        fs::write(dir.join(format!("{filename}.lcov")), self.lcov(tcx))?;
+        fs::write(dir.join(format!("{filename}.profile")), self.profile())
+    }
+
+    fn lcov(&self, tcx: TyCtxt<'tcx>) -> String {
+        let local_crates = helpers::get_local_crates(tcx);
+        let is_local = |def_id: DefId| def_id.is_local() || local_crates.contains(&def_id.krate);
+
+        // Merge the counts of all monomorphizations of a function, since they share their MIR.
+        let mut bodies: FxHashMap<DefId, (u64, IndexVec<mir::BasicBlock, u64>)> =
+            FxHashMap::default();
+        for (instance, counts) in &self.functions {
+            let ty::InstanceDef::Item(def_id) = instance.def else { continue };
+            if !is_local(def_id) {
+                continue;
+            }
+            let (calls, blocks) = bodies.entry(def_id).or_default();
+            *calls += counts.calls;
+            for (block, &count) in counts.blocks.iter_enumerated() {
+                *blocks.ensure_contains_elem(block, || 0) += count;
+            }
+        }
+        // Add the functions of the interpreted crate that were never called.
+        for def_id in tcx.hir().body_owners() {
+            let def_id = def_id.to_def_id();
+            if tcx.def_kind(def_id).is_fn_like() && tcx.is_mir_available(def_id) {
+                bodies.entry(def_id).or_default();
+            }
+        }
+
+        let mut files: BTreeMap<PathBuf, SourceFile> = BTreeMap::new();
+        for (&def_id, (calls, blocks)) in &bodies {
+            if let Some((path, line)) = source_line(tcx, tcx.def_span(def_id)) {
+                let name = tcx.def_path_str(def_id);
+                files.entry(path).or_default().functions.push((line, name, *calls));
+            }
+            let body = tcx.instance_mir(ty::InstanceDef::Item(def_id));
+            for (block, data) in body.basic_blocks.iter_enumerated() {
+                let count = blocks.get(block).copied().unwrap_or(0);
+                // Cleanup blocks only run while unwinding; ignore them unless they did.
+                if data.is_cleanup && count == 0 {
+                    continue;
+                }
+                let spans =
+                    data.statements.iter().map(|statement| statement.source_info.span).chain(
+                        data.terminator.iter().map(|terminator| terminator.source_info.span),
+                    );
+                for span in spans {
+                    if let Some((path, line)) = source_line(tcx, span) {
+                        let hits = files.entry(path).or_default().lines.entry(line).or_default();
+                        *hits = (*hits).max(count);
+                    }
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for (path, mut file) in files {
+            file.functions.sort();
+            writeln!(out, "TN:").unwrap();
+            writeln!(out, "SF:{}", path.display()).unwrap();
+            for (line, name, _) in &file.functions {
+                writeln!(out, "FN:{line},{name}").unwrap();
+            }
+            for (_, name, calls) in &file.functions {
+                writeln!(out, "FNDA:{calls},{name}").unwrap();
+            }
+            let functions_hit = file.functions.iter().filter(|(_, _, calls)| *calls > 0).count();
+            writeln!(out, "FNF:{}", file.functions.len()).unwrap();
+            writeln!(out, "FNH:{functions_hit}").unwrap();
+            for (line, count) in &file.lines {
+                writeln!(out, "DA:{line},{count}").unwrap();
+            }
+            let lines_hit = file.lines.values().filter(|&&count| count > 0).count();
+            writeln!(out, "LF:{}", file.lines.len()).unwrap();
+            writeln!(out, "LH:{lines_hit}").unwrap();
+            writeln!(out, "end_of_record").unwrap();
+        }
+        out
+    }
+
+    fn profile(&self) -> String {
+        let mut functions: Vec<(String, &FunctionCounts)> = self
+            .functions
+            .iter()
+            .map(|(instance, counts)| (instance.to_string(), counts))
+            .collect();
+        functions.sort_by(|(name_a, a), (name_b, b)| {
+            b.instructions.cmp(&a.instructions).then_with(|| name_a.cmp(name_b))
+        });
+        let total: u64 = functions.iter().map(|(_, counts)| counts.instructions).sum();
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:>14} {:>7} {:>12} {:>10}  function",
+            "instructions", "share", "blocks", "calls"
+        )
+        .unwrap();
+        for (name, counts) in functions {
+            // Avoid dividing by 0 if nothing was executed at all.
+            let share = counts.instructions as f64 * 100.0 / total.max(1) as f64;
+            let blocks: u64 = counts.blocks.iter().sum();
+            writeln!(
+                out,
+                "{:>14} {:>6.2}% {:>12} {:>10}  {name}",
+                counts.instructions, share, blocks, counts.calls
+            )
+            .unwrap();
+        }
+        writeln!(out, "{total:>14} {:>7} {:>12} {:>10}  (total)", "100%", "", "").unwrap();
+        out
+    }
+}
+
+/// The absolute path of the source file and the line `span` starts on, looking through macro
+/// expansions to the code that invoked them.
+fn source_line(tcx: TyCtxt<'_>, span: Span) -> Option<(PathBuf, usize)> {
+    let span = span.source_callsite();
+    if span.is_dummy() {
+        return None;
+    }
+    let loc = tcx.sess.source_map().lookup_char_pos(span.lo());
+    let FileName::Real(name) = &loc.file.name else { return None };
+    let path = name.local_path_if_available();
+    let path = std::env::current_dir().map_or_else(|_| path.to_owned(), |dir| dir.join(path));
+    Some((path, loc.line))
+}
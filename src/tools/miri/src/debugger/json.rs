@@ -0,0 +1,423 @@
+//! A minimal JSON representation, just enough for the messages of the Debug Adapter Protocol.
+
+use std::fmt::{self, Write};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// The members of an object, in the order they were written.
+    Object(Vec<(String, Value)>),
+}
+
+/// Shorthand for building an object from its members.
+pub fn object<const N: usize>(members: [(&str, Value); N]) -> Value {
+    Value::Object(members.into_iter().map(|(key, value)| (key.to_owned(), value)).collect())
+}
+
+impl Value {
+    /// Looks up a member of an object. Returns `Null` if there is no such member.
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) =>
+                members.iter().find(|(k, _)| k == key).map_or(&Value::Null, |(_, value)| value),
+            _ => &Value::Null,
+        }
+    }
+
+    /// Adds a member to an object.
+    pub fn insert(&mut self, key: &str, value: Value) {
+        match self {
+            Value::Object(members) => members.push((key.to_owned(), value)),
+            _ => panic!("`insert` called on a JSON value that is not an object"),
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            // Only accept integral numbers that survive the round trip.
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 2f64.powi(53) => Some(n as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Value, String> {
+        let mut parser = Parser { input: input.as_bytes(), pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(elements: Vec<Value>) -> Self {
+        Value::Array(elements)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::String(s) => write_string(f, s),
+            Value::Array(elements) => {
+                f.write_char('[')?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> String {
+        format!("invalid JSON at byte {}: {msg}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: &[u8]) -> Result<(), String> {
+        if self.input[self.pos..].starts_with(expected) {
+            self.pos += expected.len();
+            Ok(())
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.input.get(self.pos) {
+            Some(b'n') => self.expect(b"null").map(|()| Value::Null),
+            Some(b't') => self.expect(b"true").map(|()| Value::Bool(true)),
+            Some(b'f') => self.expect(b"false").map(|()| Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                self.skip_whitespace();
+                if self.input.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(elements));
+                        }
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.input.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.input.get(self.pos) != Some(&b'"') {
+                        return Err(self.error("expected a string"));
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(b":")?;
+                    members.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.input.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Value::Object(members));
+                        }
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|c| matches!(c, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+        text.parse().map(Value::Number).map_err(|_| self.error("invalid number"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits =
+            self.input.get(self.pos..self.pos + 4).ok_or_else(|| self.error("bad escape"))?;
+        // `from_str_radix` would also accept a leading `+`.
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.error("bad escape"));
+        }
+        let code = u32::from_str_radix(std::str::from_utf8(digits).unwrap(), 16).unwrap();
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        // Skip the opening quote.
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&c) = self.input.get(self.pos) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.input.get(self.pos) else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // Combine surrogate pairs.
+                            if (0xd800..0xdc00).contains(&code) && self.expect(b"\\u").is_ok() {
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("bad escape"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("bad escape"))?
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let input =
+            r#"{"seq":1,"type":"request","arguments":{"lines":[3,-4.5],"ok":true,"x":null}}"#;
+        let value = Value::parse(input).unwrap();
+        assert_eq!(value.get("seq").as_i64(), Some(1));
+        assert_eq!(value.get("type").as_str(), Some("request"));
+        assert_eq!(value.get("arguments").get("ok").as_bool(), Some(true));
+        assert_eq!(value.get("missing"), &Value::Null);
+        assert_eq!(value.to_string(), input);
+    }
+
+    #[test]
+    fn strings() {
+        let value = Value::parse(r#" "a\"b\\c\né\ud83d\ude00\/" "#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"b\\c\né😀/"));
+        assert_eq!(Value::from("\"\u{1}\n").to_string(), r#""\"\u0001\n""#);
+        assert!(Value::parse(r#""unterminated"#).is_err());
+        assert!(Value::parse("[1,]").is_err());
+        assert!(Value::parse("{} x").is_err());
+    }
+
+    #[test]
+    fn escapes() {
+        let value = Value::parse(r#""\b\f\r\t\u0041\u00e9\u20AC""#).unwrap();
+        assert_eq!(value.as_str(), Some("\u{8}\u{c}\r\tAé€"));
+        // Control characters and the characters that need escaping roundtrip.
+        let s = "\u{0}\u{1f}\"\\\n\r\t\u{7f}";
+        assert_eq!(Value::parse(&Value::from(s).to_string()).unwrap().as_str(), Some(s));
+        assert!(Value::parse(r#""\x""#).is_err());
+        assert!(Value::parse(r#""\u00""#).is_err());
+        assert!(Value::parse(r#""\u+041""#).is_err());
+        assert!(Value::parse(r#""\u00g0""#).is_err());
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(Value::parse(r#""\uD834\uDD1E""#).unwrap().as_str(), Some("𝄞"));
+        assert_eq!(Value::parse(r#""\udbff\udfff""#).unwrap().as_str(), Some("\u{10ffff}"));
+        // Unpaired or misordered surrogates are not characters.
+        assert!(Value::parse(r#""\ud83d""#).is_err());
+        assert!(Value::parse(r#""\ud83dx""#).is_err());
+        assert!(Value::parse(r#""\ude00""#).is_err());
+        assert!(Value::parse(r#""\ude00\ud83d""#).is_err());
+        assert!(Value::parse(r#""\ud83d\u0041""#).is_err());
+        assert!(Value::parse(r#""\ud83d\ud83d""#).is_err());
+    }
+
+    #[test]
+    fn nesting() {
+        let input =
+            r#" { "a" : [ [ ] , { } , [ { "b" : [ 1 , { "c" : "d" } ] } ] ] , "e" : { } } "#;
+        let value = Value::parse(input).unwrap();
+        let a = value.get("a").as_array().unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a[0], Value::Array(vec![]));
+        assert_eq!(a[1], Value::Object(vec![]));
+        let b = a[2].as_array().unwrap()[0].get("b").as_array().unwrap();
+        assert_eq!(b[0].as_i64(), Some(1));
+        assert_eq!(b[1].get("c").as_str(), Some("d"));
+        assert_eq!(value.to_string(), r#"{"a":[[],{},[{"b":[1,{"c":"d"}]}]],"e":{}}"#);
+    }
+
+    #[test]
+    fn malformed() {
+        for input in [
+            "",
+            " ",
+            "nul",
+            "tru",
+            "[",
+            "[1",
+            "[1 2]",
+            "[,1]",
+            "{",
+            r#"{"a"}"#,
+            r#"{"a":}"#,
+            r#"{"a":1,}"#,
+            r#"{a:1}"#,
+            "{1:2}",
+            "-",
+            "1.2.3",
+            "1e",
+            "+1",
+            "'a'",
+        ] {
+            assert!(Value::parse(input).is_err(), "{input:?} should not parse");
+        }
+        let err = Value::parse("[1, x]").unwrap_err();
+        assert_eq!(err, "invalid JSON at byte 4: expected a value");
+    }
+}
@@ -0,0 +1,940 @@
+//! A server for the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/),
+//! so that interpreted programs can be debugged from an editor.
+//!
+//! With `-Zmiri-debugger=<port>`, Miri waits for a client to connect to the given TCP port before
+//! running the program. With `-Zmiri-debugger=stdio`, the protocol is spoken over Miri's own
+//! standard input and output instead, and everything the program writes to its standard output and
+//! error is forwarded to the client as `output` events.
+//!
+//! The client can set breakpoints on source lines, pause the program, and step over, into, or out
+//! of function calls. Stepping goes from one source line of the local crate to the next; with the
+//! `instruction` stepping granularity, it goes from one MIR statement to the next instead. While
+//! the program is stopped, the client can inspect the stack of every thread and the variables of
+//! every frame. Values are read directly from the interpreter's frames and memory, without going
+//! through the borrow tracker or the data race detector, so inspecting the program never changes
+//! its behavior. Pointers can be expanded to show their pointee and what the borrow tracker knows
+//! about their tag at the location they point to. When the program hits Undefined Behavior (or any
+//! other error), the debugger stops with reason `exception` after the error has been reported, so
+//! that the state that led to it can be inspected.
+//!
+//! Requests are read on a separate thread, so that `pause` and `setBreakpoints` also work while
+//! the program is running.
+
+mod json;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+
+use either::Either;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::mir;
+use rustc_middle::ty::{self, Ty};
+use rustc_span::FileName;
+use rustc_target::abi::{Abi, Primitive, Size};
+
+use crate::shims::unix::FileDescriptor;
+use crate::*;
+use json::{object, Value};
+
+/// Arrays with more elements than this only show their first elements.
+const MAX_CHILDREN: usize = 1000;
+
+/// Where the debugger waits for its client.
+#[derive(Clone, Debug)]
+pub enum DebuggerEndpoint {
+    /// Miri's own standard input and output.
+    Stdio,
+    /// A TCP address to accept a single connection on.
+    Tcp(String),
+}
+
+impl fmt::Display for DebuggerEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebuggerEndpoint::Stdio => write!(f, "stdio"),
+            DebuggerEndpoint::Tcp(addr) => write!(f, "{addr}"),
+        }
+    }
+}
+
+/// The sending half of the connection to the client.
+struct Client {
+    out: Box<dyn Write>,
+    /// The sequence number of the next message.
+    seq: i64,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client").field("seq", &self.seq).finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    fn send(&mut self, message: Value) {
+        let Value::Object(mut members) = message else {
+            panic!("Debug Adapter Protocol messages must be objects")
+        };
+        members.insert(0, ("seq".to_owned(), self.seq.into()));
+        self.seq += 1;
+        let message = Value::Object(members).to_string();
+        // If the client is gone, there is nobody left to tell about it.
+        let _ = write!(self.out, "Content-Length: {}\r\n\r\n{message}", message.len())
+            .and_then(|()| self.out.flush());
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        let mut message = object([("type", "event".into()), ("event", event.into())]);
+        if body != Value::Null {
+            message.insert("body", body);
+        }
+        self.send(message);
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) {
+        let mut message = object([
+            ("type", "response".into()),
+            ("request_seq", request.get("seq").clone()),
+            ("success", result.is_ok().into()),
+            ("command", request.get("command").clone()),
+        ]);
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => message.insert("body", body),
+            Err(error) => message.insert("message", error.into()),
+        }
+        self.send(message);
+    }
+}
+
+/// Reads a single message, or returns `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length =
+                Some(value.trim().parse().map_err(|_| invalid(format!("bad header `{line}`")))?);
+        }
+    }
+    let length = length.ok_or_else(|| invalid("missing `Content-Length` header".to_owned()))?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    let content = String::from_utf8(content).map_err(|err| invalid(err.to_string()))?;
+    Value::parse(&content).map(Some).map_err(invalid)
+}
+
+/// The standard output or error of the program, when the protocol is spoken over Miri's standard
+/// output: everything the program writes is sent to the client as an `output` event.
+#[derive(Debug)]
+pub struct DebuggerOutput {
+    client: Rc<RefCell<Client>>,
+    category: &'static str,
+}
+
+impl DebuggerOutput {
+    pub fn forward(&self, bytes: &[u8]) -> usize {
+        let body = object([
+            ("category", self.category.into()),
+            ("output", String::from_utf8_lossy(bytes).into_owned().into()),
+        ]);
+        self.client.borrow_mut().event("output", body);
+        bytes.len()
+    }
+}
+
+impl FileDescriptor for DebuggerOutput {
+    fn name(&self) -> &'static str {
+        self.category
+    }
+
+    fn write<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        _tcx: ty::TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        Ok(Ok(self.forward(bytes)))
+    }
+
+    fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
+        Ok(Box::new(DebuggerOutput { client: Rc::clone(&self.client), category: self.category }))
+    }
+}
+
+/// A source line executed at a certain stack depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Position {
+    file: PathBuf,
+    line: usize,
+    depth: usize,
+}
+
+#[derive(Debug)]
+enum Mode {
+    /// Run until a breakpoint is hit.
+    Continue,
+    /// Stop before the next statement of any thread.
+    Pause { reason: &'static str },
+    /// Stop once `thread` is about to execute a statement at a stack depth of at most `max_depth`.
+    /// If `by_line` is set, only stop in the local crate, and at a different position than `from`.
+    Step { thread: ThreadId, max_depth: usize, by_line: bool, from: Option<Position> },
+    /// The client is gone, but the program keeps running.
+    Detached,
+}
+
+/// Something the client can ask the children of, with a `variables` request.
+#[derive(Debug)]
+enum Scope<'tcx> {
+    /// The variables of a stack frame.
+    Locals { thread: ThreadId, frame: usize },
+    /// The fields of a value, or the pointee and borrow state of a pointer.
+    Value(OpTy<'tcx, Provenance>),
+}
+
+/// What to do after handling a request.
+#[derive(Debug, PartialEq, Eq)]
+enum Control {
+    Stay,
+    Resume,
+    Terminate,
+}
+
+#[derive(Debug)]
+pub struct Debugger<'tcx> {
+    /// The requests of the client, read on a separate thread.
+    requests: mpsc::Receiver<Value>,
+    client: Rc<RefCell<Client>>,
+    /// Whether the program's output has to be forwarded to the client.
+    forward_output: bool,
+    mode: Mode,
+    /// Whether the client has finished its initial configuration.
+    configured: bool,
+    /// The lines with a breakpoint, per source file (as named by the client).
+    breakpoints: FxHashMap<PathBuf, FxHashSet<usize>>,
+    /// The position each thread executed last.
+    positions: FxHashMap<ThreadId, Position>,
+    /// Whether the program is currently stopped.
+    stopped: bool,
+    /// The stack frames and scopes handed out since the program stopped, indexed by their id.
+    /// Scope ids start at 1, since a `variablesReference` of 0 means "no children".
+    frames: Vec<(ThreadId, usize)>,
+    scopes: Vec<Scope<'tcx>>,
+}
+
+impl<'tcx> Debugger<'tcx> {
+    /// Waits for a client to connect.
+    pub fn connect(endpoint: &DebuggerEndpoint) -> io::Result<Self> {
+        let (mut input, out): (Box<dyn BufRead + Send>, Box<dyn Write>) = match endpoint {
+            DebuggerEndpoint::Stdio =>
+                (Box::new(BufReader::new(io::stdin())), Box::new(io::stdout())),
+            DebuggerEndpoint::Tcp(addr) => {
+                let listener = TcpListener::bind(addr)?;
+                eprintln!("waiting for a debugger to connect to {}", listener.local_addr()?);
+                let (stream, _) = listener.accept()?;
+                (Box::new(BufReader::new(stream.try_clone()?)), Box::new(stream))
+            }
+        };
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(Some(request)) = read_message(&mut input) {
+                if sender.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Debugger {
+            requests,
+            client: Rc::new(RefCell::new(Client { out, seq: 1 })),
+            forward_output: matches!(endpoint, DebuggerEndpoint::Stdio),
+            mode: Mode::Continue,
+            configured: false,
+            breakpoints: FxHashMap::default(),
+            positions: FxHashMap::default(),
+            stopped: false,
+            frames: Vec::new(),
+            scopes: Vec::new(),
+        })
+    }
+
+    /// Where the program's standard output or error (depending on `category`) has to go, if it
+    /// cannot go to Miri's own.
+    pub fn output(&self, category: &'static str) -> Option<DebuggerOutput> {
+        self.forward_output.then(|| DebuggerOutput { client: Rc::clone(&self.client), category })
+    }
+
+    /// Serves the requests of the client until it has set up its breakpoints.
+    fn configure(&mut self, ecx: &MiriInterpCx<'_, 'tcx>) {
+        while !self.configured {
+            let Ok(request) = self.requests.recv() else {
+                self.mode = Mode::Detached;
+                return;
+            };
+            if self.handle(ecx, &request) != Control::Stay {
+                // The client disconnected before the program even started.
+                return;
+            }
+        }
+    }
+
+    /// Tells the client that the program stopped, and serves its requests until it resumes the
+    /// program. Returns whether the program should keep running.
+    fn stop(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, reason: &str, text: Option<&str>) -> bool {
+        let mut body = object([
+            ("reason", reason.into()),
+            ("threadId", client_thread_id(ecx.get_active_thread()).into()),
+            ("allThreadsStopped", true.into()),
+        ]);
+        if let Some(text) = text {
+            body.insert("text", text.into());
+        }
+        self.client.borrow_mut().event("stopped", body);
+        self.stopped = true;
+        let control = loop {
+            let Ok(request) = self.requests.recv() else {
+                self.mode = Mode::Detached;
+                break Control::Resume;
+            };
+            match self.handle(ecx, &request) {
+                Control::Stay => {}
+                control => break control,
+            }
+        };
+        self.stopped = false;
+        self.frames.clear();
+        self.scopes.clear();
+        control == Control::Resume
+    }
+
+    /// Stops the program after an error has been reported, so that the client can inspect the
+    /// state that led to it.
+    pub fn stop_on_error(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, message: &str) {
+        if !matches!(self.mode, Mode::Detached) {
+            self.stop(ecx, "exception", Some(message));
+        }
+    }
+
+    /// Tells the client that the program exited, and waits for it to disconnect.
+    pub fn exited(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, exit_code: i64) {
+        if matches!(self.mode, Mode::Detached) {
+            return;
+        }
+        self.client.borrow_mut().event("exited", object([("exitCode", exit_code.into())]));
+        self.client.borrow_mut().event("terminated", Value::Null);
+        self.mode = Mode::Detached;
+        while let Ok(request) = self.requests.recv() {
+            self.handle(ecx, &request);
+            if request.get("command").as_str() == Some("disconnect") {
+                break;
+            }
+        }
+    }
+
+    /// Decides whether to stop before the active thread executes its next statement.
+    fn stop_reason(&mut self, ecx: &MiriInterpCx<'_, 'tcx>) -> Option<&'static str> {
+        match self.mode {
+            Mode::Detached => return None,
+            Mode::Continue if self.breakpoints.is_empty() => return None,
+            _ => {}
+        }
+        let thread = ecx.get_active_thread();
+        let stack = ecx.active_thread_stack();
+        let frame = stack.last()?;
+        let position = position(ecx, frame, stack.len());
+        let previous = match &position {
+            Some(position) => self.positions.insert(thread, position.clone()),
+            None => self.positions.remove(&thread),
+        };
+
+        if let Mode::Pause { reason } = self.mode {
+            self.mode = Mode::Continue;
+            return Some(reason);
+        }
+        if let Some(position) = &position {
+            if previous.as_ref() != Some(position)
+                && self.breakpoints.iter().any(|(file, lines)| {
+                    lines.contains(&position.line) && same_file(file, &position.file)
+                })
+            {
+                self.mode = Mode::Continue;
+                return Some("breakpoint");
+            }
+        }
+        if let Mode::Step { thread: stepping, max_depth, by_line, ref from } = self.mode {
+            if ecx.machine.threads.thread_stack(stepping).is_empty() {
+                // The thread we were stepping in is gone.
+                self.mode = Mode::Continue;
+            } else if thread == stepping && stack.len() <= max_depth {
+                let done = !by_line
+                    || (frame.extra.is_user_relevant
+                        && position.is_some()
+                        && position.as_ref() != from.as_ref());
+                if done {
+                    self.mode = Mode::Continue;
+                    return Some("step");
+                }
+            }
+        }
+        None
+    }
+
+    /// Handles a single request of the client.
+    fn handle(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, request: &Value) -> Control {
+        let command = request.get("command").as_str().unwrap_or_default();
+        let args = request.get("arguments");
+        let mut control = Control::Stay;
+        let result = match command {
+            "initialize" =>
+                Ok(object([
+                    ("supportsConfigurationDoneRequest", true.into()),
+                    ("supportsEvaluateForHovers", true.into()),
+                    ("supportsSteppingGranularity", true.into()),
+                ])),
+            "launch" | "attach" => {
+                if args.get("stopOnEntry").as_bool() == Some(true) {
+                    self.mode = Mode::Pause { reason: "entry" };
+                }
+                Ok(Value::Null)
+            }
+            "configurationDone" => {
+                self.configured = true;
+                Ok(Value::Null)
+            }
+            "setBreakpoints" => Ok(self.set_breakpoints(args)),
+            "setExceptionBreakpoints" => Ok(Value::Null),
+            "threads" => {
+                let threads = ecx
+                    .machine
+                    .threads
+                    .live_threads()
+                    .map(|(id, name)| {
+                        object([
+                            ("id", client_thread_id(id).into()),
+                            ("name", String::from_utf8_lossy(name).into_owned().into()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+                Ok(object([("threads", threads.into())]))
+            }
+            "stackTrace" => self.stack_trace(ecx, args),
+            "scopes" => self.scopes(args),
+            "variables" => self.variables(ecx, args),
+            "evaluate" => self.evaluate(ecx, args),
+            "continue" =>
+                self.resume(Mode::Continue).map(|()| {
+                    control = Control::Resume;
+                    object([("allThreadsContinued", true.into())])
+                }),
+            "next" | "stepIn" | "stepOut" =>
+                self.step(ecx, command, args).map(|()| {
+                    control = Control::Resume;
+                    Value::Null
+                }),
+            "pause" => {
+                if !self.stopped {
+                    self.mode = Mode::Pause { reason: "pause" };
+                }
+                Ok(Value::Null)
+            }
+            "disconnect" => {
+                // Debuggers that launched the program usually want it to be terminated.
+                if args.get("terminateDebuggee").as_bool().unwrap_or(true) {
+                    control = Control::Terminate;
+                } else {
+                    control = Control::Resume;
+                }
+                self.mode = Mode::Detached;
+                self.breakpoints.clear();
+                Ok(Value::Null)
+            }
+            _ => Err(format!("unsupported request `{command}`")),
+        };
+        self.client.borrow_mut().respond(request, result);
+        if command == "initialize" {
+            self.client.borrow_mut().event("initialized", Value::Null);
+        }
+        control
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        let path = PathBuf::from(args.get("source").get("path").as_str().unwrap_or_default());
+        let lines = args
+            .get("breakpoints")
+            .as_array()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|breakpoint| usize::try_from(breakpoint.get("line").as_i64()?).ok())
+            .collect::<Vec<_>>();
+        let breakpoints = lines
+            .iter()
+            .map(|&line| object([("verified", true.into()), ("line", line.into())]))
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            self.breakpoints.remove(&path);
+        } else {
+            self.breakpoints.insert(path, lines.into_iter().collect());
+        }
+        object([("breakpoints", breakpoints.into())])
+    }
+
+    fn resume(&mut self, mode: Mode) -> Result<(), String> {
+        if !self.stopped {
+            return Err("the program is not stopped".to_owned());
+        }
+        self.mode = mode;
+        Ok(())
+    }
+
+    fn step(
+        &mut self,
+        ecx: &MiriInterpCx<'_, 'tcx>,
+        command: &str,
+        args: &Value,
+    ) -> Result<(), String> {
+        let thread = thread_id(ecx, args)?;
+        let depth = ecx.machine.threads.thread_stack(thread).len();
+        let max_depth = match command {
+            "next" => depth,
+            "stepIn" => usize::MAX,
+            "stepOut" => depth.saturating_sub(1),
+            _ => unreachable!(),
+        };
+        let by_line = args.get("granularity").as_str() != Some("instruction");
+        let from = self.positions.get(&thread).cloned();
+        self.resume(Mode::Step { thread, max_depth, by_line, from })
+    }
+
+    fn stack_trace(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, args: &Value) -> Result<Value, String> {
+        let thread = thread_id(ecx, args)?;
+        let stack = ecx.machine.threads.thread_stack(thread);
+        let start = args.get("startFrame").as_i64().and_then(|n| usize::try_from(n).ok());
+        let levels = args.get("levels").as_i64().and_then(|n| usize::try_from(n).ok());
+        let mut frames = Vec::new();
+        for (index, frame) in stack
+            .iter()
+            .enumerate()
+            .rev()
+            .skip(start.unwrap_or(0))
+            .take(levels.filter(|&levels| levels > 0).unwrap_or(usize::MAX))
+        {
+            let mut stack_frame = object([
+                ("id", self.frames.len().into()),
+                ("name", frame.instance.to_string().into()),
+            ]);
+            self.frames.push((thread, index));
+            let span = frame.current_span().source_callsite();
+            let loc = ecx.tcx.sess.source_map().lookup_char_pos(span.lo());
+            let mut source = object([("name", loc.file.name.prefer_local().to_string().into())]);
+            if let Some(path) = source_path(&loc.file.name) {
+                let path =
+                    std::env::current_dir().map_or_else(|_| path.clone(), |dir| dir.join(&path));
+                source.insert("path", path.to_string_lossy().into_owned().into());
+            }
+            stack_frame.insert("source", source);
+            stack_frame.insert("line", loc.line.into());
+            stack_frame.insert("column", (loc.col.0 + 1).into());
+            frames.push(stack_frame);
+        }
+        Ok(object([("stackFrames", frames.into()), ("totalFrames", stack.len().into())]))
+    }
+
+    /// The stack frame with the `frameId` given in `args`.
+    fn frame(&self, args: &Value) -> Result<(ThreadId, usize), String> {
+        args.get("frameId")
+            .as_i64()
+            .and_then(|id| self.frames.get(usize::try_from(id).ok()?))
+            .copied()
+            .ok_or_else(|| "unknown stack frame".to_owned())
+    }
+
+    fn scopes(&mut self, args: &Value) -> Result<Value, String> {
+        let (thread, frame) = self.frame(args)?;
+        self.scopes.push(Scope::Locals { thread, frame });
+        let locals = object([
+            ("name", "Locals".into()),
+            ("presentationHint", "locals".into()),
+            ("variablesReference", self.scopes.len().into()),
+            ("expensive", false.into()),
+        ]);
+        Ok(object([("scopes", vec![locals].into())]))
+    }
+
+    fn variables(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, args: &Value) -> Result<Value, String> {
+        let scope = args
+            .get("variablesReference")
+            .as_i64()
+            .and_then(|id| self.scopes.get(usize::try_from(id).ok()?.checked_sub(1)?))
+            .ok_or_else(|| "unknown variables reference".to_owned())?;
+        let variables = match scope {
+            &Scope::Locals { thread, frame } =>
+                locals(ecx, thread, frame)
+                    .into_iter()
+                    .map(|(name, value)| self.variable(ecx, name, value))
+                    .collect(),
+            Scope::Value(op) => {
+                let op = op.clone();
+                self.children(ecx, &op)
+            }
+        };
+        Ok(object([("variables", variables.into())]))
+    }
+
+    fn evaluate(&mut self, ecx: &MiriInterpCx<'_, 'tcx>, args: &Value) -> Result<Value, String> {
+        let expression = args.get("expression").as_str().unwrap_or_default().trim();
+        let (thread, frame) = self.frame(args)?;
+        let (name, value) = locals(ecx, thread, frame)
+            .into_iter()
+            .rev()
+            .find(|(name, _)| name == expression)
+            .ok_or_else(|| format!("no variable `{expression}` in scope"))?;
+        let variable = self.variable(ecx, name, value);
+        Ok(object([
+            ("result", variable.get("value").clone()),
+            ("type", variable.get("type").clone()),
+            ("variablesReference", variable.get("variablesReference").clone()),
+        ]))
+    }
+
+    /// Describes a value for the client, and hands out a reference to its children if it has any.
+    fn variable(
+        &mut self,
+        ecx: &MiriInterpCx<'_, 'tcx>,
+        name: String,
+        value: Result<OpTy<'tcx, Provenance>, String>,
+    ) -> Value {
+        let mut variable = object([("name", name.into())]);
+        let op = match value {
+            Ok(op) => op,
+            Err(err) => {
+                variable.insert("value", err.into());
+                variable.insert("variablesReference", 0usize.into());
+                return variable;
+            }
+        };
+        let ty = op.layout.ty;
+        let value = match peek_immediate(ecx, &op) {
+            Ok(Some(imm)) => imm.to_string(),
+            Ok(None) => ty.to_string(),
+            Err(err) => err,
+        };
+        variable.insert("value", value.into());
+        variable.insert("type", ty.to_string().into());
+        let has_children = ty.builtin_deref(true).is_some()
+            || (!ty.is_enum() && op.layout.is_sized() && op.layout.fields.count() > 0);
+        let reference = if has_children {
+            self.scopes.push(Scope::Value(op));
+            self.scopes.len()
+        } else {
+            0
+        };
+        variable.insert("variablesReference", reference.into());
+        variable
+    }
+
+    fn children(
+        &mut self,
+        ecx: &MiriInterpCx<'_, 'tcx>,
+        op: &OpTy<'tcx, Provenance>,
+    ) -> Vec<Value> {
+        let ty = op.layout.ty;
+        if ty.builtin_deref(true).is_some() {
+            let scalar = match peek_immediate(ecx, op).ok().flatten().map(|imm| *imm) {
+                Some(Immediate::Scalar(ptr) | Immediate::ScalarPair(ptr, _)) => ptr,
+                _ => return Vec::new(),
+            };
+            let Ok(ptr) = scalar.to_pointer(ecx) else {
+                return Vec::new();
+            };
+            let pointee = peek_immediate(ecx, op)
+                .ok()
+                .flatten()
+                .and_then(|imm| ecx.ref_to_mplace(&imm).ok())
+                .ok_or_else(|| "<unavailable>".to_owned())
+                .and_then(|mplace| {
+                    if mplace.layout.is_sized() {
+                        Ok(mplace.into())
+                    } else {
+                        Err(format!("<unsized {}>", mplace.layout.ty))
+                    }
+                });
+            let borrow_state =
+                ecx.describe_borrow_state(ptr).unwrap_or_else(|_| "<unavailable>".to_owned());
+            return vec![
+                self.variable(ecx, "*".to_owned(), pointee),
+                object([
+                    ("name", "borrow state".into()),
+                    ("value", borrow_state.into()),
+                    ("variablesReference", 0usize.into()),
+                ]),
+            ];
+        }
+        (0..op.layout.fields.count().min(MAX_CHILDREN))
+            .map(|i| {
+                let field = ecx.project_field(op, i).map_err(|_| "<unavailable>".to_owned());
+                self.variable(ecx, field_name(ty, i), field)
+            })
+            .collect()
+    }
+}
+
+/// Whether the breakpoint file `breakpoint` is the source file `file`. The client names files by
+/// their absolute path, while rustc often uses a path relative to the working directory.
+fn same_file(breakpoint: &Path, file: &Path) -> bool {
+    breakpoint.ends_with(file) || file.ends_with(breakpoint)
+}
+
+fn source_path(name: &FileName) -> Option<PathBuf> {
+    match name {
+        FileName::Real(name) => Some(name.local_path_if_available().to_owned()),
+        _ => None,
+    }
+}
+
+/// The source position that `frame` (at the given stack depth) is about to execute.
+fn position<'tcx>(
+    ecx: &MiriInterpCx<'_, 'tcx>,
+    frame: &Frame<'_, 'tcx, Provenance, FrameExtra<'tcx>>,
+    depth: usize,
+) -> Option<Position> {
+    let span = frame.current_span().source_callsite();
+    if span.is_dummy() {
+        return None;
+    }
+    let loc = ecx.tcx.sess.source_map().lookup_char_pos(span.lo());
+    Some(Position { file: source_path(&loc.file.name)?, line: loc.line, depth })
+}
+
+/// The id of a thread for the client. Some clients treat a thread id of 0 as missing.
+fn client_thread_id(thread: ThreadId) -> i64 {
+    i64::from(thread.to_u32()) + 1
+}
+
+/// The live thread with the `threadId` given in `args`.
+fn thread_id(ecx: &MiriInterpCx<'_, '_>, args: &Value) -> Result<ThreadId, String> {
+    let id = args.get("threadId").as_i64();
+    ecx.machine
+        .threads
+        .live_threads()
+        .map(|(thread, _)| thread)
+        .find(|&thread| Some(client_thread_id(thread)) == id)
+        .ok_or_else(|| "unknown thread".to_owned())
+}
+
+fn field_name(ty: Ty<'_>, index: usize) -> String {
+    match ty.kind() {
+        ty::Adt(adt, _) =>
+            adt.non_enum_variant()
+                .fields
+                .iter()
+                .nth(index)
+                .map_or_else(|| index.to_string(), |field| field.name.to_string()),
+        ty::Array(..) => format!("[{index}]"),
+        _ => index.to_string(),
+    }
+}
+
+/// The user variables that are in scope in a stack frame, with their values.
+fn locals<'tcx>(
+    ecx: &MiriInterpCx<'_, 'tcx>,
+    thread: ThreadId,
+    frame: usize,
+) -> Vec<(String, Result<OpTy<'tcx, Provenance>, String>)> {
+    let frame = &ecx.machine.threads.thread_stack(thread)[frame];
+    let current_scope = frame.current_source_info().map(|source_info| source_info.scope);
+    frame
+        .body
+        .var_debug_info
+        .iter()
+        .filter(|var| var.composite.is_none())
+        .filter(|var| {
+            // Without a current statement, we do not know which variables are in scope.
+            let Some(mut scope) = current_scope else { return true };
+            loop {
+                if scope == var.source_info.scope {
+                    return true;
+                }
+                match frame.body.source_scopes[scope].parent_scope {
+                    Some(parent) => scope = parent,
+                    None => return false,
+                }
+            }
+        })
+        .map(|var| {
+            let value = match &var.value {
+                mir::VarDebugInfoContents::Place(place) => debuginfo_place(ecx, frame, *place),
+                mir::VarDebugInfoContents::Const(constant) => Err(format!("{constant}")),
+            };
+            (var.name.to_string(), value)
+        })
+        .collect()
+}
+
+/// Evaluates the place of a user variable. Debuginfo places only consist of field projections and
+/// dereferences.
+fn debuginfo_place<'tcx>(
+    ecx: &MiriInterpCx<'_, 'tcx>,
+    frame: &Frame<'_, 'tcx, Provenance, FrameExtra<'tcx>>,
+    place: mir::Place<'tcx>,
+) -> Result<OpTy<'tcx, Provenance>, String> {
+    let unavailable = |_| "<unavailable>".to_owned();
+    let mut op = ecx.local_to_op(frame, place.local, None).map_err(unavailable)?;
+    for elem in place.projection {
+        op = match elem {
+            mir::ProjectionElem::Field(field, _) =>
+                ecx.project_field(&op, field.as_usize()).map_err(unavailable)?,
+            mir::ProjectionElem::Deref => {
+                let imm = peek_immediate(ecx, &op)?
+                    .filter(|imm| !matches!(**imm, Immediate::Uninit))
+                    .ok_or_else(|| "<unavailable>".to_owned())?;
+                ecx.ref_to_mplace(&imm).map_err(unavailable)?.into()
+            }
+            _ => return Err("<unavailable>".to_owned()),
+        };
+    }
+    Ok(op)
+}
+
+/// Reads `op` if it is a scalar or a pair of scalars. Unlike the interpreter's own reads, this
+/// does not trigger the borrow tracker or the data race detector, so it can be used at any time.
+fn peek_immediate<'tcx>(
+    ecx: &MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+) -> Result<Option<ImmTy<'tcx, Provenance>>, String> {
+    let mplace = match op.as_mplace_or_imm() {
+        Either::Left(mplace) => mplace,
+        Either::Right(imm) => return Ok(Some(imm)),
+    };
+    let read = |offset: Size, scalar: rustc_target::abi::Scalar| {
+        let (alloc_id, base, _) = ecx
+            .ptr_try_get_alloc_id(mplace.ptr())
+            .map_err(|addr| format!("<dangling pointer {addr:#x}>"))?;
+        let (_kind, alloc) = ecx
+            .memory
+            .alloc_map()
+            .get(alloc_id)
+            .ok_or_else(|| format!("<{alloc_id:?} is not available>"))?;
+        let range = alloc_range(base + offset, scalar.size(ecx));
+        if range.end() > alloc.size() {
+            return Err(format!("<out of bounds of {alloc_id:?}>"));
+        }
+        let read_provenance = matches!(scalar.primitive(), Primitive::Pointer(_));
+        alloc.read_scalar(ecx, range, read_provenance).map_err(|_| "<uninit>".to_owned())
+    };
+    let imm = match mplace.layout.abi {
+        Abi::Scalar(scalar) => Immediate::Scalar(read(Size::ZERO, scalar)?),
+        Abi::ScalarPair(a, b) => {
+            let b_offset = a.size(ecx).align_to(b.align(ecx).abi);
+            Immediate::ScalarPair(read(Size::ZERO, a)?, read(b_offset, b)?)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(ImmTy::from_immediate(imm, mplace.layout)))
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    /// Waits for a debugger to connect, and lets it set up its breakpoints.
+    fn attach_debugger(&mut self, endpoint: &DebuggerEndpoint) -> io::Result<()> {
+        let this = self.eval_context_mut();
+        let mut debugger = Debugger::connect(endpoint)?;
+        if !this.machine.mute_stdout_stderr {
+            if let Some(stdout) = debugger.output("stdout") {
+                this.machine.file_handler.handles.insert(1, Box::new(stdout));
+            }
+            if let Some(stderr) = debugger.output("stderr") {
+                this.machine.file_handler.handles.insert(2, Box::new(stderr));
+            }
+        }
+        debugger.configure(this);
+        this.machine.debugger = Some(RefCell::new(debugger));
+        Ok(())
+    }
+
+    /// Called before the active thread executes its next statement: stops the program if the
+    /// debugger wants it to.
+    fn debugger_before_step(&self) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+        let mut debugger = this.machine.debugger.as_ref().unwrap().borrow_mut();
+        // Handle the requests that came in while the program was running.
+        while let Ok(request) = debugger.requests.try_recv() {
+            if debugger.handle(this, &request) == Control::Terminate {
+                throw_machine_stop!(TerminationInfo::Abort(
+                    "the debugger terminated the program".to_owned()
+                ));
+            }
+        }
+        if let Some(reason) = debugger.stop_reason(this) {
+            if !debugger.stop(this, reason, None) {
+                throw_machine_stop!(TerminationInfo::Abort(
+                    "the debugger terminated the program".to_owned()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A buffer that can be written to by a `Client`, and read from by the test.
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn messages() {
+        let buffer = Buffer::default();
+        let mut client = Client { out: Box::new(buffer.clone()), seq: 1 };
+        let request = Value::parse(r#"{"seq":3,"type":"request","command":"threads"}"#).unwrap();
+        client.respond(&request, Err("nope".to_owned()));
+        client.event("terminated", Value::Null);
+        let written = buffer.0.borrow();
+        let mut input = &written[..];
+        let response = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(response.get("seq").as_i64(), Some(1));
+        assert_eq!(response.get("request_seq").as_i64(), Some(3));
+        assert_eq!(response.get("success").as_bool(), Some(false));
+        assert_eq!(response.get("command").as_str(), Some("threads"));
+        assert_eq!(response.get("message").as_str(), Some("nope"));
+        let event = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(event.get("seq").as_i64(), Some(2));
+        assert_eq!(event.get("event").as_str(), Some("terminated"));
+        assert_eq!(event.get("body"), &Value::Null);
+        assert!(read_message(&mut input).unwrap().is_none());
+        assert!(read_message(&mut &b"Content-Type: x\r\n\r\n{}"[..]).is_err());
+    }
+
+    #[test]
+    fn breakpoint_files() {
+        assert!(same_file(Path::new("/home/user/crate/src/main.rs"), Path::new("src/main.rs")));
+        assert!(same_file(Path::new("/crate/src/main.rs"), Path::new("/crate/src/main.rs")));
+        assert!(!same_file(Path::new("/crate/src/main.rs"), Path::new("src/lib.rs")));
+        assert!(!same_file(Path::new("/crate/xsrc/main.rs"), Path::new("src/main.rs")));
+    }
+}
@@ -370,16 +370,10 @@ pub fn report_error<'tcx, 'mir>(
     }
 
     msg.insert(0, ecx.format_error(e));
+    let title =
+        if let Some(title) = title { format!("{title}: {}", msg[0]) } else { msg[0].clone() };
 
-    report_msg(
-        DiagLevel::Error,
-        if let Some(title) = title { format!("{title}: {}", msg[0]) } else { msg[0].clone() },
-        msg,
-        vec![],
-        helps,
-        &stacktrace,
-        &ecx.machine,
-    );
+    report_msg(DiagLevel::Error, title.clone(), msg, vec![], helps, &stacktrace, &ecx.machine);
 
     // Include a note like `std` does when we omit frames from a backtrace
     if was_pruned {
@@ -390,6 +384,11 @@ pub fn report_error<'tcx, 'mir>(
 
     eprint!("{extra}"); // newlines are already in the string
 
+    // Let the debugger inspect the state that led to the error.
+    if let Some(debugger) = &ecx.machine.debugger {
+        debugger.borrow_mut().stop_on_error(ecx, &title);
+    }
+
     // Debug-dump all locals.
     for (i, frame) in ecx.active_thread_stack().iter().enumerate() {
         trace!("-------------------");
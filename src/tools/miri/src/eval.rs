@@ -1,5 +1,6 @@
 //! Main evaluator loop and setting up the initial stack frame.
 
+use std::cell::RefCell;
 use std::ffi::{OsStr, OsString};
 use std::iter;
 use std::panic::{self, AssertUnwindSafe};
@@ -11,6 +12,8 @@
 use rustc_middle::ty::Ty;
 
 use crate::borrow_tracker::RetagFields;
+use crate::concurrency::exploration::{Exploration, OutputStream};
+use crate::coverage::Coverage;
 use crate::diagnostics::report_leaks;
 use rustc_data_structures::fx::FxHashSet;
 use rustc_hir::def::Namespace;
@@ -138,6 +141,9 @@ pub struct MiriConfig {
     pub mute_stdout_stderr: bool,
     /// The probability of the active thread being preempted at the end of each basic block.
     pub preemption_rate: f64,
+    /// If `Some`, systematically explore the schedules with at most this many preemptions,
+    /// instead of picking a single one.
+    pub explore_schedules: Option<u32>,
     /// Report the current instruction being executed every N basic blocks.
     pub report_progress: Option<u32>,
     /// Whether Stacked Borrows and Tree Borrows retagging should recurse into fields of datatypes.
@@ -153,6 +159,18 @@ pub struct MiriConfig {
     pub page_size: Option<u64>,
     /// Whether to collect a backtrace when each allocation is created, just in case it leaks.
     pub collect_leak_backtraces: bool,
+    /// Whether to emulate an in-memory file system when isolation is enabled.
+    pub virtual_fs: bool,
+    /// The copy of a host directory to start the virtual file system with.
+    pub virtual_fs_snapshot: Option<VfsSnapshot>,
+    /// The file to record the non-deterministic choices of the execution to.
+    pub record: Option<PathBuf>,
+    /// The recorded choices to replay.
+    pub replay: Option<Recording>,
+    /// If `Some`, wait for a Debug Adapter Protocol client to connect here before running.
+    pub debugger: Option<DebuggerEndpoint>,
+    /// If `Some`, write an lcov coverage file and a per-function profile to this directory.
+    pub coverage_out: Option<PathBuf>,
 }
 
 impl Default for MiriConfig {
@@ -182,6 +200,7 @@ fn default() -> MiriConfig {
             provenance_mode: ProvenanceMode::Default,
             mute_stdout_stderr: false,
             preemption_rate: 0.01, // 1%
+            explore_schedules: None,
             report_progress: None,
             retag_fields: RetagFields::Yes,
             external_so_file: None,
@@ -189,6 +208,12 @@ fn default() -> MiriConfig {
             num_cpus: 1,
             page_size: None,
             collect_leak_backtraces: true,
+            virtual_fs: false,
+            virtual_fs_snapshot: None,
+            record: None,
+            replay: None,
+            debugger: None,
+            coverage_out: None,
         }
     }
 }
@@ -415,6 +440,10 @@ pub fn create_ecx<'mir, 'tcx: 'mir>(
 /// Evaluates the entry function specified by `entry_id`.
 /// Returns `Some(return_code)` if program executed completed.
 /// Returns `None` if an evaluation error occurred.
+///
+/// With `-Zmiri-explore-schedules`, the program is executed repeatedly until either all schedules
+/// within the preemption bound have been explored, or one of them fails; in the latter case, the
+/// schedule that led to the failure is reported.
 #[allow(clippy::needless_lifetimes)]
 pub fn eval_entry<'tcx>(
     tcx: TyCtxt<'tcx>,
@@ -422,10 +451,79 @@ pub fn eval_entry<'tcx>(
     entry_type: EntryFnType,
     config: MiriConfig,
 ) -> Option<i64> {
-    // Copy setting before we move `config`.
-    let ignore_leaks = config.ignore_leaks;
+    let mut coverage = config.coverage_out.is_some().then(Coverage::default);
+    let res = match config.explore_schedules {
+        None => eval_entry_once(tcx, entry_id, entry_type, &config, &mut None, &mut coverage),
+        Some(bound) => explore_schedules(tcx, entry_id, entry_type, &config, bound, &mut coverage),
+    };
+    if let (Some(coverage), Some(dir)) = (coverage, &config.coverage_out) {
+        if let Err(err) = coverage.write(tcx, dir) {
+            tcx.sess.err(format!("failed to write coverage to `{}`: {err}", dir.display()));
+        }
+    }
+    res
+}
 
-    let mut ecx = match create_ecx(tcx, entry_id, entry_type, &config) {
+/// Executes the entry function once for each schedule within the preemption bound, stopping at
+/// the first failure. The output of the program is only shown for the failing execution, or for
+/// the last one, whose exit code is then returned.
+fn explore_schedules<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+    bound: u32,
+    coverage: &mut Option<Coverage<'tcx>>,
+) -> Option<i64> {
+    let mut exploration = Some(Exploration::new(bound));
+    loop {
+        let res = eval_entry_once(tcx, entry_id, entry_type, config, &mut exploration, coverage);
+        let exploration = exploration.as_mut().unwrap();
+        // Errors reported by the interpreter (including aborts and Undefined Behavior) make the
+        // schedule fail, and so do panics that make the program exit unsuccessfully. Other exit
+        // codes are just one of the outcomes of the program, and we keep exploring.
+        let failed = match res {
+            None => true,
+            Some(code) => code != 0 && exploration.panicked(),
+        };
+        if failed {
+            exploration.emit_output();
+            tcx.sess.note_without_error(format!(
+                "the failure occurred in execution {} while exploring schedules with at most {} \
+                preemptions",
+                exploration.executions(),
+                exploration.preemption_bound(),
+            ));
+            tcx.sess.note_without_error(format!(
+                "the failing schedule was: {}",
+                exploration.describe_schedule()
+            ));
+            return res;
+        }
+        if !exploration.next_execution() {
+            exploration.emit_output();
+            tcx.sess.note_without_error(format!(
+                "explored {} schedules with at most {} preemptions",
+                exploration.executions(),
+                exploration.preemption_bound(),
+            ));
+            return res;
+        }
+    }
+}
+
+/// Performs a single execution of the entry function. If `exploration` is `Some`, the scheduler
+/// follows it, and its updated state is handed back afterwards. The same goes for the `coverage`
+/// counts.
+fn eval_entry_once<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: EntryFnType,
+    config: &MiriConfig,
+    exploration: &mut Option<Exploration>,
+    coverage: &mut Option<Coverage<'tcx>>,
+) -> Option<i64> {
+    let mut ecx = match create_ecx(tcx, entry_id, entry_type, config) {
         Ok(v) => v,
         Err(err) => {
             let (kind, backtrace) = err.into_parts();
@@ -433,6 +531,29 @@ pub fn eval_entry<'tcx>(
             panic!("Miri initialization error: {kind:?}")
         }
     };
+    if let Some(exploration) = exploration.take() {
+        if !config.mute_stdout_stderr {
+            // Only the output of the failing or the last execution is shown, see
+            // `explore_schedules`.
+            for (fd, stream) in [(1, OutputStream::Stdout), (2, OutputStream::Stderr)] {
+                ecx.machine.file_handler.handles.insert(fd, Box::new(exploration.capture(stream)));
+            }
+        }
+        ecx.machine.threads.set_exploration(exploration);
+    }
+    if let Some(endpoint) = &config.debugger {
+        if let Err(err) = ecx.attach_debugger(endpoint) {
+            tcx.sess.err(format!("failed to start the debugger on `{endpoint}`: {err}"));
+            return None;
+        }
+    }
+    if let Some(mut coverage) = coverage.take() {
+        // `create_ecx` already pushed the frame of the entry function.
+        for frame in ecx.active_thread_stack() {
+            coverage.record_call(frame.instance);
+        }
+        ecx.machine.coverage = Some(RefCell::new(coverage));
+    }
 
     // Perform the main execution.
     let res: thread::Result<InterpResult<'_, !>> =
@@ -446,6 +567,23 @@ pub fn eval_entry<'tcx>(
         // `Ok` can never happen
         Ok(never) => match never {},
     };
+    *exploration = ecx.machine.threads.take_exploration();
+    *coverage = ecx.machine.coverage.take().map(RefCell::into_inner);
+
+    // Store the non-deterministic choices we made, and check that we made the replayed ones.
+    let choice_log = ecx.machine.choice_log.take();
+    if config.replay.is_some() && choice_log.diverged() {
+        tcx.sess.warn(
+            "the execution diverged from the replayed recording; \
+            some choices were made using the random seed instead",
+        );
+    }
+    if let Some(path) = &config.record {
+        let recording = choice_log.into_recording(config.seed.unwrap_or(0)).unwrap();
+        if let Err(err) = recording.write(path) {
+            tcx.sess.err(format!("failed to write recording to `{}`: {err}", path.display()));
+        }
+    }
 
     // Machine cleanup. Only do this if all threads have terminated; threads that are still running
     // might cause Stacked Borrows errors (https://github.com/rust-lang/miri/issues/2396).
@@ -458,8 +596,12 @@ pub fn eval_entry<'tcx>(
     }
 
     // Process the result.
-    let (return_code, leak_check) = report_error(&ecx, res)?;
-    if leak_check && !ignore_leaks {
+    let res = report_error(&ecx, res);
+    if let Some(debugger) = &ecx.machine.debugger {
+        debugger.borrow_mut().exited(&ecx, res.map_or(1, |(return_code, _)| return_code));
+    }
+    let (return_code, leak_check) = res?;
+    if leak_check && !config.ignore_leaks {
         // Check for thread leaks.
         if !ecx.have_all_terminated() {
             tcx.sess.err("the main thread terminated without waiting for all remaining threads");
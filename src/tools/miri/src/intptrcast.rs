@@ -135,7 +135,8 @@ fn addr_from_alloc_id(&self, alloc_id: AllocId) -> InterpResult<'tcx, u64> {
                 let slack = {
                     let mut rng = ecx.machine.rng.borrow_mut();
                     // This means that `(global_state.next_base_addr + slack) % 16` is uniformly distributed.
-                    rng.gen_range(0..16)
+                    let slack = rng.gen_range(0..16);
+                    ecx.machine.choice_log.borrow_mut().address_slack(16, slack)
                 };
                 // From next_base_addr + slack, round up to adjust for alignment.
                 let base_addr = global_state
@@ -70,6 +70,8 @@
 mod borrow_tracker;
 mod clock;
 mod concurrency;
+mod coverage;
+mod debugger;
 mod diagnostics;
 mod eval;
 mod helpers;
@@ -78,6 +80,7 @@
 mod mono_hash_map;
 mod operator;
 mod range_map;
+mod replay;
 mod shims;
 mod tag_gc;
 
@@ -111,6 +114,7 @@
     sync::{CondvarId, EvalContextExt as _, MutexId, RwLockId, SyncId},
     thread::{EvalContextExt as _, StackEmptyCallback, ThreadId, ThreadManager, Time},
 };
+pub use crate::debugger::{DebuggerEndpoint, EvalContextExt as _};
 pub use crate::diagnostics::{
     report_error, EvalContextExt as _, NonHaltingDiagnostic, TerminationInfo,
 };
@@ -126,6 +130,8 @@
 pub use crate::mono_hash_map::MonoHashMap;
 pub use crate::operator::EvalContextExt as _;
 pub use crate::range_map::RangeMap;
+pub use crate::replay::Recording;
+pub use crate::shims::unix::VfsSnapshot;
 pub use crate::tag_gc::{EvalContextExt as _, VisitTags};
 
 /// Insert rustc arguments at the beginning of the argument list that Miri wants to be
@@ -30,7 +30,10 @@
 
 use crate::{
     concurrency::{data_race, weak_memory},
-    shims::unix::FileHandler,
+    coverage::Coverage,
+    debugger::Debugger,
+    replay::ChoiceLog,
+    shims::unix::{signal::EvalContextExt as _, FileHandler},
     *,
 };
 
@@ -64,25 +67,43 @@ pub struct FrameExtra<'tcx> {
     /// optimization.
     /// This is used by `MiriMachine::current_span` and `MiriMachine::caller_span`
     pub is_user_relevant: bool,
+
+    /// If this is Some(), then this is the frame of a signal handler that was called by Miri.
+    /// When this frame is popped, we restore the signal mask of the thread.
+    pub signal_handler: Option<shims::unix::SignalHandlerFrame>,
 }
 
 impl<'tcx> std::fmt::Debug for FrameExtra<'tcx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Omitting `timing`, it does not support `Debug`.
-        let FrameExtra { borrow_tracker, catch_unwind, timing: _, is_user_relevant: _ } = self;
+        let FrameExtra {
+            borrow_tracker,
+            catch_unwind,
+            timing: _,
+            is_user_relevant: _,
+            signal_handler,
+        } = self;
         f.debug_struct("FrameData")
             .field("borrow_tracker", borrow_tracker)
             .field("catch_unwind", catch_unwind)
+            .field("signal_handler", signal_handler)
             .finish()
     }
 }
 
 impl VisitTags for FrameExtra<'_> {
     fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
-        let FrameExtra { catch_unwind, borrow_tracker, timing: _, is_user_relevant: _ } = self;
+        let FrameExtra {
+            catch_unwind,
+            borrow_tracker,
+            timing: _,
+            is_user_relevant: _,
+            signal_handler,
+        } = self;
 
         catch_unwind.visit_tags(visit);
         borrow_tracker.visit_tags(visit);
+        signal_handler.visit_tags(visit);
     }
 }
 
@@ -440,6 +461,13 @@ pub struct MiriMachine<'mir, 'tcx> {
     pub(crate) file_handler: shims::unix::FileHandler,
     /// The table of directory descriptors.
     pub(crate) dir_handler: shims::unix::DirHandler,
+    /// The in-memory file system that replaces the host's when isolation is enabled, if
+    /// `-Zmiri-virtual-fs` is set.
+    pub(crate) vfs: Option<shims::unix::Vfs>,
+    /// The emulated loopback network, and the threads blocked on its sockets.
+    pub(crate) socket_handler: shims::unix::SocketHandler<'tcx>,
+    /// The registered signal actions, and the signal masks and pending signals of all threads.
+    pub(crate) signals: shims::unix::SignalState,
 
     /// This machine's monotone clock.
     pub(crate) clock: Clock,
@@ -482,6 +510,9 @@ pub struct MiriMachine<'mir, 'tcx> {
     /// Needs to be queried by ptr_to_int, hence needs interior mutability.
     pub(crate) rng: RefCell<StdRng>,
 
+    /// Records and replays the choices that are otherwise made using `rng`.
+    pub(crate) choice_log: RefCell<ChoiceLog>,
+
     /// The allocation IDs to report when they are being allocated
     /// (helps for debugging memory leaks and use after free bugs).
     tracked_alloc_ids: FxHashSet<AllocId>,
@@ -531,6 +562,12 @@ pub struct MiriMachine<'mir, 'tcx> {
     /// The spans we will use to report where an allocation was created and deallocated in
     /// diagnostics.
     pub(crate) allocation_spans: RefCell<FxHashMap<AllocId, (Span, Option<Span>)>>,
+
+    /// The debugger the program is running under, if any.
+    pub(crate) debugger: Option<RefCell<Debugger<'tcx>>>,
+
+    /// If `-Zmiri-coverage` is set, how often each function and basic block was executed.
+    pub(crate) coverage: Option<RefCell<Coverage<'tcx>>>,
 }
 
 impl<'mir, 'tcx> MiriMachine<'mir, 'tcx> {
@@ -557,6 +594,8 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             measureme::Profiler::new(path).expect("Couldn't create `measureme` profiler")
         });
         let rng = StdRng::seed_from_u64(config.seed.unwrap_or(0));
+        let vfs = (config.virtual_fs && config.isolated_op != IsolatedOp::Allow)
+            .then(|| shims::unix::Vfs::new(config.virtual_fs_snapshot.as_ref()));
         let borrow_tracker = config.borrow_tracker.map(|bt| bt.instantiate_global_state(config));
         let data_race = config.data_race_detector.then(|| data_race::GlobalState::new(config));
         // Determine page size, stack address, and stack size.
@@ -602,6 +641,9 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             enforce_abi: config.check_abi,
             file_handler: FileHandler::new(config.mute_stdout_stderr),
             dir_handler: Default::default(),
+            vfs,
+            socket_handler: Default::default(),
+            signals: Default::default(),
             layouts,
             threads: ThreadManager::default(),
             static_roots: Vec::new(),
@@ -613,12 +655,21 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             local_crates,
             extern_statics: FxHashMap::default(),
             rng: RefCell::new(rng),
+            choice_log: RefCell::new(ChoiceLog::new(
+                config.record.is_some(),
+                config.replay.as_ref(),
+            )),
             tracked_alloc_ids: config.tracked_alloc_ids.clone(),
             check_alignment: config.check_alignment,
             cmpxchg_weak_failure_rate: config.cmpxchg_weak_failure_rate,
             mute_stdout_stderr: config.mute_stdout_stderr,
             weak_memory: config.weak_memory_emulation,
-            preemption_rate: config.preemption_rate,
+            // Exploring schedules takes over all preemption decisions.
+            preemption_rate: if config.explore_schedules.is_some() {
+                0.0
+            } else {
+                config.preemption_rate
+            },
             report_progress: config.report_progress,
             basic_block_count: 0,
             clock: Clock::new(config.isolated_op == IsolatedOp::Allow),
@@ -656,6 +707,10 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx, TyCtxt<'tcx>>)
             stack_size,
             collect_leak_backtraces: config.collect_leak_backtraces,
             allocation_spans: RefCell::new(FxHashMap::default()),
+            // The debugger is attached once the interpreter is fully set up.
+            debugger: None,
+            // The counts are handed from one execution to the next by `eval_entry`.
+            coverage: None,
         }
     }
 
@@ -806,6 +861,9 @@ fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
             cmd_line,
             extern_statics,
             dir_handler,
+            vfs: _,
+            socket_handler,
+            signals,
             borrow_tracker,
             data_race,
             intptrcast,
@@ -824,6 +882,7 @@ fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
             backtrace_style: _,
             local_crates: _,
             rng: _,
+            choice_log: _,
             tracked_alloc_ids: _,
             check_alignment: _,
             cmpxchg_weak_failure_rate: _,
@@ -841,6 +900,8 @@ fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
             stack_size: _,
             collect_leak_backtraces: _,
             allocation_spans: _,
+            debugger: _,
+            coverage: _,
         } = self;
 
         threads.visit_tags(visit);
@@ -848,6 +909,8 @@ fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
         env_vars.visit_tags(visit);
         dir_handler.visit_tags(visit);
         file_handler.visit_tags(visit);
+        socket_handler.visit_tags(visit);
+        signals.visit_tags(visit);
         data_race.visit_tags(visit);
         borrow_tracker.visit_tags(visit);
         intptrcast.visit_tags(visit);
@@ -1323,6 +1386,10 @@ fn init_frame_extra(
         ecx: &mut InterpCx<'mir, 'tcx, Self>,
         frame: Frame<'mir, 'tcx, Provenance>,
     ) -> InterpResult<'tcx, Frame<'mir, 'tcx, Provenance, FrameExtra<'tcx>>> {
+        if let Some(coverage) = &ecx.machine.coverage {
+            coverage.borrow_mut().record_call(frame.instance);
+        }
+
         // Start recording our event before doing anything else
         let timing = if let Some(profiler) = ecx.machine.profiler.as_ref() {
             let fn_name = frame.instance.to_string();
@@ -1345,6 +1412,7 @@ fn init_frame_extra(
             catch_unwind: None,
             timing,
             is_user_relevant: ecx.machine.is_user_relevant(&frame),
+            signal_handler: None,
         };
 
         Ok(frame.with_extra(extra))
@@ -1365,6 +1433,9 @@ fn stack_mut<'a>(
     fn before_terminator(ecx: &mut InterpCx<'mir, 'tcx, Self>) -> InterpResult<'tcx> {
         ecx.machine.basic_block_count += 1u64; // a u64 that is only incremented by 1 will "never" overflow
         ecx.machine.since_gc += 1;
+        if let Some(coverage) = &ecx.machine.coverage {
+            coverage.borrow_mut().record_block(ecx.frame());
+        }
         // Possibly report our progress.
         if let Some(report_progress) = ecx.machine.report_progress {
             if ecx.machine.basic_block_count % u64::from(report_progress) == 0 {
@@ -1454,6 +1525,9 @@ fn after_stack_pop(
             // user-relevant frame and restore that here.)
             ecx.active_thread_mut().recompute_top_user_relevant_frame();
         }
+        if let Some(signal_handler) = frame.extra.signal_handler.take() {
+            ecx.signal_handler_returned(signal_handler)?;
+        }
         let timing = frame.extra.timing.take();
         let res = ecx.handle_stack_pop_unwind(frame.extra, unwinding);
         if let Some(profiler) = ecx.machine.profiler.as_ref() {
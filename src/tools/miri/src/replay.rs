@@ -0,0 +1,329 @@
+//! Recording and replaying the non-deterministic choices of an execution.
+//!
+//! Miri derives all its non-deterministic choices from the random seed, so even a tiny change to
+//! the program (like adding a `println!` to investigate a data race) can change every later
+//! choice, and the failure disappears. With `-Zmiri-record=<file>`, the choices that matter for
+//! reproducing concurrency bugs are written to a file: where threads were preempted, which store
+//! each weak-memory load read from, how much slack was put before each allocation's base address,
+//! and which `compare_exchange_weak` operations failed spuriously. `-Zmiri-replay=<file>` makes the
+//! same choices again.
+//!
+//! Each kind of choice is replayed in the order it was recorded, independently of the other kinds,
+//! so that a change that only adds choices of one kind leaves the others in place. A preemption is
+//! identified by the thread and by how many basic blocks that thread executed since it was last
+//! scheduled, so a change that only makes a thread execute more basic blocks (like adding a
+//! `println!`) only affects the preemptions of that thread until it is next preempted.
+//!
+//! If a recorded choice cannot be applied (e.g. a load has fewer candidate stores than when it was
+//! recorded), the random seed is used instead and the replay is reported as diverged at the end of
+//! the execution.
+//! All other non-determinism (like the bytes returned by `getrandom`) still comes from the seed,
+//! which is therefore stored in the recording as well.
+//!
+//! The file format is plain text with one entry per line: first `seed <seed>`, followed by
+//! `preempt <thread> <basic block>`, `load <candidate>`, `slack <bytes>`, or
+//! `cmpxchg-weak <failed>`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A single non-deterministic choice.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Choice {
+    /// The given thread was preempted at the end of the given basic block, counting the blocks it
+    /// executed since it was last scheduled from 1.
+    Preemption { thread: u32, block: u64 },
+    /// A weak-memory load read from the store with the given index among the stores it could
+    /// have read from, counting from the latest one.
+    WeakMemoryLoad(u64),
+    /// The number of bytes left free before the base address of a new allocation.
+    AddressSlack(u64),
+    /// Whether a `compare_exchange_weak` that could have succeeded failed spuriously.
+    CompareExchangeWeak(bool),
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::Preemption { thread, block } => write!(f, "preempt {thread} {block}"),
+            Choice::WeakMemoryLoad(index) => write!(f, "load {index}"),
+            Choice::AddressSlack(slack) => write!(f, "slack {slack}"),
+            Choice::CompareExchangeWeak(failed) => write!(f, "cmpxchg-weak {failed}"),
+        }
+    }
+}
+
+/// The choices made by an execution, as stored in a recording file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// The seed for all other non-determinism.
+    pub seed: u64,
+    pub choices: Vec<Choice>,
+}
+
+impl Recording {
+    /// Reads a recording written by `-Zmiri-record`.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&contents)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|err| err.to_string())
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let seed = match lines.next().and_then(|(_, line)| line.trim().strip_prefix("seed ")) {
+            Some(seed) => seed.parse().map_err(|err| format!("invalid seed: {err}"))?,
+            None => return Err("the recording does not start with a `seed` line".to_owned()),
+        };
+        let mut choices = Vec::new();
+        for (index, line) in lines {
+            let invalid = || format!("invalid choice on line {}: `{line}`", index + 1);
+            let (kind, value) = line.trim().split_once(' ').ok_or_else(invalid)?;
+            let choice = match kind {
+                "preempt" => {
+                    let (thread, block) = value.split_once(' ').ok_or_else(invalid)?;
+                    Choice::Preemption {
+                        thread: thread.parse().map_err(|_| invalid())?,
+                        block: block.parse().map_err(|_| invalid())?,
+                    }
+                }
+                "load" => Choice::WeakMemoryLoad(value.parse().map_err(|_| invalid())?),
+                "slack" => Choice::AddressSlack(value.parse().map_err(|_| invalid())?),
+                "cmpxchg-weak" =>
+                    Choice::CompareExchangeWeak(value.parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            };
+            choices.push(choice);
+        }
+        Ok(Recording { seed, choices })
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        for choice in &self.choices {
+            writeln!(f, "{choice}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the choices of the current execution and/or replays those of a recording.
+///
+/// Every method takes the choice that was drawn from the random number generator, so that the
+/// generator advances exactly as it would without recording or replaying.
+#[derive(Debug, Default)]
+pub struct ChoiceLog {
+    /// The choices made so far, if we are recording.
+    recorded: Option<Vec<Choice>>,
+    /// Whether we are replaying a recording.
+    replaying: bool,
+    /// The thread that executed the last basic block, and how many blocks it executed since it was
+    /// last scheduled.
+    segment: Option<(u32, u64)>,
+    /// The recorded choices that are still to be replayed, per kind.
+    preemptions: VecDeque<(u32, u64)>,
+    loads: VecDeque<u64>,
+    slacks: VecDeque<u64>,
+    cmpxchgs: VecDeque<bool>,
+    /// Whether some recorded choice could not be replayed.
+    diverged: bool,
+}
+
+impl ChoiceLog {
+    pub fn new(record: bool, replay: Option<&Recording>) -> Self {
+        let mut log = ChoiceLog {
+            recorded: record.then(Vec::new),
+            replaying: replay.is_some(),
+            ..Default::default()
+        };
+        for &choice in replay.map(|recording| &recording.choices[..]).unwrap_or_default() {
+            match choice {
+                Choice::Preemption { thread, block } => log.preemptions.push_back((thread, block)),
+                Choice::WeakMemoryLoad(index) => log.loads.push_back(index),
+                Choice::AddressSlack(slack) => log.slacks.push_back(slack),
+                Choice::CompareExchangeWeak(failed) => log.cmpxchgs.push_back(failed),
+            }
+        }
+        log
+    }
+
+    fn record(&mut self, choice: Choice) {
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push(choice);
+        }
+    }
+
+    /// Whether to preempt `thread`, which is active, at the end of its current basic block.
+    pub fn preemption(&mut self, thread: u32, drawn: bool) -> bool {
+        let block = match &mut self.segment {
+            Some((active, blocks)) if *active == thread => {
+                *blocks = blocks.checked_add(1).unwrap();
+                *blocks
+            }
+            _ => {
+                self.segment = Some((thread, 1));
+                1
+            }
+        };
+        let preempt = if self.replaying {
+            let preempt = self.preemptions.front() == Some(&(thread, block));
+            if preempt {
+                self.preemptions.pop_front();
+            }
+            preempt
+        } else {
+            drawn
+        };
+        if preempt {
+            // Even if no other thread can run, the blocks are counted from here again.
+            self.segment = None;
+            self.record(Choice::Preemption { thread, block });
+        }
+        preempt
+    }
+
+    /// Which of the `candidates` stores a weak-memory load reads from.
+    pub fn weak_memory_load(&mut self, candidates: usize, drawn: usize) -> usize {
+        let index = if self.replaying {
+            match self.loads.pop_front().and_then(|index| usize::try_from(index).ok()) {
+                Some(index) if index < candidates => index,
+                _ => {
+                    self.diverged = true;
+                    drawn
+                }
+            }
+        } else {
+            drawn
+        };
+        self.record(Choice::WeakMemoryLoad(u64::try_from(index).unwrap()));
+        index
+    }
+
+    /// How many bytes (less than `bound`) to leave free before a new allocation.
+    pub fn address_slack(&mut self, bound: u64, drawn: u64) -> u64 {
+        let slack = if self.replaying {
+            match self.slacks.pop_front() {
+                Some(slack) if slack < bound => slack,
+                _ => {
+                    self.diverged = true;
+                    drawn
+                }
+            }
+        } else {
+            drawn
+        };
+        self.record(Choice::AddressSlack(slack));
+        slack
+    }
+
+    /// Whether a `compare_exchange_weak` that could succeed fails spuriously.
+    pub fn compare_exchange_weak_failure(&mut self, drawn: bool) -> bool {
+        let failed = if self.replaying {
+            self.cmpxchgs.pop_front().unwrap_or_else(|| {
+                self.diverged = true;
+                drawn
+            })
+        } else {
+            drawn
+        };
+        self.record(Choice::CompareExchangeWeak(failed));
+        failed
+    }
+
+    /// Whether the execution did not use exactly the replayed choices.
+    pub fn diverged(&self) -> bool {
+        self.diverged
+            || !self.preemptions.is_empty()
+            || !self.loads.is_empty()
+            || !self.slacks.is_empty()
+            || !self.cmpxchgs.is_empty()
+    }
+
+    /// The recording of this execution, if we were recording.
+    pub fn into_recording(self, seed: u64) -> Option<Recording> {
+        Some(Recording { seed, choices: self.recorded? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let recording = Recording {
+            seed: 42,
+            choices: vec![
+                Choice::AddressSlack(7),
+                Choice::Preemption { thread: 1, block: 1234 },
+                Choice::WeakMemoryLoad(1),
+                Choice::CompareExchangeWeak(true),
+            ],
+        };
+        assert_eq!(Recording::parse(&recording.to_string()), Ok(recording));
+        assert!(Recording::parse("load 1\n").is_err());
+        assert!(Recording::parse("seed 0\nstore 1\n").is_err());
+        assert!(Recording::parse("seed 0\npreempt 1\n").is_err());
+    }
+
+    #[test]
+    fn replay() {
+        let recording = Recording {
+            seed: 0,
+            choices: vec![
+                Choice::Preemption { thread: 0, block: 2 },
+                Choice::WeakMemoryLoad(1),
+                Choice::Preemption { thread: 1, block: 2 },
+                Choice::WeakMemoryLoad(4),
+            ],
+        };
+        let mut log = ChoiceLog::new(true, Some(&recording));
+        // Kinds are replayed independently of each other.
+        assert_eq!(log.weak_memory_load(2, 0), 1);
+        assert!(!log.preemption(0, true));
+        assert!(log.preemption(0, false));
+        // The blocks of thread 1 are counted from when it was scheduled.
+        assert!(!log.preemption(1, false));
+        assert!(log.preemption(1, false));
+        // A choice that is impossible now falls back to the drawn one.
+        assert_eq!(log.weak_memory_load(3, 2), 2);
+        assert!(log.diverged());
+        assert_eq!(
+            log.into_recording(0).unwrap().choices,
+            [
+                Choice::WeakMemoryLoad(1),
+                Choice::Preemption { thread: 0, block: 2 },
+                Choice::Preemption { thread: 1, block: 2 },
+                Choice::WeakMemoryLoad(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn preemptions_count_blocks_per_thread() {
+        let mut log = ChoiceLog::new(true, None);
+        assert!(!log.preemption(0, false));
+        assert!(!log.preemption(1, false));
+        assert!(!log.preemption(1, false));
+        assert!(log.preemption(1, true));
+        // Thread 1 was preempted, but kept running as no other thread could.
+        assert!(log.preemption(1, true));
+        assert!(!log.preemption(0, false));
+        assert!(log.preemption(0, true));
+        assert_eq!(
+            log.into_recording(0).unwrap().choices,
+            [
+                Choice::Preemption { thread: 1, block: 3 },
+                Choice::Preemption { thread: 1, block: 1 },
+                Choice::Preemption { thread: 0, block: 2 },
+            ]
+        );
+    }
+}
@@ -326,14 +326,20 @@ fn getcwd(
         let buf = this.read_pointer(buf_op)?;
         let size = this.read_target_usize(size_op)?;
 
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`getcwd`", reject_with)?;
-            this.set_last_error_from_io_error(ErrorKind::PermissionDenied)?;
-            return Ok(Pointer::null());
-        }
+        let cwd = if let Some(vfs) = &this.machine.vfs {
+            Ok(vfs.cwd().to_owned())
+        } else {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`getcwd`", reject_with)?;
+                this.set_last_error_from_io_error(ErrorKind::PermissionDenied)?;
+                return Ok(Pointer::null());
+            }
+
+            env::current_dir()
+        };
 
         // If we cannot get the current directory, we return null
-        match env::current_dir() {
+        match cwd {
             Ok(cwd) => {
                 if this.write_path_to_c_str(&cwd, buf, size)?.0 {
                     return Ok(buf);
@@ -382,14 +388,20 @@ fn chdir(&mut self, path_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32>
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`chdir`", reject_with)?;
-            this.set_last_error_from_io_error(ErrorKind::PermissionDenied)?;
+        let result = if let Some(vfs) = &mut this.machine.vfs {
+            vfs.set_cwd(&path)
+        } else {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`chdir`", reject_with)?;
+                this.set_last_error_from_io_error(ErrorKind::PermissionDenied)?;
 
-            return Ok(-1);
-        }
+                return Ok(-1);
+            }
 
-        match env::set_current_dir(path) {
+            env::set_current_dir(path)
+        };
+
+        match result {
             Ok(()) => Ok(0),
             Err(e) => {
                 this.set_last_error_from_io_error(e.kind())?;
@@ -22,6 +22,7 @@
 };
 
 use super::backtrace::EvalContextExt as _;
+use super::unix::signal::EvalContextExt as _;
 use crate::helpers::target_os_is_unix;
 use crate::*;
 
@@ -66,6 +67,9 @@ fn emulate_foreign_item(
         let this = self.eval_context_mut();
         let tcx = this.tcx.tcx;
 
+        // Signal handlers may only call async-signal-safe functions.
+        this.check_async_signal_safe(link_name)?;
+
         // First: functions that diverge.
         let ret = match ret {
             None =>
@@ -91,12 +95,13 @@ fn emulate_foreign_item(
                     }
                     #[rustfmt::skip]
                     | "exit"
+                    | "_exit"
                     | "ExitProcess"
                     => {
-                        let exp_abi = if link_name.as_str() == "exit" {
-                            Abi::C { unwind: false }
-                        } else {
+                        let exp_abi = if link_name.as_str() == "ExitProcess" {
                             Abi::System { unwind: false }
+                        } else {
+                            Abi::C { unwind: false }
                         };
                         let [code] = this.check_shim(abi, exp_abi, link_name, args)?;
                         // it's really u32 for ExitProcess, but we have to put it into the `Exit` variant anyway
@@ -64,6 +64,7 @@ fn handle_miri_start_panic(
         let payload = this.read_scalar(payload)?;
         let thread = this.active_thread_mut();
         thread.panic_payloads.push(payload);
+        this.machine.threads.record_panic();
 
         // Jump to the unwind block to begin unwinding.
         this.unwind_to_block(unwind)?;
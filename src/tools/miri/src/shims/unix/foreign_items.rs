@@ -12,6 +12,8 @@
 use shims::foreign_items::EmulateForeignItemResult;
 use shims::unix::fs::EvalContextExt as _;
 use shims::unix::mem::EvalContextExt as _;
+use shims::unix::signal::EvalContextExt as _;
+use shims::unix::socket::EvalContextExt as _;
 use shims::unix::sync::EvalContextExt as _;
 use shims::unix::thread::EvalContextExt as _;
 
@@ -105,8 +107,13 @@ fn emulate_foreign_item_inner(
                 let fd = this.read_scalar(fd)?.to_i32()?;
                 let buf = this.read_pointer(buf)?;
                 let count = this.read_target_usize(count)?;
-                let result = this.read(fd, buf, count)?;
-                this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+                if this.is_socket(fd) {
+                    // Reading from a socket may block, so this writes the result itself.
+                    this.read_socket(fd, buf, count, dest)?;
+                } else {
+                    let result = this.read(fd, buf, count)?;
+                    this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
+                }
             }
             "write" => {
                 let [fd, buf, n] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
@@ -114,7 +121,11 @@ fn emulate_foreign_item_inner(
                 let buf = this.read_pointer(buf)?;
                 let count = this.read_target_usize(n)?;
                 trace!("Called write({:?}, {:?}, {:?})", fd, buf, count);
-                let result = this.write(fd, buf, count)?;
+                let result = if this.is_socket(fd) {
+                    this.write_socket(fd, buf, count)?
+                } else {
+                    this.write(fd, buf, count)?
+                };
                 // Now, `result` is the value we return back to the program.
                 this.write_scalar(Scalar::from_target_isize(result, this), dest)?;
             }
@@ -491,6 +502,73 @@ fn emulate_foreign_item_inner(
                 this.write_scalar(Scalar::from_i32(result), dest)?;
             }
 
+            // Signal related shims
+            "signal" => {
+                let [sig, handler] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.signal(sig, handler)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigaction" => {
+                let [sig, act, oldact] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigaction(sig, act, oldact)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigemptyset" => {
+                let [set] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigemptyset(set)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigfillset" => {
+                let [set] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigfillset(set)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigaddset" => {
+                let [set, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigaddset(set, sig, /*add*/ true)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigdelset" => {
+                let [set, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigaddset(set, sig, /*add*/ false)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigismember" => {
+                let [set, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigismember(set, sig)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigprocmask" => {
+                let [how, set, oldset] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigprocmask(how, set, oldset)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "pthread_sigmask" => {
+                let [how, set, oldset] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pthread_sigmask(how, set, oldset)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "sigpending" => {
+                let [set] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sigpending(set)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "raise" => {
+                let [sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.raise(sig)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "kill" => {
+                let [pid, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.kill(pid, sig)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+            "pthread_kill" => {
+                let [thread, sig] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.pthread_kill(thread, sig)?;
+                this.write_scalar(Scalar::from_i32(result), dest)?;
+            }
+
             // Miscellaneous
             "isatty" => {
                 let [fd] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
@@ -574,14 +652,12 @@ fn emulate_foreign_item_inner(
                 this.write_null(dest)?;
             }
 
-            | "signal"
-            | "sigaltstack"
+            "sigaltstack"
             if this.frame_in_std() => {
                 let [_, _] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
                 this.write_null(dest)?;
             }
-            | "sigaction"
-            | "mprotect"
+            "mprotect"
             if this.frame_in_std() => {
                 let [_, _, _] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
                 this.write_null(dest)?;
@@ -2,6 +2,7 @@
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::convert::TryInto;
+use std::ffi::OsString;
 use std::fs::{
     read_dir, remove_dir, remove_file, rename, DirBuilder, File, FileType, OpenOptions, ReadDir,
 };
@@ -18,6 +19,9 @@
 use crate::shims::os_str::bytes_to_os_str;
 use crate::*;
 use shims::time::system_time_to_duration;
+use shims::unix::net::Socket;
+use shims::unix::socket::EvalContextExt as _;
+use shims::unix::vfs::{self, VirtualFile};
 
 #[derive(Debug)]
 pub struct FileHandle {
@@ -388,11 +392,22 @@ fn macos_stat_write_buf(
         Ok(0)
     }
 
-    fn file_type_to_d_type(
-        &mut self,
-        file_type: std::io::Result<FileType>,
-    ) -> InterpResult<'tcx, i32> {
+    /// Whether `fd` is open on a file of the virtual file system.
+    fn is_virtual_file(&self, fd: i32) -> bool {
+        let this = self.eval_context_ref();
+        let file_descriptor = this.machine.file_handler.handles.get(&fd);
+        file_descriptor.is_some_and(|fd| fd.downcast_ref::<VirtualFile>().is_some())
+    }
+
+    fn file_type_to_d_type(&mut self, file_type: DirEntryType) -> InterpResult<'tcx, i32> {
         let this = self.eval_context_mut();
+        let file_type = match file_type {
+            DirEntryType::Host(file_type) => file_type,
+            DirEntryType::Virtual(vfs::FileType::File) =>
+                return Ok(this.eval_libc("DT_REG").to_u8()?.into()),
+            DirEntryType::Virtual(vfs::FileType::Dir) =>
+                return Ok(this.eval_libc("DT_DIR").to_u8()?.into()),
+        };
         match file_type {
             Ok(file_type) => {
                 if file_type.is_dir() {
@@ -441,19 +456,71 @@ fn file_type_to_d_type(
 /// An open directory, tracked by DirHandler.
 #[derive(Debug)]
 pub struct OpenDir {
-    /// The directory reader on the host.
-    read_dir: ReadDir,
+    /// The directory reader.
+    read_dir: DirStream,
     /// The most recent entry returned by readdir()
     entry: Pointer<Option<Provenance>>,
 }
 
 impl OpenDir {
-    fn new(read_dir: ReadDir) -> Self {
+    fn new(read_dir: DirStream) -> Self {
         // We rely on `free` being a NOP on null pointers.
         Self { read_dir, entry: Pointer::null() }
     }
 }
 
+#[derive(Debug)]
+enum DirStream {
+    /// A directory on the host.
+    Host(ReadDir),
+    /// A directory of the virtual file system, whose entries are read when it is opened.
+    Virtual(std::vec::IntoIter<vfs::DirEntry>),
+}
+
+/// A directory entry returned by a `DirStream`.
+struct DirEntry {
+    name: OsString,
+    ino: u64,
+    file_type: DirEntryType,
+}
+
+enum DirEntryType {
+    Host(io::Result<FileType>),
+    Virtual(vfs::FileType),
+}
+
+impl Iterator for DirStream {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            DirStream::Host(read_dir) =>
+                read_dir.next().map(|dir_entry| {
+                    let dir_entry = dir_entry?;
+                    // If the host is a Unix system, fill in the inode number with its real value.
+                    // If not, use 0 as a fallback value.
+                    #[cfg(unix)]
+                    let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
+                    #[cfg(not(unix))]
+                    let ino = 0u64;
+                    Ok(DirEntry {
+                        name: dir_entry.file_name(),
+                        ino,
+                        file_type: DirEntryType::Host(dir_entry.file_type()),
+                    })
+                }),
+            DirStream::Virtual(entries) =>
+                entries.next().map(|entry| {
+                    Ok(DirEntry {
+                        name: entry.name,
+                        ino: entry.ino,
+                        file_type: DirEntryType::Virtual(entry.file_type),
+                    })
+                }),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DirHandler {
     /// Directory iterators used to emulate libc "directory streams", as used in opendir, readdir,
@@ -472,7 +539,7 @@ pub struct DirHandler {
 
 impl DirHandler {
     #[allow(clippy::arithmetic_side_effects)]
-    fn insert_new(&mut self, read_dir: ReadDir) -> u64 {
+    fn insert_new(&mut self, read_dir: DirStream) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
         self.streams.try_insert(id, OpenDir::new(read_dir)).unwrap();
@@ -531,7 +598,7 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
         let path = this.read_pointer(&args[0])?;
         let flag = this.read_scalar(&args[1])?.to_i32()?;
 
-        let mut options = OpenOptions::new();
+        let mut options = vfs::OpenOptions::default();
 
         let o_rdonly = this.eval_libc_i32("O_RDONLY");
         let o_wronly = this.eval_libc_i32("O_WRONLY");
@@ -549,11 +616,12 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
 
         if access_mode == o_rdonly {
             writable = false;
-            options.read(true);
+            options.read = true;
         } else if access_mode == o_wronly {
-            options.write(true);
+            options.write = true;
         } else if access_mode == o_rdwr {
-            options.read(true).write(true);
+            options.read = true;
+            options.write = true;
         } else {
             throw_unsup_format!("unsupported access mode {:#x}", access_mode);
         }
@@ -564,12 +632,12 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
 
         let o_append = this.eval_libc_i32("O_APPEND");
         if flag & o_append == o_append {
-            options.append(true);
+            options.append = true;
             mirror |= o_append;
         }
         let o_trunc = this.eval_libc_i32("O_TRUNC");
         if flag & o_trunc == o_trunc {
-            options.truncate(true);
+            options.truncate = true;
             mirror |= o_trunc;
         }
         let o_creat = this.eval_libc_i32("O_CREAT");
@@ -595,9 +663,9 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
             let o_excl = this.eval_libc_i32("O_EXCL");
             if flag & o_excl == o_excl {
                 mirror |= o_excl;
-                options.create_new(true);
+                options.create_new = true;
             } else {
-                options.create(true);
+                options.create = true;
             }
         }
         let o_cloexec = this.eval_libc_i32("O_CLOEXEC");
@@ -623,6 +691,14 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
 
         let path = this.read_path_from_c_str(path)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            let fd = vfs.open(&path, options).map(|file| {
+                let fh = &mut this.machine.file_handler;
+                fh.insert_fd(Box::new(file))
+            });
+            return this.try_unwrap_io_result(fd);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`open`", reject_with)?;
@@ -630,7 +706,7 @@ fn open(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32> {
             return Ok(-1);
         }
 
-        let fd = options.open(path).map(|file| {
+        let fd = options.to_host().open(path).map(|file| {
             let fh = &mut this.machine.file_handler;
             fh.insert_fd(Box::new(FileHandle { file, writable }))
         });
@@ -692,13 +768,20 @@ fn fcntl(&mut self, args: &[OpTy<'tcx, Provenance>]) -> InterpResult<'tcx, i32>
                 None => this.handle_not_found(),
             }
         } else if this.tcx.sess.target.os == "macos" && cmd == this.eval_libc_i32("F_FULLFSYNC") {
-            // Reject if isolation is enabled.
-            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            // Reject if isolation is enabled and there is no virtual file system.
+            if this.machine.vfs.is_none()
+                && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+            {
                 this.reject_in_isolation("`fcntl`", reject_with)?;
                 this.set_last_error_from_io_error(ErrorKind::PermissionDenied)?;
                 return Ok(-1);
             }
 
+            if this.is_virtual_file(fd) {
+                // Files of the virtual file system have nothing to flush.
+                return Ok(0);
+            }
+
             if let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd) {
                 // FIXME: Support fullfsync for all FDs
                 let FileHandle { file, writable } =
@@ -724,7 +807,11 @@ fn close(&mut self, fd_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, Scalar
 
         Ok(Scalar::from_i32(
             if let Some(file_descriptor) = this.machine.file_handler.handles.remove(&fd) {
+                let is_socket = file_descriptor.downcast_ref::<Socket>().is_some();
                 let result = file_descriptor.close(this.machine.communicate())?;
+                if is_socket {
+                    this.socket_closed();
+                }
                 this.try_unwrap_io_result(result)?
             } else {
                 this.handle_not_found()?
@@ -869,6 +956,11 @@ fn unlink(&mut self, path_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            let result = vfs.remove_file(&path).map(|_| 0);
+            return this.try_unwrap_io_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`unlink`", reject_with)?;
@@ -922,8 +1014,10 @@ fn macos_stat(
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`stat`", reject_with)?;
             let eacc = this.eval_libc("EACCES");
             this.set_last_error(eacc)?;
@@ -951,8 +1045,10 @@ fn macos_lstat(
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`lstat`", reject_with)?;
             let eacc = this.eval_libc("EACCES");
             this.set_last_error(eacc)?;
@@ -978,8 +1074,10 @@ fn macos_fstat(
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`fstat`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return Ok(Scalar::from_i32(this.handle_not_found()?));
@@ -1041,8 +1139,10 @@ fn linux_statx(
             )
         }
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`statx`", reject_with)?;
             let ecode = if path.is_absolute() || dirfd == this.eval_libc_i32("AT_FDCWD") {
                 // since `path` is provided, either absolute or
@@ -1193,6 +1293,11 @@ fn rename(
         let oldpath = this.read_path_from_c_str(oldpath_ptr)?;
         let newpath = this.read_path_from_c_str(newpath_ptr)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            let result = vfs.rename(&oldpath, &newpath).map(|_| 0);
+            return this.try_unwrap_io_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`rename`", reject_with)?;
@@ -1221,6 +1326,12 @@ fn mkdir(
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            // The virtual file system has no permissions, so `mode` is ignored.
+            let result = vfs.create_dir(&path).map(|_| 0i32);
+            return this.try_unwrap_io_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`mkdir`", reject_with)?;
@@ -1249,6 +1360,11 @@ fn rmdir(&mut self, path_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32>
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            let result = vfs.remove_dir(&path).map(|_| 0i32);
+            return this.try_unwrap_io_result(result);
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`rmdir`", reject_with)?;
@@ -1269,15 +1385,19 @@ fn opendir(
 
         let name = this.read_path_from_c_str(this.read_pointer(name_op)?)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`opendir`", reject_with)?;
-            let eacc = this.eval_libc("EACCES");
-            this.set_last_error(eacc)?;
-            return Ok(Scalar::null_ptr(this));
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.read_dir(&name).map(|entries| DirStream::Virtual(entries.into_iter()))
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`opendir`", reject_with)?;
+                let eacc = this.eval_libc("EACCES");
+                this.set_last_error(eacc)?;
+                return Ok(Scalar::null_ptr(this));
+            }
 
-        let result = read_dir(name);
+            read_dir(name).map(DirStream::Host)
+        };
 
         match result {
             Ok(dir_iter) => {
@@ -1305,8 +1425,10 @@ fn linux_readdir64(
 
         let dirp = this.read_target_usize(dirp_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`readdir`", reject_with)?;
             let eacc = this.eval_libc("EBADF");
             this.set_last_error(eacc)?;
@@ -1332,7 +1454,7 @@ fn linux_readdir64(
                 //     pub d_name: [c_char; 256],
                 // }
 
-                let mut name = dir_entry.file_name(); // not a Path as there are no separators!
+                let mut name = dir_entry.name; // not a Path as there are no separators!
                 name.push("\0"); // Add a NUL terminator
                 let name_bytes = name.as_encoded_bytes();
                 let name_len = u64::try_from(name_bytes.len()).unwrap();
@@ -1344,18 +1466,11 @@ fn linux_readdir64(
                 let entry =
                     this.malloc(size, /*zero_init:*/ false, MiriMemoryKind::Runtime)?;
 
-                // If the host is a Unix system, fill in the inode number with its real value.
-                // If not, use 0 as a fallback value.
-                #[cfg(unix)]
-                let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
-                #[cfg(not(unix))]
-                let ino = 0u64;
-
-                let file_type = this.file_type_to_d_type(dir_entry.file_type())?;
+                let file_type = this.file_type_to_d_type(dir_entry.file_type)?;
 
                 this.write_int_fields_named(
                     &[
-                        ("d_ino", ino.into()),
+                        ("d_ino", dir_entry.ino.into()),
                         ("d_off", 0),
                         ("d_reclen", size.into()),
                         ("d_type", file_type.into()),
@@ -1397,8 +1512,10 @@ fn macos_readdir_r(
 
         let dirp = this.read_target_usize(dirp_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`readdir_r`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return Ok(Scalar::from_i32(this.handle_not_found()?));
@@ -1426,7 +1543,7 @@ fn macos_readdir_r(
                 let entry_place = this.deref_pointer_as(entry_op, this.libc_ty_layout("dirent"))?;
                 let name_place = this.project_field(&entry_place, 5)?;
 
-                let file_name = dir_entry.file_name(); // not a Path as there are no separators!
+                let file_name = dir_entry.name; // not a Path as there are no separators!
                 let (name_fits, file_name_buf_len) = this.write_os_str_to_c_str(
                     &file_name,
                     name_place.ptr(),
@@ -1439,18 +1556,11 @@ fn macos_readdir_r(
                     );
                 }
 
-                // If the host is a Unix system, fill in the inode number with its real value.
-                // If not, use 0 as a fallback value.
-                #[cfg(unix)]
-                let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
-                #[cfg(not(unix))]
-                let ino = 0u64;
-
-                let file_type = this.file_type_to_d_type(dir_entry.file_type())?;
+                let file_type = this.file_type_to_d_type(dir_entry.file_type)?;
 
                 this.write_int_fields_named(
                     &[
-                        ("d_ino", ino.into()),
+                        ("d_ino", dir_entry.ino.into()),
                         ("d_seekoff", 0),
                         ("d_reclen", 0),
                         ("d_namlen", file_name_len.into()),
@@ -1488,8 +1598,10 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i
 
         let dirp = this.read_target_usize(dirp_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`closedir`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return this.handle_not_found();
@@ -1514,13 +1626,25 @@ fn ftruncate64(
         let fd = this.read_scalar(fd_op)?.to_i32()?;
         let length = this.read_scalar(length_op)?.to_i64()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`ftruncate64`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return Ok(Scalar::from_i32(this.handle_not_found()?));
         }
 
+        if let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd)
+            && let Some(file) = file_descriptor.downcast_ref::<VirtualFile>()
+        {
+            let result = match u64::try_from(length) {
+                Ok(length) => file.set_len(length).map(|_| 0i32),
+                Err(_) => Err(ErrorKind::InvalidInput.into()),
+            };
+            return Ok(Scalar::from_i32(this.try_unwrap_io_result(result)?));
+        }
+
         Ok(Scalar::from_i32(
             if let Some(file_descriptor) = this.machine.file_handler.handles.get_mut(&fd) {
                 // FIXME: Support ftruncate64 for all FDs
@@ -1561,13 +1685,20 @@ fn fsync(&mut self, fd_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32> {
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`fsync`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return this.handle_not_found();
         }
 
+        if this.is_virtual_file(fd) {
+            // Files of the virtual file system have nothing to flush.
+            return Ok(0);
+        }
+
         if let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd) {
             // FIXME: Support fsync for all FDs
             let FileHandle { file, writable } =
@@ -1586,13 +1717,20 @@ fn fdatasync(&mut self, fd_op: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i3
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`fdatasync`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return this.handle_not_found();
         }
 
+        if this.is_virtual_file(fd) {
+            // Files of the virtual file system have nothing to flush.
+            return Ok(0);
+        }
+
         if let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd) {
             // FIXME: Support fdatasync for all FDs
             let FileHandle { file, writable } =
@@ -1636,13 +1774,20 @@ fn sync_file_range(
             return Ok(Scalar::from_i32(-1));
         }
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+        // Reject if isolation is enabled and there is no virtual file system.
+        if this.machine.vfs.is_none()
+            && let IsolatedOp::Reject(reject_with) = this.machine.isolated_op
+        {
             this.reject_in_isolation("`sync_file_range`", reject_with)?;
             // Set error code as "EBADF" (bad fd)
             return Ok(Scalar::from_i32(this.handle_not_found()?));
         }
 
+        if this.is_virtual_file(fd) {
+            // Files of the virtual file system have nothing to flush.
+            return Ok(Scalar::from_i32(0));
+        }
+
         if let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd) {
             // FIXME: Support sync_data_range for all FDs
             let FileHandle { file, writable } =
@@ -1670,15 +1815,19 @@ fn readlink(
         let buf = this.read_pointer(buf_op)?;
         let bufsize = this.read_target_usize(bufsize_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`readlink`", reject_with)?;
-            let eacc = this.eval_libc("EACCES");
-            this.set_last_error(eacc)?;
-            return Ok(-1);
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.read_link(&pathname)
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`readlink`", reject_with)?;
+                let eacc = this.eval_libc("EACCES");
+                this.set_last_error(eacc)?;
+                return Ok(-1);
+            }
 
-        let result = std::fs::read_link(pathname);
+            std::fs::read_link(pathname)
+        };
         match result {
             Ok(resolved) => {
                 // 'readlink' truncates the resolved path if the provided buffer is not large
@@ -1737,15 +1886,19 @@ fn realpath(
         let pathname = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
         let processed_ptr = this.read_pointer(processed_path_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`realpath`", reject_with)?;
-            let eacc = this.eval_libc("EACCES");
-            this.set_last_error(eacc)?;
-            return Ok(Scalar::from_target_usize(0, this));
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.canonicalize(&pathname)
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`realpath`", reject_with)?;
+                let eacc = this.eval_libc("EACCES");
+                this.set_last_error(eacc)?;
+                return Ok(Scalar::from_target_usize(0, this));
+            }
 
-        let result = std::fs::canonicalize(pathname);
+            std::fs::canonicalize(pathname)
+        };
         match result {
             Ok(resolved) => {
                 let path_max = this
@@ -1945,6 +2098,12 @@ fn from_path<'tcx>(
         path: &Path,
         follow_symlink: bool,
     ) -> InterpResult<'tcx, Option<FileMetadata>> {
+        if let Some(vfs) = &ecx.machine.vfs {
+            // The virtual file system has no symbolic links to follow.
+            let metadata = vfs.metadata(path);
+            return FileMetadata::from_vfs(ecx, metadata);
+        }
+
         let metadata =
             if follow_symlink { std::fs::metadata(path) } else { std::fs::symlink_metadata(path) };
 
@@ -1956,6 +2115,10 @@ fn from_fd<'tcx>(
         fd: i32,
     ) -> InterpResult<'tcx, Option<FileMetadata>> {
         let option = ecx.machine.file_handler.handles.get(&fd);
+        if let Some(file) = option.and_then(|fd| fd.downcast_ref::<VirtualFile>()) {
+            let metadata = file.metadata();
+            return FileMetadata::from_vfs(ecx, Ok(metadata));
+        }
         let file = match option {
             Some(file_descriptor) =>
                 &file_descriptor
@@ -2006,4 +2169,32 @@ fn from_meta<'tcx>(
         // FIXME: Provide more fields using platform specific methods.
         Ok(Some(FileMetadata { mode, size, created, accessed, modified }))
     }
+
+    fn from_vfs<'tcx>(
+        ecx: &mut MiriInterpCx<'_, 'tcx>,
+        metadata: io::Result<vfs::Metadata>,
+    ) -> InterpResult<'tcx, Option<FileMetadata>> {
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                ecx.set_last_error_from_io_error(e.kind())?;
+                return Ok(None);
+            }
+        };
+
+        let mode_name = match metadata.file_type {
+            vfs::FileType::File => "S_IFREG",
+            vfs::FileType::Dir => "S_IFDIR",
+        };
+        let mode = ecx.eval_libc(mode_name);
+
+        // The virtual file system has no timestamps, which would make runs non-deterministic.
+        Ok(Some(FileMetadata {
+            mode,
+            size: metadata.size,
+            created: None,
+            accessed: None,
+            modified: None,
+        }))
+    }
 }
@@ -4,11 +4,13 @@
 use crate::machine::SIGRTMAX;
 use crate::machine::SIGRTMIN;
 use crate::*;
+use helpers::check_arg_count;
 use shims::foreign_items::EmulateForeignItemResult;
 use shims::unix::fs::EvalContextExt as _;
 use shims::unix::linux::fd::EvalContextExt as _;
 use shims::unix::linux::mem::EvalContextExt as _;
 use shims::unix::linux::sync::futex;
+use shims::unix::socket::EvalContextExt as _;
 use shims::unix::sync::EvalContextExt as _;
 use shims::unix::thread::EvalContextExt as _;
 
@@ -97,6 +99,100 @@ fn emulate_foreign_item_inner(
                 this.write_scalar(Scalar::from_i32(SIGRTMAX), dest)?;
             }
 
+            // Sockets on the emulated loopback network
+            "socket" => {
+                let [domain, type_, protocol] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.socket(domain, type_, protocol)?;
+                this.write_scalar(result, dest)?;
+            }
+            "bind" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.bind(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "listen" => {
+                let [fd, backlog] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.listen(fd, backlog)?;
+                this.write_scalar(result, dest)?;
+            }
+            "accept" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.accept4(fd, addr, addrlen, None, dest)?;
+            }
+            "accept4" => {
+                let [fd, addr, addrlen, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.accept4(fd, addr, addrlen, Some(flags), dest)?;
+            }
+            "connect" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.connect(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockname" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getsockname(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getpeername" => {
+                let [fd, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getpeername(fd, addr, addrlen)?;
+                this.write_scalar(result, dest)?;
+            }
+            "setsockopt" => {
+                let [fd, level, name, value, len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.setsockopt(fd, level, name, value, len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockopt" => {
+                let [fd, level, name, value, len] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.getsockopt(fd, level, name, value, len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "send" => {
+                let [fd, buf, len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sendto(fd, buf, len, flags, None)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sendto" => {
+                let [fd, buf, len, flags, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.sendto(fd, buf, len, flags, Some((addr, addrlen)))?;
+                this.write_scalar(result, dest)?;
+            }
+            "recv" => {
+                let [fd, buf, len, flags] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.recvfrom(fd, buf, len, flags, None, dest)?;
+            }
+            "recvfrom" => {
+                let [fd, buf, len, flags, addr, addrlen] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                this.recvfrom(fd, buf, len, flags, Some((addr, addrlen)), dest)?;
+            }
+            "shutdown" => {
+                let [fd, how] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let result = this.shutdown(fd, how)?;
+                this.write_scalar(result, dest)?;
+            }
+            "ioctl" => {
+                // `ioctl` is variadic, but we only support requests that take one argument.
+                this.check_abi_and_shim_symbol_clash(abi, Abi::C { unwind: false }, link_name)?;
+                let [fd, request, arg] = check_arg_count(args)?;
+                let result = this.ioctl(fd, request, arg)?;
+                this.write_scalar(result, dest)?;
+            }
+
             // Threading
             "pthread_condattr_setclock" => {
                 let [attr, clock_id] =
@@ -1,16 +1,23 @@
 pub mod foreign_items;
+pub mod signal;
 
 mod fs;
 mod mem;
+mod net;
+mod socket;
 mod sync;
 mod thread;
+mod vfs;
 
 mod android;
 mod freebsd;
 mod linux;
 mod macos;
 
-pub use fs::{DirHandler, FileHandler};
+pub use fs::{DirHandler, FileDescriptor, FileHandler};
+pub use socket::SocketHandler;
+pub use signal::{SignalHandlerFrame, SignalState};
+pub use vfs::{Vfs, VfsSnapshot};
 
 // Make up some constants.
 const UID: u32 = 1000;
@@ -0,0 +1,544 @@
+//! An in-process loopback network backing the socket shims.
+//!
+//! Only `127.0.0.1` and `::1` exist on this network (binding to the unspecified address binds to
+//! the loopback address of the same family). Nothing ever leaves the interpreter, so sockets can
+//! be used with isolation enabled, and everything is deterministic: ephemeral ports are handed
+//! out in order and data is delivered the moment it is sent. This module only manages the state
+//! of the network; blocking and synchronization between threads are handled by the shims.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::rc::{Rc, Weak};
+
+use crate::concurrency::VClock;
+use crate::shims::unix::fs::FileDescriptor;
+use crate::*;
+
+/// The first port handed out to sockets that are bound to port 0.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Protocol {
+    /// A TCP socket, created with `SOCK_STREAM`.
+    Stream,
+    /// A UDP socket, created with `SOCK_DGRAM`.
+    Datagram,
+}
+
+/// A port of the network. TCP and UDP, as well as IPv4 and IPv6, have separate port spaces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Port {
+    protocol: Protocol,
+    ipv6: bool,
+    number: u16,
+}
+
+/// The loopback network.
+#[derive(Debug)]
+pub struct Network {
+    /// The sockets bound to each port. Ports whose socket has been closed are free again.
+    ports: BTreeMap<Port, Weak<RefCell<SocketInner>>>,
+    /// The next port to try when binding to port 0.
+    next_port: u16,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network { ports: BTreeMap::new(), next_port: FIRST_EPHEMERAL_PORT }
+    }
+}
+
+/// A socket on the loopback network. All file descriptors referring to the same socket share
+/// this state; the socket is closed when the last of them is closed.
+#[derive(Clone, Debug)]
+pub struct Socket(Rc<RefCell<SocketInner>>);
+
+#[derive(Debug)]
+struct SocketInner {
+    protocol: Protocol,
+    ipv6: bool,
+    nonblocking: bool,
+    /// The address this socket is bound to, if any.
+    local: Option<SocketAddr>,
+    state: State,
+}
+
+#[derive(Debug)]
+enum State {
+    /// A TCP socket that is neither listening nor connected.
+    Idle,
+    /// A TCP socket accepting connections, with the connections that have not been accepted yet.
+    Listening(VecDeque<Pending>),
+    /// A connected TCP socket.
+    Connected(Stream),
+    /// A UDP socket, with the datagrams that have not been received yet.
+    Datagram { inbox: VecDeque<Datagram>, peer: Option<SocketAddr> },
+}
+
+/// A connection that has not been accepted yet.
+#[derive(Debug)]
+struct Pending {
+    /// The server side of the connection.
+    stream: Stream,
+    /// The clock of the thread that connected.
+    clock: VClock,
+}
+
+/// A datagram that has not been received yet.
+#[derive(Debug)]
+struct Datagram {
+    from: SocketAddr,
+    data: Vec<u8>,
+    /// The clock of the thread that sent the datagram.
+    clock: VClock,
+}
+
+/// One direction of a TCP connection.
+#[derive(Debug, Default)]
+struct Pipe {
+    data: VecDeque<u8>,
+    /// The clocks of all threads that wrote to this pipe.
+    clock: VClock,
+    /// Whether the writing side has been shut down or closed.
+    write_closed: bool,
+    /// Whether the reading side has been shut down or closed.
+    read_closed: bool,
+}
+
+/// One end of a TCP connection.
+#[derive(Debug)]
+struct Stream {
+    peer: SocketAddr,
+    incoming: Rc<RefCell<Pipe>>,
+    outgoing: Rc<RefCell<Pipe>>,
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // Closing one end of a connection shuts down both directions, so the peer reads EOF and
+        // fails to write.
+        self.incoming.borrow_mut().read_closed = true;
+        self.outgoing.borrow_mut().write_closed = true;
+    }
+}
+
+impl Socket {
+    pub fn new(protocol: Protocol, ipv6: bool, nonblocking: bool) -> Socket {
+        let state = match protocol {
+            Protocol::Stream => State::Idle,
+            Protocol::Datagram => State::Datagram { inbox: VecDeque::new(), peer: None },
+        };
+        Socket(Rc::new(RefCell::new(SocketInner {
+            protocol,
+            ipv6,
+            nonblocking,
+            local: None,
+            state,
+        })))
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.0.borrow().nonblocking
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.0.borrow_mut().nonblocking = nonblocking;
+    }
+
+    /// The address this socket is bound to. Unbound sockets report the unspecified address.
+    pub fn local_addr(&self) -> SocketAddr {
+        let inner = self.0.borrow();
+        inner.local.unwrap_or_else(|| SocketAddr::new(unspecified(inner.ipv6), 0))
+    }
+
+    /// The address of the socket this socket is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match &self.0.borrow().state {
+            State::Connected(stream) => Ok(stream.peer),
+            State::Datagram { peer: Some(peer), .. } => Ok(*peer),
+            _ => Err(ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+impl Network {
+    /// Binds `socket` to `addr`. Port 0 picks the next free ephemeral port.
+    pub fn bind(&mut self, socket: &Socket, addr: SocketAddr) -> io::Result<()> {
+        let mut inner = socket.0.borrow_mut();
+        if inner.local.is_some() || addr.is_ipv6() != inner.ipv6 {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+        if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+            return Err(ErrorKind::AddrNotAvailable.into());
+        }
+        let port = if addr.port() == 0 {
+            self.free_port(inner.protocol, inner.ipv6)?
+        } else {
+            let port = Port { protocol: inner.protocol, ipv6: inner.ipv6, number: addr.port() };
+            if self.is_in_use(port) {
+                return Err(ErrorKind::AddrInUse.into());
+            }
+            port
+        };
+        self.ports.insert(port, Rc::downgrade(&socket.0));
+        inner.local = Some(SocketAddr::new(addr.ip(), port.number));
+        Ok(())
+    }
+
+    /// Makes `socket` accept connections.
+    pub fn listen(&mut self, socket: &Socket) -> io::Result<()> {
+        self.bind_if_unbound(socket)?;
+        let mut inner = socket.0.borrow_mut();
+        match inner.state {
+            State::Idle => inner.state = State::Listening(VecDeque::new()),
+            State::Listening(_) => {}
+            State::Connected(_) | State::Datagram { .. } =>
+                return Err(ErrorKind::InvalidInput.into()),
+        }
+        Ok(())
+    }
+
+    /// Connects `socket` to `addr`. For TCP, this queues the connection on the listening socket
+    /// and succeeds right away; the connection still has to be accepted on the other side.
+    pub fn connect(&mut self, socket: &Socket, addr: SocketAddr, clock: VClock) -> io::Result<()> {
+        let (protocol, ipv6) = {
+            let inner = socket.0.borrow();
+            (inner.protocol, inner.ipv6)
+        };
+        if addr.is_ipv6() != ipv6 {
+            return Err(ErrorKind::InvalidInput.into());
+        }
+        if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+            return Err(ErrorKind::NetworkUnreachable.into());
+        }
+        // Connecting to the unspecified address connects to the loopback address.
+        let addr = SocketAddr::new(loopback(ipv6), addr.port());
+        match protocol {
+            Protocol::Stream => {
+                if !matches!(socket.0.borrow().state, State::Idle) {
+                    return Err(ErrorKind::InvalidInput.into());
+                }
+                let listener = self
+                    .socket_at(Port { protocol, ipv6, number: addr.port() })
+                    .filter(|listener| matches!(listener.borrow().state, State::Listening(_)))
+                    .ok_or(ErrorKind::ConnectionRefused)?;
+                self.bind_if_unbound(socket)?;
+                let local = socket.0.borrow().connected_addr();
+                let to_server = Rc::new(RefCell::new(Pipe::default()));
+                let to_client = Rc::new(RefCell::new(Pipe::default()));
+                let server = Stream {
+                    peer: local,
+                    incoming: Rc::clone(&to_server),
+                    outgoing: Rc::clone(&to_client),
+                };
+                let State::Listening(backlog) = &mut listener.borrow_mut().state else {
+                    unreachable!()
+                };
+                backlog.push_back(Pending { stream: server, clock });
+                socket.0.borrow_mut().state = State::Connected(Stream {
+                    peer: addr,
+                    incoming: to_client,
+                    outgoing: to_server,
+                });
+            }
+            Protocol::Datagram => {
+                self.bind_if_unbound(socket)?;
+                let State::Datagram { peer, .. } = &mut socket.0.borrow_mut().state else {
+                    unreachable!()
+                };
+                *peer = Some(addr);
+            }
+        }
+        Ok(())
+    }
+
+    /// Accepts a connection on the listening `socket`. Returns the new socket, the address of its
+    /// peer and the clock of the thread that connected.
+    pub fn accept(
+        &mut self,
+        socket: &Socket,
+        nonblocking: bool,
+    ) -> io::Result<(Socket, SocketAddr, VClock)> {
+        let mut inner = socket.0.borrow_mut();
+        let State::Listening(backlog) = &mut inner.state else {
+            return Err(ErrorKind::InvalidInput.into());
+        };
+        let Pending { stream, clock } = backlog.pop_front().ok_or(ErrorKind::WouldBlock)?;
+        let peer = stream.peer;
+        let local = inner.connected_addr();
+        let accepted = SocketInner {
+            protocol: Protocol::Stream,
+            ipv6: inner.ipv6,
+            nonblocking,
+            local: Some(local),
+            state: State::Connected(stream),
+        };
+        Ok((Socket(Rc::new(RefCell::new(accepted))), peer, clock))
+    }
+
+    /// Sends `data` through `socket`, to `to` or to the peer it is connected to. Returns how many
+    /// bytes were sent.
+    pub fn send(
+        &mut self,
+        socket: &Socket,
+        data: &[u8],
+        to: Option<SocketAddr>,
+        clock: VClock,
+    ) -> io::Result<usize> {
+        let protocol = socket.0.borrow().protocol;
+        match protocol {
+            Protocol::Stream => {
+                let inner = socket.0.borrow();
+                let State::Connected(stream) = &inner.state else {
+                    return Err(ErrorKind::NotConnected.into());
+                };
+                let mut pipe = stream.outgoing.borrow_mut();
+                if pipe.write_closed || pipe.read_closed {
+                    return Err(ErrorKind::BrokenPipe.into());
+                }
+                pipe.data.extend(data);
+                pipe.clock.join(&clock);
+            }
+            Protocol::Datagram => {
+                let to = match to {
+                    Some(to) => to,
+                    None => socket.peer_addr()?,
+                };
+                if to.is_ipv6() != socket.0.borrow().ipv6 {
+                    return Err(ErrorKind::InvalidInput.into());
+                }
+                if !to.ip().is_loopback() && !to.ip().is_unspecified() {
+                    return Err(ErrorKind::NetworkUnreachable.into());
+                }
+                self.bind_if_unbound(socket)?;
+                let from = socket.0.borrow().connected_addr();
+                let port = Port { protocol, ipv6: to.is_ipv6(), number: to.port() };
+                // Like on a real network, datagrams sent to a port nobody listens on are lost.
+                if let Some(receiver) = self.socket_at(port) {
+                    let State::Datagram { inbox, peer } = &mut receiver.borrow_mut().state else {
+                        unreachable!()
+                    };
+                    // Connected UDP sockets only receive datagrams from their peer.
+                    if peer.map_or(true, |peer| peer == from) {
+                        inbox.push_back(Datagram { from, data: data.to_vec(), clock });
+                    }
+                }
+            }
+        }
+        Ok(data.len())
+    }
+
+    /// Receives at most `len` bytes from `socket`. With `peek`, the data is left in the socket.
+    /// Returns the data, the address it came from and the clock of the thread(s) that sent it.
+    /// Reaching the end of a stream returns no data.
+    pub fn recv(
+        &mut self,
+        socket: &Socket,
+        len: usize,
+        peek: bool,
+    ) -> io::Result<(Vec<u8>, SocketAddr, VClock)> {
+        let mut inner = socket.0.borrow_mut();
+        match &mut inner.state {
+            State::Connected(stream) => {
+                let mut pipe = stream.incoming.borrow_mut();
+                if pipe.data.is_empty() && !pipe.write_closed && !pipe.read_closed && len > 0 {
+                    return Err(ErrorKind::WouldBlock.into());
+                }
+                let len = len.min(pipe.data.len());
+                let data = if peek {
+                    pipe.data.iter().take(len).copied().collect()
+                } else {
+                    pipe.data.drain(..len).collect()
+                };
+                Ok((data, stream.peer, pipe.clock.clone()))
+            }
+            State::Datagram { inbox, .. } => {
+                let datagram = inbox.front().ok_or(ErrorKind::WouldBlock)?;
+                // Whatever does not fit into the buffer is discarded.
+                let data = datagram.data[..len.min(datagram.data.len())].to_vec();
+                let result = (data, datagram.from, datagram.clock.clone());
+                if !peek {
+                    inbox.pop_front();
+                }
+                Ok(result)
+            }
+            State::Idle | State::Listening(_) => Err(ErrorKind::NotConnected.into()),
+        }
+    }
+
+    /// Shuts down the reading and/or writing half of the connection of `socket`.
+    pub fn shutdown(&mut self, socket: &Socket, read: bool, write: bool) -> io::Result<()> {
+        let inner = socket.0.borrow();
+        let State::Connected(stream) = &inner.state else {
+            return Err(ErrorKind::NotConnected.into());
+        };
+        if read {
+            let mut pipe = stream.incoming.borrow_mut();
+            pipe.read_closed = true;
+            pipe.data.clear();
+        }
+        if write {
+            stream.outgoing.borrow_mut().write_closed = true;
+        }
+        Ok(())
+    }
+
+    fn bind_if_unbound(&mut self, socket: &Socket) -> io::Result<()> {
+        let ipv6 = socket.0.borrow().ipv6;
+        if socket.0.borrow().local.is_none() {
+            self.bind(socket, SocketAddr::new(loopback(ipv6), 0))?;
+        }
+        Ok(())
+    }
+
+    fn socket_at(&self, port: Port) -> Option<Rc<RefCell<SocketInner>>> {
+        self.ports.get(&port).and_then(Weak::upgrade)
+    }
+
+    fn is_in_use(&self, port: Port) -> bool {
+        self.socket_at(port).is_some()
+    }
+
+    fn free_port(&mut self, protocol: Protocol, ipv6: bool) -> io::Result<Port> {
+        let ephemeral_ports = u32::from(u16::MAX - FIRST_EPHEMERAL_PORT) + 1;
+        for _ in 0..ephemeral_ports {
+            let port = Port { protocol, ipv6, number: self.next_port };
+            self.next_port = self.next_port.checked_add(1).unwrap_or(FIRST_EPHEMERAL_PORT);
+            if !self.is_in_use(port) {
+                return Ok(port);
+            }
+        }
+        Err(ErrorKind::AddrInUse.into())
+    }
+}
+
+impl SocketInner {
+    /// The address a connection from or to this socket uses as its local end.
+    fn connected_addr(&self) -> SocketAddr {
+        let local = self.local.expect("socket should be bound");
+        if local.ip().is_unspecified() {
+            SocketAddr::new(loopback(self.ipv6), local.port())
+        } else {
+            local
+        }
+    }
+}
+
+fn loopback(ipv6: bool) -> IpAddr {
+    if ipv6 { Ipv6Addr::LOCALHOST.into() } else { Ipv4Addr::LOCALHOST.into() }
+}
+
+fn unspecified(ipv6: bool) -> IpAddr {
+    if ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+}
+
+impl FileDescriptor for Socket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<i32>> {
+        // Dropping the last reference to the socket frees its port and shuts down its connection.
+        Ok(Ok(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn bind() {
+        let mut net = Network::default();
+        let a = Socket::new(Protocol::Stream, false, false);
+        net.bind(&a, addr("127.0.0.1:0")).unwrap();
+        assert_eq!(a.local_addr(), addr("127.0.0.1:49152"));
+
+        // The port is taken until the socket is closed, but only for the same protocol.
+        let b = Socket::new(Protocol::Stream, false, false);
+        assert_eq!(net.bind(&b, addr("0.0.0.0:49152")).unwrap_err().kind(), ErrorKind::AddrInUse);
+        let udp = Socket::new(Protocol::Datagram, false, false);
+        net.bind(&udp, addr("127.0.0.1:49152")).unwrap();
+        drop(a);
+        net.bind(&b, addr("0.0.0.0:49152")).unwrap();
+        assert_eq!(b.local_addr(), addr("0.0.0.0:49152"));
+
+        let c = Socket::new(Protocol::Stream, false, false);
+        let err = net.bind(&c, addr("10.0.0.1:80")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrNotAvailable);
+        assert_eq!(net.bind(&c, addr("[::1]:80")).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn stream() {
+        let mut net = Network::default();
+        let listener = Socket::new(Protocol::Stream, false, false);
+        net.bind(&listener, addr("0.0.0.0:8080")).unwrap();
+        let client = Socket::new(Protocol::Stream, false, false);
+        let err = net.connect(&client, addr("127.0.0.1:8080"), VClock::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+        net.listen(&listener).unwrap();
+        assert_eq!(
+            net.accept(&listener, false).map(drop).unwrap_err().kind(),
+            ErrorKind::WouldBlock
+        );
+
+        net.connect(&client, addr("127.0.0.1:8080"), VClock::default()).unwrap();
+        let (server, peer, _) = net.accept(&listener, false).unwrap();
+        assert_eq!(peer, client.local_addr());
+        assert_eq!(server.local_addr(), addr("127.0.0.1:8080"));
+        assert_eq!(client.peer_addr().unwrap(), addr("127.0.0.1:8080"));
+
+        assert_eq!(net.recv(&server, 4, false).unwrap_err().kind(), ErrorKind::WouldBlock);
+        net.send(&client, b"hello", None, VClock::default()).unwrap();
+        assert_eq!(net.recv(&server, 4, true).unwrap().0, b"hell");
+        assert_eq!(net.recv(&server, 4, false).unwrap().0, b"hell");
+        assert_eq!(net.recv(&server, 4, false).unwrap().0, b"o");
+
+        net.shutdown(&client, false, true).unwrap();
+        assert_eq!(net.recv(&server, 4, false).unwrap().0, b"");
+        net.send(&server, b"bye", None, VClock::default()).unwrap();
+        drop(server);
+        assert_eq!(net.recv(&client, 4, false).unwrap().0, b"bye");
+        assert_eq!(net.recv(&client, 4, false).unwrap().0, b"");
+        let err = net.send(&client, b"?", None, VClock::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn datagram() {
+        let mut net = Network::default();
+        let a = Socket::new(Protocol::Datagram, true, false);
+        net.bind(&a, addr("[::1]:9000")).unwrap();
+        let b = Socket::new(Protocol::Datagram, true, false);
+        assert_eq!(
+            net.send(&b, b"x", None, VClock::default()).unwrap_err().kind(),
+            ErrorKind::NotConnected
+        );
+        net.send(&b, b"first", Some(addr("[::1]:9000")), VClock::default()).unwrap();
+        net.send(&b, b"second", Some(addr("[::1]:9000")), VClock::default()).unwrap();
+        let (data, from, _) = net.recv(&a, 3, false).unwrap();
+        assert_eq!((data.as_slice(), from), (&b"fir"[..], b.local_addr()));
+        assert_eq!(net.recv(&a, 100, false).unwrap().0, b"second");
+        assert_eq!(net.recv(&a, 100, false).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+        // A connected socket ignores datagrams from anyone but its peer.
+        let c = Socket::new(Protocol::Datagram, true, false);
+        net.connect(&a, addr("[::1]:1"), VClock::default()).unwrap();
+        net.send(&c, b"ignored", Some(addr("[::1]:9000")), VClock::default()).unwrap();
+        assert_eq!(net.recv(&a, 100, false).unwrap_err().kind(), ErrorKind::WouldBlock);
+    }
+}
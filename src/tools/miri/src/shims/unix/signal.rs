@@ -0,0 +1,848 @@
+//! Emulation of POSIX signals.
+//!
+//! Signals can only originate from the program itself, via `raise`, `kill` (of its own process)
+//! and `pthread_kill`. A signal that is sent to a thread stays pending until that thread is about
+//! to start executing a new basic block (in any frame) while the signal is not blocked; then the
+//! registered handler is called on top of that thread's stack, and execution continues at the
+//! start of the interrupted block once it returns. A signal that is sent to the process goes to
+//! the first thread that reaches such a point with the signal unblocked. In particular, a thread
+//! that is blocked (e.g. waiting for a lock) only receives signals once it runs again; signals do
+//! not interrupt blocking calls.
+//!
+//! While a handler is running, calling a foreign function that is not async-signal-safe is
+//! reported as Undefined Behavior.
+
+use std::iter;
+
+use either::Either;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_index::Idx;
+use rustc_middle::mir;
+use rustc_middle::ty::layout::TyAndLayout;
+use rustc_span::Symbol;
+use rustc_target::abi::Size;
+use rustc_target::spec::abi::Abi;
+
+use crate::machine::SIGRTMAX;
+use crate::*;
+
+/// The foreign functions that may be called from a signal handler: the async-signal-safe functions
+/// listed by POSIX that Miri supports, and the accessors for `errno`.
+const ASYNC_SIGNAL_SAFE: &[&str] = &[
+    "__errno_location",
+    "__error",
+    "_exit",
+    "abort",
+    "accept",
+    "bind",
+    "clock_gettime",
+    "close",
+    "connect",
+    "fcntl",
+    "fdatasync",
+    "fstat",
+    "fstat$INODE64",
+    "fstat64",
+    "fsync",
+    "ftruncate",
+    "ftruncate64",
+    "getpeername",
+    "getpid",
+    "getsockname",
+    "getsockopt",
+    "getuid",
+    "kill",
+    "listen",
+    "lseek",
+    "lseek64",
+    "lstat",
+    "lstat$INODE64",
+    "lstat64",
+    "memchr",
+    "memcmp",
+    "memcpy",
+    "mkdir",
+    "open",
+    "open64",
+    "pthread_kill",
+    "pthread_self",
+    "pthread_sigmask",
+    "raise",
+    "read",
+    "readlink",
+    "recv",
+    "recvfrom",
+    "rename",
+    "rmdir",
+    "send",
+    "sendto",
+    "setsockopt",
+    "shutdown",
+    "sigaction",
+    "sigaddset",
+    "sigdelset",
+    "sigemptyset",
+    "sigfillset",
+    "sigismember",
+    "signal",
+    "sigpending",
+    "sigprocmask",
+    "socket",
+    "socketpair",
+    "stat",
+    "stat$INODE64",
+    "stat64",
+    "strlen",
+    "symlink",
+    "time",
+    "unlink",
+    "write",
+];
+
+/// The signals whose names are used in diagnostics.
+const SIGNAL_NAMES: &[&str] = &[
+    "SIGHUP",
+    "SIGINT",
+    "SIGQUIT",
+    "SIGILL",
+    "SIGTRAP",
+    "SIGABRT",
+    "SIGBUS",
+    "SIGFPE",
+    "SIGKILL",
+    "SIGUSR1",
+    "SIGSEGV",
+    "SIGUSR2",
+    "SIGPIPE",
+    "SIGALRM",
+    "SIGTERM",
+    "SIGCHLD",
+    "SIGCONT",
+    "SIGSTOP",
+    "SIGTSTP",
+    "SIGTTIN",
+    "SIGTTOU",
+    "SIGURG",
+    "SIGXCPU",
+    "SIGXFSZ",
+    "SIGVTALRM",
+    "SIGPROF",
+    "SIGWINCH",
+    "SIGIO",
+    "SIGSYS",
+];
+
+/// The action registered for a signal with `signal` or `sigaction`.
+#[derive(Clone, Debug)]
+struct SignalAction {
+    /// The raw `sa_sigaction` value: `SIG_DFL`, `SIG_IGN`, or a function pointer. Turning it into
+    /// a function is deferred until the signal is actually delivered.
+    handler: Scalar<Provenance>,
+    /// The signals that are additionally blocked while the handler runs.
+    mask: u64,
+    /// The `sa_flags`.
+    flags: i32,
+}
+
+/// The signal state of a single thread.
+#[derive(Debug, Default)]
+struct ThreadSignals {
+    /// The signal mask.
+    blocked: u64,
+    /// Signals sent to this thread that have not been delivered yet.
+    pending: u64,
+    /// How many signal handlers are currently running on this thread.
+    handler_depth: u32,
+}
+
+/// The signal dispositions and masks of the program. Sets of signals are represented as bitmasks,
+/// where signal `n` is bit `n - 1`.
+#[derive(Debug, Default)]
+pub struct SignalState {
+    /// The registered actions. Signals without an entry have the default action.
+    actions: FxHashMap<i32, SignalAction>,
+    /// Threads without an entry have no pending signals and an empty mask.
+    threads: FxHashMap<ThreadId, ThreadSignals>,
+    /// Signals sent to the process that have not been delivered to any thread yet.
+    process_pending: u64,
+}
+
+impl SignalState {
+    /// Whether there is any signal waiting to be delivered. Checked before every step.
+    pub fn any_pending(&self) -> bool {
+        self.process_pending != 0 || self.threads.values().any(|thread| thread.pending != 0)
+    }
+
+    /// Whether a signal handler is currently running on `thread`.
+    pub fn in_handler(&self, thread: ThreadId) -> bool {
+        self.threads.get(&thread).is_some_and(|thread| thread.handler_depth > 0)
+    }
+
+    /// A new thread inherits the signal mask of the thread that created it.
+    pub fn thread_created(&mut self, parent: ThreadId, child: ThreadId) {
+        let blocked = self.blocked(parent);
+        if blocked != 0 {
+            self.threads.insert(child, ThreadSignals { blocked, ..Default::default() });
+        }
+    }
+
+    fn blocked(&self, thread: ThreadId) -> u64 {
+        self.threads.get(&thread).map_or(0, |thread| thread.blocked)
+    }
+
+    /// Picks the next signal that can be delivered to `thread` and removes it from the pending
+    /// set. Returns the signal and whether it was sent to the thread rather than the process.
+    fn take_deliverable(&mut self, thread: ThreadId) -> Option<(i32, bool)> {
+        let blocked = self.blocked(thread);
+        if let Some(state) = self.threads.get_mut(&thread) {
+            if let Some(sig) = lowest_signal(state.pending & !blocked) {
+                state.pending &= !signal_bit(sig);
+                return Some((sig, true));
+            }
+        }
+        let sig = lowest_signal(self.process_pending & !blocked)?;
+        self.process_pending &= !signal_bit(sig);
+        Some((sig, false))
+    }
+}
+
+impl VisitTags for SignalState {
+    fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
+        let SignalState { actions, threads: _, process_pending: _ } = self;
+        for action in actions.values() {
+            action.handler.visit_tags(visit);
+        }
+    }
+}
+
+/// Stored in the frame of a running signal handler, to undo the effects of the delivery when it
+/// returns.
+#[derive(Debug)]
+pub struct SignalHandlerFrame {
+    /// The signal mask of the thread before the handler was called.
+    blocked: u64,
+    /// The `siginfo_t` that was passed to an `SA_SIGINFO` handler.
+    siginfo: Option<Pointer<Option<Provenance>>>,
+}
+
+impl VisitTags for SignalHandlerFrame {
+    fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
+        let SignalHandlerFrame { blocked: _, siginfo } = self;
+        siginfo.visit_tags(visit);
+    }
+}
+
+/// What happens when a signal is delivered.
+enum Disposition {
+    /// `SIG_DFL`.
+    Default,
+    /// `SIG_IGN`.
+    Ignore,
+    /// A function registered by the program.
+    Handler,
+}
+
+/// The default action of a signal.
+enum DefaultAction {
+    Ignore,
+    Stop,
+    Terminate,
+}
+
+fn signal_bit(sig: i32) -> u64 {
+    1 << (sig - 1)
+}
+
+fn lowest_signal(set: u64) -> Option<i32> {
+    (set != 0).then(|| i32::try_from(set.trailing_zeros()).unwrap() + 1)
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    fn signal(
+        &mut self,
+        sig: &OpTy<'tcx, Provenance>,
+        handler: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        let handler = this.read_scalar(handler)?;
+
+        if !this.signal_can_be_caught(sig) {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(this.eval_libc("SIG_ERR"));
+        }
+
+        // All supported targets give `signal` BSD semantics: the handler stays installed, the
+        // signal is blocked while it runs, and interrupted calls are restarted.
+        let action = SignalAction { handler, mask: 0, flags: this.eval_libc_i32("SA_RESTART") };
+        let old = this.set_signal_action(sig, Some(action))?;
+        Ok(old.map_or_else(|| this.eval_libc("SIG_DFL"), |old| old.handler))
+    }
+
+    fn sigaction(
+        &mut self,
+        sig: &OpTy<'tcx, Provenance>,
+        act: &OpTy<'tcx, Provenance>,
+        oldact: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+        let act = this.read_pointer(act)?;
+        let oldact = this.read_pointer(oldact)?;
+        let sigaction_layout = this.libc_ty_layout("sigaction");
+
+        if !this.signal_is_valid(sig)
+            || (!this.ptr_is_null(act)? && !this.signal_can_be_caught(sig))
+        {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        // Read the new action before writing the old one, in case they are the same struct.
+        let new = if this.ptr_is_null(act)? {
+            None
+        } else {
+            let act = this.ptr_to_mplace(act, sigaction_layout);
+            let handler = this.read_scalar(&this.project_field_named(&act, "sa_sigaction")?)?;
+            let mask = this.read_sigset(&this.project_field_named(&act, "sa_mask")?)?;
+            let flags = this.read_scalar(&this.project_field_named(&act, "sa_flags")?)?.to_i32()?;
+            Some(SignalAction { handler, mask, flags })
+        };
+        let old = match new {
+            Some(new) => this.set_signal_action(sig, Some(new))?,
+            None => this.machine.signals.actions.get(&sig).cloned(),
+        };
+
+        if !this.ptr_is_null(oldact)? {
+            let oldact = this.ptr_to_mplace(oldact, sigaction_layout);
+            let old = old.unwrap_or_else(|| {
+                SignalAction { handler: this.eval_libc("SIG_DFL"), mask: 0, flags: 0 }
+            });
+            // Zero the whole struct first, this also covers the fields we do not know about.
+            this.write_bytes_ptr(
+                oldact.ptr(),
+                iter::repeat(0u8).take(sigaction_layout.size.bytes_usize()),
+            )?;
+            this.write_scalar(old.handler, &this.project_field_named(&oldact, "sa_sigaction")?)?;
+            this.write_sigset(&this.project_field_named(&oldact, "sa_mask")?, old.mask)?;
+            this.write_int(old.flags, &this.project_field_named(&oldact, "sa_flags")?)?;
+        }
+
+        Ok(0)
+    }
+
+    fn sigemptyset(&mut self, set: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        this.write_sigset(&set, 0)?;
+        Ok(0)
+    }
+
+    fn sigfillset(&mut self, set: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        let all = u64::MAX >> (64 - this.max_signal());
+        this.write_sigset(&set, all)?;
+        Ok(0)
+    }
+
+    /// Implements `sigaddset` (if `add` is true) and `sigdelset`.
+    fn sigaddset(
+        &mut self,
+        set: &OpTy<'tcx, Provenance>,
+        sig: &OpTy<'tcx, Provenance>,
+        add: bool,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        if !this.signal_is_valid(sig) {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        let mask = this.read_sigset(&set)?;
+        let mask = if add { mask | signal_bit(sig) } else { mask & !signal_bit(sig) };
+        this.write_sigset(&set, mask)?;
+        Ok(0)
+    }
+
+    fn sigismember(
+        &mut self,
+        set: &OpTy<'tcx, Provenance>,
+        sig: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        if !this.signal_is_valid(sig) {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        let mask = this.read_sigset(&set)?;
+        Ok(i32::from(mask & signal_bit(sig) != 0))
+    }
+
+    /// Returns 0 on success, and the error code otherwise.
+    fn pthread_sigmask(
+        &mut self,
+        how: &OpTy<'tcx, Provenance>,
+        set: &OpTy<'tcx, Provenance>,
+        oldset: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let how = this.read_scalar(how)?.to_i32()?;
+        let set = this.read_pointer(set)?;
+        let oldset = this.read_pointer(oldset)?;
+        let sigset_layout = this.libc_ty_layout("sigset_t");
+
+        let thread = this.get_active_thread();
+        let blocked = this.machine.signals.blocked(thread);
+
+        if !this.ptr_is_null(set)? {
+            let mask = this.read_sigset(&this.ptr_to_mplace(set, sigset_layout))?;
+            let blocked = if how == this.eval_libc_i32("SIG_BLOCK") {
+                blocked | mask
+            } else if how == this.eval_libc_i32("SIG_UNBLOCK") {
+                blocked & !mask
+            } else if how == this.eval_libc_i32("SIG_SETMASK") {
+                mask
+            } else {
+                return Ok(this.eval_libc_i32("EINVAL"));
+            };
+            // Attempts to block `SIGKILL` or `SIGSTOP` are silently ignored.
+            let unblockable = signal_bit(this.eval_libc_i32("SIGKILL"))
+                | signal_bit(this.eval_libc_i32("SIGSTOP"));
+            this.machine.signals.threads.entry(thread).or_default().blocked =
+                blocked & !unblockable;
+        }
+
+        if !this.ptr_is_null(oldset)? {
+            this.write_sigset(&this.ptr_to_mplace(oldset, sigset_layout), blocked)?;
+        }
+
+        Ok(0)
+    }
+
+    fn sigprocmask(
+        &mut self,
+        how: &OpTy<'tcx, Provenance>,
+        set: &OpTy<'tcx, Provenance>,
+        oldset: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        // With threads, the behavior of `sigprocmask` is unspecified. Like the common
+        // implementations, we change the mask of the current thread.
+        let result = this.pthread_sigmask(how, set, oldset)?;
+        if result != 0 {
+            this.set_last_error(Scalar::from_i32(result))?;
+            return Ok(-1);
+        }
+        Ok(0)
+    }
+
+    fn sigpending(&mut self, set: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        let signals = &this.machine.signals;
+        let thread = signals.threads.get(&this.get_active_thread());
+        let pending = thread.map_or(0, |thread| thread.pending & thread.blocked);
+        let pending =
+            pending | (signals.process_pending & signals.blocked(this.get_active_thread()));
+        this.write_sigset(&set, pending)?;
+        Ok(0)
+    }
+
+    fn raise(&mut self, sig: &OpTy<'tcx, Provenance>) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        if !this.signal_is_valid(sig) {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+
+        let thread = this.get_active_thread();
+        this.send_signal(Some(thread), sig)?;
+        Ok(0)
+    }
+
+    /// Returns 0 on success, and the error code otherwise.
+    fn pthread_kill(
+        &mut self,
+        thread: &OpTy<'tcx, Provenance>,
+        sig: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let thread = this.read_target_usize(thread)?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        let thread = ThreadId::try_from(thread).ok().filter(|&thread| {
+            thread.index() < this.machine.threads.get_total_thread_count()
+                && !this.machine.threads.has_terminated(thread)
+        });
+        let Some(thread) = thread else {
+            return Ok(this.eval_libc_i32("ESRCH"));
+        };
+        // Signal 0 only checks whether the thread exists.
+        if sig == 0 {
+            return Ok(0);
+        }
+        if !this.signal_is_valid(sig) {
+            return Ok(this.eval_libc_i32("EINVAL"));
+        }
+
+        this.send_signal(Some(thread), sig)?;
+        Ok(0)
+    }
+
+    fn kill(
+        &mut self,
+        pid: &OpTy<'tcx, Provenance>,
+        sig: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, i32> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid)?.to_i32()?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        if sig != 0 && !this.signal_is_valid(sig) {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(-1);
+        }
+        // A `pid` of 0 stands for the process group of the caller, which as far as we know only
+        // consists of this process.
+        if pid != 0 && u32::try_from(pid).ok() != Some(std::process::id()) {
+            throw_unsup_format!("sending signals to other processes is not supported");
+        }
+        // Signal 0 only checks whether the process exists.
+        if sig == 0 {
+            return Ok(0);
+        }
+
+        this.send_signal(None, sig)?;
+        Ok(0)
+    }
+
+    /// Checks that `link_name` may be called from the active thread, i.e., that it is
+    /// async-signal-safe if a signal handler is running.
+    fn check_async_signal_safe(&self, link_name: Symbol) -> InterpResult<'tcx> {
+        let this = self.eval_context_ref();
+
+        if !this.machine.signals.in_handler(this.get_active_thread()) {
+            return Ok(());
+        }
+        let name = link_name.as_str();
+        // LLVM intrinsics, like the one behind `spin_loop`, are not calls into the C library.
+        if name.starts_with("miri_")
+            || name.starts_with("llvm.")
+            || ASYNC_SIGNAL_SAFE.contains(&name)
+        {
+            return Ok(());
+        }
+        throw_ub_format!("calling `{name}` in a signal handler, but it is not async-signal-safe")
+    }
+
+    /// Called before every step of the active thread. If the thread is at the start of a basic
+    /// block and a signal can be delivered to it, runs the action of that signal.
+    fn deliver_pending_signal(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let Some(frame) = this.active_thread_stack().last() else { return Ok(()) };
+        let Either::Left(loc) = frame.loc else { return Ok(()) };
+        if loc.statement_index != 0 {
+            return Ok(());
+        }
+        let thread = this.get_active_thread();
+        let Some((sig, thread_directed)) = this.machine.signals.take_deliverable(thread) else {
+            return Ok(());
+        };
+        this.run_signal_action(sig, thread_directed, loc.block)
+    }
+
+    /// Called when the frame of a signal handler is popped.
+    fn signal_handler_returned(&mut self, frame: SignalHandlerFrame) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let thread = this.get_active_thread();
+        let state = this.machine.signals.threads.get_mut(&thread).unwrap();
+        state.blocked = frame.blocked;
+        state.handler_depth -= 1;
+        if let Some(siginfo) = frame.siginfo {
+            this.deallocate_ptr(siginfo, None, MiriMemoryKind::Machine.into())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextPrivExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+trait EvalContextPrivExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    /// The highest signal number we accept. Sets of signals are bitmasks in a `u64`, so this
+    /// is at most 64, even where the target supports more real-time signals.
+    fn max_signal(&self) -> i32 {
+        let this = self.eval_context_ref();
+        match this.tcx.sess.target.os.as_ref() {
+            "linux" => SIGRTMAX,
+            "macos" => 31,
+            _ => 64,
+        }
+    }
+
+    fn signal_is_valid(&self, sig: i32) -> bool {
+        (1..=self.max_signal()).contains(&sig)
+    }
+
+    /// Whether the action of `sig` can be changed. Fails for `SIGKILL` and `SIGSTOP`.
+    fn signal_can_be_caught(&self, sig: i32) -> bool {
+        let this = self.eval_context_ref();
+        this.signal_is_valid(sig)
+            && sig != this.eval_libc_i32("SIGKILL")
+            && sig != this.eval_libc_i32("SIGSTOP")
+    }
+
+    fn signal_name(&self, sig: i32) -> String {
+        let this = self.eval_context_ref();
+        SIGNAL_NAMES
+            .iter()
+            .find(|name| this.eval_libc_i32(name) == sig)
+            .map_or_else(|| format!("signal {sig}"), |name| name.to_string())
+    }
+
+    fn default_action(&self, sig: i32) -> DefaultAction {
+        let this = self.eval_context_ref();
+        let is = |name| sig == this.eval_libc_i32(name);
+        // `SIGCONT` continues a stopped process, and we are not stopped.
+        if is("SIGCHLD") || is("SIGURG") || is("SIGWINCH") || is("SIGCONT") {
+            DefaultAction::Ignore
+        } else if is("SIGSTOP") || is("SIGTSTP") || is("SIGTTIN") || is("SIGTTOU") {
+            DefaultAction::Stop
+        } else {
+            DefaultAction::Terminate
+        }
+    }
+
+    fn disposition(&self, handler: Scalar<Provenance>) -> InterpResult<'tcx, Disposition> {
+        let this = self.eval_context_ref();
+        let handler = handler.to_pointer(this)?;
+        if handler.provenance.is_none() {
+            let addr = handler.addr().bytes();
+            if addr == this.eval_libc("SIG_DFL").to_target_usize(this)? {
+                return Ok(Disposition::Default);
+            }
+            if addr == this.eval_libc("SIG_IGN").to_target_usize(this)? {
+                return Ok(Disposition::Ignore);
+            }
+        }
+        Ok(Disposition::Handler)
+    }
+
+    /// Whether `sig` would currently be discarded when delivered.
+    fn signal_is_ignored(&self, sig: i32) -> InterpResult<'tcx, bool> {
+        let this = self.eval_context_ref();
+        let Some(action) = this.machine.signals.actions.get(&sig) else {
+            return Ok(matches!(this.default_action(sig), DefaultAction::Ignore));
+        };
+        Ok(match this.disposition(action.handler)? {
+            Disposition::Default => matches!(this.default_action(sig), DefaultAction::Ignore),
+            Disposition::Ignore => true,
+            Disposition::Handler => false,
+        })
+    }
+
+    /// Sets the action of `sig` (`None` meaning the default action), and returns the old one.
+    fn set_signal_action(
+        &mut self,
+        sig: i32,
+        action: Option<SignalAction>,
+    ) -> InterpResult<'tcx, Option<SignalAction>> {
+        let this = self.eval_context_mut();
+        let old = match action {
+            Some(action) => this.machine.signals.actions.insert(sig, action),
+            None => this.machine.signals.actions.remove(&sig),
+        };
+        // Pending signals that are now ignored are discarded.
+        if this.signal_is_ignored(sig)? {
+            let signals = &mut this.machine.signals;
+            signals.process_pending &= !signal_bit(sig);
+            for thread in signals.threads.values_mut() {
+                thread.pending &= !signal_bit(sig);
+            }
+        }
+        Ok(old)
+    }
+
+    /// Sends `sig` to `thread`, or to the process if that is `None`.
+    fn send_signal(&mut self, thread: Option<ThreadId>, sig: i32) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        if this.signal_is_ignored(sig)? {
+            return Ok(());
+        }
+        let signals = &mut this.machine.signals;
+        match thread {
+            Some(thread) => signals.threads.entry(thread).or_default().pending |= signal_bit(sig),
+            None => signals.process_pending |= signal_bit(sig),
+        }
+        Ok(())
+    }
+
+    /// The integer type that a `sigset_t` consists of.
+    fn sigset_word(&self) -> TyAndLayout<'tcx> {
+        let this = self.eval_context_ref();
+        match this.tcx.sess.target.os.as_ref() {
+            "linux" | "android" => this.libc_ty_layout("c_ulong"),
+            _ => this.machine.layouts.u32,
+        }
+    }
+
+    /// Reads the first 64 signals of a `sigset_t`.
+    fn read_sigset(&self, set: &MPlaceTy<'tcx, Provenance>) -> InterpResult<'tcx, u64> {
+        let this = self.eval_context_ref();
+        let word = this.sigset_word();
+        let mut mask = 0;
+        let mut offset = Size::ZERO;
+        while offset < set.layout.size.min(Size::from_bytes(8)) {
+            let bits = this.read_scalar(&set.offset(offset, word, this)?)?.to_bits(word.size)?;
+            mask |= u64::try_from(bits).unwrap() << offset.bits();
+            offset += word.size;
+        }
+        Ok(mask)
+    }
+
+    /// Writes a `sigset_t` that contains exactly the signals in `mask`.
+    fn write_sigset(&mut self, set: &MPlaceTy<'tcx, Provenance>, mask: u64) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let word = this.sigset_word();
+        this.write_bytes_ptr(set.ptr(), iter::repeat(0u8).take(set.layout.size.bytes_usize()))?;
+        let mut offset = Size::ZERO;
+        while offset < set.layout.size.min(Size::from_bytes(8)) {
+            let bits = (mask >> offset.bits()) & (u64::MAX >> (64 - word.size.bits()));
+            this.write_int(bits, &set.offset(offset, word, this)?)?;
+            offset += word.size;
+        }
+        Ok(())
+    }
+
+    /// The `si_code` of a signal sent by `kill` or `raise` (if `thread_directed`).
+    fn si_code(&self, thread_directed: bool) -> i32 {
+        let this = self.eval_context_ref();
+        // These constants are not available in the `libc` crate on all targets.
+        match (this.tcx.sess.target.os.as_ref(), thread_directed) {
+            ("linux" | "android", false) => 0, // SI_USER
+            ("linux" | "android", true) => -6, // SI_TKILL
+            ("freebsd", true) => 0x10007,      // SI_LWP
+            _ => 0x10001,                      // SI_USER
+        }
+    }
+
+    /// Runs the action of `sig` on the active thread, which has been interrupted at the start of
+    /// `block` in its topmost frame.
+    fn run_signal_action(
+        &mut self,
+        sig: i32,
+        thread_directed: bool,
+        block: mir::BasicBlock,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let Some(action) = this.machine.signals.actions.get(&sig).cloned() else {
+            return this.run_default_action(sig);
+        };
+        let handler = match this.disposition(action.handler)? {
+            Disposition::Default => return this.run_default_action(sig),
+            Disposition::Ignore => return Ok(()),
+            Disposition::Handler => action.handler.to_pointer(this)?,
+        };
+        // Handlers registered by casting a function pointer to `sighandler_t` lost their
+        // provenance; this acts like the cast back to a function pointer that C code would do.
+        let handler = match handler.provenance {
+            Some(_) => handler,
+            None => this.ptr_from_addr_cast(handler.addr().bytes())?,
+        };
+        let instance = this.get_ptr_fn(handler)?.as_instance()?;
+
+        // Block the signals of the mask, and the signal itself unless `SA_NODEFER` is set.
+        let thread = this.get_active_thread();
+        let mut mask = action.mask;
+        if action.flags & this.eval_libc_i32("SA_NODEFER") == 0 {
+            mask |= signal_bit(sig);
+        }
+        let unblockable =
+            signal_bit(this.eval_libc_i32("SIGKILL")) | signal_bit(this.eval_libc_i32("SIGSTOP"));
+        let state = this.machine.signals.threads.entry(thread).or_default();
+        let blocked = state.blocked;
+        state.blocked |= mask & !unblockable;
+        state.handler_depth += 1;
+        if action.flags & this.eval_libc_i32("SA_RESETHAND") != 0 {
+            this.set_signal_action(sig, None)?;
+        }
+
+        let mut args: Vec<Immediate<Provenance>> = vec![Scalar::from_i32(sig).into()];
+        let siginfo = if action.flags & this.eval_libc_i32("SA_SIGINFO") != 0 {
+            let siginfo_layout = this.libc_ty_layout("siginfo_t");
+            let siginfo = this.allocate(siginfo_layout, MiriMemoryKind::Machine.into())?;
+            this.write_bytes_ptr(
+                siginfo.ptr(),
+                iter::repeat(0u8).take(siginfo_layout.size.bytes_usize()),
+            )?;
+            let si_code = this.si_code(thread_directed);
+            this.write_int_fields_named(
+                &[("si_signo", sig.into()), ("si_code", si_code.into())],
+                &siginfo,
+            )?;
+            args.push(Scalar::from_maybe_pointer(siginfo.ptr(), this).into());
+            // We do not provide a `ucontext_t`.
+            args.push(Scalar::null_ptr(this).into());
+            Some(siginfo.ptr())
+        } else {
+            None
+        };
+
+        // Return to the start of the interrupted block. Unwinding out of a handler is not
+        // allowed.
+        this.call_function(
+            instance,
+            Abi::C { unwind: false },
+            &args,
+            None,
+            StackPopCleanup::Goto { ret: Some(block), unwind: mir::UnwindAction::Unreachable },
+        )?;
+        this.frame_mut().extra.signal_handler = Some(SignalHandlerFrame { blocked, siginfo });
+        Ok(())
+    }
+
+    fn run_default_action(&mut self, sig: i32) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let name = this.signal_name(sig);
+        match this.default_action(sig) {
+            DefaultAction::Ignore => Ok(()),
+            DefaultAction::Stop =>
+                throw_unsup_format!("stopping the program with {name} is not supported"),
+            DefaultAction::Terminate =>
+                throw_machine_stop!(TerminationInfo::Abort(format!(
+                    "the program was terminated by {name}"
+                ))),
+        }
+    }
+}
@@ -0,0 +1,807 @@
+use std::io::ErrorKind;
+use std::iter;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use rustc_target::abi::Size;
+
+use crate::concurrency::thread::{MachineCallback, Time};
+use crate::concurrency::VClock;
+use crate::*;
+use shims::unix::fs::EvalContextExt as _;
+use shims::unix::net::{Network, Protocol, Socket};
+
+/// The loopback network, and the threads waiting for something to happen on it.
+#[derive(Debug, Default)]
+pub struct SocketHandler<'tcx> {
+    network: Network,
+    /// Threads blocked in a socket operation that could not complete yet. Any activity on the
+    /// network wakes all of them up to retry their operation.
+    blocked: Vec<BlockedThread<'tcx>>,
+}
+
+impl VisitTags for SocketHandler<'_> {
+    fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
+        let SocketHandler { network: _, blocked } = self;
+        for blocked in blocked {
+            blocked.visit_tags(visit);
+        }
+    }
+}
+
+/// A socket operation that blocks until it can complete.
+#[derive(Clone, Debug)]
+enum SocketOp {
+    /// `accept` and `accept4`.
+    Accept {
+        fd: i32,
+        addr: Pointer<Option<Provenance>>,
+        addrlen: Pointer<Option<Provenance>>,
+        flags: i32,
+    },
+    /// `recv`, `recvfrom` and `read`.
+    Recv {
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        len: u64,
+        flags: i32,
+        addr: Pointer<Option<Provenance>>,
+        addrlen: Pointer<Option<Provenance>>,
+    },
+}
+
+#[derive(Debug)]
+struct BlockedThread<'tcx> {
+    thread: ThreadId,
+    op: SocketOp,
+    dest: PlaceTy<'tcx, Provenance>,
+}
+
+impl VisitTags for BlockedThread<'_> {
+    fn visit_tags(&self, visit: &mut dyn FnMut(BorTag)) {
+        let BlockedThread { thread: _, op, dest } = self;
+        match op {
+            SocketOp::Accept { fd: _, addr, addrlen, flags: _ } => {
+                addr.visit_tags(visit);
+                addrlen.visit_tags(visit);
+            }
+            SocketOp::Recv { fd: _, buf, len: _, flags: _, addr, addrlen } => {
+                buf.visit_tags(visit);
+                addr.visit_tags(visit);
+                addrlen.visit_tags(visit);
+            }
+        }
+        dest.visit_tags(visit);
+    }
+}
+
+impl<'mir, 'tcx: 'mir> MachineCallback<'mir, 'tcx> for BlockedThread<'tcx> {
+    fn call(&self, this: &mut MiriInterpCx<'mir, 'tcx>) -> InterpResult<'tcx> {
+        // Retry the operation. If it still cannot complete, this blocks the thread again.
+        this.unblock_thread(self.thread);
+        this.socket_op(self.op.clone(), &self.dest)
+    }
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextPrivExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+trait EvalContextPrivExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    /// Returns the socket `fd` refers to. If there is none, sets the last error and returns
+    /// `None`.
+    fn get_socket(&mut self, fd: i32) -> InterpResult<'tcx, Option<Socket>> {
+        let this = self.eval_context_mut();
+        let Some(file_descriptor) = this.machine.file_handler.handles.get(&fd) else {
+            this.handle_not_found::<i32>()?;
+            return Ok(None);
+        };
+        if let Some(socket) = file_descriptor.downcast_ref::<Socket>() {
+            Ok(Some(socket.clone()))
+        } else {
+            let enotsock = this.eval_libc("ENOTSOCK");
+            this.set_last_error(enotsock)?;
+            Ok(None)
+        }
+    }
+
+    /// Runs `op` on the active thread and writes its result to `dest`. If the operation cannot
+    /// complete yet, blocks the thread until some other thread does something on the network.
+    fn socket_op(&mut self, op: SocketOp, dest: &PlaceTy<'tcx, Provenance>) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let result = match op {
+            SocketOp::Accept { fd, addr, addrlen, flags } =>
+                this.try_accept(fd, addr, addrlen, flags)?,
+            SocketOp::Recv { fd, buf, len, flags, addr, addrlen } =>
+                this.try_recv(fd, buf, len, flags, addr, addrlen)?,
+        };
+        if let Some(result) = result {
+            this.write_scalar(result, dest)?;
+        } else {
+            let thread = this.get_active_thread();
+            this.block_thread(thread);
+            let blocked = BlockedThread { thread, op, dest: dest.clone() };
+            this.machine.socket_handler.blocked.push(blocked);
+        }
+        Ok(())
+    }
+
+    /// Wakes up all threads blocked in a socket operation. They retry their operation the next
+    /// time the scheduler runs, in the order in which they blocked.
+    fn wake_socket_waiters(&mut self) {
+        let this = self.eval_context_mut();
+        let now = this.machine.clock.now();
+        for blocked in std::mem::take(&mut this.machine.socket_handler.blocked) {
+            this.register_timeout_callback(blocked.thread, Time::Monotonic(now), Box::new(blocked));
+        }
+    }
+
+    /// Returns `None` if the operation would block.
+    fn try_accept(
+        &mut self,
+        fd: i32,
+        addr: Pointer<Option<Provenance>>,
+        addrlen: Pointer<Option<Provenance>>,
+        flags: i32,
+    ) -> InterpResult<'tcx, Option<Scalar<Provenance>>> {
+        let this = self.eval_context_mut();
+
+        let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+        let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+        if flags & !(sock_nonblock | sock_cloexec) != 0 {
+            throw_unsup_format!("unsupported flags {:#x} for `accept4`", flags);
+        }
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Some(Scalar::from_i32(-1)));
+        };
+        // Miri does not support exec, so `SOCK_CLOEXEC` has no effect.
+        let nonblocking = flags & sock_nonblock != 0;
+        match this.machine.socket_handler.network.accept(&socket, nonblocking) {
+            Ok((accepted, peer, clock)) => {
+                // Connecting to a socket happens-before accepting the connection.
+                this.acquire_socket_clock(&clock);
+                this.write_socket_addr(peer, addr, addrlen)?;
+                let fd = this.machine.file_handler.insert_fd(Box::new(accepted));
+                Ok(Some(Scalar::from_i32(fd)))
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock && !socket.is_nonblocking() => Ok(None),
+            Err(e) => {
+                this.set_last_error_from_io_error(e.kind())?;
+                Ok(Some(Scalar::from_i32(-1)))
+            }
+        }
+    }
+
+    /// Returns `None` if the operation would block.
+    fn try_recv(
+        &mut self,
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        len: u64,
+        flags: i32,
+        addr: Pointer<Option<Provenance>>,
+        addrlen: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx, Option<Scalar<Provenance>>> {
+        let this = self.eval_context_mut();
+
+        let msg_peek = this.eval_libc_i32("MSG_PEEK");
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        if flags & !(msg_peek | msg_dontwait) != 0 {
+            throw_unsup_format!("unsupported flags {:#x} for `recv`", flags);
+        }
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Some(Scalar::from_target_isize(-1, this)));
+        };
+        // Like `read`, we cap the length so that the result fits into the target's `isize`.
+        let len = len.min(u64::try_from(this.target_isize_max()).unwrap());
+        let len = usize::try_from(len).unwrap();
+        let peek = flags & msg_peek != 0;
+        match this.machine.socket_handler.network.recv(&socket, len, peek) {
+            Ok((data, from, clock)) => {
+                // Sending data happens-before receiving it.
+                this.acquire_socket_clock(&clock);
+                let read_bytes = i64::try_from(data.len()).unwrap();
+                this.write_bytes_ptr(buf, data)?;
+                this.write_socket_addr(from, addr, addrlen)?;
+                Ok(Some(Scalar::from_target_isize(read_bytes, this)))
+            }
+            Err(e)
+                if e.kind() == ErrorKind::WouldBlock
+                    && flags & msg_dontwait == 0
+                    && !socket.is_nonblocking() =>
+                Ok(None),
+            Err(e) => {
+                this.set_last_error_from_io_error(e.kind())?;
+                Ok(Some(Scalar::from_target_isize(-1, this)))
+            }
+        }
+    }
+
+    /// Sends `len` bytes at `buf` through the socket `fd`, to `to` or to the peer the socket is
+    /// connected to. Sending never blocks.
+    fn send_socket(
+        &mut self,
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        len: u64,
+        to: Option<SocketAddr>,
+    ) -> InterpResult<'tcx, i64> {
+        let this = self.eval_context_mut();
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(-1);
+        };
+        let bytes = this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(len))?.to_vec();
+        let clock = this.release_socket_clock();
+        let result = this.machine.socket_handler.network.send(&socket, &bytes, to, clock);
+        this.wake_socket_waiters();
+        this.try_unwrap_io_result(result.map(|sent| i64::try_from(sent).unwrap()))
+    }
+
+    /// Returns the clock to store with an event that other threads can synchronize with.
+    fn release_socket_clock(&mut self) -> VClock {
+        let this = self.eval_context_mut();
+        let mut clock = VClock::default();
+        if let Some(data_race) = &this.machine.data_race {
+            let thread = this.get_active_thread();
+            data_race.validate_lock_release(&mut clock, thread, this.machine.current_span());
+        }
+        clock
+    }
+
+    /// Synchronizes the active thread with an event stored with `clock`.
+    fn acquire_socket_clock(&mut self, clock: &VClock) {
+        let this = self.eval_context_mut();
+        if let Some(data_race) = &this.machine.data_race {
+            data_race.validate_lock_acquire(clock, this.get_active_thread());
+        }
+    }
+
+    /// Reads the `sockaddr_in` or `sockaddr_in6` of `len` bytes at `ptr`. Returns an
+    /// `InvalidInput` error if it is neither.
+    fn read_socket_addr(
+        &self,
+        ptr: Pointer<Option<Provenance>>,
+        len: u32,
+    ) -> InterpResult<'tcx, std::io::Result<SocketAddr>> {
+        let this = self.eval_context_ref();
+
+        let family_layout = this.libc_ty_layout("sa_family_t");
+        if u64::from(len) < family_layout.size.bytes() {
+            return Ok(Err(ErrorKind::InvalidInput.into()));
+        }
+        let family = this.read_scalar(&this.ptr_to_mplace(ptr, family_layout))?.to_u16()?;
+        let family = i32::from(family);
+
+        if family == this.eval_libc_i32("AF_INET") {
+            let layout = this.libc_ty_layout("sockaddr_in");
+            if u64::from(len) < layout.size.bytes() {
+                return Ok(Err(ErrorKind::InvalidInput.into()));
+            }
+            let place = this.ptr_to_mplace(ptr, layout);
+            let port = this.read_network_bytes::<2>(&place, "sin_port")?;
+            let ip = this.read_network_bytes::<4>(&place, "sin_addr")?;
+            Ok(Ok(SocketAddrV4::new(Ipv4Addr::from(ip), u16::from_be_bytes(port)).into()))
+        } else if family == this.eval_libc_i32("AF_INET6") {
+            let layout = this.libc_ty_layout("sockaddr_in6");
+            if u64::from(len) < layout.size.bytes() {
+                return Ok(Err(ErrorKind::InvalidInput.into()));
+            }
+            let place = this.ptr_to_mplace(ptr, layout);
+            let port = this.read_network_bytes::<2>(&place, "sin6_port")?;
+            let flowinfo = this.read_network_bytes::<4>(&place, "sin6_flowinfo")?;
+            let ip = this.read_network_bytes::<16>(&place, "sin6_addr")?;
+            let scope_id = this.project_field_named(&place, "sin6_scope_id")?;
+            let scope_id = this.read_scalar(&scope_id)?.to_u32()?;
+            Ok(Ok(SocketAddrV6::new(
+                Ipv6Addr::from(ip),
+                u16::from_be_bytes(port),
+                u32::from_be_bytes(flowinfo),
+                scope_id,
+            )
+            .into()))
+        } else {
+            Ok(Err(ErrorKind::InvalidInput.into()))
+        }
+    }
+
+    /// Writes `addr` to the buffer at `ptr`, whose size is stored at `len_ptr`, and stores the
+    /// size of the address at `len_ptr`. Like on Linux, the address is truncated if the buffer is
+    /// too small. Does nothing if `ptr` is null.
+    fn write_socket_addr(
+        &mut self,
+        addr: SocketAddr,
+        ptr: Pointer<Option<Provenance>>,
+        len_ptr: Pointer<Option<Provenance>>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        if this.ptr_is_null(ptr)? {
+            return Ok(());
+        }
+        let len_place = this.ptr_to_mplace(len_ptr, this.libc_ty_layout("socklen_t"));
+        let capacity = this.read_scalar(&len_place)?.to_u32()?;
+
+        // Build the address in a temporary allocation, so that it can be truncated.
+        let layout = match addr {
+            SocketAddr::V4(_) => this.libc_ty_layout("sockaddr_in"),
+            SocketAddr::V6(_) => this.libc_ty_layout("sockaddr_in6"),
+        };
+        let place = this.allocate(layout, MiriMemoryKind::Machine.into())?;
+        let size = layout.size.bytes();
+        this.write_bytes_ptr(place.ptr(), iter::repeat(0u8).take(usize::try_from(size).unwrap()))?;
+        match addr {
+            SocketAddr::V4(addr) => {
+                let family = this.eval_libc_i32("AF_INET");
+                this.write_int_fields_named(&[("sin_family", family.into())], &place)?;
+                this.write_network_bytes(&place, "sin_port", &addr.port().to_be_bytes())?;
+                this.write_network_bytes(&place, "sin_addr", &addr.ip().octets())?;
+            }
+            SocketAddr::V6(addr) => {
+                let family = this.eval_libc_i32("AF_INET6");
+                this.write_int_fields_named(
+                    &[("sin6_family", family.into()), ("sin6_scope_id", addr.scope_id().into())],
+                    &place,
+                )?;
+                this.write_network_bytes(&place, "sin6_port", &addr.port().to_be_bytes())?;
+                this.write_network_bytes(&place, "sin6_flowinfo", &addr.flowinfo().to_be_bytes())?;
+                this.write_network_bytes(&place, "sin6_addr", &addr.ip().octets())?;
+            }
+        }
+        let copied = Size::from_bytes(size.min(capacity.into()));
+        this.mem_copy(place.ptr(), ptr, copied, /*nonoverlapping*/ true)?;
+        this.deallocate_ptr(place.ptr(), None, MiriMemoryKind::Machine.into())?;
+
+        this.write_scalar(Scalar::from_u32(u32::try_from(size).unwrap()), &len_place)?;
+        Ok(())
+    }
+
+    /// Reads the bytes of a field that is stored in network byte order.
+    fn read_network_bytes<const N: usize>(
+        &self,
+        place: &MPlaceTy<'tcx, Provenance>,
+        field: &str,
+    ) -> InterpResult<'tcx, [u8; N]> {
+        let this = self.eval_context_ref();
+        let field = this.project_field_named(place, field)?;
+        let bytes = this.read_bytes_ptr_strip_provenance(field.ptr(), Size::from_bytes(N))?;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    /// Writes the bytes of a field that is stored in network byte order.
+    fn write_network_bytes(
+        &mut self,
+        place: &MPlaceTy<'tcx, Provenance>,
+        field: &str,
+        bytes: &[u8],
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let field = this.project_field_named(place, field)?;
+        this.write_bytes_ptr(field.ptr(), bytes.iter().copied())
+    }
+}
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub trait EvalContextExt<'mir, 'tcx: 'mir>: crate::MiriInterpCxExt<'mir, 'tcx> {
+    /// Creates a TCP or UDP socket on the loopback network.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/socket.2.html>
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx, Provenance>,
+        type_: &OpTy<'tcx, Provenance>,
+        protocol: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let type_ = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let ipv6 = if domain == this.eval_libc_i32("AF_INET") {
+            false
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            true
+        } else {
+            throw_unsup_format!("`socket` is only supported for AF_INET and AF_INET6");
+        };
+
+        // The type can be combined with flags.
+        let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+        let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+        let flags = type_ & (sock_nonblock | sock_cloexec);
+        let type_ = type_ & !flags;
+        let (socket_protocol, default_protocol) = if type_ == this.eval_libc_i32("SOCK_STREAM") {
+            (Protocol::Stream, this.eval_libc_i32("IPPROTO_TCP"))
+        } else if type_ == this.eval_libc_i32("SOCK_DGRAM") {
+            (Protocol::Datagram, this.eval_libc_i32("IPPROTO_UDP"))
+        } else {
+            throw_unsup_format!("`socket` is only supported for SOCK_STREAM and SOCK_DGRAM");
+        };
+        if protocol != 0 && protocol != default_protocol {
+            throw_unsup_format!("unsupported protocol {protocol} for `socket`");
+        }
+
+        // Miri does not support exec, so `SOCK_CLOEXEC` has no effect.
+        let socket = Socket::new(socket_protocol, ipv6, flags & sock_nonblock != 0);
+        let fd = this.machine.file_handler.insert_fd(Box::new(socket));
+        Ok(Scalar::from_i32(fd))
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/bind.2.html>
+    fn bind(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_scalar(addrlen)?.to_u32()?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let result = match this.read_socket_addr(addr, addrlen)? {
+            Ok(addr) => this.machine.socket_handler.network.bind(&socket, addr).map(|()| 0),
+            Err(e) => Err(e),
+        };
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+    }
+
+    /// The size of the backlog is ignored: connections are never refused because of it.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/listen.2.html>
+    fn listen(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        backlog: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let result = this.machine.socket_handler.network.listen(&socket).map(|()| 0);
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+    }
+
+    /// Blocks until a connection arrives, unless the socket is non-blocking.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/accept.2.html>
+    fn accept4(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+        flags: Option<&OpTy<'tcx, Provenance>>,
+        dest: &PlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+        let flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+
+        this.socket_op(SocketOp::Accept { fd, addr, addrlen, flags }, dest)
+    }
+
+    /// Connecting a TCP socket succeeds as soon as there is a socket listening on the address;
+    /// it does not wait for the connection to be accepted.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/connect.2.html>
+    fn connect(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_scalar(addrlen)?.to_u32()?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let result = match this.read_socket_addr(addr, addrlen)? {
+            Ok(addr) => {
+                let clock = this.release_socket_clock();
+                let result = this.machine.socket_handler.network.connect(&socket, addr, clock);
+                this.wake_socket_waiters();
+                result.map(|()| 0)
+            }
+            Err(e) => Err(e),
+        };
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/getsockname.2.html>
+    fn getsockname(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        this.write_socket_addr(socket.local_addr(), addr, addrlen)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/getpeername.2.html>
+    fn getpeername(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        addr: &OpTy<'tcx, Provenance>,
+        addrlen: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let addr = this.read_pointer(addr)?;
+        let addrlen = this.read_pointer(addrlen)?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let result = match socket.peer_addr() {
+            Ok(peer) => {
+                this.write_socket_addr(peer, addr, addrlen)?;
+                Ok(0)
+            }
+            Err(e) => Err(e),
+        };
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+    }
+
+    /// Only the options set by the standard library are supported, and since data on the
+    /// loopback network is delivered immediately, none of them has any effect.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/setsockopt.2.html>
+    fn setsockopt(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+        let _value = this.read_pointer(value)?;
+        let _len = this.read_scalar(len)?.to_u32()?;
+
+        let Some(_socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let supported = [("SOL_SOCKET", "SO_REUSEADDR"), ("IPPROTO_TCP", "TCP_NODELAY")];
+        if !supported.iter().any(|&(supported_level, supported_name)| {
+            level == this.eval_libc_i32(supported_level)
+                && name == this.eval_libc_i32(supported_name)
+        }) {
+            throw_unsup_format!("unsupported socket option {name} at level {level}");
+        }
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Only `SO_ERROR` is supported. Errors are always reported right away, so there never is a
+    /// pending error.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/getsockopt.2.html>
+    fn getsockopt(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        level: &OpTy<'tcx, Provenance>,
+        name: &OpTy<'tcx, Provenance>,
+        value: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let name = this.read_scalar(name)?.to_i32()?;
+
+        let Some(_socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        if level != this.eval_libc_i32("SOL_SOCKET") || name != this.eval_libc_i32("SO_ERROR") {
+            throw_unsup_format!("unsupported socket option {name} at level {level}");
+        }
+        let len = this.deref_pointer_as(len, this.libc_ty_layout("socklen_t"))?;
+        if this.read_scalar(&len)?.to_u32()? < 4 {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(Scalar::from_i32(-1));
+        }
+        let value = this.deref_pointer_as(value, this.machine.layouts.i32)?;
+        this.write_scalar(Scalar::from_i32(0), &value)?;
+        this.write_scalar(Scalar::from_u32(4), &len)?;
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Sending never blocks: the data is delivered immediately.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/send.2.html>
+    fn sendto(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        buf: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+        flags: &OpTy<'tcx, Provenance>,
+        addr: Option<(&OpTy<'tcx, Provenance>, &OpTy<'tcx, Provenance>)>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+
+        // There are no signals, so `MSG_NOSIGNAL` has no effect; and sending never blocks.
+        let msg_nosignal = this.eval_libc_i32("MSG_NOSIGNAL");
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        if flags & !(msg_nosignal | msg_dontwait) != 0 {
+            throw_unsup_format!("unsupported flags {:#x} for `send`", flags);
+        }
+
+        let to = match addr {
+            Some((addr, addrlen)) => {
+                let addr = this.read_pointer(addr)?;
+                let addrlen = this.read_scalar(addrlen)?.to_u32()?;
+                if this.ptr_is_null(addr)? {
+                    None
+                } else {
+                    match this.read_socket_addr(addr, addrlen)? {
+                        Ok(addr) => Some(addr),
+                        Err(e) => {
+                            this.set_last_error_from_io_error(e.kind())?;
+                            return Ok(Scalar::from_target_isize(-1, this));
+                        }
+                    }
+                }
+            }
+            None => None,
+        };
+        let result = this.send_socket(fd, buf, len, to)?;
+        Ok(Scalar::from_target_isize(result, this))
+    }
+
+    /// Blocks until data arrives, unless the socket is non-blocking.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/recv.2.html>
+    fn recvfrom(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        buf: &OpTy<'tcx, Provenance>,
+        len: &OpTy<'tcx, Provenance>,
+        flags: &OpTy<'tcx, Provenance>,
+        addr: Option<(&OpTy<'tcx, Provenance>, &OpTy<'tcx, Provenance>)>,
+        dest: &PlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let len = this.read_target_usize(len)?;
+        let flags = this.read_scalar(flags)?.to_i32()?;
+        let (addr, addrlen) = match addr {
+            Some((addr, addrlen)) => (this.read_pointer(addr)?, this.read_pointer(addrlen)?),
+            None => (Pointer::null(), Pointer::null()),
+        };
+
+        this.socket_op(SocketOp::Recv { fd, buf, len, flags, addr, addrlen }, dest)
+    }
+
+    /// <https://man7.org/linux/man-pages/man2/shutdown.2.html>
+    fn shutdown(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        how: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let how = this.read_scalar(how)?.to_i32()?;
+
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let (read, write) = if how == this.eval_libc_i32("SHUT_RD") {
+            (true, false)
+        } else if how == this.eval_libc_i32("SHUT_WR") {
+            (false, true)
+        } else if how == this.eval_libc_i32("SHUT_RDWR") {
+            (true, true)
+        } else {
+            let einval = this.eval_libc("EINVAL");
+            this.set_last_error(einval)?;
+            return Ok(Scalar::from_i32(-1));
+        };
+        let result = this.machine.socket_handler.network.shutdown(&socket, read, write);
+        this.wake_socket_waiters();
+        Ok(Scalar::from_i32(this.try_unwrap_io_result(result.map(|()| 0))?))
+    }
+
+    /// Only `FIONBIO` on sockets is supported, which is what the standard library uses to make
+    /// sockets non-blocking.
+    ///
+    /// <https://man7.org/linux/man-pages/man2/ioctl.2.html>
+    fn ioctl(
+        &mut self,
+        fd: &OpTy<'tcx, Provenance>,
+        request: &OpTy<'tcx, Provenance>,
+        arg: &OpTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, Scalar<Provenance>> {
+        let this = self.eval_context_mut();
+
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let request = this.read_scalar(request)?;
+
+        if request != this.eval_libc("FIONBIO") {
+            throw_unsup_format!("`ioctl` is only supported for FIONBIO");
+        }
+        let Some(socket) = this.get_socket(fd)? else {
+            return Ok(Scalar::from_i32(-1));
+        };
+        let nonblocking = this.deref_pointer_as(arg, this.machine.layouts.i32)?;
+        let nonblocking = this.read_scalar(&nonblocking)?.to_i32()?;
+        socket.set_nonblocking(nonblocking != 0);
+        Ok(Scalar::from_i32(0))
+    }
+
+    /// Whether `fd` is a socket. `read`, `write` and `close` on sockets are handled here.
+    fn is_socket(&self, fd: i32) -> bool {
+        let this = self.eval_context_ref();
+        let file_descriptor = this.machine.file_handler.handles.get(&fd);
+        file_descriptor.is_some_and(|fd| fd.downcast_ref::<Socket>().is_some())
+    }
+
+    /// `read` on a socket, which is `recv` without flags.
+    fn read_socket(
+        &mut self,
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        count: u64,
+        dest: &PlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let (addr, addrlen) = (Pointer::null(), Pointer::null());
+        this.socket_op(SocketOp::Recv { fd, buf, len: count, flags: 0, addr, addrlen }, dest)
+    }
+
+    /// `write` on a socket, which is `send` without flags.
+    fn write_socket(
+        &mut self,
+        fd: i32,
+        buf: Pointer<Option<Provenance>>,
+        count: u64,
+    ) -> InterpResult<'tcx, i64> {
+        self.eval_context_mut().send_socket(fd, buf, count, None)
+    }
+
+    /// Called after a socket has been closed, since that may unblock threads waiting for its
+    /// peer.
+    fn socket_closed(&mut self) {
+        self.eval_context_mut().wake_socket_waiters();
+    }
+}
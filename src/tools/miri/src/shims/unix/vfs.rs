@@ -0,0 +1,649 @@
+//! A deterministic in-memory file system, used by the file system shims instead of the host file
+//! system when isolation is enabled and `-Zmiri-virtual-fs` is set.
+//!
+//! The file system starts out with only `/` and `/tmp`, plus a copy of the host directory given
+//! with `-Zmiri-virtual-fs-snapshot`. It has no symbolic links, permissions or timestamps, inode
+//! numbers are handed out in creation order, and directories list their entries sorted by name,
+//! so a program sees the exact same file system in every run.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, ErrorKind, SeekFrom};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use rustc_middle::ty::TyCtxt;
+
+use crate::shims::unix::fs::FileDescriptor;
+use crate::*;
+
+/// The kind of a file of the virtual file system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Dir,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Dir {
+        ino: u64,
+    },
+    /// A regular file. The contents are shared with the descriptors opened on it, which keep
+    /// working after the file is removed, like on a real file system.
+    File {
+        ino: u64,
+        contents: Rc<RefCell<Vec<u8>>>,
+    },
+}
+
+impl Node {
+    fn ino(&self) -> u64 {
+        match self {
+            Node::Dir { ino } | Node::File { ino, .. } => *ino,
+        }
+    }
+
+    fn metadata(&self) -> Metadata {
+        match self {
+            Node::Dir { .. } => Metadata { file_type: FileType::Dir, size: 0 },
+            Node::File { contents, .. } =>
+                Metadata {
+                    file_type: FileType::File,
+                    size: u64::try_from(contents.borrow().len()).unwrap(),
+                },
+        }
+    }
+}
+
+/// What `stat` reports about a file of the virtual file system.
+#[derive(Debug)]
+pub struct Metadata {
+    pub file_type: FileType,
+    pub size: u64,
+}
+
+/// An entry of a virtual directory, as returned by `readdir`.
+#[derive(Debug)]
+pub struct DirEntry {
+    pub name: OsString,
+    pub ino: u64,
+    pub file_type: FileType,
+}
+
+/// The flags that `open` supports, in a form that can be used for both the virtual and the host
+/// file system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpenOptions {
+    pub read: bool,
+    pub write: bool,
+    pub append: bool,
+    pub truncate: bool,
+    pub create: bool,
+    pub create_new: bool,
+}
+
+impl OpenOptions {
+    pub fn to_host(self) -> fs::OpenOptions {
+        let mut options = fs::OpenOptions::new();
+        options
+            .read(self.read)
+            .write(self.write)
+            .append(self.append)
+            .truncate(self.truncate)
+            .create(self.create)
+            .create_new(self.create_new);
+        options
+    }
+}
+
+/// A copy of a host directory, taken when the command line is parsed so that a directory that
+/// can't be read is reported right away.
+#[derive(Clone, Debug, Default)]
+pub struct VfsSnapshot {
+    /// The directories and the contents of the files, relative to the copied directory. Every
+    /// directory comes before its entries, which are sorted by name so that the inode numbers
+    /// do not depend on the host.
+    entries: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl VfsSnapshot {
+    pub fn read(host_dir: &Path) -> io::Result<VfsSnapshot> {
+        let mut snapshot = VfsSnapshot::default();
+        let mut ancestors = vec![fs::canonicalize(host_dir)?];
+        snapshot.copy_from_host(host_dir, Path::new(""), &mut ancestors)?;
+        Ok(snapshot)
+    }
+
+    /// Copies the entries of `host_dir` to `dir`. `ancestors` holds the canonical paths of
+    /// `host_dir` and of the copied directories containing it.
+    fn copy_from_host(
+        &mut self,
+        host_dir: &Path,
+        dir: &Path,
+        ancestors: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        let mut entries = fs::read_dir(host_dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let host_path = entry.path();
+            let path = dir.join(entry.file_name());
+            // Symbolic links are followed, since the virtual file system has none. Dangling links,
+            // as well as sockets, devices and the like, are left out.
+            let Ok(metadata) = fs::metadata(&host_path) else { continue };
+            if metadata.is_dir() {
+                // So are links to a directory containing them, which would be copied forever.
+                let canonical = fs::canonicalize(&host_path)?;
+                if ancestors.contains(&canonical) {
+                    continue;
+                }
+                self.entries.push((path.clone(), None));
+                ancestors.push(canonical);
+                self.copy_from_host(&host_path, &path, ancestors)?;
+                ancestors.pop();
+            } else if metadata.is_file() {
+                self.entries.push((path, Some(fs::read(&host_path)?)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Vfs {
+    /// All files and directories, indexed by their absolute path without `.` and `..`
+    /// components. Paths are ordered component-wise, so the entries below a directory directly
+    /// follow it, sorted by name.
+    nodes: BTreeMap<PathBuf, Node>,
+    /// The working directory that relative paths are resolved against.
+    cwd: PathBuf,
+    /// The inode number of the next file or directory to be created.
+    next_ino: u64,
+}
+
+impl Vfs {
+    /// Creates a file system holding `/`, `/tmp` and the contents of `snapshot`.
+    pub fn new(snapshot: Option<&VfsSnapshot>) -> Vfs {
+        let root = PathBuf::from("/");
+        let mut vfs = Vfs { nodes: BTreeMap::new(), cwd: root.clone(), next_ino: 1 };
+        vfs.insert_dir(root.clone());
+        if let Some(snapshot) = snapshot {
+            for (path, contents) in &snapshot.entries {
+                match contents {
+                    None => vfs.insert_dir(root.join(path)),
+                    Some(contents) => {
+                        vfs.insert_file(root.join(path), contents.clone());
+                    }
+                }
+            }
+        }
+        // `std::env::temp_dir()` is `/tmp` when the `TMPDIR` variable is not set.
+        let tmp = root.join("tmp");
+        if !vfs.nodes.contains_key(&tmp) {
+            vfs.insert_dir(tmp);
+        }
+        vfs
+    }
+
+    fn next_ino(&mut self) -> u64 {
+        let ino = self.next_ino;
+        self.next_ino = ino.checked_add(1).unwrap();
+        ino
+    }
+
+    fn insert_dir(&mut self, path: PathBuf) {
+        let node = Node::Dir { ino: self.next_ino() };
+        self.nodes.insert(path, node);
+    }
+
+    fn insert_file(&mut self, path: PathBuf, contents: Vec<u8>) -> Node {
+        let node = Node::File { ino: self.next_ino(), contents: Rc::new(RefCell::new(contents)) };
+        self.nodes.insert(path, node.clone());
+        node
+    }
+
+    /// Turns `path` into a key of `nodes`. Since there are no symbolic links, a `..` component
+    /// can just remove the component before it.
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        if path.as_os_str().is_empty() {
+            return Err(ErrorKind::NotFound.into());
+        }
+        let mut resolved = self.cwd.clone();
+        for component in path.components() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => resolved = PathBuf::from("/"),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::Normal(name) => resolved.push(name),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Returns the node at the resolved `path`, or the error a lookup of `path` fails with.
+    fn node(&self, path: &Path) -> io::Result<&Node> {
+        match self.nodes.get(path) {
+            Some(node) => Ok(node),
+            None => {
+                self.check_parent(path)?;
+                Err(ErrorKind::NotFound.into())
+            }
+        }
+    }
+
+    /// Checks that the parent of the resolved `path` is a directory, so that `path` can be
+    /// created.
+    fn check_parent(&self, path: &Path) -> io::Result<()> {
+        let Some(parent) = path.parent() else { return Ok(()) };
+        match self.nodes.get(parent) {
+            Some(Node::Dir { .. }) => Ok(()),
+            Some(Node::File { .. }) => Err(ErrorKind::NotADirectory.into()),
+            None => {
+                self.check_parent(parent)?;
+                Err(ErrorKind::NotFound.into())
+            }
+        }
+    }
+
+    /// Returns the entries below the resolved directory `dir`, at any depth.
+    fn descendants<'a>(
+        &'a self,
+        dir: &'a Path,
+    ) -> impl Iterator<Item = (&'a PathBuf, &'a Node)> + 'a {
+        self.nodes
+            .range::<Path, _>((Bound::Excluded(dir), Bound::Unbounded))
+            .take_while(move |(path, _)| path.starts_with(dir))
+    }
+
+    pub fn open(&mut self, path: &Path, options: OpenOptions) -> io::Result<VirtualFile> {
+        let path = self.resolve(path)?;
+        let writable = options.write || options.append;
+        let node = match self.nodes.get(&path) {
+            Some(_) if options.create_new => return Err(ErrorKind::AlreadyExists.into()),
+            Some(Node::Dir { .. }) if writable => return Err(ErrorKind::IsADirectory.into()),
+            Some(node) => node.clone(),
+            None if options.create || options.create_new => {
+                self.check_parent(&path)?;
+                self.insert_file(path, Vec::new())
+            }
+            None => return Err(self.node(&path).unwrap_err()),
+        };
+        if options.truncate
+            && let Node::File { contents, .. } = &node
+        {
+            contents.borrow_mut().clear();
+        }
+        Ok(VirtualFile {
+            node,
+            offset: Rc::new(Cell::new(0)),
+            readable: options.read,
+            writable,
+            append: options.append,
+        })
+    }
+
+    pub fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        Ok(self.node(&self.resolve(path)?)?.metadata())
+    }
+
+    pub fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let path = self.resolve(path)?;
+        if let Node::File { .. } = self.node(&path)? {
+            return Err(ErrorKind::NotADirectory.into());
+        }
+        Ok(self
+            .descendants(&path)
+            .filter(|(entry, _)| entry.parent() == Some(&path))
+            .map(|(entry, node)| {
+                DirEntry {
+                    name: entry.file_name().unwrap().to_owned(),
+                    ino: node.ino(),
+                    file_type: node.metadata().file_type,
+                }
+            })
+            .collect())
+    }
+
+    pub fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        if self.nodes.contains_key(&path) {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
+        self.check_parent(&path)?;
+        self.insert_dir(path);
+        Ok(())
+    }
+
+    pub fn remove_dir(&mut self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        if let Node::File { .. } = self.node(&path)? {
+            return Err(ErrorKind::NotADirectory.into());
+        }
+        if path.parent().is_none() {
+            return Err(ErrorKind::ResourceBusy.into());
+        }
+        if self.descendants(&path).next().is_some() {
+            return Err(ErrorKind::DirectoryNotEmpty.into());
+        }
+        self.nodes.remove(&path);
+        Ok(())
+    }
+
+    pub fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        if let Node::Dir { .. } = self.node(&path)? {
+            return Err(ErrorKind::IsADirectory.into());
+        }
+        self.nodes.remove(&path);
+        Ok(())
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let from = self.resolve(from)?;
+        let to = self.resolve(to)?;
+        let from_is_dir = matches!(self.node(&from)?, Node::Dir { .. });
+        self.check_parent(&to)?;
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(&from) {
+            // A directory cannot become its own subdirectory.
+            return Err(ErrorKind::InvalidInput.into());
+        }
+        match self.nodes.get(&to) {
+            Some(Node::Dir { .. }) if !from_is_dir => return Err(ErrorKind::IsADirectory.into()),
+            Some(Node::File { .. }) if from_is_dir => return Err(ErrorKind::NotADirectory.into()),
+            Some(Node::Dir { .. }) if self.descendants(&to).next().is_some() =>
+                return Err(ErrorKind::DirectoryNotEmpty.into()),
+            _ => {}
+        }
+        let mut moved = vec![from.clone()];
+        moved.extend(self.descendants(&from).map(|(path, _)| path.clone()));
+        for path in moved {
+            let node = self.nodes.remove(&path).unwrap();
+            let relative = path.strip_prefix(&from).unwrap();
+            let new_path =
+                if relative.as_os_str().is_empty() { to.clone() } else { to.join(relative) };
+            self.nodes.insert(new_path, node);
+        }
+        Ok(())
+    }
+
+    /// Resolves `path` like `realpath` does.
+    pub fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = self.resolve(path)?;
+        self.node(&path)?;
+        Ok(path)
+    }
+
+    /// Reads a symbolic link. Since there are none, this only fails in the right way.
+    pub fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.node(&self.resolve(path)?)?;
+        Err(ErrorKind::InvalidInput.into())
+    }
+
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    pub fn set_cwd(&mut self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path)?;
+        if let Node::File { .. } = self.node(&path)? {
+            return Err(ErrorKind::NotADirectory.into());
+        }
+        self.cwd = path;
+        Ok(())
+    }
+}
+
+/// A file descriptor for a file or directory of the virtual file system.
+#[derive(Clone, Debug)]
+pub struct VirtualFile {
+    node: Node,
+    /// The offset is shared with the duplicates of this descriptor.
+    offset: Rc<Cell<u64>>,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+impl VirtualFile {
+    pub fn metadata(&self) -> Metadata {
+        self.node.metadata()
+    }
+
+    /// Truncates or extends the file, like `ftruncate`.
+    pub fn set_len(&self, len: u64) -> io::Result<()> {
+        match &self.node {
+            Node::File { contents, .. } if self.writable => {
+                let len = usize::try_from(len).map_err(|_| ErrorKind::FileTooLarge)?;
+                contents.borrow_mut().resize(len, 0);
+                Ok(())
+            }
+            _ => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+
+    fn read_bytes(&self, bytes: &mut [u8]) -> io::Result<usize> {
+        let Node::File { contents, .. } = &self.node else {
+            return Err(ErrorKind::IsADirectory.into());
+        };
+        let contents = contents.borrow();
+        let offset = self.offset.get();
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(contents.len());
+        let len = bytes.len().min(contents.len() - start);
+        bytes[..len].copy_from_slice(&contents[start..][..len]);
+        self.offset.set(offset.checked_add(u64::try_from(len).unwrap()).unwrap());
+        Ok(len)
+    }
+
+    fn write_bytes(&self, bytes: &[u8]) -> io::Result<usize> {
+        let Node::File { contents, .. } = &self.node else {
+            return Err(ErrorKind::IsADirectory.into());
+        };
+        let mut contents = contents.borrow_mut();
+        let start = if self.append {
+            contents.len()
+        } else {
+            usize::try_from(self.offset.get()).map_err(|_| ErrorKind::FileTooLarge)?
+        };
+        let end = start.checked_add(bytes.len()).ok_or(ErrorKind::FileTooLarge)?;
+        if contents.len() < end {
+            // Writing past the end of the file fills the gap with zeros.
+            contents.resize(end, 0);
+        }
+        contents[start..end].copy_from_slice(bytes);
+        self.offset.set(u64::try_from(end).unwrap());
+        Ok(bytes.len())
+    }
+
+    fn seek_to(&self, offset: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match offset {
+            SeekFrom::Start(offset) => (offset, 0),
+            SeekFrom::Current(delta) => (self.offset.get(), delta),
+            SeekFrom::End(delta) => (self.metadata().size, delta),
+        };
+        let offset = base.checked_add_signed(delta).ok_or(ErrorKind::InvalidInput)?;
+        self.offset.set(offset);
+        Ok(offset)
+    }
+}
+
+impl FileDescriptor for VirtualFile {
+    fn name(&self) -> &'static str {
+        "virtual FILE"
+    }
+
+    fn read<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        bytes: &mut [u8],
+        _tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        if !self.readable {
+            throw_unsup_format!("cannot read from a virtual file that was not opened for reading");
+        }
+        Ok(self.read_bytes(bytes))
+    }
+
+    fn write<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        bytes: &[u8],
+        _tcx: TyCtxt<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<usize>> {
+        if !self.writable {
+            throw_unsup_format!("cannot write to a virtual file that was not opened for writing");
+        }
+        Ok(self.write_bytes(bytes))
+    }
+
+    fn seek<'tcx>(
+        &mut self,
+        _communicate_allowed: bool,
+        offset: SeekFrom,
+    ) -> InterpResult<'tcx, io::Result<u64>> {
+        Ok(self.seek_to(offset))
+    }
+
+    fn close<'tcx>(
+        self: Box<Self>,
+        _communicate_allowed: bool,
+    ) -> InterpResult<'tcx, io::Result<i32>> {
+        Ok(Ok(0))
+    }
+
+    fn dup(&mut self) -> io::Result<Box<dyn FileDescriptor>> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(vfs: &mut Vfs, path: &str) -> VirtualFile {
+        let options = OpenOptions { read: true, write: true, create: true, ..Default::default() };
+        vfs.open(Path::new(path), options).unwrap()
+    }
+
+    fn names(vfs: &Vfs, path: &str) -> Vec<String> {
+        let entries = vfs.read_dir(Path::new(path)).unwrap();
+        entries.into_iter().map(|entry| entry.name.into_string().unwrap()).collect()
+    }
+
+    fn kind<T>(result: io::Result<T>) -> ErrorKind {
+        result.err().unwrap().kind()
+    }
+
+    #[test]
+    fn resolve() {
+        let mut vfs = Vfs::new(None);
+        vfs.create_dir(Path::new("/a")).unwrap();
+        vfs.set_cwd(Path::new("a")).unwrap();
+        assert_eq!(vfs.resolve(Path::new("b/./c/..")).unwrap(), Path::new("/a/b"));
+        assert_eq!(vfs.resolve(Path::new("../../..")).unwrap(), Path::new("/"));
+        assert_eq!(vfs.resolve(Path::new("/tmp")).unwrap(), Path::new("/tmp"));
+        assert_eq!(kind(vfs.resolve(Path::new(""))), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn errors() {
+        let mut vfs = Vfs::new(None);
+        open(&mut vfs, "/tmp/file");
+        assert_eq!(kind(vfs.metadata(Path::new("/a/file"))), ErrorKind::NotFound);
+        assert_eq!(kind(vfs.metadata(Path::new("/tmp/file/a"))), ErrorKind::NotADirectory);
+        assert_eq!(kind(vfs.create_dir(Path::new("/tmp"))), ErrorKind::AlreadyExists);
+        assert_eq!(kind(vfs.create_dir(Path::new("/a/b"))), ErrorKind::NotFound);
+        assert_eq!(kind(vfs.remove_file(Path::new("/tmp"))), ErrorKind::IsADirectory);
+        assert_eq!(kind(vfs.remove_dir(Path::new("/tmp"))), ErrorKind::DirectoryNotEmpty);
+        assert_eq!(kind(vfs.remove_dir(Path::new("/tmp/file"))), ErrorKind::NotADirectory);
+        assert_eq!(
+            kind(vfs.rename(Path::new("/tmp"), Path::new("/tmp/a"))),
+            ErrorKind::InvalidInput
+        );
+        let write = OpenOptions { write: true, ..Default::default() };
+        assert_eq!(kind(vfs.open(Path::new("/tmp"), write)), ErrorKind::IsADirectory);
+        let create_new = OpenOptions { write: true, create_new: true, ..Default::default() };
+        assert_eq!(kind(vfs.open(Path::new("/tmp/file"), create_new)), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn read_dir() {
+        let mut vfs = Vfs::new(None);
+        for name in ["b", "a-b", "a", "c"] {
+            open(&mut vfs, &format!("/tmp/{name}"));
+        }
+        vfs.create_dir(Path::new("/tmp/d")).unwrap();
+        open(&mut vfs, "/tmp/d/e");
+        assert_eq!(names(&vfs, "/tmp"), ["a", "a-b", "b", "c", "d"]);
+        assert_eq!(names(&vfs, "/"), ["tmp"]);
+    }
+
+    #[test]
+    fn rename_dir() {
+        let mut vfs = Vfs::new(None);
+        vfs.create_dir(Path::new("/tmp/a")).unwrap();
+        vfs.create_dir(Path::new("/tmp/a/b")).unwrap();
+        open(&mut vfs, "/tmp/a/b/c").write_bytes(b"hello").unwrap();
+        vfs.create_dir(Path::new("/tmp/d")).unwrap();
+        vfs.rename(Path::new("/tmp/a"), Path::new("/tmp/d")).unwrap();
+        assert_eq!(names(&vfs, "/tmp"), ["d"]);
+        assert_eq!(vfs.metadata(Path::new("/tmp/d/b/c")).unwrap().size, 5);
+        assert_eq!(kind(vfs.metadata(Path::new("/tmp/a"))), ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn snapshot() {
+        let host_dir =
+            std::env::temp_dir().join(format!("miri-vfs-snapshot-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&host_dir);
+        fs::create_dir_all(host_dir.join("b/c")).unwrap();
+        fs::write(host_dir.join("a"), "hello").unwrap();
+        // Links to a directory containing them are left out, other links are followed.
+        std::os::unix::fs::symlink(&host_dir, host_dir.join("b/c/loop")).unwrap();
+        std::os::unix::fs::symlink(host_dir.join("a"), host_dir.join("b/d")).unwrap();
+        std::os::unix::fs::symlink(host_dir.join("missing"), host_dir.join("e")).unwrap();
+        let snapshot = VfsSnapshot::read(&host_dir);
+        fs::remove_dir_all(&host_dir).unwrap();
+
+        let vfs = Vfs::new(Some(&snapshot.unwrap()));
+        assert_eq!(names(&vfs, "/"), ["a", "b", "tmp"]);
+        assert_eq!(names(&vfs, "/b"), ["c", "d"]);
+        assert!(names(&vfs, "/b/c").is_empty());
+        assert_eq!(vfs.metadata(Path::new("/b/d")).unwrap().size, 5);
+        assert_eq!(kind(VfsSnapshot::read(&host_dir)), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn read_write_seek() {
+        let mut vfs = Vfs::new(None);
+        let file = open(&mut vfs, "/tmp/file");
+        let dup = file.clone();
+        assert_eq!(file.write_bytes(b"hello").unwrap(), 5);
+        // The offset is shared with the duplicate.
+        assert_eq!(dup.seek_to(SeekFrom::Current(-4)).unwrap(), 1);
+        let mut buf = [0; 8];
+        assert_eq!(file.read_bytes(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"ello");
+        assert_eq!(file.read_bytes(&mut buf).unwrap(), 0);
+        assert_eq!(kind(file.seek_to(SeekFrom::End(-6))), ErrorKind::InvalidInput);
+        // Writing past the end of the file fills the gap with zeros.
+        file.seek_to(SeekFrom::End(2)).unwrap();
+        file.write_bytes(b"!").unwrap();
+        file.seek_to(SeekFrom::Start(0)).unwrap();
+        assert_eq!(file.read_bytes(&mut buf).unwrap(), 8);
+        assert_eq!(&buf, b"hello\0\0!");
+        // The descriptor keeps working after the file is removed.
+        vfs.remove_file(Path::new("/tmp/file")).unwrap();
+        file.set_len(2).unwrap();
+        assert_eq!(dup.metadata().size, 2);
+        assert_eq!(kind(vfs.metadata(Path::new("/tmp/file"))), ErrorKind::NotFound);
+    }
+}
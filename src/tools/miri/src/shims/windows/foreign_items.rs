@@ -5,6 +5,7 @@
 use rustc_target::abi::Size;
 use rustc_target::spec::abi::Abi;
 
+use crate::concurrency::exploration::OutputStream;
 use crate::*;
 use shims::foreign_items::EmulateForeignItemResult;
 use shims::windows::handle::{EvalContextExt as _, Handle, PseudoHandle};
@@ -110,8 +111,20 @@ fn emulate_foreign_item_inner(
 
                     let buf_cont =
                         this.read_bytes_ptr_strip_provenance(buf, Size::from_bytes(u64::from(n)))?;
+                    let category = if handle == -11 { "stdout" } else { "stderr" };
+                    let debugger_output = this
+                        .machine
+                        .debugger
+                        .as_ref()
+                        .and_then(|debugger| debugger.borrow().output(category));
+                    let stream =
+                        if handle == -11 { OutputStream::Stdout } else { OutputStream::Stderr };
                     let res = if this.machine.mute_stdout_stderr {
                         Ok(buf_cont.len())
+                    } else if let Some(output) = debugger_output {
+                        Ok(output.forward(buf_cont))
+                    } else if this.machine.threads.capture_output(stream, buf_cont) {
+                        Ok(buf_cont.len())
                     } else if handle == -11 {
                         io::stdout().write(buf_cont)
                     } else {
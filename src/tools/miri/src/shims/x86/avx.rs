@@ -0,0 +1,409 @@
+use rustc_apfloat::ieee::{Double, Single};
+use rustc_middle::mir;
+use rustc_span::Symbol;
+use rustc_target::spec::abi::Abi;
+
+use super::{
+    bin_op_simd_float_all, blendv, conditional_dot_product, convert_float_to_int, high_bit_set,
+    horizontal_bin_op, mask_load, mask_store, permute_2x128, round_all, simd_128bit_lanes,
+    test_bits_masked, test_high_bits_masked, unary_op_ps, FloatBinOp, FloatCmpOp, FloatUnaryOp,
+};
+use crate::*;
+use shims::foreign_items::EmulateForeignItemResult;
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub(super) trait EvalContextExt<'mir, 'tcx: 'mir>:
+    crate::MiriInterpCxExt<'mir, 'tcx>
+{
+    fn emulate_x86_avx_intrinsic(
+        &mut self,
+        link_name: Symbol,
+        abi: Abi,
+        args: &[OpTy<'tcx, Provenance>],
+        dest: &PlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, EmulateForeignItemResult> {
+        let this = self.eval_context_mut();
+        // Prefix should have already been checked.
+        let unprefixed_name = link_name.as_str().strip_prefix("llvm.x86.avx.").unwrap();
+
+        // Intrinsics suffixed with ".256" operate on 256-bit vectors, the
+        // remaining ones on 128-bit vectors.
+        match unprefixed_name {
+            // Used to implement _mm256_min_ps and _mm256_max_ps functions.
+            // Note that the semantics are a bit different from Rust simd_min
+            // and simd_max intrinsics regarding handling of NaN and -0.0: Rust
+            // matches the IEEE min/max operations, while x86 has different
+            // semantics.
+            "min.ps.256" | "max.ps.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = match unprefixed_name {
+                    "min.ps.256" => FloatBinOp::Min,
+                    "max.ps.256" => FloatBinOp::Max,
+                    _ => unreachable!(),
+                };
+
+                bin_op_simd_float_all::<Single>(this, which, left, right, dest)?;
+            }
+            // Used to implement _mm256_min_pd and _mm256_max_pd functions.
+            "min.pd.256" | "max.pd.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = match unprefixed_name {
+                    "min.pd.256" => FloatBinOp::Min,
+                    "max.pd.256" => FloatBinOp::Max,
+                    _ => unreachable!(),
+                };
+
+                bin_op_simd_float_all::<Double>(this, which, left, right, dest)?;
+            }
+            // Used to implement the _mm256_round_ps function.
+            // Rounds the elements of `op` according to `rounding`.
+            "round.ps.256" => {
+                let [op, rounding] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                round_all::<Single>(this, op, rounding, dest)?;
+            }
+            // Used to implement the _mm256_round_pd function.
+            // Rounds the elements of `op` according to `rounding`.
+            "round.pd.256" => {
+                let [op, rounding] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                round_all::<Double>(this, op, rounding, dest)?;
+            }
+            // Used to implement the _mm256_{sqrt,rcp,rsqrt}_ps functions.
+            // Performs the operations on all components of `op`.
+            "sqrt.ps.256" | "rcp.ps.256" | "rsqrt.ps.256" => {
+                let [op] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = match unprefixed_name {
+                    "sqrt.ps.256" => FloatUnaryOp::Sqrt,
+                    "rcp.ps.256" => FloatUnaryOp::Rcp,
+                    "rsqrt.ps.256" => FloatUnaryOp::Rsqrt,
+                    _ => unreachable!(),
+                };
+
+                unary_op_ps(this, which, op, dest)?;
+            }
+            // Used to implement the _mm256_addsub_p{s,d} functions.
+            // Subtracts the even elements and adds the odd elements of
+            // `left` and `right`.
+            "addsub.ps.256" | "addsub.pd.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (left, left_len) = this.operand_to_simd(left)?;
+                let (right, right_len) = this.operand_to_simd(right)?;
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+
+                assert_eq!(dest_len, left_len);
+                assert_eq!(dest_len, right_len);
+
+                for i in 0..dest_len {
+                    let left = this.read_immediate(&this.project_index(&left, i)?)?;
+                    let right = this.read_immediate(&this.project_index(&right, i)?)?;
+                    let dest = this.project_index(&dest, i)?;
+
+                    let which = if i % 2 == 0 { mir::BinOp::Sub } else { mir::BinOp::Add };
+                    let res = this.wrapping_binary_op(which, &left, &right)?;
+
+                    this.write_immediate(*res, &dest)?;
+                }
+            }
+            // Used to implement the _mm256_blendv_p{s,d} functions.
+            // Selects each element from `right` when the highest bit of the
+            // corresponding element of `mask` is set, and from `left` otherwise.
+            "blendv.ps.256" | "blendv.pd.256" => {
+                let [left, right, mask] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                blendv(this, left, right, mask, dest)?;
+            }
+            // Used to implement the _mm256_dp_ps function.
+            // Same as _mm_dp_ps, applied to each 128-bit lane.
+            "dp.ps.256" => {
+                let [left, right, imm] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                conditional_dot_product(this, left, right, imm, dest)?;
+            }
+            // Used to implement the _mm256_h{add,sub}_p{s,d} functions.
+            // Horizontally add/subtract adjacent floating point values
+            // in `left` and `right`, within each 128-bit lane.
+            "hadd.ps.256" | "hadd.pd.256" | "hsub.ps.256" | "hsub.pd.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = match unprefixed_name {
+                    "hadd.ps.256" | "hadd.pd.256" => mir::BinOp::Add,
+                    "hsub.ps.256" | "hsub.pd.256" => mir::BinOp::Sub,
+                    _ => unreachable!(),
+                };
+
+                horizontal_bin_op(this, which, /*saturating*/ false, left, right, dest)?;
+            }
+            // Used to implement the _mm256_cmp_ps function.
+            // Performs a comparison operation on each component of `left`
+            // and `right`. For each component, returns 0 if false or u32::MAX
+            // if true.
+            "cmp.ps.256" => {
+                let [left, right, imm] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = FloatBinOp::Cmp(FloatCmpOp::from_intrinsic_imm(
+                    this.read_scalar(imm)?.to_i8()?,
+                    "llvm.x86.avx.cmp.ps.256",
+                )?);
+
+                bin_op_simd_float_all::<Single>(this, which, left, right, dest)?;
+            }
+            // Used to implement the _mm256_cmp_pd function.
+            // Performs a comparison operation on each component of `left`
+            // and `right`. For each component, returns 0 if false or u64::MAX
+            // if true.
+            "cmp.pd.256" => {
+                let [left, right, imm] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let which = FloatBinOp::Cmp(FloatCmpOp::from_intrinsic_imm(
+                    this.read_scalar(imm)?.to_i8()?,
+                    "llvm.x86.avx.cmp.pd.256",
+                )?);
+
+                bin_op_simd_float_all::<Double>(this, which, left, right, dest)?;
+            }
+            // Used to implement the _mm256_cvtps_epi32 and _mm256_cvttps_epi32 functions.
+            // Converts packed f32 to packed i32.
+            "cvt.ps2dq.256" | "cvtt.ps2dq.256" => {
+                let [op] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let rnd = match unprefixed_name {
+                    // "current SSE rounding mode", assume nearest
+                    "cvt.ps2dq.256" => rustc_apfloat::Round::NearestTiesToEven,
+                    // always truncate
+                    "cvtt.ps2dq.256" => rustc_apfloat::Round::TowardZero,
+                    _ => unreachable!(),
+                };
+
+                convert_float_to_int::<Single>(this, op, rnd, dest)?;
+            }
+            // Used to implement the _mm256_cvtpd_epi32 and _mm256_cvttpd_epi32 functions.
+            // Converts packed f64 to packed i32.
+            "cvt.pd2dq.256" | "cvtt.pd2dq.256" => {
+                let [op] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let rnd = match unprefixed_name {
+                    // "current SSE rounding mode", assume nearest
+                    "cvt.pd2dq.256" => rustc_apfloat::Round::NearestTiesToEven,
+                    // always truncate
+                    "cvtt.pd2dq.256" => rustc_apfloat::Round::TowardZero,
+                    _ => unreachable!(),
+                };
+
+                convert_float_to_int::<Double>(this, op, rnd, dest)?;
+            }
+            // Used to implement the _mm256_cvtpd_ps function.
+            // Converts packed f64 to packed f32.
+            "cvt.pd2.ps.256" => {
+                let [op] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (op, op_len) = this.operand_to_simd(op)?;
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+
+                assert_eq!(dest_len, op_len);
+
+                for i in 0..dest_len {
+                    let op = this.read_immediate(&this.project_index(&op, i)?)?;
+                    let dest = this.project_index(&dest, i)?;
+
+                    let res = this.float_to_float_or_int(&op, dest.layout)?;
+                    this.write_immediate(*res, &dest)?;
+                }
+            }
+            // Used to implement the _mm_permutevar_ps and _mm256_permutevar_ps
+            // functions.
+            // Shuffles the f32 elements of each 128-bit lane of `left` using the
+            // lowest 2 bits of the corresponding element of `right` as index.
+            "vpermilvar.ps" | "vpermilvar.ps.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (left, left_len) = this.operand_to_simd(left)?;
+                let (right, right_len) = this.operand_to_simd(right)?;
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+
+                assert_eq!(dest_len, left_len);
+                assert_eq!(dest_len, right_len);
+
+                for i in 0..dest_len {
+                    let index = this.read_scalar(&this.project_index(&right, i)?)?.to_u32()?;
+                    // Indices are relative to the 128-bit (4 element) lane of `i`.
+                    let j = (i & !0b11).checked_add(u64::from(index & 0b11)).unwrap();
+
+                    this.copy_op(
+                        &this.project_index(&left, j)?,
+                        &this.project_index(&dest, i)?,
+                        /*allow_transmute*/ false,
+                    )?;
+                }
+            }
+            // Used to implement the _mm_permutevar_pd and _mm256_permutevar_pd
+            // functions.
+            // Shuffles the f64 elements of each 128-bit lane of `left` using the
+            // second bit of the corresponding element of `right` as index.
+            "vpermilvar.pd" | "vpermilvar.pd.256" => {
+                let [left, right] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (left, left_len) = this.operand_to_simd(left)?;
+                let (right, right_len) = this.operand_to_simd(right)?;
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+
+                assert_eq!(dest_len, left_len);
+                assert_eq!(dest_len, right_len);
+
+                for i in 0..dest_len {
+                    let index = this.read_scalar(&this.project_index(&right, i)?)?.to_u64()?;
+                    // Indices are relative to the 128-bit (2 element) lane of `i`.
+                    let j = (i & !0b1).checked_add((index >> 1) & 0b1).unwrap();
+
+                    this.copy_op(
+                        &this.project_index(&left, j)?,
+                        &this.project_index(&dest, i)?,
+                        /*allow_transmute*/ false,
+                    )?;
+                }
+            }
+            // Used to implement the _mm256_permute2f128_{ps,pd,si256} functions.
+            // Selects each 128-bit half of `dest` from the halves of `left`
+            // and `right`, or zero.
+            "vperm2f128.ps.256" | "vperm2f128.pd.256" | "vperm2f128.si.256" => {
+                let [left, right, imm] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                permute_2x128(this, left, right, imm, dest)?;
+            }
+            // Used to implement the _mm256_broadcast_p{s,d} functions.
+            // Loads a 128-bit vector from `ptr` (which needs no particular
+            // alignment) into both halves of `dest`.
+            "vbroadcastf128.ps.256" | "vbroadcastf128.pd.256" => {
+                let [ptr] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let ptr = this.read_pointer(ptr)?;
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+                let (num_lanes, lane_len) = simd_128bit_lanes(&dest, dest_len);
+
+                for lane in 0..num_lanes {
+                    let dest = this.project_index(&dest, lane.checked_mul(lane_len).unwrap())?;
+                    this.mem_copy(
+                        ptr,
+                        dest.ptr(),
+                        dest.layout.size.checked_mul(lane_len, this).unwrap(),
+                        /*nonoverlapping*/ true,
+                    )?;
+                }
+            }
+            // Used to implement the _mm_maskload_p{s,d} and _mm256_maskload_p{s,d}
+            // functions.
+            // Loads the elements whose corresponding element in `mask` has its
+            // highest bit set, and sets the remaining ones to zero.
+            "maskload.ps" | "maskload.pd" | "maskload.ps.256" | "maskload.pd.256" => {
+                let [ptr, mask] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                mask_load(this, ptr, mask, dest)?;
+            }
+            // Used to implement the _mm_maskstore_p{s,d} and _mm256_maskstore_p{s,d}
+            // functions.
+            // Stores the elements whose corresponding element in `mask` has its
+            // highest bit set.
+            "maskstore.ps" | "maskstore.pd" | "maskstore.ps.256" | "maskstore.pd.256" => {
+                let [ptr, mask, value] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                mask_store(this, ptr, mask, value)?;
+            }
+            // Used to implement the _mm256_lddqu_si256 function.
+            // Reads a 256-bit vector from an unaligned pointer. This intrinsic
+            // is expected to perform better than a regular unaligned read when
+            // the data crosses a cache line, but for Miri this is just a regular
+            // unaligned read.
+            "ldu.dq.256" => {
+                let [src_ptr] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let src_ptr = this.read_pointer(src_ptr)?;
+                let dest = dest.force_mplace(this)?;
+
+                this.mem_copy(src_ptr, dest.ptr(), dest.layout.size, /*nonoverlapping*/ true)?;
+            }
+            // Used to implement the _mm256_testz_si256, _mm256_testc_si256 and
+            // _mm256_testnzc_si256 functions.
+            // Tests `op & mask == 0`, `op & mask == mask` or
+            // `op & mask != 0 && op & mask != mask`
+            "ptestz.256" | "ptestc.256" | "ptestnzc.256" => {
+                let [op, mask] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (all_zero, masked_set) = test_bits_masked(this, op, mask)?;
+                let res = match unprefixed_name {
+                    "ptestz.256" => all_zero,
+                    "ptestc.256" => masked_set,
+                    "ptestnzc.256" => !all_zero && !masked_set,
+                    _ => unreachable!(),
+                };
+
+                this.write_scalar(Scalar::from_i32(res.into()), dest)?;
+            }
+            // Used to implement the _mm{,256}_test{z,c,nzc}_p{s,d} functions.
+            // Calculates two booleans:
+            // `direct`, which is true when the highest bit of each element of
+            // `op & mask` is zero.
+            // `negated`, which is true when the highest bit of each element of
+            // `!op & mask` is zero.
+            // Return `direct` (testz), `negated` (testc) or `!direct & !negated` (testnzc)
+            "vtestz.ps" | "vtestz.pd" | "vtestz.ps.256" | "vtestz.pd.256" | "vtestc.ps"
+            | "vtestc.pd" | "vtestc.ps.256" | "vtestc.pd.256" | "vtestnzc.ps" | "vtestnzc.pd"
+            | "vtestnzc.ps.256" | "vtestnzc.pd.256" => {
+                let [op, mask] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (direct, negated) = test_high_bits_masked(this, op, mask)?;
+                let res = if unprefixed_name.starts_with("vtestz.") {
+                    direct
+                } else if unprefixed_name.starts_with("vtestc.") {
+                    negated
+                } else {
+                    !direct && !negated
+                };
+
+                this.write_scalar(Scalar::from_i32(res.into()), dest)?;
+            }
+            // Used to implement the _mm256_movemask_p{s,d} functions.
+            // Returns a scalar whose bit `i` is the highest bit of element `i`
+            // of `op`.
+            "movmsk.ps.256" | "movmsk.pd.256" => {
+                let [op] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+
+                let (op, op_len) = this.operand_to_simd(op)?;
+
+                let mut res = 0u32;
+                for i in 0..op_len {
+                    if high_bit_set(this, &this.project_index(&op, i)?)? {
+                        res |= 1u32.checked_shl(u32::try_from(i).unwrap()).unwrap();
+                    }
+                }
+
+                this.write_scalar(Scalar::from_u32(res), dest)?;
+            }
+            // Used to implement the _mm256_zeroupper and _mm256_zeroall functions.
+            // These clear the (upper halves of the) vector registers, which Rust
+            // code cannot observe, so they are no-ops in Miri.
+            "vzeroupper" | "vzeroall" => {
+                let [] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+            }
+            _ => return Ok(EmulateForeignItemResult::NotSupported),
+        }
+        Ok(EmulateForeignItemResult::NeedsJumping)
+    }
+}
@@ -1,17 +1,27 @@
+use std::cmp::Ordering;
+
+use rustc_apfloat::{ieee::Single, Float as _};
 use rustc_middle::mir;
+use rustc_middle::ty::layout::LayoutOf as _;
+use rustc_middle::ty::Ty;
 use rustc_span::Symbol;
 use rustc_target::abi::Size;
 use rustc_target::spec::abi::Abi;
 
+use rand::Rng as _;
+
 use crate::*;
 use helpers::bool_to_simd_element;
 use shims::foreign_items::EmulateForeignItemResult;
 
 mod aesni;
+mod avx;
+mod avx2;
 mod sse;
 mod sse2;
 mod sse3;
 mod sse41;
+mod sse42;
 mod ssse3;
 
 impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
@@ -107,6 +117,21 @@ fn emulate_x86_intrinsic(
                     this, link_name, abi, args, dest,
                 );
             }
+            name if name.starts_with("sse42.") => {
+                return sse42::EvalContextExt::emulate_x86_sse42_intrinsic(
+                    this, link_name, abi, args, dest,
+                );
+            }
+            name if name.starts_with("avx.") => {
+                return avx::EvalContextExt::emulate_x86_avx_intrinsic(
+                    this, link_name, abi, args, dest,
+                );
+            }
+            name if name.starts_with("avx2.") => {
+                return avx2::EvalContextExt::emulate_x86_avx2_intrinsic(
+                    this, link_name, abi, args, dest,
+                );
+            }
             name if name.starts_with("aesni.") => {
                 return aesni::EvalContextExt::emulate_x86_aesni_intrinsic(
                     this, link_name, abi, args, dest,
@@ -125,38 +150,57 @@ fn emulate_x86_intrinsic(
 /// <https://www.felixcloutier.com/x86/cmpps>
 /// <https://www.felixcloutier.com/x86/cmpsd>
 /// <https://www.felixcloutier.com/x86/cmppd>
+///
+/// The operation is represented by the result it produces for each
+/// possible relation between the operands.
 #[derive(Copy, Clone)]
-enum FloatCmpOp {
-    Eq,
-    Lt,
-    Le,
-    Unord,
-    Neq,
-    /// Not less-than
-    Nlt,
-    /// Not less-or-equal
-    Nle,
-    /// Ordered, i.e. neither of them is NaN
-    Ord,
+struct FloatCmpOp {
+    /// Result when the left-hand side is greater than the right-hand side
+    gt: bool,
+    /// Result when the left-hand side is less than the right-hand side
+    lt: bool,
+    /// Result when both sides are equal
+    eq: bool,
+    /// Result when at least one side is NaN
+    unord: bool,
 }
 
 impl FloatCmpOp {
     /// Convert from the `imm` argument used to specify the comparison
     /// operation in intrinsics such as `llvm.x86.sse.cmp.ss`.
     fn from_intrinsic_imm(imm: i8, intrinsic: &str) -> InterpResult<'_, Self> {
-        match imm {
-            0 => Ok(Self::Eq),
-            1 => Ok(Self::Lt),
-            2 => Ok(Self::Le),
-            3 => Ok(Self::Unord),
-            4 => Ok(Self::Neq),
-            5 => Ok(Self::Nlt),
-            6 => Ok(Self::Nle),
-            7 => Ok(Self::Ord),
-            imm => {
-                throw_unsup_format!("invalid `imm` parameter of {intrinsic}: {imm}");
-            }
+        // Only bits 0..=4 are used, the remaining ones must be zero.
+        if imm & !0b1_1111 != 0 {
+            throw_unsup_format!("invalid `imm` parameter of {intrinsic}: 0x{imm:x}");
         }
+        // Bits 0..=2 select the base operation.
+        let (gt, lt, eq, unord) = match imm & 0b111 {
+            // Equal
+            0 => (false, false, true, false),
+            // Less-than
+            1 => (false, true, false, false),
+            // Less-or-equal
+            2 => (false, true, true, false),
+            // Unordered, i.e. at least one of them is NaN
+            3 => (false, false, false, true),
+            // Not equal
+            4 => (true, true, false, true),
+            // Not less-than
+            5 => (true, false, true, true),
+            // Not less-or-equal
+            6 => (true, false, false, true),
+            // Ordered, i.e. neither of them is NaN
+            7 => (true, true, true, false),
+            _ => unreachable!(),
+        };
+        // Bit 3 (only available with AVX) negates the result for unordered
+        // operands, which gives the remaining predicates (e.g. 0x0E is
+        // greater-than and 0x0F is always true).
+        let unord = unord ^ (imm & 0b1000 != 0);
+        // Bit 4 (only available with AVX) selects whether quiet NaNs raise
+        // an exception. Miri does not model the floating point status
+        // register, so it has no effect.
+        Ok(Self { gt, lt, eq, unord })
     }
 }
 
@@ -198,17 +242,11 @@ fn bin_op_float<'tcx, F: rustc_apfloat::Float>(
         FloatBinOp::Cmp(which) => {
             let left = left.to_scalar().to_float::<F>()?;
             let right = right.to_scalar().to_float::<F>()?;
-            // FIXME: Make sure that these operations match the semantics
-            // of cmpps/cmpss/cmppd/cmpsd
-            let res = match which {
-                FloatCmpOp::Eq => left == right,
-                FloatCmpOp::Lt => left < right,
-                FloatCmpOp::Le => left <= right,
-                FloatCmpOp::Unord => left.is_nan() || right.is_nan(),
-                FloatCmpOp::Neq => left != right,
-                FloatCmpOp::Nlt => !(left < right),
-                FloatCmpOp::Nle => !(left <= right),
-                FloatCmpOp::Ord => !left.is_nan() && !right.is_nan(),
+            let res = match left.partial_cmp(&right) {
+                None => which.unord,
+                Some(Ordering::Less) => which.lt,
+                Some(Ordering::Equal) => which.eq,
+                Some(Ordering::Greater) => which.gt,
             };
             Ok(bool_to_simd_element(res, Size::from_bits(F::BITS)))
         }
@@ -312,8 +350,22 @@ fn bin_op_simd_float_all<'tcx, F: rustc_apfloat::Float>(
     Ok(())
 }
 
+/// Returns the number of 128-bit lanes of the SIMD vector `op`, which has
+/// `len` elements, and the number of elements in each lane.
+///
+/// Most 256-bit operations behave like the equivalent 128-bit operation
+/// applied to each 128-bit lane independently.
+fn simd_128bit_lanes(op: &MPlaceTy<'_, Provenance>, len: u64) -> (u64, u64) {
+    let num_lanes = op.layout.size.bytes().checked_div(16).unwrap();
+    assert_ne!(num_lanes, 0);
+    (num_lanes, len.checked_div(num_lanes).unwrap())
+}
+
 /// Horizontaly performs `which` operation on adjacent values of
 /// `left` and `right` SIMD vectors and stores the result in `dest`.
+///
+/// The first half of each 128-bit lane of `dest` comes from the same lane
+/// of `left` and the second half from the same lane of `right`.
 fn horizontal_bin_op<'tcx>(
     this: &mut crate::MiriInterpCx<'_, 'tcx>,
     which: mir::BinOp,
@@ -328,26 +380,1056 @@ fn horizontal_bin_op<'tcx>(
 
     assert_eq!(dest_len, left_len);
     assert_eq!(dest_len, right_len);
-    assert_eq!(dest_len % 2, 0);
 
-    let middle = dest_len / 2;
+    let (num_lanes, lane_len) = simd_128bit_lanes(&dest, dest_len);
+    assert_eq!(lane_len % 2, 0);
+
+    let middle = lane_len / 2;
+    for lane in 0..num_lanes {
+        let lane_base = lane.checked_mul(lane_len).unwrap();
+        for i in 0..lane_len {
+            // `i` is the index in the lane of `dest`
+            // `j` is the index of the 2-item chunk in the lane of `src`
+            let (j, src) =
+                if i < middle { (i, &left) } else { (i.checked_sub(middle).unwrap(), &right) };
+            // `base_i` is the index of the first item of the 2-item chunk in `src`
+            let base_i = lane_base.checked_add(j.checked_mul(2).unwrap()).unwrap();
+            let lhs = this.read_immediate(&this.project_index(src, base_i)?)?;
+            let rhs =
+                this.read_immediate(&this.project_index(src, base_i.checked_add(1).unwrap())?)?;
+
+            let res = if saturating {
+                Immediate::from(this.saturating_arith(which, &lhs, &rhs)?)
+            } else {
+                *this.wrapping_binary_op(which, &lhs, &rhs)?
+            };
+
+            this.write_immediate(
+                res,
+                &this.project_index(&dest, lane_base.checked_add(i).unwrap())?,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+enum FloatUnaryOp {
+    /// sqrt(x)
+    ///
+    /// <https://www.felixcloutier.com/x86/sqrtss>
+    /// <https://www.felixcloutier.com/x86/sqrtps>
+    Sqrt,
+    /// Approximation of 1/x
+    ///
+    /// <https://www.felixcloutier.com/x86/rcpss>
+    /// <https://www.felixcloutier.com/x86/rcpps>
+    Rcp,
+    /// Approximation of 1/sqrt(x)
+    ///
+    /// <https://www.felixcloutier.com/x86/rsqrtss>
+    /// <https://www.felixcloutier.com/x86/rsqrtps>
+    Rsqrt,
+}
+
+/// Performs `which` scalar operation on `op` and returns the result.
+#[allow(clippy::arithmetic_side_effects)] // floating point operations without side effects
+fn unary_op_f32<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    which: FloatUnaryOp,
+    op: &ImmTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, Scalar<Provenance>> {
+    match which {
+        FloatUnaryOp::Sqrt => {
+            let op = op.to_scalar();
+            // FIXME using host floats
+            Ok(Scalar::from_u32(f32::from_bits(op.to_u32()?).sqrt().to_bits()))
+        }
+        FloatUnaryOp::Rcp => {
+            let op = op.to_scalar().to_f32()?;
+            let div = (Single::from_u128(1).value / op).value;
+            // Apply a relative error with a magnitude on the order of 2^-12 to simulate the
+            // inaccuracy of RCP.
+            let res = apply_random_float_error(this, div, -12);
+            Ok(Scalar::from_f32(res))
+        }
+        FloatUnaryOp::Rsqrt => {
+            let op = op.to_scalar().to_u32()?;
+            // FIXME using host floats
+            let sqrt = Single::from_bits(f32::from_bits(op).sqrt().to_bits().into());
+            let rsqrt = (Single::from_u128(1).value / sqrt).value;
+            // Apply a relative error with a magnitude on the order of 2^-12 to simulate the
+            // inaccuracy of RSQRT.
+            let res = apply_random_float_error(this, rsqrt, -12);
+            Ok(Scalar::from_f32(res))
+        }
+    }
+}
+
+/// Disturbes a floating-point result by a relative error on the order of (-2^scale, 2^scale).
+#[allow(clippy::arithmetic_side_effects)] // floating point arithmetic cannot panic
+fn apply_random_float_error<F: rustc_apfloat::Float>(
+    this: &mut crate::MiriInterpCx<'_, '_>,
+    val: F,
+    err_scale: i32,
+) -> F {
+    let rng = this.machine.rng.get_mut();
+    // generates rand(0, 2^64) * 2^(scale - 64) = rand(0, 1) * 2^scale
+    let err =
+        F::from_u128(rng.gen::<u64>().into()).value.scalbn(err_scale.checked_sub(64).unwrap());
+    // give it a random sign
+    let err = if rng.gen::<bool>() { -err } else { err };
+    // multiple the value with (1+err)
+    (val * (F::from_u128(1).value + err).value).value
+}
+
+/// Performs `which` operation on each component of `op`, storing the
+/// result is stored in `dest`.
+fn unary_op_ps<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    which: FloatUnaryOp,
+    op: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, op_len);
+
+    for i in 0..dest_len {
+        let op = this.read_immediate(&this.project_index(&op, i)?)?;
+        let dest = this.project_index(&dest, i)?;
+
+        let res = unary_op_f32(this, which, &op)?;
+        this.write_scalar(res, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Gets the rounding mode specified by the `rounding` argument of the
+/// `round.{ss,sd,ps,pd}` intrinsics.
+fn rounding_from_imm<'tcx>(rounding: i32) -> InterpResult<'tcx, rustc_apfloat::Round> {
+    // The fourth bit of `rounding` only affects the SSE status
+    // register, which cannot be accessed from Miri (or from Rust,
+    // for that matter), so we can ignore it.
+    match rounding & !0b1000 {
+        // When the third bit is 0, the rounding mode is determined by the
+        // first two bits.
+        0b000 => Ok(rustc_apfloat::Round::NearestTiesToEven),
+        0b001 => Ok(rustc_apfloat::Round::TowardNegative),
+        0b010 => Ok(rustc_apfloat::Round::TowardPositive),
+        0b011 => Ok(rustc_apfloat::Round::TowardZero),
+        // When the third bit is 1, the rounding mode is determined by the
+        // SSE status register. Since we do not support modifying it from
+        // Miri (or Rust), we assume it to be at its default mode (round-to-nearest).
+        0b100..=0b111 => Ok(rustc_apfloat::Round::NearestTiesToEven),
+        rounding => throw_unsup_format!("unsupported rounding mode 0x{rounding:02x}"),
+    }
+}
+
+/// Rounds the first element of `right` according to `rounding`
+/// and copies the remaining elements from `left`.
+fn round_first<'tcx, F: rustc_apfloat::Float>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    rounding: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    let rounding = rounding_from_imm(this.read_scalar(rounding)?.to_i32()?)?;
+
+    let op0: F = this.read_scalar(&this.project_index(&right, 0)?)?.to_float()?;
+    let res = op0.round_to_integral(rounding).value;
+    this.write_scalar(
+        Scalar::from_uint(res.to_bits(), Size::from_bits(F::BITS)),
+        &this.project_index(&dest, 0)?,
+    )?;
+
+    for i in 1..dest_len {
+        this.copy_op(
+            &this.project_index(&left, i)?,
+            &this.project_index(&dest, i)?,
+            /*allow_transmute*/ false,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Rounds all elements of `op` according to `rounding`.
+fn round_all<'tcx, F: rustc_apfloat::Float>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    rounding: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, op_len);
+
+    let rounding = rounding_from_imm(this.read_scalar(rounding)?.to_i32()?)?;
+
+    for i in 0..dest_len {
+        let op: F = this.read_scalar(&this.project_index(&op, i)?)?.to_float()?;
+        let res = op.round_to_integral(rounding).value;
+        this.write_scalar(
+            Scalar::from_uint(res.to_bits(), Size::from_bits(F::BITS)),
+            &this.project_index(&dest, i)?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Converts each element of `op` from floating point to signed integer.
+///
+/// When the input value is NaN or out of range, falls back to the minimum
+/// value, like the hardware does.
+///
+/// If `op` has less elements than `dest`, the remaining elements of `dest`
+/// are filled with zeros.
+fn convert_float_to_int<'tcx, F>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    rnd: rustc_apfloat::Round,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()>
+where
+    F: rustc_apfloat::Float + Into<Scalar<Provenance>>,
+{
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert!(op_len <= dest_len);
+
+    for i in 0..op_len {
+        let op: F = this.read_scalar(&this.project_index(&op, i)?)?.to_float()?;
+        let dest = this.project_index(&dest, i)?;
+
+        let res = this.float_to_int_checked(op, dest.layout, rnd).unwrap_or_else(|| {
+            // Fallback to minimum acording to SSE semantics.
+            ImmTy::from_int(dest.layout.size.signed_int_min(), dest.layout)
+        });
+        this.write_immediate(*res, &dest)?;
+    }
+    // Fill the remaining with zeros
+    for i in op_len..dest_len {
+        let dest = this.project_index(&dest, i)?;
+        this.write_scalar(Scalar::from_int(0, dest.layout.size), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Conditionally multiplies the packed floating-point elements in
+/// `left` and `right` using the high 4 bits in `imm`, sums the products,
+/// and conditionally stores the sum in `dest` using the low 4 bits of
+/// `imm`. Each 128-bit lane is handled independently.
+fn conditional_dot_product<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    imm: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(left_len, right_len);
+    assert_eq!(dest_len, left_len);
+
+    // The immediate is an `u8` for the 128-bit intrinsics and an `i32`
+    // for the 256-bit one. Only its lowest 8 bits are used.
+    let imm = this.read_scalar(imm)?.to_uint(imm.layout.size)?;
+
+    let element_layout = left.layout.field(this, 0);
+    let (num_lanes, lane_len) = simd_128bit_lanes(&dest, dest_len);
+    assert!(lane_len <= 4);
+
+    for lane in 0..num_lanes {
+        let lane_base = lane.checked_mul(lane_len).unwrap();
+
+        // Calculate dot product
+        // Elements are floating point numbers, but we can use `from_int`
+        // because the representation of 0.0 is all zero bits.
+        let mut sum = ImmTy::from_int(0u8, element_layout);
+        for i in 0..lane_len {
+            if imm & (1 << i.checked_add(4).unwrap()) != 0 {
+                let j = lane_base.checked_add(i).unwrap();
+                let left = this.read_immediate(&this.project_index(&left, j)?)?;
+                let right = this.read_immediate(&this.project_index(&right, j)?)?;
+
+                let mul = this.wrapping_binary_op(mir::BinOp::Mul, &left, &right)?;
+                sum = this.wrapping_binary_op(mir::BinOp::Add, &sum, &mul)?;
+            }
+        }
+
+        // Write to destination (conditioned to imm)
+        for i in 0..lane_len {
+            let dest = this.project_index(&dest, lane_base.checked_add(i).unwrap())?;
+
+            if imm & (1 << i) != 0 {
+                this.write_immediate(*sum, &dest)?;
+            } else {
+                this.write_scalar(Scalar::from_int(0u8, element_layout.size), &dest)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Calculates two booleans.
+///
+/// The first is true when all the bits of `op & mask` are zero.
+/// The second is true when `(op & mask) == mask`
+fn test_bits_masked<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    mask: &OpTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, (bool, bool)> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (mask, mask_len) = this.operand_to_simd(mask)?;
+
+    assert_eq!(op_len, mask_len);
+
+    let mut all_zero = true;
+    let mut masked_set = true;
+    for i in 0..op_len {
+        let op = this.project_index(&op, i)?;
+        let mask = this.project_index(&mask, i)?;
+
+        let op = this.read_scalar(&op)?.to_uint(op.layout.size)?;
+        let mask = this.read_scalar(&mask)?.to_uint(mask.layout.size)?;
+        all_zero &= (op & mask) == 0;
+        masked_set &= (op & mask) == mask;
+    }
+
+    Ok((all_zero, masked_set))
+}
+
+/// Calculates two booleans.
+///
+/// The first is true when the highest bit of each element of `op & mask` is zero.
+/// The second is true when the highest bit of each element of `!op & mask` is zero.
+fn test_high_bits_masked<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    mask: &OpTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, (bool, bool)> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (mask, mask_len) = this.operand_to_simd(mask)?;
+
+    assert_eq!(op_len, mask_len);
+
+    let size = op.layout.field(this, 0).size;
+    let high_bit_offset = size.bits().checked_sub(1).unwrap();
+
+    let mut direct = true;
+    let mut negated = true;
+    for i in 0..op_len {
+        let op = this.read_scalar(&this.project_index(&op, i)?)?.to_uint(size)?;
+        let mask = this.read_scalar(&this.project_index(&mask, i)?)?.to_uint(size)?;
+        direct &= (op & mask) >> high_bit_offset == 0;
+        negated &= (!op & mask) >> high_bit_offset == 0;
+    }
+
+    Ok((direct, negated))
+}
+
+/// Returns whether the highest bit of the integer or floating point
+/// element `op` is set.
+fn high_bit_set<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    op: &MPlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, bool> {
+    let size = op.layout.size;
+    let op = this.read_scalar(op)?.to_uint(size)?;
+    Ok(op >> size.bits().checked_sub(1).unwrap() != 0)
+}
+
+/// Selects each element from `right` when the highest bit of the
+/// corresponding element of `mask` is set, and from `left` otherwise.
+fn blendv<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    mask: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (mask, mask_len) = this.operand_to_simd(mask)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+    assert_eq!(dest_len, mask_len);
+
+    for i in 0..dest_len {
+        let src = if high_bit_set(this, &this.project_index(&mask, i)?)? { &right } else { &left };
+        this.copy_op(
+            &this.project_index(src, i)?,
+            &this.project_index(&dest, i)?,
+            /*allow_transmute*/ false,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Conditionally loads from `ptr` according the high bit of each
+/// element of `mask`. `ptr` does not need to be aligned. Elements
+/// that are not loaded are set to zero.
+fn mask_load<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    ptr: &OpTy<'tcx, Provenance>,
+    mask: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (mask, mask_len) = this.operand_to_simd(mask)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, mask_len);
+
+    let ptr = this.read_pointer(ptr)?;
+    for i in 0..dest_len {
+        let mask = this.project_index(&mask, i)?;
+        let dest = this.project_index(&dest, i)?;
+
+        if high_bit_set(this, &mask)? {
+            let src_ptr = ptr.wrapping_offset(dest.layout.size.checked_mul(i, this).unwrap(), this);
+            // `mem_copy` does not require any alignment.
+            this.mem_copy(src_ptr, dest.ptr(), dest.layout.size, /*nonoverlapping*/ true)?;
+        } else {
+            this.write_scalar(Scalar::from_int(0, dest.layout.size), &dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Conditionally stores the elements of `value` into `ptr` according
+/// the high bit of each element of `mask`. `ptr` does not need to be
+/// aligned. Memory corresponding to elements that are not stored is
+/// not accessed.
+fn mask_store<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    ptr: &OpTy<'tcx, Provenance>,
+    mask: &OpTy<'tcx, Provenance>,
+    value: &OpTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (mask, mask_len) = this.operand_to_simd(mask)?;
+    let (value, value_len) = this.operand_to_simd(value)?;
+
+    assert_eq!(value_len, mask_len);
+
+    let ptr = this.read_pointer(ptr)?;
+    for i in 0..value_len {
+        let mask = this.project_index(&mask, i)?;
+        let value = this.project_index(&value, i)?;
+
+        if high_bit_set(this, &mask)? {
+            let dest_ptr =
+                ptr.wrapping_offset(value.layout.size.checked_mul(i, this).unwrap(), this);
+            // `mem_copy` does not require any alignment.
+            this.mem_copy(value.ptr(), dest_ptr, value.layout.size, /*nonoverlapping*/ true)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds each 128-bit half of `dest` from one of the 128-bit halves of
+/// `left` or `right`, or zero, as selected by 4 bits of `imm` per half.
+///
+/// <https://www.felixcloutier.com/x86/vperm2f128>
+/// <https://www.felixcloutier.com/x86/vperm2i128>
+fn permute_2x128<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    imm: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    let (num_lanes, lane_len) = simd_128bit_lanes(&dest, dest_len);
+    assert_eq!(num_lanes, 2);
+
+    let imm = this.read_scalar(imm)?.to_uint(imm.layout.size)?;
+    for lane in 0..num_lanes {
+        let dest_base = lane.checked_mul(lane_len).unwrap();
+        // Bits `0..=3` control the low half and bits `4..=7` the high half.
+        let control = if lane == 0 { imm & 0xF } else { (imm >> 4) & 0xF };
+
+        if control & 0b1000 != 0 {
+            // Zero this half
+            for i in 0..lane_len {
+                let dest = this.project_index(&dest, dest_base.checked_add(i).unwrap())?;
+                this.write_scalar(Scalar::from_int(0, dest.layout.size), &dest)?;
+            }
+        } else {
+            // Copy one of the source halves
+            let src = if control & 0b10 == 0 { &left } else { &right };
+            let src_base = if control & 0b01 == 0 { 0 } else { lane_len };
+            for i in 0..lane_len {
+                this.copy_op(
+                    &this.project_index(src, src_base.checked_add(i).unwrap())?,
+                    &this.project_index(&dest, dest_base.checked_add(i).unwrap())?,
+                    /*allow_transmute*/ false,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+enum ShiftOp {
+    /// Shift left, filling with zeros
+    Left,
+    /// Shift right, filling with zeros
+    RightLogic,
+    /// Shift right, filling with copies of the sign bit
+    RightArith,
+}
+
+/// Shifts the integer `op` of size `size` by `shift` bits.
+///
+/// For logic shifts, when `shift` is larger than the number of bits, zero
+/// is produced. For arithmetic shifts, the sign bit is copied to all bits
+/// instead.
+fn shift_element<'tcx>(
+    which: ShiftOp,
+    op: Scalar<Provenance>,
+    shift: u32,
+    size: Size,
+) -> InterpResult<'tcx, Scalar<Provenance>> {
+    Ok(match which {
+        ShiftOp::Left => {
+            let op = op.to_uint(size)?;
+            let res = op.checked_shl(shift).unwrap_or(0);
+            // `truncate` discards the bits that were shifted out.
+            Scalar::from_uint(size.truncate(res), size)
+        }
+        ShiftOp::RightLogic => {
+            let op = op.to_uint(size)?;
+            Scalar::from_uint(op.checked_shr(shift).unwrap_or(0), size)
+        }
+        ShiftOp::RightArith => {
+            let op = op.to_int(size)?;
+            // Copy the sign bit to the remaining bits
+            Scalar::from_int(op.checked_shr(shift).unwrap_or(op >> 127), size)
+        }
+    })
+}
+
+/// Shifts each element of `left` by the amount in the lowest 64 bits of
+/// `right` (which is always a 128-bit vector).
+fn shift_simd_by_scalar<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    which: ShiftOp,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, _) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+
+    // Get the 64-bit shift operand and convert it to the type expected
+    // by checked_{shl,shr} (u32).
+    // It is ok to saturate the value to u32::MAX because any value
+    // above the number of bits will produce the same result.
+    let shift = extract_first_u64(this, &right)?.try_into().unwrap_or(u32::MAX);
+
+    for i in 0..dest_len {
+        let left = this.read_scalar(&this.project_index(&left, i)?)?;
+        let dest = this.project_index(&dest, i)?;
+
+        let res = shift_element(which, left, shift, dest.layout.size)?;
+        this.write_scalar(res, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Shifts each element of `left` by the amount in the corresponding
+/// element of `right`.
+fn shift_simd_by_simd<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    which: ShiftOp,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    for i in 0..dest_len {
+        let left = this.read_scalar(&this.project_index(&left, i)?)?;
+        let right = this.read_scalar(&this.project_index(&right, i)?)?;
+        let dest = this.project_index(&dest, i)?;
+
+        // The shift amount is interpreted as unsigned, and it is ok to
+        // saturate it to u32::MAX because any value above the number of
+        // bits will produce the same result.
+        let shift = right.to_uint(dest.layout.size)?.try_into().unwrap_or(u32::MAX);
+
+        let res = shift_element(which, left, shift, dest.layout.size)?;
+        this.write_scalar(res, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Takes a 128-bit vector, transmutes it to `[u64; 2]` and extracts
+/// the first value.
+fn extract_first_u64<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    op: &MPlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, u64> {
+    // Transmute vector to `[u64; 2]`
+    let u64_array_layout = this.layout_of(Ty::new_array(this.tcx.tcx, this.tcx.types.u64, 2))?;
+    let op = op.transmute(u64_array_layout, this)?;
+
+    // Get the first u64 from the array
+    this.read_scalar(&this.project_index(&op, 0)?)?.to_u64()
+}
+
+/// Packs two N-bit integer vectors into a single N/2-bit integer vector,
+/// converting each element with `f`.
+///
+/// Each 128-bit lane of `dest` is built from the same lane of `left`
+/// (first half) and `right` (second half).
+fn pack_generic<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+    f: impl Fn(Scalar<Provenance>) -> InterpResult<'tcx, Scalar<Provenance>>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(left_len, right_len);
+    assert_eq!(dest_len, left_len.checked_mul(2).unwrap());
+
+    let (num_lanes, op_lane_len) = simd_128bit_lanes(&left, left_len);
+
+    for lane in 0..num_lanes {
+        let op_base = lane.checked_mul(op_lane_len).unwrap();
+        let dest_base = op_base.checked_mul(2).unwrap();
+        for i in 0..op_lane_len {
+            let op_i = op_base.checked_add(i).unwrap();
+            let left = this.read_scalar(&this.project_index(&left, op_i)?)?;
+            let right = this.read_scalar(&this.project_index(&right, op_i)?)?;
+            let left_dest = this.project_index(&dest, dest_base.checked_add(i).unwrap())?;
+            let right_dest = this.project_index(
+                &dest,
+                dest_base.checked_add(op_lane_len).unwrap().checked_add(i).unwrap(),
+            )?;
+
+            this.write_scalar(f(left)?, &left_dest)?;
+            this.write_scalar(f(right)?, &right_dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts two 16-bit integer vectors to a single 8-bit integer
+/// vector with signed saturation.
+fn packsswb<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    pack_generic(this, left, right, dest, |op| {
+        let op = op.to_i16()?;
+        let res = i8::try_from(op).unwrap_or(if op < 0 { i8::MIN } else { i8::MAX });
+        Ok(Scalar::from_i8(res))
+    })
+}
+
+/// Converts two 16-bit signed integer vectors to a single 8-bit
+/// unsigned integer vector with saturation.
+fn packuswb<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    pack_generic(this, left, right, dest, |op| {
+        let op = op.to_i16()?;
+        let res = u8::try_from(op).unwrap_or(if op < 0 { 0 } else { u8::MAX });
+        Ok(Scalar::from_u8(res))
+    })
+}
+
+/// Converts two 32-bit integer vectors to a single 16-bit integer
+/// vector with signed saturation.
+fn packssdw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    pack_generic(this, left, right, dest, |op| {
+        let op = op.to_i32()?;
+        let res = i16::try_from(op).unwrap_or(if op < 0 { i16::MIN } else { i16::MAX });
+        Ok(Scalar::from_i16(res))
+    })
+}
+
+/// Converts two 32-bit integer vectors to a single 16-bit unsigned
+/// integer vector with saturation.
+fn packusdw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    pack_generic(this, left, right, dest, |op| {
+        let op = op.to_i32()?;
+        let res = u16::try_from(op).unwrap_or(if op < 0 { 0 } else { u16::MAX });
+        Ok(Scalar::from_u16(res))
+    })
+}
+
+/// Multiplies packed signed 16-bit integers in `left` and `right`, producing
+/// intermediate signed 32-bit integers. Horizontally add adjacent pairs of
+/// intermediate 32-bit integers, and pack the results in `dest`.
+fn pmaddwd<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(left_len, right_len);
+    assert_eq!(dest_len.checked_mul(2).unwrap(), left_len);
+
+    for i in 0..dest_len {
+        let j1 = i.checked_mul(2).unwrap();
+        let left1 = this.read_scalar(&this.project_index(&left, j1)?)?.to_i16()?;
+        let right1 = this.read_scalar(&this.project_index(&right, j1)?)?.to_i16()?;
+
+        let j2 = j1.checked_add(1).unwrap();
+        let left2 = this.read_scalar(&this.project_index(&left, j2)?)?.to_i16()?;
+        let right2 = this.read_scalar(&this.project_index(&right, j2)?)?.to_i16()?;
+
+        let dest = this.project_index(&dest, i)?;
+
+        // Multiplications are i16*i16->i32, which will not overflow.
+        let mul1 = i32::from(left1).checked_mul(right1.into()).unwrap();
+        let mul2 = i32::from(left2).checked_mul(right2.into()).unwrap();
+        // However, this addition can overflow in the most extreme case
+        // (-0x8000)*(-0x8000)+(-0x8000)*(-0x8000) = 0x80000000
+        let res = mul1.wrapping_add(mul2);
+
+        this.write_scalar(Scalar::from_i32(res), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Multiplies packed 8-bit unsigned integers from `left` and packed
+/// signed 8-bit integers from `right` into 16-bit signed integers. Then,
+/// the saturating sum of the products with indices `2*i` and `2*i+1`
+/// produces the output at index `i`.
+fn pmaddubsw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(left_len, right_len);
+    assert_eq!(dest_len.checked_mul(2).unwrap(), left_len);
+
+    for i in 0..dest_len {
+        let j1 = i.checked_mul(2).unwrap();
+        let left1 = this.read_scalar(&this.project_index(&left, j1)?)?.to_u8()?;
+        let right1 = this.read_scalar(&this.project_index(&right, j1)?)?.to_i8()?;
+
+        let j2 = j1.checked_add(1).unwrap();
+        let left2 = this.read_scalar(&this.project_index(&left, j2)?)?.to_u8()?;
+        let right2 = this.read_scalar(&this.project_index(&right, j2)?)?.to_i8()?;
+
+        let dest = this.project_index(&dest, i)?;
+
+        // Multiplication of a u8 and an i8 into an i16 cannot overflow.
+        let mul1 = i16::from(left1).checked_mul(right1.into()).unwrap();
+        let mul2 = i16::from(left2).checked_mul(right2.into()).unwrap();
+        let res = mul1.saturating_add(mul2);
+
+        this.write_scalar(Scalar::from_i16(res), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Multiplies packed 16-bit signed integer values, truncates the 32-bit
+/// product to the 18 most significant bits by right-shifting, and then
+/// divides the 18-bit value by 2 (rounding to nearest) by first adding
+/// 1 and then taking the bits `1..=16`.
+fn pmulhrsw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    for i in 0..dest_len {
+        let left = this.read_scalar(&this.project_index(&left, i)?)?.to_i16()?;
+        let right = this.read_scalar(&this.project_index(&right, i)?)?.to_i16()?;
+        let dest = this.project_index(&dest, i)?;
+
+        let res =
+            (i32::from(left).checked_mul(right.into()).unwrap() >> 14).checked_add(1).unwrap() >> 1;
+
+        // The result of this operation can overflow a signed 16-bit integer.
+        // When `left` and `right` are -0x8000, the result is 0x8000.
+        #[allow(clippy::cast_possible_truncation)]
+        let res = res as i16;
+
+        this.write_scalar(Scalar::from_i16(res), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Negates elements from `left` when the corresponding element in
+/// `right` is negative. If an element from `right` is zero, zero
+/// is writen to the corresponding output element.
+/// Basically, we multiply `left` with `right.signum()`.
+fn psign<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    for i in 0..dest_len {
+        let dest = this.project_index(&dest, i)?;
+        let left = this.read_immediate(&this.project_index(&left, i)?)?;
+        let right = this.read_scalar(&this.project_index(&right, i)?)?.to_int(dest.layout.size)?;
+
+        let res = this.wrapping_binary_op(
+            mir::BinOp::Mul,
+            &left,
+            &ImmTy::from_int(right.signum(), dest.layout),
+        )?;
+
+        this.write_immediate(*res, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Calculates the absolute value of packed signed integers.
+fn pabs<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(op_len, dest_len);
+
     for i in 0..dest_len {
-        // `i` is the index in `dest`
-        // `j` is the index of the 2-item chunk in `src`
-        let (j, src) =
-            if i < middle { (i, &left) } else { (i.checked_sub(middle).unwrap(), &right) };
-        // `base_i` is the index of the first item of the 2-item chunk in `src`
-        let base_i = j.checked_mul(2).unwrap();
-        let lhs = this.read_immediate(&this.project_index(src, base_i)?)?;
-        let rhs = this.read_immediate(&this.project_index(src, base_i.checked_add(1).unwrap())?)?;
-
-        let res = if saturating {
-            Immediate::from(this.saturating_arith(which, &lhs, &rhs)?)
+        let op = this.read_scalar(&this.project_index(&op, i)?)?;
+        let dest = this.project_index(&dest, i)?;
+
+        // Converting to a host "i128" works since the input is always signed.
+        let res = op.to_int(dest.layout.size)?.unsigned_abs();
+
+        this.write_scalar(Scalar::from_uint(res, dest.layout.size), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Shuffles bytes from `left` using `right` as pattern. Each 128-bit
+/// lane is shuffled independently.
+///
+/// <https://www.felixcloutier.com/x86/pshufb>
+fn pshufb<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(dest_len, left_len);
+    assert_eq!(dest_len, right_len);
+
+    for i in 0..dest_len {
+        let right = this.read_scalar(&this.project_index(&right, i)?)?.to_u8()?;
+        let dest = this.project_index(&dest, i)?;
+
+        let res = if right & 0x80 == 0 {
+            // The index wraps around within the 16-byte lane of `i`.
+            let j = (i & !15).checked_add(u64::from(right % 16)).unwrap();
+            this.read_scalar(&this.project_index(&left, j)?)?
         } else {
-            *this.wrapping_binary_op(which, &lhs, &rhs)?
+            // If the highest bit in `right` is 1, write zero.
+            Scalar::from_u8(0)
         };
 
-        this.write_immediate(res, &this.project_index(&dest, i)?)?;
+        this.write_scalar(res, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Computes the absolute differences of packed unsigned 8-bit integers
+/// in `left` and `right`, then horizontally sums each consecutive 8
+/// differences to produce unsigned 16-bit integers, which are stored in
+/// the low 16 bits of the 64-bit elements of `dest`.
+///
+/// <https://www.felixcloutier.com/x86/psadbw>
+fn psadbw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    // left and right are u8xN, dest is u64x(N/8)
+    assert_eq!(left_len, right_len);
+    assert_eq!(left_len, dest_len.checked_mul(8).unwrap());
+
+    for i in 0..dest_len {
+        let dest = this.project_index(&dest, i)?;
+
+        let mut res: u16 = 0;
+        for j in 0..8 {
+            let op_i = i.checked_mul(8).unwrap().checked_add(j).unwrap();
+            let left = this.read_scalar(&this.project_index(&left, op_i)?)?.to_u8()?;
+            let right = this.read_scalar(&this.project_index(&right, op_i)?)?.to_u8()?;
+
+            res = res.checked_add(left.abs_diff(right).into()).unwrap();
+        }
+
+        this.write_scalar(Scalar::from_u64(res.into()), &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Computes the sum of absolute differences of quadruplets of unsigned
+/// 8-bit integers in `left` and `right`, and stores the 16-bit results
+/// in `dest`. Quadruplets are selected from `left` and `right` with
+/// offsets specified in `imm`. Each 128-bit lane uses 3 bits of `imm`.
+///
+/// <https://www.felixcloutier.com/x86/mpsadbw>
+fn mpsadbw<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    left: &OpTy<'tcx, Provenance>,
+    right: &OpTy<'tcx, Provenance>,
+    imm: &OpTy<'tcx, Provenance>,
+    dest: &PlaceTy<'tcx, Provenance>,
+) -> InterpResult<'tcx, ()> {
+    let (left, left_len) = this.operand_to_simd(left)?;
+    let (right, right_len) = this.operand_to_simd(right)?;
+    let (dest, dest_len) = this.place_to_simd(dest)?;
+
+    assert_eq!(left_len, right_len);
+    assert_eq!(left_len, dest_len.checked_mul(2).unwrap());
+
+    // The immediate is an `u8` for the 128-bit intrinsic and an `i32`
+    // for the 256-bit one.
+    let imm = this.read_scalar(imm)?.to_uint(imm.layout.size)?;
+
+    let (num_lanes, op_lane_len) = simd_128bit_lanes(&left, left_len);
+    let dest_lane_len = op_lane_len.checked_div(2).unwrap();
+
+    for lane in 0..num_lanes {
+        let op_base = lane.checked_mul(op_lane_len).unwrap();
+        let dest_base = lane.checked_mul(dest_lane_len).unwrap();
+        // Bits `0..=2` of `imm` are used by the first lane and bits `3..=5`
+        // by the second one.
+        let lane_imm = if lane == 0 { imm } else { imm >> 3 };
+        // Bit 2 of `lane_imm` specifies the offset for indices of `left`.
+        // The offset is 0 when the bit is 0 or 4 when the bit is 1.
+        let left_offset = u64::try_from((lane_imm >> 2) & 1).unwrap().checked_mul(4).unwrap();
+        // Bits 0..=1 of `lane_imm` specify the offset for indices of
+        // `right` in blocks of 4 elements.
+        let right_offset = u64::try_from(lane_imm & 0b11).unwrap().checked_mul(4).unwrap();
+
+        for i in 0..dest_lane_len {
+            let left_offset = op_base.checked_add(left_offset).unwrap().checked_add(i).unwrap();
+            let right_offset = op_base.checked_add(right_offset).unwrap();
+            let mut res: u16 = 0;
+            for j in 0..4 {
+                let left = this
+                    .read_scalar(&this.project_index(&left, left_offset.checked_add(j).unwrap())?)?
+                    .to_u8()?;
+                let right = this
+                    .read_scalar(
+                        &this.project_index(&right, right_offset.checked_add(j).unwrap())?,
+                    )?
+                    .to_u8()?;
+                res = res.checked_add(left.abs_diff(right).into()).unwrap();
+            }
+            this.write_scalar(
+                Scalar::from_u16(res),
+                &this.project_index(&dest, dest_base.checked_add(i).unwrap())?,
+            )?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,338 @@
+use rustc_span::Symbol;
+use rustc_target::spec::abi::Abi;
+
+use crate::*;
+use shims::foreign_items::EmulateForeignItemResult;
+
+impl<'mir, 'tcx: 'mir> EvalContextExt<'mir, 'tcx> for crate::MiriInterpCx<'mir, 'tcx> {}
+pub(super) trait EvalContextExt<'mir, 'tcx: 'mir>:
+    crate::MiriInterpCxExt<'mir, 'tcx>
+{
+    fn emulate_x86_sse42_intrinsic(
+        &mut self,
+        link_name: Symbol,
+        abi: Abi,
+        args: &[OpTy<'tcx, Provenance>],
+        dest: &PlaceTy<'tcx, Provenance>,
+    ) -> InterpResult<'tcx, EmulateForeignItemResult> {
+        let this = self.eval_context_mut();
+        // Prefix should have already been checked.
+        let unprefixed_name = link_name.as_str().strip_prefix("llvm.x86.sse42.").unwrap();
+
+        match unprefixed_name {
+            // Used to implement the _mm_cmpestrm and _mm_cmpistrm functions.
+            // Compares the strings in `str1` and `str2` (with explicit lengths
+            // for the "e" variant and null-terminated for the "i" variant) and
+            // returns the result as a bit mask or as an element mask.
+            // https://www.felixcloutier.com/x86/pcmpestrm
+            // https://www.felixcloutier.com/x86/pcmpistrm
+            "pcmpestrm128" | "pcmpistrm128" => {
+                let (res, imm) =
+                    compare_strings_from_args(this, unprefixed_name, abi, link_name, args)?;
+
+                let mut mask = [0u8; 16];
+                if imm & 0b100_0000 == 0 {
+                    // The result bits, zero-extended.
+                    mask[..2].copy_from_slice(&res.bits.to_le_bytes());
+                } else {
+                    // Each bit expanded to all the bits of its element.
+                    let elem_size = if res.num_elems == 8 { 2 } else { 1 };
+                    for (i, elem) in mask.chunks_exact_mut(elem_size).enumerate() {
+                        if res.bits & (1 << i) != 0 {
+                            elem.fill(u8::MAX);
+                        }
+                    }
+                }
+
+                let (dest, dest_len) = this.place_to_simd(dest)?;
+                assert_eq!(dest_len, 16);
+                for (i, byte) in mask.into_iter().enumerate() {
+                    this.write_scalar(
+                        Scalar::from_u8(byte),
+                        &this.project_index(&dest, i.try_into().unwrap())?,
+                    )?;
+                }
+            }
+            // Used to implement the _mm_cmpestri and _mm_cmpistri functions.
+            // Compares the strings like the above, and returns the index of the
+            // least (or most) significant bit set in the result, or the number of
+            // elements if there is none.
+            // https://www.felixcloutier.com/x86/pcmpestri
+            // https://www.felixcloutier.com/x86/pcmpistri
+            "pcmpestri128" | "pcmpistri128" => {
+                let (res, imm) =
+                    compare_strings_from_args(this, unprefixed_name, abi, link_name, args)?;
+
+                let index = if res.bits == 0 {
+                    res.num_elems
+                } else if imm & 0b100_0000 == 0 {
+                    res.bits.trailing_zeros()
+                } else {
+                    15u32.checked_sub(res.bits.leading_zeros()).unwrap()
+                };
+
+                this.write_scalar(Scalar::from_i32(index.try_into().unwrap()), dest)?;
+            }
+            // Used to implement the _mm_cmpestr{a,c,o,s,z} and _mm_cmpistr{a,c,o,s,z}
+            // functions.
+            // Compares the strings like the above, and returns one of the flags
+            // set by the instruction:
+            // * "a": the result is zero and `str2` has no end within the vector.
+            // * "c" (CF): the result is not zero.
+            // * "o" (OF): the first bit of the result is set.
+            // * "s" (SF): `str1` ends within the vector.
+            // * "z" (ZF): `str2` ends within the vector.
+            "pcmpestria128" | "pcmpestric128" | "pcmpestrio128" | "pcmpestris128"
+            | "pcmpestriz128" | "pcmpistria128" | "pcmpistric128" | "pcmpistrio128"
+            | "pcmpistris128" | "pcmpistriz128" => {
+                let (res, _) =
+                    compare_strings_from_args(this, unprefixed_name, abi, link_name, args)?;
+
+                let flag = match unprefixed_name.strip_prefix("pcmp").unwrap() {
+                    "estria128" | "istria128" => res.bits == 0 && res.len2 == res.num_elems,
+                    "estric128" | "istric128" => res.bits != 0,
+                    "estrio128" | "istrio128" => res.bits & 1 != 0,
+                    "estris128" | "istris128" => res.len1 < res.num_elems,
+                    "estriz128" | "istriz128" => res.len2 < res.num_elems,
+                    _ => unreachable!(),
+                };
+
+                this.write_scalar(Scalar::from_i32(flag.into()), dest)?;
+            }
+            // Used to implement the _mm_crc32_u{8,16,32,64} functions.
+            // Accumulates the CRC-32C (Castagnoli) checksum of `data` on top
+            // of `crc`.
+            // https://www.felixcloutier.com/x86/crc32
+            "crc32.32.8" | "crc32.32.16" | "crc32.32.32" | "crc32.64.64" => {
+                if unprefixed_name == "crc32.64.64" && this.tcx.sess.target.arch != "x86_64" {
+                    return Ok(EmulateForeignItemResult::NotSupported);
+                }
+
+                let [crc, data] =
+                    this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+                let crc = this.read_scalar(crc)?;
+                let data = this.read_scalar(data)?;
+
+                let (crc, data, bits) = match unprefixed_name {
+                    "crc32.32.8" => (crc.to_u32()?, u64::from(data.to_u8()?), 8),
+                    "crc32.32.16" => (crc.to_u32()?, u64::from(data.to_u16()?), 16),
+                    "crc32.32.32" => (crc.to_u32()?, u64::from(data.to_u32()?), 32),
+                    // The upper 32 bits of `crc` are ignored.
+                    "crc32.64.64" =>
+                        (
+                            (crc.to_u64()? & u64::from(u32::MAX)).try_into().unwrap(),
+                            data.to_u64()?,
+                            64,
+                        ),
+                    _ => unreachable!(),
+                };
+
+                let res = crc32c(crc, data, bits);
+                if dest.layout.size.bits() == 64 {
+                    this.write_scalar(Scalar::from_u64(res.into()), dest)?;
+                } else {
+                    this.write_scalar(Scalar::from_u32(res), dest)?;
+                }
+            }
+            _ => return Ok(EmulateForeignItemResult::NotSupported),
+        }
+        Ok(EmulateForeignItemResult::NeedsJumping)
+    }
+}
+
+/// Result of a string comparison done by one of the `pcmp{e,i}str*`
+/// intrinsics.
+struct StrCmpResult {
+    /// Bit `i` is the (possibly negated) comparison result for element `i`
+    /// (`IntRes2` in the Intel documentation).
+    bits: u16,
+    /// Number of elements in each operand (16 bytes or 8 words).
+    num_elems: u32,
+    /// Length of the first string, which is at most `num_elems`.
+    len1: u32,
+    /// Length of the second string, which is at most `num_elems`.
+    len2: u32,
+}
+
+/// Reads the arguments of a `pcmpestr*` (`str1`, `len1`, `str2`, `len2`,
+/// `imm`) or `pcmpistr*` (`str1`, `str2`, `imm`) intrinsic and compares the
+/// strings. Also returns `imm`.
+fn compare_strings_from_args<'tcx>(
+    this: &mut crate::MiriInterpCx<'_, 'tcx>,
+    unprefixed_name: &str,
+    abi: Abi,
+    link_name: Symbol,
+    args: &[OpTy<'tcx, Provenance>],
+) -> InterpResult<'tcx, (StrCmpResult, u8)> {
+    if unprefixed_name.starts_with("pcmpestr") {
+        let [str1, len1, str2, len2, imm] =
+            this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+        let len1 = this.read_scalar(len1)?.to_i32()?;
+        let len2 = this.read_scalar(len2)?.to_i32()?;
+        let imm = this.read_scalar(imm)?.to_u8()?;
+        let res = compare_strings(this, str1, str2, Some((len1, len2)), imm)?;
+        Ok((res, imm))
+    } else {
+        let [str1, str2, imm] = this.check_shim(abi, Abi::C { unwind: false }, link_name, args)?;
+        let imm = this.read_scalar(imm)?.to_u8()?;
+        let res = compare_strings(this, str1, str2, None, imm)?;
+        Ok((res, imm))
+    }
+}
+
+/// Compares the strings in the 128-bit vectors `str1` and `str2` as
+/// specified by `imm`.
+///
+/// The lengths of the strings are given by `len` (whose absolute values
+/// are saturated to the number of elements), or, when `len` is `None`,
+/// by the position of the first null element.
+fn compare_strings<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    str1: &OpTy<'tcx, Provenance>,
+    str2: &OpTy<'tcx, Provenance>,
+    len: Option<(i32, i32)>,
+    imm: u8,
+) -> InterpResult<'tcx, StrCmpResult> {
+    // Bits 0..=1 specify the format of the elements: unsigned bytes,
+    // unsigned words, signed bytes or signed words.
+    let words = imm & 0b01 != 0;
+    let signed = imm & 0b10 != 0;
+    let str1 = read_string(this, str1, words, signed)?;
+    let str2 = read_string(this, str2, words, signed)?;
+    let num_elems = str1.len();
+
+    let (len1, len2) = match len {
+        Some((len1, len2)) => {
+            let saturate = |len: i32| usize::try_from(len.unsigned_abs()).unwrap().min(num_elems);
+            (saturate(len1), saturate(len2))
+        }
+        None => {
+            let null_pos = |s: &[i32]| s.iter().position(|&c| c == 0).unwrap_or(s.len());
+            (null_pos(&str1), null_pos(&str2))
+        }
+    };
+    let valid1 = &str1[..len1];
+
+    // Bits 2..=3 specify the aggregation operation. `res[j]` is the result
+    // for element `j` of `str2`.
+    let mut res: Vec<bool> = match (imm >> 2) & 0b11 {
+        // Equal any: whether `str2[j]` is any of the characters in `str1`.
+        0b00 => (0..num_elems).map(|j| j < len2 && valid1.contains(&str2[j])).collect(),
+        // Ranges: whether `str2[j]` is within any of the ranges given by the
+        // pairs of characters in `str1`.
+        0b01 =>
+            (0..num_elems)
+                .map(|j| {
+                    j < len2
+                        && valid1
+                            .chunks_exact(2)
+                            .any(|range| range[0] <= str2[j] && str2[j] <= range[1])
+                })
+                .collect(),
+        // Equal each: whether `str1[j] == str2[j]`, where both being out of
+        // bounds counts as equal.
+        0b10 =>
+            (0..num_elems)
+                .map(|j| {
+                    match (j < len1, j < len2) {
+                        (true, true) => str1[j] == str2[j],
+                        (false, false) => true,
+                        _ => false,
+                    }
+                })
+                .collect(),
+        // Equal ordered: whether `str1` is a substring of `str2` starting
+        // at `j`, where `str1` may extend past the end of the vector.
+        0b11 =>
+            (0..num_elems)
+                .map(|j| {
+                    (0..num_elems.checked_sub(j).unwrap()).all(|i| {
+                        let k = j.checked_add(i).unwrap();
+                        if i >= len1 {
+                            true
+                        } else if k >= len2 {
+                            false
+                        } else {
+                            str1[i] == str2[k]
+                        }
+                    })
+                })
+                .collect(),
+        _ => unreachable!(),
+    };
+
+    // Bits 4..=5 specify the polarity: whether to negate all results, only
+    // the ones within `str2`, or none.
+    match (imm >> 4) & 0b11 {
+        0b00 | 0b10 => {}
+        0b01 =>
+            for r in res.iter_mut() {
+                *r = !*r;
+            },
+        0b11 =>
+            for r in res.iter_mut().take(len2) {
+                *r = !*r;
+            },
+        _ => unreachable!(),
+    }
+
+    let bits = res.iter().rev().fold(0u16, |bits, &r| (bits << 1) | u16::from(r));
+    Ok(StrCmpResult {
+        bits,
+        num_elems: num_elems.try_into().unwrap(),
+        len1: len1.try_into().unwrap(),
+        len2: len2.try_into().unwrap(),
+    })
+}
+
+/// Reads the 16 bytes or 8 words of the 128-bit vector `op`, extended
+/// to `i32`.
+fn read_string<'tcx>(
+    this: &crate::MiriInterpCx<'_, 'tcx>,
+    op: &OpTy<'tcx, Provenance>,
+    words: bool,
+    signed: bool,
+) -> InterpResult<'tcx, Vec<i32>> {
+    let (op, op_len) = this.operand_to_simd(op)?;
+    assert_eq!(op_len, 16);
+
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = this.read_scalar(&this.project_index(&op, i.try_into().unwrap())?)?.to_u8()?;
+    }
+
+    let string = if words {
+        bytes
+            .chunks_exact(2)
+            .map(|word| {
+                let word = [word[0], word[1]];
+                if signed {
+                    i32::from(i16::from_le_bytes(word))
+                } else {
+                    i32::from(u16::from_le_bytes(word))
+                }
+            })
+            .collect()
+    } else {
+        bytes
+            .into_iter()
+            .map(|byte| if signed { i32::from(i8::from_le_bytes([byte])) } else { i32::from(byte) })
+            .collect()
+    };
+    Ok(string)
+}
+
+/// Accumulates the CRC-32C of the lowest `bits` bits of `data` on top of
+/// `crc`. Like the `crc32` instruction, this uses the bit-reflected form,
+/// where the least significant bit is processed first.
+fn crc32c(crc: u32, data: u64, bits: u32) -> u32 {
+    // The Castagnoli polynomial 0x1EDC6F41, bit-reflected.
+    const POLY: u64 = 0x82F6_3B78;
+
+    let mut value = u64::from(crc) ^ data;
+    for _ in 0..bits {
+        value = if value & 1 != 0 { (value >> 1) ^ POLY } else { value >> 1 };
+    }
+    // After shifting out all the data bits, only the low 32 bits remain.
+    value.try_into().unwrap()
+}
@@ -0,0 +1,127 @@
+//! Runs a small program with `-Zmiri-coverage` and checks the lcov file and the profile.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn miri_path() -> PathBuf {
+    PathBuf::from(option_env!("MIRI").unwrap_or(env!("CARGO_BIN_EXE_miri")))
+}
+
+/// The records of a single source file in an lcov file.
+#[derive(Debug, Default)]
+struct Record {
+    functions: BTreeMap<String, usize>,
+    calls: BTreeMap<String, u64>,
+    lines: BTreeMap<usize, u64>,
+    totals: BTreeMap<String, usize>,
+}
+
+fn parse_lcov(lcov: &str, source: &str) -> Record {
+    let mut records = lcov.split("end_of_record\n");
+    let record = records
+        .find(|record| record.lines().any(|line| line.starts_with("SF:") && line.ends_with(source)))
+        .unwrap_or_else(|| panic!("no record for `{source}` in\n{lcov}"));
+    let mut parsed = Record::default();
+    for line in record.lines() {
+        let (kind, value) = line.split_once(':').unwrap();
+        match kind {
+            "TN" | "SF" => {}
+            "FN" => {
+                let (line, name) = value.split_once(',').unwrap();
+                parsed.functions.insert(name.to_owned(), line.parse().unwrap());
+            }
+            "FNDA" => {
+                let (calls, name) = value.split_once(',').unwrap();
+                parsed.calls.insert(name.to_owned(), calls.parse().unwrap());
+            }
+            "DA" => {
+                let (line, count) = value.split_once(',').unwrap();
+                parsed.lines.insert(line.parse().unwrap(), count.parse().unwrap());
+            }
+            "FNF" | "FNH" | "LF" | "LH" => {
+                parsed.totals.insert(kind.to_owned(), value.parse().unwrap());
+            }
+            _ => panic!("unexpected lcov line `{line}`"),
+        }
+    }
+    parsed
+}
+
+#[test]
+fn coverage() {
+    let program = "tests/coverage/program.rs";
+    let source = fs::read_to_string(program).unwrap();
+    // The (1-based) line that contains `marker`.
+    let line = |marker: &str| source.lines().position(|line| line.contains(marker)).unwrap() + 1;
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("coverage");
+    let _ = fs::remove_dir_all(&out);
+
+    let mut cmd = Command::new(miri_path());
+    if let Ok(flags) = env::var("MIRIFLAGS") {
+        cmd.args(flags.split_whitespace());
+    }
+    if let Ok(target) = env::var("MIRI_TEST_TARGET") {
+        cmd.arg("--target").arg(target);
+    }
+    let mut flag = std::ffi::OsString::from("-Zmiri-coverage=");
+    flag.push(&out);
+    let status = cmd.arg(flag).arg(program).status().expect("failed to start Miri");
+    assert!(status.success());
+
+    let output = |extension: &str| {
+        let mut files = fs::read_dir(&out)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension));
+        let file = files.next().unwrap_or_else(|| panic!("no `.{extension}` file was written"));
+        assert!(files.next().is_none(), "more than one `.{extension}` file was written");
+        fs::read_to_string(file).unwrap()
+    };
+
+    let record = parse_lcov(&output("lcov"), program);
+    assert_eq!(record.functions["never_called"], line("fn never_called"));
+    assert_eq!(record.functions["twice"], line("fn twice"));
+    assert_eq!(record.calls["never_called"], 0);
+    assert_eq!(record.calls["twice"], 2);
+    assert_eq!(record.calls["exit_holding_vec"], 1);
+    assert_eq!(record.calls["main"], 1);
+    assert_eq!(record.totals["FNF"], 4);
+    assert_eq!(record.totals["FNH"], 3);
+    assert_eq!(record.lines[&line("// never called")], 0);
+    assert_eq!(record.lines[&line("// positive")], 1);
+    assert_eq!(record.lines[&line("// negative")], 1);
+    assert_eq!(record.lines[&line("let sum")], 1);
+    // Dropping `_v` only happens in a cleanup block, which never ran.
+    assert_eq!(record.lines.get(&line("// only reached when")), None, "{record:#?}");
+    // Everything but `never_called` ran.
+    let never_called = line("fn never_called")..line("fn twice");
+    for (line, &count) in &record.lines {
+        assert_eq!(count == 0, never_called.contains(line), "line {line}: {record:#?}");
+    }
+    assert_eq!(record.totals["LF"], record.lines.len());
+    assert_eq!(record.totals["LH"], record.lines.values().filter(|&&count| count > 0).count());
+
+    let profile = output("profile");
+    let mut lines = profile.lines();
+    assert_eq!(lines.next(), Some("  instructions   share       blocks      calls  function"));
+    let total = lines.next_back().unwrap();
+    let total = total.split_whitespace().collect::<Vec<_>>();
+    assert_eq!(total[1..], ["100%", "(total)"]);
+    let instructions = lines
+        .clone()
+        .map(|line| line.split_whitespace().next().unwrap().parse::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    // Sorted by the number of instructions, which add up to the total.
+    assert!(instructions.windows(2).all(|pair| pair[0] >= pair[1]), "{profile}");
+    assert_eq!(instructions.iter().sum::<u64>().to_string(), total[0]);
+    let calls = |name: &str| {
+        let line = lines.clone().find(|line| line.ends_with(&format!("  {name}"))).unwrap();
+        line.split_whitespace().nth(3).unwrap().to_owned()
+    };
+    assert_eq!(calls("twice"), "2");
+    assert_eq!(calls("main"), "1");
+    assert!(!lines.clone().any(|line| line.ends_with("  never_called")), "{profile}");
+}
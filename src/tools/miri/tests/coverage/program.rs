@@ -0,0 +1,23 @@
+#[allow(dead_code)]
+fn never_called() -> i32 {
+    1 // never called
+}
+
+fn twice(x: i32) -> i32 {
+    if x > 0 {
+        x // positive
+    } else {
+        -x // negative
+    }
+}
+
+fn exit_holding_vec() -> ! {
+    let _v: Vec<u8> = Vec::new();
+    std::process::exit(0)
+} // only reached when `exit` unwinds
+
+fn main() {
+    let sum = twice(1) + twice(-2);
+    assert_eq!(sum, 3);
+    exit_holding_vec();
+}
@@ -0,0 +1,174 @@
+//! Debugs a small program over the Debug Adapter Protocol, speaking it over Miri's standard input
+//! and output with `-Zmiri-debugger=stdio`.
+
+#[allow(dead_code)]
+#[path = "../src/debugger/json.rs"]
+mod json;
+
+use std::collections::VecDeque;
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use json::{object, Value};
+
+fn miri_path() -> PathBuf {
+    PathBuf::from(option_env!("MIRI").unwrap_or(env!("CARGO_BIN_EXE_miri")))
+}
+
+/// A client talking to a Miri that runs `program` under the debugger.
+struct Session {
+    miri: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    /// The sequence number of the next request.
+    seq: i64,
+    /// Events that arrived while waiting for a response.
+    events: VecDeque<Value>,
+}
+
+impl Session {
+    fn start(program: &str) -> Self {
+        let mut cmd = Command::new(miri_path());
+        if let Ok(flags) = env::var("MIRIFLAGS") {
+            cmd.args(flags.split_whitespace());
+        }
+        if let Ok(target) = env::var("MIRI_TEST_TARGET") {
+            cmd.arg("--target").arg(target);
+        }
+        cmd.arg("-Zmiri-debugger=stdio").arg(program);
+        let mut miri =
+            cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().expect("failed to start Miri");
+        let input = miri.stdin.take().unwrap();
+        let output = BufReader::new(miri.stdout.take().unwrap());
+        Session { miri, input, output, seq: 1, events: VecDeque::new() }
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            assert_ne!(self.output.read_line(&mut line).unwrap(), 0, "Miri closed its output");
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = Some(value.trim().parse().unwrap());
+            }
+        }
+        let mut content = vec![0; length.expect("missing `Content-Length` header")];
+        self.output.read_exact(&mut content).unwrap();
+        Value::parse(&String::from_utf8(content).unwrap()).unwrap()
+    }
+
+    /// Sends a request and returns the body of its response, which has to be successful.
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        let seq = self.seq;
+        self.seq += 1;
+        let message = object([
+            ("seq", seq.into()),
+            ("type", "request".into()),
+            ("command", command.into()),
+            ("arguments", arguments),
+        ])
+        .to_string();
+        write!(self.input, "Content-Length: {}\r\n\r\n{message}", message.len()).unwrap();
+        self.input.flush().unwrap();
+        loop {
+            let message = self.receive();
+            if message.get("type").as_str() != Some("response") {
+                self.events.push_back(message);
+                continue;
+            }
+            assert_eq!(message.get("request_seq").as_i64(), Some(seq));
+            assert_eq!(message.get("command").as_str(), Some(command));
+            assert_eq!(message.get("success").as_bool(), Some(true), "{message}");
+            return message.get("body").clone();
+        }
+    }
+
+    /// Waits for the next event, which has to be `event`, and returns its body.
+    fn event(&mut self, event: &str) -> Value {
+        let message = self.events.pop_front().unwrap_or_else(|| self.receive());
+        assert_eq!(message.get("type").as_str(), Some("event"), "{message}");
+        assert_eq!(message.get("event").as_str(), Some(event), "{message}");
+        message.get("body").clone()
+    }
+
+    /// Collects everything the program writes to its standard output until the next other event.
+    fn stdout(&mut self) -> String {
+        let mut stdout = String::new();
+        loop {
+            let message = self.events.pop_front().unwrap_or_else(|| self.receive());
+            if message.get("event").as_str() != Some("output") {
+                self.events.push_front(message);
+                return stdout;
+            }
+            assert_eq!(message.get("body").get("category").as_str(), Some("stdout"));
+            stdout.push_str(message.get("body").get("output").as_str().unwrap());
+        }
+    }
+}
+
+#[test]
+fn breakpoint() {
+    let program = "tests/debugger/breakpoint.rs";
+    // Editors name files by their absolute path.
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(program);
+    let line = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .position(|line| line.ends_with("// breakpoint"))
+        .unwrap()
+        + 1;
+    let mut session = Session::start(program);
+
+    let capabilities = session.request("initialize", object([("adapterID", "miri".into())]));
+    assert_eq!(capabilities.get("supportsConfigurationDoneRequest").as_bool(), Some(true));
+    session.event("initialized");
+    let breakpoints = session.request(
+        "setBreakpoints",
+        object([
+            ("source", object([("path", path.to_str().unwrap().into())])),
+            ("breakpoints", vec![object([("line", line.into())])].into()),
+        ]),
+    );
+    let breakpoints = breakpoints.get("breakpoints").as_array().unwrap();
+    assert_eq!(breakpoints.len(), 1);
+    assert_eq!(breakpoints[0].get("verified").as_bool(), Some(true));
+    session.request("configurationDone", Value::Null);
+
+    let stopped = session.event("stopped");
+    assert_eq!(stopped.get("reason").as_str(), Some("breakpoint"));
+    let thread = stopped.get("threadId").clone();
+    let trace = session.request("stackTrace", object([("threadId", thread.clone())]));
+    let top = &trace.get("stackFrames").as_array().unwrap()[0];
+    assert!(top.get("name").as_str().unwrap().ends_with("add"), "{top}");
+    assert_eq!(top.get("line").as_i64(), Some(i64::try_from(line).unwrap()));
+    assert!(top.get("source").get("path").as_str().unwrap().ends_with("breakpoint.rs"), "{top}");
+
+    let scopes = session.request("scopes", object([("frameId", top.get("id").clone())]));
+    let locals = scopes.get("scopes").as_array().unwrap()[0].get("variablesReference").clone();
+    let variables = session.request("variables", object([("variablesReference", locals)]));
+    let value = |name: &str| {
+        let variables = variables.get("variables").as_array().unwrap();
+        let variable =
+            variables.iter().find(|variable| variable.get("name").as_str() == Some(name));
+        variable.unwrap_or_else(|| panic!("no variable `{name}`")).get("value").clone()
+    };
+    assert!(value("a").as_str().unwrap().contains("40"), "{}", value("a"));
+    assert!(value("b").as_str().unwrap().contains('2'), "{}", value("b"));
+
+    session.request("continue", object([("threadId", thread)]));
+    assert_eq!(session.stdout(), "42\n");
+    let exited = session.event("exited");
+    assert_eq!(exited.get("exitCode").as_i64(), Some(0));
+    session.event("terminated");
+    session.request("disconnect", Value::Null);
+    assert!(session.events.is_empty(), "unexpected events: {:?}", session.events);
+
+    drop(session.input);
+    assert!(session.miri.wait().unwrap().success());
+}
@@ -0,0 +1,9 @@
+fn add(a: i32, b: i32) -> i32 {
+    let sum = a + b; // breakpoint
+    sum
+}
+
+fn main() {
+    let y = add(40, 2);
+    println!("{y}");
+}
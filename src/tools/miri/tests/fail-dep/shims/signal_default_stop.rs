@@ -0,0 +1,15 @@
+//! Stopping the program is the default action of some signals, but Miri cannot resume it.
+//@ignore-target-windows: No libc on Windows
+//@revisions: sigstop sigtstp
+
+fn main() {
+    // `SIGSTOP` cannot be caught; `SIGTSTP` can be, but stops the program by default.
+    #[cfg(sigstop)]
+    let sig = libc::SIGSTOP;
+    #[cfg(sigtstp)]
+    let sig = libc::SIGTSTP;
+    unsafe {
+        libc::raise(sig);
+        //~^ ERROR: unsupported operation: stopping the program with
+    }
+}
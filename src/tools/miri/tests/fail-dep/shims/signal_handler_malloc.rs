@@ -0,0 +1,15 @@
+//@ignore-target-windows: No libc on Windows
+//@compile-flags: -Zmiri-permissive-provenance
+
+extern "C" fn handler(_sig: libc::c_int) {
+    unsafe {
+        libc::malloc(8); //~ ERROR: calling `malloc` in a signal handler, but it is not async-signal-safe
+    }
+}
+
+fn main() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+        libc::raise(libc::SIGUSR1);
+    }
+}
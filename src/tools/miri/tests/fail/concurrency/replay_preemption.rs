@@ -0,0 +1,41 @@
+// The `record` revision preempts at the end of every basic block, which makes the spawned thread
+// observe the intermediate state of `main`. The `replay` revision does not preempt on its own, so
+// the failure only reproduces if the recorded preemptions are replayed. The revisions run in the
+// order they are listed.
+//@revisions: record replay
+//@compile-flags: -Zmiri-disable-weak-memory-emulation
+//@[record]compile-flags: -Zmiri-preemption-rate=1
+//@[record]compile-flags: -Zmiri-record=tests/fail/concurrency/replay_preemption.recording
+//@[replay]compile-flags: -Zmiri-preemption-rate=0
+//@[replay]compile-flags: -Zmiri-replay=tests/fail/concurrency/replay_preemption.recording
+#![feature(core_intrinsics)]
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst};
+use std::thread;
+
+static READY: AtomicBool = AtomicBool::new(false);
+static GO: AtomicBool = AtomicBool::new(false);
+static STATE: AtomicUsize = AtomicUsize::new(0);
+
+fn main() {
+    let handle = thread::spawn(|| {
+        READY.store(true, SeqCst);
+        while !GO.load(SeqCst) {
+            thread::yield_now();
+        }
+        let state = STATE.load(SeqCst);
+        unsafe { std::intrinsics::assume(state != 1) } //~ ERROR: `assume` called with `false`
+    });
+    while !READY.load(SeqCst) {
+        thread::yield_now();
+    }
+    GO.store(true, SeqCst);
+    STATE.store(1, SeqCst);
+    // Take long enough for the other thread to read `STATE`, unless we are not preempted.
+    for i in 0..1000 {
+        black_box(i);
+    }
+    STATE.store(2, SeqCst);
+    handle.join().unwrap();
+}
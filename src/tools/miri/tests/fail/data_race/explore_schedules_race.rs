@@ -0,0 +1,30 @@
+// The race below only happens if the first thread is preempted right after its load, which the
+// schedule exploration is guaranteed to find.
+//@compile-flags: -Zmiri-explore-schedules -Zmiri-disable-weak-memory-emulation
+//@normalize-stderr-test: "execution \d+" -> "execution N"
+//@normalize-stderr-test: "schedule was: .*" -> "schedule was: SCHEDULE"
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::spawn;
+
+static CLAIMED: AtomicBool = AtomicBool::new(false);
+static mut DATA: usize = 0;
+
+fn main() {
+    // Both threads run the same closure, so the report looks the same no matter which of them
+    // is preempted.
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            spawn(|| {
+                // A check-then-act race: both threads can observe `false` before either stores.
+                if !CLAIMED.load(Ordering::SeqCst) {
+                    CLAIMED.store(true, Ordering::SeqCst);
+                    unsafe { DATA = 1 } //~ ERROR: Data race detected between (1) non-atomic write on thread `<unnamed>` and (2) non-atomic write on thread `<unnamed>`
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+// The lost update below only happens if the first thread is preempted between its load and its
+// store, which the schedule exploration is guaranteed to find.
+//@compile-flags: -Zmiri-explore-schedules
+//@normalize-stderr-test: "execution \d+" -> "execution N"
+//@normalize-stderr-test: "schedule was: .*" -> "schedule was: SCHEDULE"
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn main() {
+    // This is printed in every execution, but only shown once, for the failing one.
+    println!("incrementing the counter on two threads");
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            thread::spawn(|| {
+                let value = COUNTER.load(Ordering::SeqCst);
+                COUNTER.store(value + 1, Ordering::SeqCst);
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
+}
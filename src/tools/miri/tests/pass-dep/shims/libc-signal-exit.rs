@@ -0,0 +1,15 @@
+//@ignore-target-windows: No libc on Windows
+//@compile-flags: -Zmiri-permissive-provenance
+
+extern "C" fn handler(_sig: libc::c_int) {
+    // `_exit` is async-signal-safe, unlike `exit`.
+    unsafe { libc::_exit(0) }
+}
+
+fn main() {
+    unsafe {
+        libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+        libc::raise(libc::SIGTERM);
+    }
+    unreachable!("the handler exits the program");
+}
@@ -0,0 +1,226 @@
+//@ignore-target-windows: No libc on Windows
+//@compile-flags: -Zmiri-disable-isolation -Zmiri-permissive-provenance
+
+use std::mem;
+use std::os::unix::thread::JoinHandleExt;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::thread;
+
+static LAST_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn handler(sig: libc::c_int) {
+    LAST_SIGNAL.store(sig, Ordering::Relaxed);
+    HANDLED.fetch_add(1, Ordering::Relaxed);
+}
+
+fn handled() -> usize {
+    HANDLED.swap(0, Ordering::Relaxed)
+}
+
+fn empty_sigset() -> libc::sigset_t {
+    unsafe {
+        let mut set = mem::MaybeUninit::uninit();
+        assert_eq!(libc::sigemptyset(set.as_mut_ptr()), 0);
+        set.assume_init()
+    }
+}
+
+fn sigset_of(sig: libc::c_int) -> libc::sigset_t {
+    let mut set = empty_sigset();
+    assert_eq!(unsafe { libc::sigaddset(&mut set, sig) }, 0);
+    set
+}
+
+fn test_sigset() {
+    unsafe {
+        let mut set = empty_sigset();
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR1), 0);
+        assert_eq!(libc::sigaddset(&mut set, libc::SIGUSR1), 0);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR1), 1);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR2), 0);
+        assert_eq!(libc::sigdelset(&mut set, libc::SIGUSR1), 0);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR1), 0);
+
+        assert_eq!(libc::sigfillset(&mut set), 0);
+        assert_eq!(libc::sigismember(&set, libc::SIGINT), 1);
+        assert_eq!(libc::sigismember(&set, libc::SIGTERM), 1);
+
+        assert_eq!(libc::sigaddset(&mut set, 0), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+    }
+}
+
+fn test_raise() {
+    unsafe {
+        let old = libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+        assert_eq!(old, libc::SIG_DFL);
+        assert_eq!(libc::raise(libc::SIGUSR1), 0);
+        // The handler runs before `raise` returns to us.
+        assert_eq!(handled(), 1);
+        assert_eq!(LAST_SIGNAL.load(Ordering::Relaxed), libc::SIGUSR1);
+
+        // The handler stays installed.
+        assert_eq!(libc::raise(libc::SIGUSR1), 0);
+        assert_eq!(handled(), 1);
+
+        let old = libc::signal(libc::SIGUSR1, libc::SIG_IGN);
+        assert_eq!(old, handler as libc::sighandler_t);
+        assert_eq!(libc::raise(libc::SIGUSR1), 0);
+        assert_eq!(handled(), 0);
+
+        // `SIGKILL` cannot be caught.
+        assert_eq!(libc::signal(libc::SIGKILL, libc::SIG_IGN), libc::SIG_ERR);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
+
+static SIGINFO_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static MASKED_IN_HANDLER: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn siginfo_handler(
+    sig: libc::c_int,
+    info: *mut libc::siginfo_t,
+    _context: *mut libc::c_void,
+) {
+    unsafe {
+        assert_eq!((*info).si_signo, sig);
+        SIGINFO_SIGNAL.store(sig, Ordering::Relaxed);
+        // The signal is blocked while its handler runs.
+        let mut set = empty_sigset();
+        assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set), 0);
+        MASKED_IN_HANDLER.store(libc::sigismember(&set, sig) == 1, Ordering::Relaxed);
+    }
+}
+
+fn test_sigaction() {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = siginfo_handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO;
+        action.sa_mask = empty_sigset();
+        let mut old: libc::sigaction = mem::zeroed();
+        assert_eq!(libc::sigaction(libc::SIGUSR2, &action, &mut old), 0);
+        assert_eq!(old.sa_sigaction, libc::SIG_DFL);
+
+        assert_eq!(libc::raise(libc::SIGUSR2), 0);
+        assert_eq!(SIGINFO_SIGNAL.load(Ordering::Relaxed), libc::SIGUSR2);
+        assert!(MASKED_IN_HANDLER.load(Ordering::Relaxed));
+
+        // After the handler returned, the signal is no longer blocked.
+        let mut set = empty_sigset();
+        assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set), 0);
+        assert_eq!(libc::sigismember(&set, libc::SIGUSR2), 0);
+
+        // Query the current action.
+        assert_eq!(libc::sigaction(libc::SIGUSR2, ptr::null(), &mut old), 0);
+        assert_eq!(old.sa_sigaction, siginfo_handler as libc::sighandler_t);
+        assert_ne!(old.sa_flags & libc::SA_SIGINFO, 0);
+
+        // With `SA_RESETHAND`, the action is reset to the default when the signal is delivered.
+        action.sa_sigaction = handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESETHAND;
+        assert_eq!(libc::sigaction(libc::SIGUSR2, &action, ptr::null_mut()), 0);
+        assert_eq!(libc::raise(libc::SIGUSR2), 0);
+        assert_eq!(handled(), 1);
+        assert_eq!(libc::sigaction(libc::SIGUSR2, ptr::null(), &mut old), 0);
+        assert_eq!(old.sa_sigaction, libc::SIG_DFL);
+    }
+}
+
+fn test_mask() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+
+        let set = sigset_of(libc::SIGUSR1);
+        assert_eq!(libc::sigprocmask(libc::SIG_BLOCK, &set, ptr::null_mut()), 0);
+        assert_eq!(libc::raise(libc::SIGUSR1), 0);
+        assert_eq!(handled(), 0);
+
+        let mut pending = empty_sigset();
+        assert_eq!(libc::sigpending(&mut pending), 0);
+        assert_eq!(libc::sigismember(&pending, libc::SIGUSR1), 1);
+
+        // Unblocking delivers the pending signal.
+        assert_eq!(libc::sigprocmask(libc::SIG_UNBLOCK, &set, ptr::null_mut()), 0);
+        assert_eq!(handled(), 1);
+        assert_eq!(libc::sigpending(&mut pending), 0);
+        assert_eq!(libc::sigismember(&pending, libc::SIGUSR1), 0);
+
+        assert_eq!(libc::sigprocmask(42, &set, ptr::null_mut()), -1);
+        assert_eq!(std::io::Error::last_os_error().raw_os_error(), Some(libc::EINVAL));
+
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
+
+static HANDLER_THREAD: AtomicUsize = AtomicUsize::new(0);
+static THREAD_SIGNALED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn thread_handler(_sig: libc::c_int) {
+    HANDLER_THREAD.store(unsafe { libc::pthread_self() } as usize, Ordering::Relaxed);
+    THREAD_SIGNALED.store(true, Ordering::Relaxed);
+}
+
+fn test_pthread_kill() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, thread_handler as libc::sighandler_t);
+
+        let child = thread::spawn(|| {
+            while !THREAD_SIGNALED.load(Ordering::Relaxed) {
+                thread::yield_now();
+            }
+        });
+        let child_id = child.as_pthread_t();
+        // Signal 0 only checks that the thread exists.
+        assert_eq!(libc::pthread_kill(child_id, 0), 0);
+        assert_eq!(libc::pthread_kill(child_id, libc::SIGUSR1), 0);
+        child.join().unwrap();
+
+        assert_eq!(HANDLER_THREAD.load(Ordering::Relaxed), child_id as usize);
+
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
+
+fn test_kill_self() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+
+        assert_eq!(libc::kill(libc::getpid(), 0), 0);
+        assert_eq!(libc::kill(libc::getpid(), libc::SIGUSR1), 0);
+        assert_eq!(handled(), 1);
+        assert_eq!(libc::kill(0, libc::SIGUSR1), 0);
+        assert_eq!(handled(), 1);
+
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
+
+extern "C" fn spinning_handler(_sig: libc::c_int) {
+    // This is an LLVM intrinsic on some targets, not a call into the C library.
+    std::hint::spin_loop();
+    HANDLED.fetch_add(1, Ordering::Relaxed);
+}
+
+fn test_intrinsic_in_handler() {
+    unsafe {
+        libc::signal(libc::SIGUSR1, spinning_handler as libc::sighandler_t);
+        assert_eq!(libc::raise(libc::SIGUSR1), 0);
+        assert_eq!(handled(), 1);
+        libc::signal(libc::SIGUSR1, libc::SIG_DFL);
+    }
+}
+
+fn main() {
+    test_sigset();
+    test_raise();
+    test_sigaction();
+    test_mask();
+    test_pthread_kill();
+    test_kill_self();
+    test_intrinsic_in_handler();
+}
@@ -288,6 +288,72 @@ fn test_iriw_sc_rlx() {
     assert!(c || d);
 }
 
+// Store buffering with SC fences, where one of the stores only happens-before
+// the fence through release/acquire synchronization with another thread.
+// C++20 orders everything that happens-before an SC fence, not just what is
+// sequenced-before it.
+fn test_sc_fence_after_synchronized_store() {
+    let x = static_atomic(0);
+    let y = static_atomic(0);
+    let z = static_atomic(0);
+
+    let j1 = spawn(move || {
+        y.store(1, Relaxed);
+        z.store(1, Release);
+    });
+
+    let j2 = spawn(move || {
+        acquires_value(z, 1);
+        fence(SeqCst);
+        x.load(Relaxed)
+    });
+
+    let j3 = spawn(move || {
+        x.store(1, Relaxed);
+        fence(SeqCst);
+        y.load(Relaxed)
+    });
+
+    j1.join().unwrap();
+    let a = j2.join().unwrap();
+    let b = j3.join().unwrap();
+
+    assert_ne!((a, b), (0, 0));
+}
+
+// Store buffering with SC fences, where one of the loads only happens-after
+// the fence through release/acquire synchronization with another thread.
+// C++20 makes an SC fence affect every load that happens-after it, not just
+// the loads of the fencing thread.
+fn test_sc_fence_before_synchronized_load() {
+    let x = static_atomic(0);
+    let y = static_atomic(0);
+    let z = static_atomic(0);
+
+    let j1 = spawn(move || {
+        y.store(1, Relaxed);
+        fence(SeqCst);
+        x.load(Relaxed)
+    });
+
+    let j2 = spawn(move || {
+        x.store(1, Relaxed);
+        fence(SeqCst);
+        z.store(1, Release);
+    });
+
+    let j3 = spawn(move || {
+        acquires_value(z, 1);
+        y.load(Relaxed)
+    });
+
+    let a = j1.join().unwrap();
+    j2.join().unwrap();
+    let b = j3.join().unwrap();
+
+    assert_ne!((a, b), (0, 0));
+}
+
 pub fn main() {
     for _ in 0..50 {
         test_single_thread();
@@ -299,5 +365,7 @@ pub fn main() {
         test_sc_store_buffering();
         test_sync_through_rmw_and_fences();
         test_iriw_sc_rlx();
+        test_sc_fence_after_synchronized_store();
+        test_sc_fence_before_synchronized_load();
     }
 }
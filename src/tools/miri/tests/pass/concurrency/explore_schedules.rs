@@ -0,0 +1,48 @@
+//@compile-flags: -Zmiri-explore-schedules
+//@normalize-stderr-test: "explored \d+ schedules" -> "explored N schedules"
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Release/acquire message passing must work in every interleaving.
+fn message_passing() {
+    static mut DATA: usize = 0;
+    static READY: AtomicBool = AtomicBool::new(false);
+
+    let writer = thread::spawn(|| {
+        unsafe { DATA = 42 };
+        READY.store(true, Ordering::Release);
+    });
+    let reader = thread::spawn(|| {
+        if READY.load(Ordering::Acquire) {
+            assert_eq!(unsafe { DATA }, 42);
+        }
+    });
+    writer.join().unwrap();
+    reader.join().unwrap();
+}
+
+/// Read-modify-write operations never lose an update.
+fn counter() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+
+fn main() {
+    message_passing();
+    counter();
+    // This is printed in every execution, but only shown once, for the last one.
+    println!("all schedules passed");
+}
@@ -0,0 +1,321 @@
+// Ignore everything except x86 and x86_64
+// Any additional target are added to CI should be ignored here
+// (We cannot use `cfg`-based tricks here since the `target-feature` flags below only work on x86.)
+//@ignore-target-aarch64
+//@ignore-target-arm
+//@ignore-target-avr
+//@ignore-target-s390x
+//@ignore-target-thumbv7em
+//@ignore-target-wasm32
+//@compile-flags: -C target-feature=+avx
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::mem::transmute;
+
+fn main() {
+    assert!(is_x86_feature_detected!("avx"));
+
+    unsafe {
+        test_avx();
+    }
+}
+
+#[target_feature(enable = "avx")]
+unsafe fn test_avx() {
+    // Mostly copied from library/stdarch/crates/core_arch/src/x86/avx.rs
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_max_min_ps() {
+        let a = _mm256_setr_ps(1., 4., 5., 8., 9., 12., 13., 16.);
+        let b = _mm256_setr_ps(2., 3., 6., 7., 10., 11., 14., 15.);
+        let r = _mm256_max_ps(a, b);
+        let e = _mm256_setr_ps(2., 4., 6., 8., 10., 12., 14., 16.);
+        assert_eq_m256(r, e);
+        let r = _mm256_min_ps(a, b);
+        let e = _mm256_setr_ps(1., 3., 5., 7., 9., 11., 13., 15.);
+        assert_eq_m256(r, e);
+
+        // > If the values being compared are both 0.0s (of either sign), the
+        // > value in the second operand (source operand) is returned.
+        let w = _mm256_max_ps(_mm256_set1_ps(0.0), _mm256_set1_ps(-0.0));
+        let x = _mm256_max_ps(_mm256_set1_ps(-0.0), _mm256_set1_ps(0.0));
+        let wu: [u32; 8] = transmute(w);
+        let xu: [u32; 8] = transmute(x);
+        assert_eq!(wu, [0x8000_0000u32; 8]);
+        assert_eq!(xu, [0u32; 8]);
+    }
+    test_mm256_max_min_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_round_pd() {
+        let a = _mm256_setr_pd(1.55, 2.2, 3.99, -1.2);
+        let r = _mm256_round_pd::<0b0000>(a);
+        assert_eq_m256d(r, _mm256_setr_pd(2., 2., 4., -1.));
+        let r = _mm256_round_pd::<0b0001>(a);
+        assert_eq_m256d(r, _mm256_setr_pd(1., 2., 3., -2.));
+        let r = _mm256_round_pd::<0b0010>(a);
+        assert_eq_m256d(r, _mm256_setr_pd(2., 3., 4., -1.));
+        let r = _mm256_round_pd::<0b0011>(a);
+        assert_eq_m256d(r, _mm256_setr_pd(1., 2., 3., -1.));
+    }
+    test_mm256_round_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_sqrt_ps() {
+        let a = _mm256_setr_ps(4., 9., 16., 25., 4., 9., 16., 25.);
+        let r = _mm256_sqrt_ps(a);
+        let e = _mm256_setr_ps(2., 3., 4., 5., 2., 3., 4., 5.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_sqrt_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_addsub_ps() {
+        let a = _mm256_setr_ps(1., 2., 3., 4., 1., 2., 3., 4.);
+        let b = _mm256_setr_ps(5., 6., 7., 8., 5., 6., 7., 8.);
+        let r = _mm256_addsub_ps(a, b);
+        let e = _mm256_setr_ps(-4., 8., -4., 12., -4., 8., -4., 12.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_addsub_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_blendv_pd() {
+        let a = _mm256_setr_pd(4., 9., 16., 25.);
+        let b = _mm256_setr_pd(4., 3., 2., 5.);
+        let c = _mm256_setr_pd(0., 0., !0 as f64, !0 as f64);
+        let r = _mm256_blendv_pd(a, b, c);
+        let e = _mm256_setr_pd(4., 9., 2., 5.);
+        assert_eq_m256d(r, e);
+    }
+    test_mm256_blendv_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_dp_ps() {
+        let a = _mm256_setr_ps(4., 9., 16., 25., 4., 9., 16., 25.);
+        let b = _mm256_setr_ps(4., 3., 2., 5., 8., 9., 64., 50.);
+        let r = _mm256_dp_ps::<0xFF>(a, b);
+        let e = _mm256_setr_ps(200., 200., 200., 200., 2387., 2387., 2387., 2387.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_dp_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_hadd_hsub_pd() {
+        let a = _mm256_setr_pd(4., 9., 16., 25.);
+        let b = _mm256_setr_pd(4., 3., 2., 5.);
+        let r = _mm256_hadd_pd(a, b);
+        let e = _mm256_setr_pd(13., 7., 41., 7.);
+        assert_eq_m256d(r, e);
+        let r = _mm256_hsub_pd(a, b);
+        let e = _mm256_setr_pd(-5., 1., -9., -3.);
+        assert_eq_m256d(r, e);
+    }
+    test_mm256_hadd_hsub_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_cmp_ps() {
+        let a = _mm256_setr_ps(1., 2., 3., f32::NAN, 5., 6., 7., 8.);
+        let b = _mm256_setr_ps(2., 2., 1., 4., f32::NAN, 6., 9., 0.);
+
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_GT_OQ>(a, b));
+        assert_eq!(r, [0, 0, !0, 0, 0, 0, 0, !0]);
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_NLE_UQ>(a, b));
+        assert_eq!(r, [0, 0, !0, !0, !0, 0, 0, !0]);
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_EQ_UQ>(a, b));
+        assert_eq!(r, [0, !0, 0, !0, !0, !0, 0, 0]);
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_NEQ_OQ>(a, b));
+        assert_eq!(r, [!0, 0, !0, 0, 0, 0, !0, !0]);
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_TRUE_US>(a, b));
+        assert_eq!(r, [!0; 8]);
+        let r: [u32; 8] = transmute(_mm256_cmp_ps::<_CMP_FALSE_OS>(a, b));
+        assert_eq!(r, [0; 8]);
+    }
+    test_mm256_cmp_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm_cmp_pd() {
+        let a = _mm_setr_pd(4., f64::NAN);
+        let b = _mm_setr_pd(10., 3.);
+        let r: [u64; 2] = transmute(_mm_cmp_pd::<_CMP_GE_OS>(a, b));
+        assert_eq!(r, [0, 0]);
+        let r: [u64; 2] = transmute(_mm_cmp_pd::<_CMP_NGE_US>(a, b));
+        assert_eq!(r, [!0, !0]);
+        let r: [u64; 2] = transmute(_mm_cmp_pd::<_CMP_ORD_Q>(a, b));
+        assert_eq!(r, [!0, 0]);
+    }
+    test_mm_cmp_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_cvt() {
+        let a = _mm256_setr_ps(1.5, 2.5, -1.5, -2.5, 3.7, -3.7, 1e10, f32::NAN);
+        let r: [i32; 8] = transmute(_mm256_cvtps_epi32(a));
+        assert_eq!(r, [2, 2, -2, -2, 4, -4, i32::MIN, i32::MIN]);
+        let r: [i32; 8] = transmute(_mm256_cvttps_epi32(a));
+        assert_eq!(r, [1, 2, -1, -2, 3, -3, i32::MIN, i32::MIN]);
+
+        let a = _mm256_setr_pd(1.5, -2.5, 3.7, 1e10);
+        let r: [i32; 4] = transmute(_mm256_cvtpd_epi32(a));
+        assert_eq!(r, [2, -2, 4, i32::MIN]);
+        let r: [i32; 4] = transmute(_mm256_cvttpd_epi32(a));
+        assert_eq!(r, [1, -2, 3, i32::MIN]);
+        let r = _mm256_cvtpd_ps(a);
+        assert_eq_m128(r, _mm_setr_ps(1.5, -2.5, 3.7, 1e10));
+    }
+    test_mm256_cvt();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_permutevar_ps() {
+        let a = _mm256_setr_ps(4., 3., 2., 5., 8., 9., 64., 50.);
+        let b = _mm256_setr_epi32(1, 2, 3, 4, 5, 6, 7, 8);
+        let r = _mm256_permutevar_ps(a, b);
+        let e = _mm256_setr_ps(3., 2., 5., 4., 9., 64., 50., 8.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_permutevar_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_permutevar_pd() {
+        // Only bit 1 of each element of `b` is used.
+        let a = _mm256_setr_pd(4., 3., 2., 5.);
+        let b = _mm256_setr_epi64x(1, 2, 3, 4);
+        let r = _mm256_permutevar_pd(a, b);
+        let e = _mm256_setr_pd(4., 3., 5., 2.);
+        assert_eq_m256d(r, e);
+    }
+    test_mm256_permutevar_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_permute2f128_ps() {
+        let a = _mm256_setr_ps(1., 2., 3., 4., 1., 2., 3., 4.);
+        let b = _mm256_setr_ps(5., 6., 7., 8., 5., 6., 7., 8.);
+        let r = _mm256_permute2f128_ps::<0x13>(a, b);
+        let e = _mm256_setr_ps(5., 6., 7., 8., 1., 2., 3., 4.);
+        assert_eq_m256(r, e);
+
+        // Setting bit 3 or 7 zeroes the corresponding lane.
+        let r = _mm256_permute2f128_ps::<0x83>(a, b);
+        let e = _mm256_setr_ps(5., 6., 7., 8., 0., 0., 0., 0.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_permute2f128_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_broadcast_ps() {
+        let a = _mm_setr_ps(4., 3., 2., 5.);
+        let r = _mm256_broadcast_ps(&a);
+        let e = _mm256_setr_ps(4., 3., 2., 5., 4., 3., 2., 5.);
+        assert_eq_m256(r, e);
+    }
+    test_mm256_broadcast_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_maskload_maskstore_pd() {
+        let a = &[1.0f64, 2., 3., 4.];
+        let mask = _mm256_setr_epi64x(0, !0, 0, !0);
+        let r = _mm256_maskload_pd(a.as_ptr(), mask);
+        let e = _mm256_setr_pd(0., 2., 0., 4.);
+        assert_eq_m256d(r, e);
+
+        let mut r = [0.0f64; 4];
+        _mm256_maskstore_pd(r.as_mut_ptr(), mask, _mm256_set1_pd(9.));
+        assert_eq!(r, [0., 9., 0., 9.]);
+
+        // Masked-out elements may be out of bounds.
+        let a = &[1.0f32, 2.];
+        let mask = _mm_setr_epi32(!0, !0, 0, 0);
+        let r = _mm_maskload_ps(a.as_ptr(), mask);
+        let e = _mm_setr_ps(1., 2., 0., 0.);
+        assert_eq_m128(r, e);
+    }
+    test_mm256_maskload_maskstore_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_lddqu_si256() {
+        let a = _mm256_setr_epi64x(1, 2, 3, 4);
+        let r = _mm256_lddqu_si256(&a);
+        assert_eq_m256i(r, a);
+    }
+    test_mm256_lddqu_si256();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_test_si256() {
+        let a = _mm256_setr_epi64x(1, 2, 3, 4);
+        let b = _mm256_setr_epi64x(5, 6, 7, 8);
+        assert_eq!(_mm256_testz_si256(a, b), 0);
+        assert_eq!(_mm256_testz_si256(a, _mm256_set1_epi64x(0)), 1);
+        assert_eq!(_mm256_testc_si256(a, b), 0);
+        assert_eq!(_mm256_testc_si256(_mm256_set1_epi64x(!0), b), 1);
+        assert_eq!(_mm256_testnzc_si256(a, b), 1);
+        assert_eq!(_mm256_testnzc_si256(a, _mm256_set1_epi64x(0)), 0);
+    }
+    test_mm256_test_si256();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_test_pd() {
+        let a = _mm256_setr_pd(1., -2., 3., 4.);
+        let b = _mm256_setr_pd(5., 6., 7., 8.);
+        assert_eq!(_mm256_testz_pd(a, b), 1);
+        assert_eq!(_mm256_testc_pd(a, b), 1);
+        assert_eq!(_mm256_testnzc_pd(a, b), 0);
+
+        let b = _mm256_setr_pd(5., -6., 7., 8.);
+        assert_eq!(_mm256_testz_pd(a, b), 0);
+        assert_eq!(_mm256_testc_pd(a, b), 1);
+
+        let a = _mm_setr_ps(-1., 2., -3., 4.);
+        let b = _mm_setr_ps(-1., -2., 3., 4.);
+        assert_eq!(_mm_testz_ps(a, b), 0);
+        assert_eq!(_mm_testc_ps(a, b), 0);
+        assert_eq!(_mm_testnzc_ps(a, b), 1);
+    }
+    test_mm256_test_pd();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_movemask_ps() {
+        let a = _mm256_setr_ps(-1., 2., -3., 4., -0., 0., f32::NAN, -f32::NAN);
+        assert_eq!(_mm256_movemask_ps(a), 0b1001_0101);
+        let a = _mm256_setr_pd(-1., 2., -3., 4.);
+        assert_eq!(_mm256_movemask_pd(a), 0b0101);
+    }
+    test_mm256_movemask_ps();
+
+    #[target_feature(enable = "avx")]
+    unsafe fn test_mm256_zeroupper() {
+        // These only affect the upper bits of the registers, which are not
+        // observable from Rust.
+        _mm256_zeroupper();
+        _mm256_zeroall();
+    }
+    test_mm256_zeroupper();
+}
+
+#[track_caller]
+#[target_feature(enable = "sse")]
+unsafe fn assert_eq_m128(a: __m128, b: __m128) {
+    let r = _mm_cmpeq_ps(a, b);
+    if _mm_movemask_ps(r) != 0b1111 {
+        panic!("{:?} != {:?}", a, b);
+    }
+}
+
+#[track_caller]
+#[target_feature(enable = "avx")]
+unsafe fn assert_eq_m256(a: __m256, b: __m256) {
+    assert_eq!(transmute::<_, [u32; 8]>(a), transmute::<_, [u32; 8]>(b))
+}
+
+#[track_caller]
+#[target_feature(enable = "avx")]
+unsafe fn assert_eq_m256d(a: __m256d, b: __m256d) {
+    assert_eq!(transmute::<_, [u64; 4]>(a), transmute::<_, [u64; 4]>(b))
+}
+
+#[track_caller]
+#[target_feature(enable = "avx")]
+unsafe fn assert_eq_m256i(a: __m256i, b: __m256i) {
+    assert_eq!(transmute::<_, [u64; 4]>(a), transmute::<_, [u64; 4]>(b))
+}
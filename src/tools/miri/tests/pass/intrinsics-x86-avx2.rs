@@ -0,0 +1,325 @@
+// Ignore everything except x86 and x86_64
+// Any additional target are added to CI should be ignored here
+// (We cannot use `cfg`-based tricks here since the `target-feature` flags below only work on x86.)
+//@ignore-target-aarch64
+//@ignore-target-arm
+//@ignore-target-avr
+//@ignore-target-s390x
+//@ignore-target-thumbv7em
+//@ignore-target-wasm32
+//@compile-flags: -C target-feature=+avx2
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::mem::transmute;
+
+fn main() {
+    assert!(is_x86_feature_detected!("avx"));
+    assert!(is_x86_feature_detected!("avx2"));
+
+    unsafe {
+        test_avx2();
+    }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn test_avx2() {
+    // Mostly copied from library/stdarch/crates/core_arch/src/x86/avx2.rs
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_abs_epi16() {
+        let a =
+            _mm256_setr_epi16(0, 1, -1, 2, -2, 3, -3, 4, -4, 5, -5, i16::MAX, i16::MIN, 0, 7, -7);
+        let r = _mm256_abs_epi16(a);
+        let e = _mm256_setr_epi16(0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, i16::MAX, i16::MIN, 0, 7, 7);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_abs_epi16();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_hadds_epi16() {
+        let a = _mm256_set1_epi16(2);
+        let a = _mm256_insert_epi16::<0>(a, 0x7fff);
+        let a = _mm256_insert_epi16::<1>(a, 1);
+        let b = _mm256_set1_epi16(4);
+        let r = _mm256_hadds_epi16(a, b);
+        let e = _mm256_setr_epi16(0x7FFF, 4, 4, 4, 8, 8, 8, 8, 4, 4, 4, 4, 8, 8, 8, 8);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_hadds_epi16();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_hsub_epi32() {
+        let a = _mm256_setr_epi32(1, 2, 3, 5, 8, 13, 21, 34);
+        let b = _mm256_setr_epi32(0, 1, 0, 2, 0, 3, 0, 4);
+        let r = _mm256_hsub_epi32(a, b);
+        let e = _mm256_setr_epi32(-1, -2, -1, -2, -5, -13, -3, -4);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_hsub_epi32();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_madd() {
+        let a = _mm256_set1_epi16(2);
+        let b = _mm256_set1_epi16(4);
+        let r = _mm256_madd_epi16(a, b);
+        let e = _mm256_set1_epi32(16);
+        assert_eq_m256i(r, e);
+
+        let a = _mm256_set1_epi8(-1);
+        let b = _mm256_set1_epi8(-128);
+        let r = _mm256_maddubs_epi16(a, b);
+        let e = _mm256_set1_epi16(i16::MIN);
+        assert_eq_m256i(r, e);
+
+        let a = _mm256_set1_epi16(0x4000);
+        let b = _mm256_set1_epi16(0x4000);
+        let r = _mm256_mulhrs_epi16(a, b);
+        let e = _mm256_set1_epi16(0x2000);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_madd();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_mpsadbw_epu8() {
+        let a = _mm256_setr_epi8(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, //
+            0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30,
+        );
+        let r = _mm256_mpsadbw_epu8::<0>(a, a);
+        let e = _mm256_setr_epi16(0, 4, 8, 12, 16, 20, 24, 28, 0, 8, 16, 24, 32, 40, 48, 56);
+        assert_eq_m256i(r, e);
+
+        // The upper lane uses bits 3..=5 of the immediate.
+        let r = _mm256_mpsadbw_epu8::<0b001_000>(a, a);
+        let e = _mm256_setr_epi16(0, 4, 8, 12, 16, 20, 24, 28, 32, 24, 16, 8, 0, 8, 16, 24);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_mpsadbw_epu8();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_packs() {
+        let a = _mm256_setr_epi16(2, -2, 300, -300, 2, 2, 2, 2, 4, 4, 4, 4, 4, 4, 4, 4);
+        let b = _mm256_set1_epi16(-1);
+        let r = _mm256_packs_epi16(a, b);
+        #[rustfmt::skip]
+        let e = _mm256_setr_epi8(
+            2, -2, 127, -128, 2, 2, 2, 2, -1, -1, -1, -1, -1, -1, -1, -1,
+            4, 4, 4, 4, 4, 4, 4, 4, -1, -1, -1, -1, -1, -1, -1, -1,
+        );
+        assert_eq_m256i(r, e);
+
+        let r = _mm256_packus_epi16(a, b);
+        #[rustfmt::skip]
+        let e = _mm256_setr_epi8(
+            2, 0, -1, 0, 2, 2, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0,
+            4, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0,
+        );
+        assert_eq_m256i(r, e);
+
+        let a = _mm256_setr_epi32(2, -2, 70000, -70000, 4, 4, 4, 4);
+        let b = _mm256_set1_epi32(-1);
+        let r = _mm256_packs_epi32(a, b);
+        let e = _mm256_setr_epi16(2, -2, 32767, -32768, -1, -1, -1, -1, 4, 4, 4, 4, -1, -1, -1, -1);
+        assert_eq_m256i(r, e);
+
+        let r = _mm256_packus_epi32(a, b);
+        let e = _mm256_setr_epi16(2, 0, -1, 0, 0, 0, 0, 0, 4, 4, 4, 4, 0, 0, 0, 0);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_packs();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_sad_epu8() {
+        let a = _mm256_set1_epi8(2);
+        let b = _mm256_set1_epi8(4);
+        let r = _mm256_sad_epu8(a, b);
+        let e = _mm256_set1_epi64x(16);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_sad_epu8();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_shuffle_epi8() {
+        #[rustfmt::skip]
+        let a = _mm256_setr_epi8(
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+            17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,
+        );
+        #[rustfmt::skip]
+        let b = _mm256_setr_epi8(
+            4, 128u8 as i8, 4, 3, 24, 12, 6, 19, 12, 5, 5, 10, 4, 1, 8, 0,
+            4, 128u8 as i8, 4, 3, 24, 12, 6, 19, 12, 5, 5, 10, 4, 1, 8, 0,
+        );
+        #[rustfmt::skip]
+        let e = _mm256_setr_epi8(
+            5, 0, 5, 4, 9, 13, 7, 4, 13, 6, 6, 11, 5, 2, 9, 1,
+            21, 0, 21, 20, 25, 29, 23, 20, 29, 22, 22, 27, 21, 18, 25, 17,
+        );
+        let r = _mm256_shuffle_epi8(a, b);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_shuffle_epi8();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_sign_epi32() {
+        let a = _mm256_set1_epi32(2);
+        let b = _mm256_setr_epi32(-1, 0, 1, -5, 5, 0, -7, 7);
+        let r = _mm256_sign_epi32(a, b);
+        let e = _mm256_setr_epi32(-2, 0, 2, -2, 2, 0, -2, 2);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_sign_epi32();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_shift() {
+        let a = _mm256_setr_epi32(1, -2, 3, -4, 5, -6, 7, i32::MIN);
+        let r = _mm256_sll_epi32(a, _mm_set_epi64x(0, 4));
+        let e = _mm256_setr_epi32(16, -32, 48, -64, 80, -96, 112, 0);
+        assert_eq_m256i(r, e);
+        let r = _mm256_srl_epi32(a, _mm_set_epi64x(0, 28));
+        let e = _mm256_setr_epi32(0, 15, 0, 15, 0, 15, 0, 8);
+        assert_eq_m256i(r, e);
+        let r = _mm256_sra_epi32(a, _mm_set_epi64x(0, 1));
+        let e = _mm256_setr_epi32(0, -1, 1, -2, 2, -3, 3, i32::MIN / 2);
+        assert_eq_m256i(r, e);
+
+        // Shift amounts larger than the element size zero the result, or fill
+        // it with the sign bit for arithmetic shifts.
+        let r = _mm256_sll_epi32(a, _mm_set_epi64x(0, 32));
+        assert_eq_m256i(r, _mm256_setzero_si256());
+        let r = _mm256_sra_epi32(a, _mm_set_epi64x(0, 100));
+        let e = _mm256_setr_epi32(0, -1, 0, -1, 0, -1, 0, -1);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_shift();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_shiftv() {
+        let a = _mm256_set1_epi32(-8);
+        let count = _mm256_setr_epi32(0, 1, 2, 31, 32, 33, -1, 3);
+        let r = _mm256_sllv_epi32(a, count);
+        let e = _mm256_setr_epi32(-8, -16, -32, 0, 0, 0, 0, -64);
+        assert_eq_m256i(r, e);
+        let r = _mm256_srlv_epi32(a, count);
+        let e = _mm256_setr_epi32(-8, 0x7FFFFFFC, 0x3FFFFFFE, 1, 0, 0, 0, 0x1FFFFFFF);
+        assert_eq_m256i(r, e);
+        let r = _mm256_srav_epi32(a, count);
+        let e = _mm256_setr_epi32(-8, -4, -2, -1, -1, -1, -1, -1);
+        assert_eq_m256i(r, e);
+
+        let a = _mm_set1_epi64x(1);
+        let count = _mm_set_epi64x(64, 63);
+        let r = _mm_sllv_epi64(a, count);
+        let e = _mm_set_epi64x(0, i64::MIN);
+        assert_eq_m128i(r, e);
+    }
+    test_mm256_shiftv();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_permutevar8x32() {
+        let a = _mm256_setr_epi32(100, 200, 300, 400, 500, 600, 700, 800);
+        let b = _mm256_setr_epi32(5, 0, 5, 1, 7, 6, 3, 4 + 8);
+        let r = _mm256_permutevar8x32_epi32(a, b);
+        let e = _mm256_setr_epi32(600, 100, 600, 200, 800, 700, 400, 500);
+        assert_eq_m256i(r, e);
+
+        let a = _mm256_setr_ps(1., 2., 3., 4., 5., 6., 7., 8.);
+        let r = _mm256_permutevar8x32_ps(a, b);
+        let e = _mm256_setr_ps(6., 1., 6., 2., 8., 7., 4., 5.);
+        assert_eq!(transmute::<_, [f32; 8]>(r), transmute::<_, [f32; 8]>(e));
+    }
+    test_mm256_permutevar8x32();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_permute2x128_si256() {
+        let a = _mm256_setr_epi64x(100, 200, 500, 600);
+        let b = _mm256_setr_epi64x(300, 400, 700, 800);
+        let r = _mm256_permute2x128_si256::<0b00_01_00_11>(a, b);
+        let e = _mm256_setr_epi64x(700, 800, 500, 600);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_permute2x128_si256();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_blendv_epi8() {
+        let (a, b) = (_mm256_set1_epi8(4), _mm256_set1_epi8(2));
+        let mask = _mm256_insert_epi8::<2>(_mm256_set1_epi8(0), -1);
+        let e = _mm256_insert_epi8::<2>(_mm256_set1_epi8(4), 2);
+        let r = _mm256_blendv_epi8(a, b, mask);
+        assert_eq_m256i(r, e);
+    }
+    test_mm256_blendv_epi8();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_movemask_epi8() {
+        let a = _mm256_setr_epi8(
+            -1, 0, -1, 0, 1, -128, 127, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -1,
+        );
+        let r = _mm256_movemask_epi8(a);
+        assert_eq!(r as u32, 0x8000_0025);
+    }
+    test_mm256_movemask_epi8();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_maskload_maskstore_epi32() {
+        let a = &[1i32, 2, 3, 4, 5, 6, 7, 8];
+        let mask = _mm256_setr_epi32(-1, 0, 0, -1, -1, 0, 0, -1);
+        let r = _mm256_maskload_epi32(a.as_ptr(), mask);
+        let e = _mm256_setr_epi32(1, 0, 0, 4, 5, 0, 0, 8);
+        assert_eq_m256i(r, e);
+
+        let mut r = [0i64; 2];
+        let mask = _mm_set_epi64x(0, -1);
+        _mm_maskstore_epi64(r.as_mut_ptr(), mask, _mm_set1_epi64x(42));
+        assert_eq!(r, [42, 0]);
+    }
+    test_mm256_maskload_maskstore_epi32();
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn test_mm256_i32gather() {
+        let arr: [i32; 128] = core::array::from_fn(|i| i as i32);
+        // A multiplier of 4 is word-addressing.
+        let r =
+            _mm256_i32gather_epi32::<4>(arr.as_ptr(), _mm256_setr_epi32(0, 16, 32, 48, 1, 2, 3, 4));
+        let e = _mm256_setr_epi32(0, 16, 32, 48, 1, 2, 3, 4);
+        assert_eq_m256i(r, e);
+
+        // Masked-out lanes keep the value from `src`.
+        let r = _mm256_mask_i32gather_epi32::<4>(
+            _mm256_set1_epi32(256),
+            arr.as_ptr(),
+            _mm256_setr_epi32(0, 16, 64, 96, 0, 0, 0, 0),
+            _mm256_setr_epi32(-1, -1, -1, 0, 0, 0, 0, 0),
+        );
+        let e = _mm256_setr_epi32(0, 16, 64, 256, 256, 256, 256, 256);
+        assert_eq_m256i(r, e);
+
+        // With 32-bit offsets, only the lower half of a 64-bit result is
+        // gathered and the upper half is zeroed.
+        let arr: [i64; 8] = core::array::from_fn(|i| i as i64);
+        let r = _mm_i64gather_epi32::<8>(arr.as_ptr() as *const i32, _mm_set_epi64x(7, 2));
+        let e = _mm_setr_epi32(2, 7, 0, 0);
+        assert_eq_m128i(r, e);
+
+        let arr = [0.0f64, 1.0, 2.0, 3.0];
+        let r = _mm_i32gather_pd::<8>(arr.as_ptr(), _mm_setr_epi32(3, 1, 0, 0));
+        assert_eq!(transmute::<_, [f64; 2]>(r), [3.0, 1.0]);
+    }
+    test_mm256_i32gather();
+}
+
+#[track_caller]
+#[target_feature(enable = "sse2")]
+unsafe fn assert_eq_m128i(a: __m128i, b: __m128i) {
+    assert_eq!(transmute::<_, [u64; 2]>(a), transmute::<_, [u64; 2]>(b))
+}
+
+#[track_caller]
+#[target_feature(enable = "avx")]
+unsafe fn assert_eq_m256i(a: __m256i, b: __m256i) {
+    assert_eq!(transmute::<_, [u64; 4]>(a), transmute::<_, [u64; 4]>(b))
+}
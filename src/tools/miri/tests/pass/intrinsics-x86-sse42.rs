@@ -0,0 +1,173 @@
+// Ignore everything except x86 and x86_64
+// Any additional target are added to CI should be ignored here
+// (We cannot use `cfg`-based tricks here since the `target-feature` flags below only work on x86.)
+//@ignore-target-aarch64
+//@ignore-target-arm
+//@ignore-target-avr
+//@ignore-target-s390x
+//@ignore-target-thumbv7em
+//@ignore-target-wasm32
+//@compile-flags: -C target-feature=+sse4.2
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::mem::transmute;
+
+fn main() {
+    assert!(is_x86_feature_detected!("sse4.2"));
+
+    unsafe {
+        test_sse42();
+    }
+}
+
+#[target_feature(enable = "sse4.2")]
+unsafe fn test_sse42() {
+    // Mostly copied from library/stdarch/crates/core_arch/src/x86/sse42.rs
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn str_to_m128i(s: &[u8]) -> __m128i {
+        assert!(s.len() <= 16);
+        let mut array = [0u8; 16];
+        array[..s.len()].copy_from_slice(s);
+        _mm_loadu_si128(array.as_ptr().cast())
+    }
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_mm_cmpistrm() {
+        let a = str_to_m128i(b"Hello! Good-Bye!");
+        let b = str_to_m128i(b"hello! good-bye!");
+        let i = _mm_cmpistrm::<_SIDD_UNIT_MASK>(a, b);
+        #[rustfmt::skip]
+        let res = _mm_setr_epi8(
+            0x00, !0, !0, !0, !0, !0, !0, 0x00,
+            !0, !0, !0, !0, 0x00, !0, !0, !0,
+        );
+        assert_eq_m128i(i, res);
+
+        let i = _mm_cmpistrm::<_SIDD_BIT_MASK>(a, b);
+        assert_eq!(_mm_extract_epi16::<0>(i), 0b1110_1111_0111_1110);
+    }
+    test_mm_cmpistrm();
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_mm_cmpistri() {
+        let a = str_to_m128i(b"Hello");
+        let b = str_to_m128i(b"   Hello        ");
+        let i = _mm_cmpistri::<_SIDD_CMP_EQUAL_ORDERED>(a, b);
+        assert_eq!(3, i);
+
+        // Looking for any of the characters in `a`.
+        let a = str_to_m128i(b"aeiou");
+        let b = str_to_m128i(b"xyz miri");
+        let i = _mm_cmpistri::<_SIDD_CMP_EQUAL_ANY>(a, b);
+        assert_eq!(5, i);
+        let i = _mm_cmpistri::<{ _SIDD_CMP_EQUAL_ANY | _SIDD_MOST_SIGNIFICANT }>(a, b);
+        assert_eq!(7, i);
+
+        // No match returns the number of elements.
+        let a = str_to_m128i(b"q");
+        let i = _mm_cmpistri::<_SIDD_CMP_EQUAL_ANY>(a, b);
+        assert_eq!(16, i);
+    }
+    test_mm_cmpistri();
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_mm_cmpistr_flags() {
+        let a = str_to_m128i(b"Hello");
+        let b = str_to_m128i(b"Hello");
+        assert_eq!(1, _mm_cmpistrz::<_SIDD_CMP_EQUAL_ORDERED>(a, b));
+        assert_eq!(1, _mm_cmpistrs::<_SIDD_CMP_EQUAL_ORDERED>(a, b));
+        assert_eq!(1, _mm_cmpistrc::<_SIDD_CMP_EQUAL_ORDERED>(a, b));
+        assert_eq!(1, _mm_cmpistro::<_SIDD_CMP_EQUAL_ORDERED>(a, b));
+        assert_eq!(0, _mm_cmpistra::<_SIDD_CMP_EQUAL_ORDERED>(a, b));
+
+        let a = str_to_m128i(b"0123456789abcdef");
+        assert_eq!(0, _mm_cmpistrz::<_SIDD_CMP_EQUAL_EACH>(a, a));
+        assert_eq!(0, _mm_cmpistrs::<_SIDD_CMP_EQUAL_EACH>(a, a));
+        let i = _mm_cmpistra::<{ _SIDD_CMP_EQUAL_EACH | _SIDD_NEGATIVE_POLARITY }>(a, a);
+        assert_eq!(1, i);
+    }
+    test_mm_cmpistr_flags();
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_mm_cmpistrm_ranges() {
+        // Find all characters in the ranges 'a'..='z' and '0'..='9'.
+        let a = str_to_m128i(b"az09");
+        let b = str_to_m128i(b"Miri 2024 TEST");
+        let i = _mm_cmpistrm::<_SIDD_CMP_RANGES>(a, b);
+        assert_eq!(_mm_extract_epi16::<0>(i), 0b0000_0001_1110_1110);
+
+        // With negative polarity, the bits after the end of `b` are also set.
+        let i = _mm_cmpistrm::<{ _SIDD_CMP_RANGES | _SIDD_NEGATIVE_POLARITY }>(a, b);
+        assert_eq!(_mm_extract_epi16::<0>(i) as u16, 0b1111_1110_0001_0001);
+        let i = _mm_cmpistrm::<{ _SIDD_CMP_RANGES | _SIDD_MASKED_NEGATIVE_POLARITY }>(a, b);
+        assert_eq!(_mm_extract_epi16::<0>(i), 0b0011_1110_0001_0001);
+    }
+    test_mm_cmpistrm_ranges();
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_mm_cmpestr() {
+        let a = str_to_m128i(b"Hello");
+        let b = str_to_m128i(b"World");
+        let i = _mm_cmpestri::<_SIDD_CMP_EQUAL_ANY>(a, 3, b, 5);
+        assert_eq!(3, i);
+        let i = _mm_cmpestri::<_SIDD_CMP_EQUAL_ANY>(a, 1, b, 5);
+        assert_eq!(16, i);
+
+        // Explicit lengths allow comparing strings containing null characters,
+        // and lengths are clamped to the vector size.
+        let a = _mm_setr_epi16(0, 1, 0, 2, 0, 0, 0, 0);
+        let b = _mm_setr_epi16(0, 1, 0, 3, 0, 0, 0, 0);
+        let i = _mm_cmpestrm::<{ _SIDD_UWORD_OPS | _SIDD_CMP_EQUAL_EACH }>(a, 100, b, -100);
+        assert_eq!(_mm_extract_epi16::<0>(i), 0b1111_0111);
+        let i = _mm_cmpestrm::<{ _SIDD_UWORD_OPS | _SIDD_CMP_EQUAL_EACH | _SIDD_UNIT_MASK }>(
+            a, 4, b, 4,
+        );
+        let e = _mm_setr_epi16(-1, -1, -1, 0, -1, -1, -1, -1);
+        assert_eq_m128i(i, e);
+
+        assert_eq!(1, _mm_cmpestrz::<_SIDD_CMP_EQUAL_ANY>(a, 16, b, 3));
+        assert_eq!(0, _mm_cmpestrz::<_SIDD_CMP_EQUAL_ANY>(a, 16, b, 16));
+        assert_eq!(1, _mm_cmpestrs::<_SIDD_CMP_EQUAL_ANY>(a, 3, b, 16));
+        assert_eq!(0, _mm_cmpestrc::<_SIDD_CMP_EQUAL_ANY>(a, 0, b, 16));
+        assert_eq!(0, _mm_cmpestro::<_SIDD_CMP_EQUAL_ANY>(a, 0, b, 16));
+        assert_eq!(1, _mm_cmpestra::<_SIDD_CMP_EQUAL_ANY>(a, 0, b, 16));
+    }
+    test_mm_cmpestr();
+
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn test_crc32() {
+        let data = b"123456789";
+        let mut crc = !0u32;
+        for &byte in data {
+            crc = _mm_crc32_u8(crc, byte);
+        }
+        assert_eq!(!crc, 0xE3069283);
+
+        let crc = _mm_crc32_u16(!0, u16::from_le_bytes([b'1', b'2']));
+        let crc = _mm_crc32_u32(crc, u32::from_le_bytes([b'3', b'4', b'5', b'6']));
+        let crc = _mm_crc32_u8(crc, b'7');
+        let crc = _mm_crc32_u16(crc, u16::from_le_bytes([b'8', b'9']));
+        assert_eq!(!crc, 0xE3069283);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let crc = _mm_crc32_u64(!0, u64::from_le_bytes(*b"12345678"));
+            let crc = _mm_crc32_u8(crc as u32, b'9');
+            assert_eq!(!crc, 0xE3069283);
+        }
+
+        assert_eq!(_mm_crc32_u8(10, 100), 3_451_792_453);
+        assert_eq!(_mm_crc32_u32(0xFFFF_FFFF, 0), 0xB798_B438);
+    }
+    test_crc32();
+}
+
+#[track_caller]
+#[target_feature(enable = "sse2")]
+unsafe fn assert_eq_m128i(a: __m128i, b: __m128i) {
+    assert_eq!(transmute::<_, [u64; 2]>(a), transmute::<_, [u64; 2]>(b))
+}
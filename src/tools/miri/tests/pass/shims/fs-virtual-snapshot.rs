@@ -0,0 +1,24 @@
+//@ignore-target-windows: File handling is not implemented yet
+//@compile-flags: -Zmiri-virtual-fs-snapshot=tests/pass/shims/fs-virtual-snapshot
+
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    // The snapshot is copied into the root, next to `/tmp`.
+    let mut names = fs::read_dir("/")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["data", "hello.txt", "tmp"]);
+
+    assert_eq!(fs::read_to_string("hello.txt").unwrap(), "Hello from the host!\n");
+    assert_eq!(fs::read_to_string("/data/nested.txt").unwrap(), "nested\n");
+
+    // Changes only affect the virtual file system.
+    fs::write("hello.txt", "Hello from Miri!\n").unwrap();
+    assert_eq!(fs::read_to_string("/hello.txt").unwrap(), "Hello from Miri!\n");
+    fs::remove_dir_all("data").unwrap();
+    assert!(!Path::new("/data").exists());
+}
@@ -0,0 +1,129 @@
+//@ignore-target-windows: File handling is not implemented yet
+//@compile-flags: -Zmiri-virtual-fs
+
+#![feature(io_error_more)]
+
+use std::env;
+use std::fs::{
+    self, canonicalize, create_dir, read_dir, read_link, remove_dir, remove_dir_all, remove_file,
+    rename, File, OpenOptions,
+};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+fn main() {
+    test_file();
+    test_seek_and_set_len();
+    test_errors();
+    test_directory();
+    test_rename();
+    test_cwd();
+}
+
+fn test_file() {
+    let path = Path::new("/tmp/miri_test_fs_virtual_file.txt");
+
+    let mut file = File::create(path).unwrap();
+    file.write_all(b"Hello, ").unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 7);
+    drop(file);
+    let mut file = OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(b"World!").unwrap();
+    assert_eq!(fs::read_to_string(path).unwrap(), "Hello, World!");
+
+    // Files have no timestamps, so that runs are reproducible.
+    let metadata = path.metadata().unwrap();
+    assert!(metadata.is_file());
+    assert_eq!(metadata.len(), 13);
+    assert_eq!(metadata.modified().unwrap(), SystemTime::UNIX_EPOCH);
+
+    // An open file can still be used after it is removed.
+    let mut file = File::open(path).unwrap();
+    remove_file(path).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "Hello, World!");
+    assert_eq!(File::open(path).unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+fn test_seek_and_set_len() {
+    let path = Path::new("/tmp/miri_test_fs_virtual_seek.txt");
+    let mut file = OpenOptions::new().read(true).write(true).create_new(true).open(path).unwrap();
+    file.write_all(b"0123456789").unwrap();
+    file.seek(SeekFrom::End(-3)).unwrap();
+    let mut buf = [0; 8];
+    assert_eq!(file.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf[..3], b"789");
+
+    // The duplicate shares the offset.
+    let mut clone = file.try_clone().unwrap();
+    assert_eq!(clone.seek(SeekFrom::Current(0)).unwrap(), 10);
+
+    file.set_len(4).unwrap();
+    file.sync_all().unwrap();
+    file.seek(SeekFrom::Start(6)).unwrap();
+    file.write_all(b"!").unwrap();
+    assert_eq!(fs::read(path).unwrap(), b"0123\0\0!");
+    remove_file(path).unwrap();
+}
+
+fn test_errors() {
+    assert_eq!(File::open("/tmp/does_not_exist").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(File::create("/nowhere/file").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(File::create("/tmp").unwrap_err().kind(), ErrorKind::IsADirectory);
+    assert_eq!(remove_file("/tmp").unwrap_err().kind(), ErrorKind::IsADirectory);
+    assert_eq!(read_link("/tmp").unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+fn test_directory() {
+    let dir = Path::new("/tmp/miri_test_fs_virtual_dir");
+    create_dir(dir).unwrap();
+    assert_eq!(create_dir(dir).unwrap_err().kind(), ErrorKind::AlreadyExists);
+    for name in ["c", "a", "b"] {
+        File::create(dir.join(name)).unwrap();
+    }
+    create_dir(dir.join("d")).unwrap();
+    File::create(dir.join("d/e")).unwrap();
+
+    // Entries are listed in a deterministic order.
+    let entries = read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.file_name().into_string().unwrap(), entry.file_type().unwrap().is_dir())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [("a".into(), false), ("b".into(), false), ("c".into(), false), ("d".into(), true)]
+    );
+
+    assert_eq!(remove_dir(dir).unwrap_err().kind(), ErrorKind::DirectoryNotEmpty);
+    assert_eq!(
+        canonicalize("/tmp/miri_test_fs_virtual_dir/./d/../a").unwrap(),
+        Path::new("/tmp/miri_test_fs_virtual_dir/a")
+    );
+    remove_dir_all(dir).unwrap();
+    assert_eq!(dir.metadata().unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+fn test_rename() {
+    let dir = Path::new("/tmp/miri_test_fs_virtual_rename");
+    create_dir(dir).unwrap();
+    fs::write(dir.join("file"), "contents").unwrap();
+    rename(dir, "/tmp/miri_test_fs_virtual_renamed").unwrap();
+    assert_eq!(fs::read_to_string("/tmp/miri_test_fs_virtual_renamed/file").unwrap(), "contents");
+    assert_eq!(rename(dir, "/tmp/other").unwrap_err().kind(), ErrorKind::NotFound);
+    remove_dir_all("/tmp/miri_test_fs_virtual_renamed").unwrap();
+}
+
+fn test_cwd() {
+    assert_eq!(env::current_dir().unwrap(), Path::new("/"));
+    fs::write("tmp/miri_test_fs_virtual_cwd.txt", "relative").unwrap();
+    env::set_current_dir("/tmp").unwrap();
+    assert_eq!(env::current_dir().unwrap(), Path::new("/tmp"));
+    assert_eq!(fs::read_to_string("miri_test_fs_virtual_cwd.txt").unwrap(), "relative");
+    remove_file("miri_test_fs_virtual_cwd.txt").unwrap();
+    assert_eq!(env::set_current_dir("/nowhere").unwrap_err().kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,117 @@
+//@only-target-linux: the loopback network is only emulated on Linux
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+
+fn main() {
+    test_tcp();
+    test_tcp_ipv6();
+    test_tcp_errors();
+    test_tcp_nonblocking();
+    test_udp();
+}
+
+fn test_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    // Ephemeral ports are handed out deterministically.
+    assert_eq!(addr.port(), 49152);
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.write_all(b"hello, ").unwrap();
+        stream.write_all(b"world").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "HELLO, WORLD");
+        stream.local_addr().unwrap()
+    });
+
+    let (mut stream, peer) = listener.accept().unwrap();
+    let mut request = Vec::new();
+    stream.read_to_end(&mut request).unwrap();
+    assert_eq!(request, b"hello, world");
+    stream.write_all(&request.to_ascii_uppercase()).unwrap();
+    drop(stream);
+    assert_eq!(client.join().unwrap(), peer);
+}
+
+fn test_tcp_ipv6() {
+    let listener = TcpListener::bind("[::1]:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        stream.write_all(&buf).unwrap();
+    });
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"ping").unwrap();
+    let mut buf = [0; 4];
+    // Blocks until the server thread has echoed the data.
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+    server.join().unwrap();
+}
+
+fn test_tcp_errors() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(TcpListener::bind(addr).unwrap_err().kind(), ErrorKind::AddrInUse);
+    assert_eq!(TcpListener::bind("192.0.2.1:80").unwrap_err().kind(), ErrorKind::AddrNotAvailable);
+    drop(listener);
+    // The port is free again, so nobody is listening anymore.
+    assert_eq!(TcpStream::connect(addr).unwrap_err().kind(), ErrorKind::ConnectionRefused);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    drop(server);
+    let mut buf = [0; 1];
+    assert_eq!(client.read(&mut buf).unwrap(), 0);
+    assert_eq!(client.write(b"x").unwrap_err().kind(), ErrorKind::BrokenPipe);
+}
+
+fn test_tcp_nonblocking() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    server.set_nonblocking(true).unwrap();
+    let mut buf = [0; 8];
+    assert_eq!(server.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+    client.write_all(b"data").unwrap();
+    assert_eq!(server.peek(&mut buf).unwrap(), 4);
+    assert_eq!(server.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf[..4], b"data");
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    let receiver = thread::spawn(move || {
+        let mut buf = [0; 16];
+        let (len, from) = b.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"first");
+        b.connect(from).unwrap();
+        // Datagrams that do not fit into the buffer are truncated.
+        let mut small = [0; 3];
+        assert_eq!(b.recv(&mut small).unwrap(), 3);
+        assert_eq!(&small, b"sec");
+        b.send(b"reply").unwrap();
+    });
+
+    a.send_to(b"first", b_addr).unwrap();
+    a.send_to(b"second", b_addr).unwrap();
+    let mut buf = [0; 16];
+    let (len, from) = a.recv_from(&mut buf).unwrap();
+    assert_eq!((&buf[..len], from), (&b"reply"[..], b_addr));
+    receiver.join().unwrap();
+}
@@ -136,6 +136,64 @@ pub fn rdmw(storing: &AtomicUsize, sync: &AtomicUsize, loading: &AtomicUsize) ->
     (a, b) == (0, 0)
 }
 
+// Store buffering with an SC fence in only one of the threads. The fence does not constrain the
+// load of the other thread, which does not happen-after it, so both loads can read 0.
+fn sc_fence_one_sided() -> bool {
+    let x = static_atomic(0);
+    assert_eq!(x.load(Relaxed), 0); // work around https://github.com/rust-lang/miri/issues/2164
+    let y = static_atomic(0);
+    assert_eq!(y.load(Relaxed), 0); // work around https://github.com/rust-lang/miri/issues/2164
+
+    let j1 = spawn(move || {
+        x.store(1, Relaxed);
+        fence(SeqCst);
+        y.load(Relaxed)
+    });
+
+    let j2 = spawn(move || {
+        y.store(1, Relaxed);
+        x.load(Relaxed)
+    });
+
+    let a = j1.join().unwrap();
+    let b = j2.join().unwrap();
+    (a, b) == (0, 0)
+}
+
+// Store buffering with SC fences, where the second load is only preceded by a relaxed load of a
+// value written after the second fence. Without acquire synchronization, the load does not
+// happen-after the fence, so both loads can still read 0.
+fn sc_fence_without_synchronization() -> bool {
+    let x = static_atomic(0);
+    assert_eq!(x.load(Relaxed), 0); // work around https://github.com/rust-lang/miri/issues/2164
+    let y = static_atomic(0);
+    assert_eq!(y.load(Relaxed), 0); // work around https://github.com/rust-lang/miri/issues/2164
+    let z = static_atomic(0);
+    assert_eq!(z.load(Relaxed), 0); // work around https://github.com/rust-lang/miri/issues/2164
+
+    let j1 = spawn(move || {
+        y.store(1, Relaxed);
+        fence(SeqCst);
+        x.load(Relaxed)
+    });
+
+    let j2 = spawn(move || {
+        x.store(1, Relaxed);
+        fence(SeqCst);
+        z.store(1, Release);
+    });
+
+    let j3 = spawn(move || {
+        reads_value(z, 1);
+        y.load(Relaxed)
+    });
+
+    let a = j1.join().unwrap();
+    j2.join().unwrap();
+    let b = j3.join().unwrap();
+    (a, b) == (0, 0)
+}
+
 /// Asserts that the function returns true at least once in 100 runs
 fn assert_once(f: fn() -> bool) {
     assert!(std::iter::repeat_with(|| f()).take(100).any(|x| x));
@@ -147,4 +205,6 @@ pub fn main() {
     assert_once(|| initialization_write(false));
     assert_once(|| initialization_write(true));
     assert_once(faa_replaced_by_load);
+    assert_once(sc_fence_one_sided);
+    assert_once(sc_fence_without_synchronization);
 }
@@ -371,6 +371,19 @@ function loadSearchJS(doc_folder, resource_suffix) {
     const searchModule = require(path.join(staticFiles, searchJs));
     const searchWords = searchModule.initSearch(searchIndex.searchIndex);
 
+    // Crates documented with `--full-text-search` have their full-text index in its own file.
+    const fullTextFolder = path.join(doc_folder, "full-text-index");
+    if (fs.existsSync(fullTextFolder)) {
+        const suffix = resource_suffix + ".js";
+        for (const file of fs.readdirSync(fullTextFolder)) {
+            if (file.endsWith(suffix)) {
+                const crate = file.slice(0, file.length - suffix.length);
+                const fullTextIndex = require(path.join(fullTextFolder, file)).fullTextIndex;
+                searchModule.addFullTextIndex(crate, fullTextIndex);
+            }
+        }
+    }
+
     return {
         doSearch: function(queryStr, filterCrate, currentCrate) {
             return searchModule.execQuery(searchModule.parseQuery(queryStr), searchWords,
@@ -0,0 +1,5 @@
+#[test]
+#[should_panic(expected = "<\"expected\"> & more")]
+fn e() {
+    panic!("<'unexpected'>");
+}
@@ -0,0 +1,39 @@
+// @has foobar/fn.hello.html
+// @has - '//div[@class="doctest-result"]/pre[@class="doctest-output"]/code' 'Hello, world!'
+// @!has - '//div[@class="doctest-result"]/span'
+/// ```
+/// println!("Hello, world!");
+/// ```
+pub fn hello() {}
+
+// @has foobar/fn.panics.html
+// @has - '//div[@class="doctest-result"]/span[@class="stab should_panic"]' 'should_panic'
+// @has - '//div[@class="doctest-result"]/pre[@class="doctest-output"]/code' 'before the panic'
+/// ```should_panic
+/// println!("before the panic");
+/// panic!();
+/// ```
+pub fn panics() {}
+
+// @has foobar/fn.not_run.html
+// @has - '//div[@class="doctest-result"]/span[@class="stab no_run"]' 'no_run'
+// @!has - '//pre[@class="doctest-output"]'
+/// ```no_run
+/// println!("never printed");
+/// ```
+pub fn not_run() {}
+
+// @has foobar/fn.ignored.html
+// @has - '//div[@class="doctest-result"]/span[@class="stab ignored"]' 'ignored'
+/// ```ignore
+/// this is not compiled
+/// ```
+pub fn ignored() {}
+
+// @has foobar/fn.quiet.html
+// @!has - '//div[@class="doctest-result"]'
+/// ```
+/// let x = 1;
+/// # assert_eq!(x, 1);
+/// ```
+pub fn quiet() {}
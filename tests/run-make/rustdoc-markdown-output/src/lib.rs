@@ -0,0 +1,25 @@
+//! A crate documented as Markdown.
+//!
+//! # Usage
+//!
+//! Start with [`Foo`] or [`bar::baz`].
+
+/// A struct.
+pub struct Foo {
+    /// The value.
+    pub value: u32,
+}
+
+pub mod bar {
+    //! Functions.
+
+    /// Makes a [`Foo`](crate::Foo).
+    ///
+    /// ```
+    /// # use foo::Foo;
+    /// let foo: Foo = foo::bar::baz();
+    /// ```
+    pub fn baz() -> crate::Foo {
+        crate::Foo { value: 0 }
+    }
+}
@@ -0,0 +1,33 @@
+// exact-check
+
+const EXPECTED = [
+    {
+        // Items whose documentation mentions the query.
+        'query': 'capacity',
+        'others': [
+            { 'path': 'full_text', 'name': 'reserve' },
+            { 'path': 'full_text', 'name': 'shrink_to_fit' },
+        ],
+    },
+    {
+        // Items that contain more of the words come first.
+        'query': 'reserving elements capacity',
+        'others': [
+            { 'path': 'full_text', 'name': 'reserve' },
+            { 'path': 'full_text', 'name': 'shrink_to_fit' },
+        ],
+    },
+    {
+        // Name matches come before documentation matches, and aren't repeated.
+        'query': 'counter',
+        'others': [
+            { 'path': 'full_text', 'name': 'Counter' },
+        ],
+    },
+    {
+        'query': 'shared thread',
+        'others': [
+            { 'path': 'full_text', 'name': 'Counter' },
+        ],
+    },
+];
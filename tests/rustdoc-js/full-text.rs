@@ -0,0 +1,10 @@
+// compile-flags: -Zunstable-options --full-text-search
+
+/// Reserves capacity for at least `additional` more elements.
+pub fn reserve(additional: usize) {}
+
+/// Shrinks the capacity as much as possible.
+pub fn shrink_to_fit() {}
+
+/// A counter that can be shared between threads.
+pub struct Counter;
@@ -0,0 +1,17 @@
+// compile-flags:-Z unstable-options --show-coverage
+// compile-flags:--coverage-threshold examples=50 --coverage-threshold total=100
+
+//! Everything is documented, but only one item out of four has a code example.
+
+/// Doc
+///
+/// ```
+/// let x = 2;
+/// ```
+pub struct Foo;
+
+/// Doc
+pub trait Bar {}
+
+/// Doc
+pub fn foo() {}
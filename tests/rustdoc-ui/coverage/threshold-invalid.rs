@@ -0,0 +1,4 @@
+// compile-flags:-Z unstable-options --show-coverage --coverage-threshold docs=50
+
+/// Foo
+pub struct Xo;
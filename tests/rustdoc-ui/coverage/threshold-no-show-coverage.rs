@@ -0,0 +1,4 @@
+// compile-flags:-Z unstable-options --coverage-threshold total=50
+
+/// Foo
+pub struct Xo;
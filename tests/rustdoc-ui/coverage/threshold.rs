@@ -0,0 +1,12 @@
+// compile-flags:-Z unstable-options --show-coverage
+// compile-flags:--coverage-threshold functions=80 --coverage-threshold structs=100%
+
+//! Only the functions of this crate are missing documentation.
+
+/// Documented.
+pub struct Foo;
+
+/// Documented.
+pub fn documented() {}
+
+pub fn undocumented() {}
@@ -0,0 +1,21 @@
+// ignore-windows
+// check-pass
+// Checks that doctests which fail to compile once merged with `--merge-doctests`, here because
+// they define the same `#[no_mangle]` function, are compiled and run separately instead.
+
+// compile-flags:--test --edition 2021 -Z unstable-options --merge-doctests
+// compile-flags:--test-args --test-threads=1
+// normalize-stdout-test: "tests/rustdoc-ui/doctest" -> "$$DIR"
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+
+/// ```
+/// #[no_mangle]
+/// pub extern "C" fn clash() {}
+/// ```
+pub struct First;
+
+/// ```
+/// #[no_mangle]
+/// pub extern "C" fn clash() {}
+/// ```
+pub struct Second;
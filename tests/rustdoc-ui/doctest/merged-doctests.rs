@@ -0,0 +1,44 @@
+// ignore-windows
+// Checks that the doctests that can be merged with `--merge-doctests` are reported like the other
+// ones, and that their panics point to the line of the doctest.
+
+// compile-flags:--test --edition 2021 -Z unstable-options --merge-doctests
+// compile-flags:--test-args --test-threads=1
+// rustc-env:RUST_BACKTRACE=0
+// normalize-stdout-test: "tests/rustdoc-ui/doctest" -> "$$DIR"
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// failure-status: 101
+
+/// ```
+/// assert_eq!(1 + 1, 2);
+/// ```
+pub struct Passes;
+
+/// ```should_panic
+/// panic!("expected");
+/// ```
+pub struct ShouldPanic;
+
+/// ```
+/// fn main() -> Result<(), String> {
+///     Err("returned an error".to_owned())
+/// }
+/// ```
+pub struct ReturnsError;
+
+/// ```
+/// #![allow(dead_code)]
+/// fn unused() {}
+/// ```
+pub struct CrateAttribute;
+
+/// ```compile_fail
+/// let x: u8 = "";
+/// ```
+pub struct CompileFail;
+
+/// ```
+/// println!("before the panic");
+/// panic!("oh no");
+/// ```
+pub struct Panics;
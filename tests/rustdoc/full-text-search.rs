@@ -0,0 +1,10 @@
+// compile-flags: -Zunstable-options --full-text-search
+
+#![crate_name = "foo"]
+
+// @hasraw search-index.js '"w":1'
+// @hasraw full-text-index/foo.js '{"w":["capacity","reserv"],"p":["b","b"]}'
+// @hasraw full-text-index/foo.js 'window.addFullTextIndex("foo", fullTextIndex)'
+
+/// Reserves capacity.
+pub fn reserve() {}
@@ -0,0 +1,5 @@
+// compile-flags: --test
+
+#[test]
+#[test_fixture(setup)] //~ ERROR the `#[test_fixture]` attribute is an experimental feature
+fn setup() {}
@@ -0,0 +1,5 @@
+// compile-flags: --test
+
+#[test]
+#[test_resources("port")] //~ ERROR the `#[test_resources]` attribute is an experimental feature
+fn f() {}
@@ -0,0 +1,7 @@
+// compile-flags: --test
+
+#![feature(test_fixture)]
+
+#[test]
+#[test_fixture(before)] //~ ERROR invalid test fixture kind
+fn f() {}
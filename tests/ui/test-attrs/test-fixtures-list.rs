@@ -0,0 +1,25 @@
+// no-prefer-dynamic
+// compile-flags: --test
+// run-flags: --list
+// run-pass
+// check-run-results
+
+// Checks that fixtures are not listed as tests.
+
+#![feature(test_fixture)]
+
+mod db {
+    #[test]
+    #[test_fixture(setup)]
+    fn setup() {}
+
+    #[test]
+    #[test_fixture(teardown)]
+    fn teardown() {}
+
+    #[test]
+    fn query() {}
+}
+
+#[test]
+fn a_test() {}
@@ -0,0 +1,21 @@
+// no-prefer-dynamic
+// compile-flags: --test -Cpanic=abort -Zpanic_abort_tests
+// run-flags: --test-threads=1
+// run-fail
+// check-run-results
+
+// ignore-wasm no panic or subprocess support
+// ignore-emscripten no panic or subprocess support
+// ignore-sgx no subprocess support
+
+// Checks that fixtures are rejected when the tests run in subprocesses, which a fixture couldn't
+// set anything up for.
+
+#![feature(test_fixture)]
+
+#[test]
+#[test_fixture(setup)]
+fn setup() {}
+
+#[test]
+fn it_works() {}
@@ -0,0 +1,42 @@
+// compile-flags: --test
+// run-fail
+// run-flags: --test-threads=1
+// check-run-results
+// exec-env:RUST_BACKTRACE=0
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// ignore-emscripten no threads support
+// needs-unwind
+
+// Checks that fixtures run around the tests of their module, and that the tests of a module
+// whose setup failed are reported as failed with the output of the setup.
+
+#![feature(test_fixture)]
+
+mod db {
+    #[test]
+    #[test_fixture(setup)]
+    fn setup() {
+        println!("connecting");
+        panic!("connection refused");
+    }
+
+    #[test]
+    #[test_fixture(teardown)]
+    fn teardown() {}
+
+    #[test]
+    fn query() {}
+}
+
+mod fs {
+    #[test]
+    #[test_fixture(setup)]
+    fn setup() {}
+
+    #[test]
+    #[test_fixture(teardown)]
+    fn teardown() {}
+
+    #[test]
+    fn read() {}
+}
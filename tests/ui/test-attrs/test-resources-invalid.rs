@@ -0,0 +1,7 @@
+// compile-flags: --test
+
+#![feature(test_resources)]
+
+#[test]
+#[test_resources(port)] //~ ERROR test resources must be string literals
+fn f() {}
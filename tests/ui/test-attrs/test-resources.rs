@@ -0,0 +1,38 @@
+// compile-flags: --test
+// run-pass
+// run-flags: --test-threads=4
+// ignore-emscripten no threads support
+
+// Checks that tests sharing a resource don't run concurrently.
+
+#![feature(test_resources)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static IN_USE: AtomicBool = AtomicBool::new(false);
+
+fn use_resource() {
+    assert!(!IN_USE.swap(true, Ordering::SeqCst), "resource already in use");
+    thread::sleep(Duration::from_millis(10));
+    IN_USE.store(false, Ordering::SeqCst);
+}
+
+#[test]
+#[test_resources("shared")]
+fn a() {
+    use_resource();
+}
+
+#[test]
+#[test_resources("other", "shared")]
+fn b() {
+    use_resource();
+}
+
+#[test]
+#[test_resources("shared")]
+fn c() {
+    use_resource();
+}
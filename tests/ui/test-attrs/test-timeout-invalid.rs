@@ -0,0 +1,33 @@
+// compile-flags: --test
+
+// The attribute accepts the same durations as `--test-timeout`, except for zero.
+
+#![feature(test_timeout)]
+
+#[test]
+#[test_timeout = "2h"] //~ ERROR invalid test timeout `2h`
+fn hours() {}
+
+#[test]
+#[test_timeout = "1.5s"] //~ ERROR invalid test timeout `1.5s`
+fn fraction() {}
+
+#[test]
+#[test_timeout = "-1s"] //~ ERROR invalid test timeout `-1s`
+fn negative() {}
+
+#[test]
+#[test_timeout = "ms"] //~ ERROR invalid test timeout `ms`
+fn no_number() {}
+
+#[test]
+#[test_timeout = "0"] //~ ERROR invalid test timeout `0`
+fn zero() {}
+
+#[test]
+#[test_timeout = "30"]
+fn seconds() {}
+
+#[test]
+#[test_timeout = "500ms"]
+fn millis() {}
@@ -0,0 +1,29 @@
+// compile-flags: --test
+// run-fail
+// run-flags: --test-threads=1
+// check-run-results
+// exec-env:RUST_BACKTRACE=0
+// normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+// ignore-emscripten no threads support
+// ignore-wasm no threads support
+// needs-unwind
+
+// Checks that a test running for longer than its `#[test_timeout]` is stopped and reported as
+// timed out, without holding up the tests after it.
+
+#![feature(test_timeout)]
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+#[test_timeout = "100ms"]
+fn hangs() {
+    loop {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[test]
+#[test_timeout = "1m"]
+fn passes() {}
@@ -19,3 +19,7 @@ fn z_test() {}
 
 #[test]
 fn a_test() {}
+
+#[test]
+#[should_panic(expected = "a \"quoted\" message")]
+fn s_test() {}